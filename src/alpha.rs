@@ -0,0 +1,139 @@
+// The MIT License (MIT)
+//
+// Copyright (c) 2018 Michael Dilger
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+// Converting between straight and premultiplied alpha
+
+use crate::codec::{linear_to_srgb, srgb_to_linear};
+use crate::{AlphaMode, D3DFormat, Dds, Error, PixelFormat};
+
+/// Multiply the color of RGBA floats by their alpha
+pub(crate) fn premultiply(rgba: &mut [f32]) {
+    for texel in rgba.chunks_exact_mut(4) {
+        let alpha = texel[3];
+        for c in &mut texel[..3] {
+            *c *= alpha;
+        }
+    }
+}
+
+/// Divide the color of RGBA floats by their alpha. Fully transparent texels
+/// are left black.
+pub(crate) fn unpremultiply(rgba: &mut [f32]) {
+    for texel in rgba.chunks_exact_mut(4) {
+        let alpha = texel[3];
+        for c in &mut texel[..3] {
+            *c = if alpha > 0.0 { *c / alpha } else { 0.0 };
+        }
+    }
+}
+
+impl Dds {
+    /// This gets how the alpha channel is to be interpreted: the alpha mode of
+    /// the DX10 header, or premultiplied for the legacy DXT2 and DXT4 formats
+    pub fn get_alpha_mode(&self) -> AlphaMode {
+        match self.header10 {
+            Some(ref h10) => h10.alpha_mode,
+            None => match self.get_d3d_format() {
+                Some(D3DFormat::DXT2) | Some(D3DFormat::DXT4) => AlphaMode::PreMultiplied,
+                _ => AlphaMode::Unknown,
+            },
+        }
+    }
+
+    /// Multiply the color of every texel by its alpha, and declare the alpha
+    /// mode premultiplied: in the DX10 header, or by switching DXT3 and DXT5
+    /// to DXT2 and DXT4. A texture already declared premultiplied is left as
+    /// it is.
+    ///
+    /// Every subresource is decoded and encoded again, which loses precision
+    /// for compressed formats. sRGB data is premultiplied in linear space.
+    pub fn premultiply_alpha(&mut self) -> Result<(), Error> {
+        if self.get_alpha_mode() == AlphaMode::PreMultiplied {
+            return Ok(());
+        }
+        self.convert_alpha(premultiply)?;
+        self.set_alpha_mode(AlphaMode::PreMultiplied);
+        Ok(())
+    }
+
+    /// Divide the color of every texel by its alpha, and declare the alpha
+    /// mode straight: in the DX10 header, or by switching DXT2 and DXT4 to DXT3
+    /// and DXT5. A texture already declared straight or opaque is left as it
+    /// is. See `premultiply_alpha`.
+    pub fn unpremultiply_alpha(&mut self) -> Result<(), Error> {
+        if matches!(
+            self.get_alpha_mode(),
+            AlphaMode::Straight | AlphaMode::Opaque
+        ) {
+            return Ok(());
+        }
+        self.convert_alpha(unpremultiply)?;
+        self.set_alpha_mode(AlphaMode::Straight);
+        Ok(())
+    }
+
+    fn convert_alpha(&mut self, convert: fn(&mut [f32])) -> Result<(), Error> {
+        let srgb = match self.header10 {
+            Some(ref h10) => h10.dxgi_format.is_srgb(),
+            None => false,
+        };
+        for array_layer in 0..self.get_num_array_layers() {
+            for mipmap_level in 0..self.get_num_mipmap_levels() {
+                let mut rgba = self.decode_rgba32f(array_layer, mipmap_level)?;
+                if srgb {
+                    for texel in rgba.chunks_exact_mut(4) {
+                        for c in &mut texel[..3] {
+                            *c = srgb_to_linear(*c);
+                        }
+                    }
+                }
+                convert(&mut rgba);
+                if srgb {
+                    for texel in rgba.chunks_exact_mut(4) {
+                        for c in &mut texel[..3] {
+                            *c = linear_to_srgb(*c);
+                        }
+                    }
+                }
+                self.encode_rgba32f(array_layer, mipmap_level, &rgba)?;
+            }
+        }
+        Ok(())
+    }
+
+    // Legacy headers can only record premultiplied alpha for DXT2 and DXT4
+    fn set_alpha_mode(&mut self, alpha_mode: AlphaMode) {
+        if let Some(ref mut h10) = self.header10 {
+            h10.alpha_mode = alpha_mode;
+            return;
+        }
+        let premultiplied = alpha_mode == AlphaMode::PreMultiplied;
+        let format = match self.get_d3d_format() {
+            Some(D3DFormat::DXT3) if premultiplied => D3DFormat::DXT2,
+            Some(D3DFormat::DXT5) if premultiplied => D3DFormat::DXT4,
+            Some(D3DFormat::DXT2) if !premultiplied => D3DFormat::DXT3,
+            Some(D3DFormat::DXT4) if !premultiplied => D3DFormat::DXT5,
+            _ => return,
+        };
+        self.header.spf = PixelFormat::from(format);
+    }
+}
@@ -0,0 +1,297 @@
+// The MIT License (MIT)
+//
+// Copyright (c) 2018 Michael Dilger
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+// Splitting textures into their layers and mipmap levels, and assembling
+// several textures into one
+
+use crate::{
+    Caps2, D3D10ResourceDimension, Dds, DxgiFormat, Error, Expected, NewD3dParams, NewDxgiParams,
+};
+
+/// The kind of texture to assemble several textures into
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Assembly {
+    /// A texture array with one layer per texture
+    Array,
+    /// A cubemap, or an array of cubemaps, from faces in the order +X, -X, +Y,
+    /// -Y, +Z, -Z
+    Cubemap,
+    /// A volume texture with one depth slice per texture
+    Volume,
+}
+
+impl Dds {
+    /// Extract one array layer (or cubemap face) with all of its mipmap levels
+    /// as a standalone texture. Volume textures have just the one layer.
+    pub fn extract_layer(&self, array_layer: u32) -> Result<Dds, Error> {
+        let levels = self.get_num_mipmap_levels();
+        let depth = match self.is_volume() {
+            true => Some(self.get_depth()),
+            false => None,
+        };
+        let mut dds =
+            self.new_like(self.get_width(), self.get_height(), depth, levels, 1, false)?;
+        for mipmap_level in 0..levels {
+            let data = self.get_mipmap_data(array_layer, mipmap_level)?;
+            dds.get_mut_mipmap_data(0, mipmap_level)?
+                .copy_from_slice(data);
+        }
+        Ok(dds)
+    }
+
+    /// Extract a single mipmap level of one array layer (or cubemap face) as a
+    /// standalone texture without mipmaps
+    pub fn extract_subresource(&self, array_layer: u32, mipmap_level: u32) -> Result<Dds, Error> {
+        let data = self.get_mipmap_data(array_layer, mipmap_level)?;
+        let (width, height, depth) = self.get_mipmap_dimensions(mipmap_level);
+        let depth = match self.is_volume() {
+            true => Some(depth),
+            false => None,
+        };
+        let mut dds = self.new_like(width, height, depth, 1, 1, false)?;
+        dds.get_mut_mipmap_data(0, 0)?.copy_from_slice(data);
+        Ok(dds)
+    }
+
+    /// Assemble several textures of the same format, dimensions and number of
+    /// mipmap levels into a texture array, a cubemap (six faces per cube) or a
+    /// volume. The parts must be single 2D layers, and volume slices must not
+    /// have mipmaps, since each level of a volume halves its depth as well.
+    pub fn assemble(parts: &[Dds], assembly: Assembly) -> Result<Dds, Error> {
+        let first = match parts.first() {
+            Some(first) => first,
+            None => {
+                return Err(Error::InvalidField {
+                    field: "textures",
+                    value: 0,
+                    offset: None,
+                    expected: Expected::Description("at least one"),
+                })
+            }
+        };
+        let format = first.get_equivalent_dxgi_format();
+        let (width, height) = (first.get_width(), first.get_height());
+        let levels = first.get_num_mipmap_levels();
+
+        for (index, part) in parts.iter().enumerate() {
+            let mismatch = |property, found: String, expected: String| Error::Mismatch {
+                index,
+                property,
+                found,
+                expected,
+            };
+            if part.is_volume() {
+                return Err(mismatch(
+                    "resource dimension",
+                    "3D".to_owned(),
+                    "2D".to_owned(),
+                ));
+            }
+            if part.get_num_array_layers() != 1 {
+                return Err(mismatch(
+                    "array layers",
+                    part.get_num_array_layers().to_string(),
+                    "1".to_owned(),
+                ));
+            }
+            let same_format = match format {
+                Some(_) => part.get_equivalent_dxgi_format() == format,
+                None => part.get_d3d_format() == first.get_d3d_format(),
+            };
+            if !same_format {
+                return Err(mismatch("format", part.format_name(), first.format_name()));
+            }
+            if (part.get_width(), part.get_height()) != (width, height) {
+                return Err(mismatch(
+                    "dimensions",
+                    format!("{}x{}", part.get_width(), part.get_height()),
+                    format!("{}x{}", width, height),
+                ));
+            }
+            if part.get_num_mipmap_levels() != levels {
+                return Err(mismatch(
+                    "mipmap levels",
+                    part.get_num_mipmap_levels().to_string(),
+                    levels.to_string(),
+                ));
+            }
+        }
+
+        let count = parts.len() as u32;
+        let mut dds = match assembly {
+            Assembly::Array => first.new_like(width, height, None, levels, count, false)?,
+            Assembly::Cubemap => {
+                if width != height {
+                    return Err(Error::InvalidField {
+                        field: "height",
+                        value: height as u64,
+                        offset: None,
+                        expected: Expected::Description("the same as the width, for a cubemap"),
+                    });
+                }
+                if !count.is_multiple_of(6) {
+                    return Err(Error::InvalidField {
+                        field: "faces",
+                        value: count as u64,
+                        offset: None,
+                        expected: Expected::Description("a multiple of six"),
+                    });
+                }
+                first.new_like(width, height, None, levels, count, true)?
+            }
+            Assembly::Volume => {
+                if levels > 1 {
+                    return Err(Error::InvalidField {
+                        field: "mipmap levels",
+                        value: levels as u64,
+                        offset: None,
+                        expected: Expected::Range(1, 1),
+                    });
+                }
+                first.new_like(width, height, Some(count), 1, 1, false)?
+            }
+        };
+
+        match assembly {
+            Assembly::Volume => {
+                let slices = dds.get_mut_data(0)?;
+                let slice_size = slices.len() / parts.len();
+                for (part, slice) in parts.iter().zip(slices.chunks_exact_mut(slice_size)) {
+                    slice.copy_from_slice(part.get_mipmap_data(0, 0)?);
+                }
+            }
+            _ => {
+                for (array_layer, part) in parts.iter().enumerate() {
+                    for mipmap_level in 0..levels {
+                        dds.get_mut_mipmap_data(array_layer as u32, mipmap_level)?
+                            .copy_from_slice(part.get_mipmap_data(0, mipmap_level)?);
+                    }
+                }
+            }
+        }
+        Ok(dds)
+    }
+
+    // The DxgiFormat of the DX10 header, or the equivalent of the D3DFormat
+    fn get_equivalent_dxgi_format(&self) -> Option<DxgiFormat> {
+        match self.header10 {
+            Some(ref h10) => Some(h10.dxgi_format),
+            None => self
+                .get_d3d_format()
+                .and_then(|f| f.to_dxgi_format())
+                .or_else(|| self.get_dxgi_format()),
+        }
+    }
+
+    // The name of the format, for error messages
+    pub(crate) fn format_name(&self) -> String {
+        match (self.get_equivalent_dxgi_format(), self.get_d3d_format()) {
+            (Some(f), _) => f.to_string(),
+            (None, Some(f)) => f.to_string(),
+            (None, None) => "unknown".to_owned(),
+        }
+    }
+
+    // Create an empty texture in the same format as this one, keeping a legacy
+    // header when it can describe the result
+    pub(crate) fn new_like(
+        &self,
+        width: u32,
+        height: u32,
+        depth: Option<u32>,
+        mipmap_levels: u32,
+        array_layers: u32,
+        is_cubemap: bool,
+    ) -> Result<Dds, Error> {
+        let legacy = match is_cubemap {
+            true => array_layers == 6,
+            false => array_layers == 1,
+        };
+        if self.header10.is_none() && legacy {
+            if let Some(format) = self.get_d3d_format() {
+                return Dds::new_d3d(NewD3dParams {
+                    height,
+                    width,
+                    depth,
+                    format,
+                    mipmap_levels: Some(mipmap_levels),
+                    caps2: caps2_for(depth, is_cubemap),
+                });
+            }
+        }
+
+        let format = match self.get_equivalent_dxgi_format() {
+            Some(f) => f,
+            None => return Err(Error::UnsupportedFormat),
+        };
+        self.new_dxgi_like(
+            format,
+            (width, height, depth),
+            mipmap_levels,
+            array_layers,
+            is_cubemap,
+        )
+    }
+
+    // Create an empty texture with a DX10 header in the given format, keeping
+    // the alpha mode of this one and keeping 1D textures 1D
+    pub(crate) fn new_dxgi_like(
+        &self,
+        format: DxgiFormat,
+        (width, height, depth): (u32, u32, Option<u32>),
+        mipmap_levels: u32,
+        array_layers: u32,
+        is_cubemap: bool,
+    ) -> Result<Dds, Error> {
+        let resource_dimension = match self.header10 {
+            _ if depth.is_some() => D3D10ResourceDimension::Texture3D,
+            Some(ref h10)
+                if h10.resource_dimension == D3D10ResourceDimension::Texture1D && height == 1 =>
+            {
+                D3D10ResourceDimension::Texture1D
+            }
+            _ => D3D10ResourceDimension::Texture2D,
+        };
+        Dds::new_dxgi(NewDxgiParams {
+            height,
+            width,
+            depth,
+            format,
+            mipmap_levels: Some(mipmap_levels),
+            array_layers: Some(array_layers),
+            caps2: caps2_for(depth, is_cubemap),
+            is_cubemap,
+            resource_dimension,
+            alpha_mode: self.get_alpha_mode(),
+        })
+    }
+}
+
+fn caps2_for(depth: Option<u32>, is_cubemap: bool) -> Option<Caps2> {
+    if depth.is_some() {
+        Some(Caps2::VOLUME)
+    } else if is_cubemap {
+        Some(Caps2::CUBEMAP | Caps2::CUBEMAP_ALLFACES)
+    } else {
+        None
+    }
+}
@@ -0,0 +1,589 @@
+// The MIT License (MIT)
+//
+// Copyright (c) 2018 Michael Dilger
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+// Packing many images into atlas textures
+
+use crate::codec::{linear_to_srgb, srgb_to_linear, Layout};
+use crate::{
+    AlphaMode, BlockRegion, D3D10ResourceDimension, Dds, DxgiFormat, Error, Expected, Mipmaps,
+    NewDxgiParams,
+};
+use image::imageops::{self, FilterType};
+use image::{DynamicImage, Rgba32FImage};
+use std::fmt::Write;
+use std::mem;
+use std::path::PathBuf;
+
+#[derive(Debug, Clone)]
+enum Source {
+    Image(DynamicImage),
+    Path(PathBuf),
+    Texture(Dds),
+}
+
+/// Packs many source images into the pages of an atlas: separate textures, or
+/// the layers of one texture array.
+///
+/// Each entry is surrounded by a gutter of its own edge texels, regenerated at
+/// every mipmap level from the entry alone, so that filtering near an edge does
+/// not pick up its neighbours. Source textures in the same kind of block
+/// compression as the atlas have their blocks copied without compressing them
+/// again, wherever their size is a multiple of four.
+///
+/// ```no_run
+/// # use ddsfile::{AtlasBuilder, DxgiFormat, Mipmaps};
+/// let atlas = AtlasBuilder::new(DxgiFormat::BC3_UNorm, 1024, 1024)
+///     .path("button", "button.png")
+///     .path("slider", "slider.png")
+///     .padding(4)
+///     .mipmaps(Mipmaps::Levels(3))
+///     .build()?;
+/// std::fs::write("ui.json", atlas.to_json())?;
+/// # Ok::<(), ddsfile::Error>(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct AtlasBuilder {
+    format: DxgiFormat,
+    width: u32,
+    height: u32,
+    sources: Vec<(String, Source)>,
+    padding: u32,
+    mipmaps: Mipmaps,
+    filter: FilterType,
+    is_array: bool,
+}
+
+/// Where an entry was placed in an atlas
+#[derive(Debug, Clone, PartialEq)]
+pub struct AtlasEntry {
+    pub name: String,
+    /// The texture, or the array layer for a texture array, holding the entry
+    pub page: u32,
+    /// The left column of the entry, in texels, without the gutter
+    pub x: u32,
+    /// The top row of the entry, in texels, without the gutter
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+    /// The texture coordinates of the left, top, right and bottom edges
+    pub uv: [f32; 4],
+}
+
+/// The textures and entries of a packed atlas
+#[derive(Debug, Clone)]
+pub struct Atlas {
+    /// One texture per page, or a single texture array
+    pub textures: Vec<Dds>,
+    /// The entries, in the order they were added
+    pub entries: Vec<AtlasEntry>,
+    /// The width of every page, in texels
+    pub width: u32,
+    /// The height of every page, in texels
+    pub height: u32,
+}
+
+impl AtlasBuilder {
+    /// Start building an atlas in the given format, with pages of the given
+    /// size, no padding and no mipmaps
+    pub fn new(format: DxgiFormat, width: u32, height: u32) -> AtlasBuilder {
+        AtlasBuilder {
+            format,
+            width,
+            height,
+            sources: Vec::new(),
+            padding: 0,
+            mipmaps: Mipmaps::None,
+            filter: FilterType::Triangle,
+            is_array: false,
+        }
+    }
+
+    /// Add a named source image
+    pub fn image<S: Into<String>>(mut self, name: S, image: DynamicImage) -> AtlasBuilder {
+        self.sources.push((name.into(), Source::Image(image)));
+        self
+    }
+
+    /// Add a named source image file, opened by `build()`
+    pub fn path<S: Into<String>, P: Into<PathBuf>>(mut self, name: S, path: P) -> AtlasBuilder {
+        self.sources.push((name.into(), Source::Path(path.into())));
+        self
+    }
+
+    /// Add a named source texture. Its first array layer is used, with its own
+    /// mipmap levels where they are the right size.
+    pub fn texture<S: Into<String>>(mut self, name: S, texture: Dds) -> AtlasBuilder {
+        self.sources.push((name.into(), Source::Texture(texture)));
+        self
+    }
+
+    /// Set the width of the gutter around each entry at the top mipmap level,
+    /// halved at each level below. It is rounded up to keep entries aligned to
+    /// compressed blocks, and to the texels of every mipmap level down to the
+    /// one where the smallest entry is a single texel wide. The alignment never
+    /// exceeds the size of the smallest entry.
+    pub fn padding(mut self, padding: u32) -> AtlasBuilder {
+        self.padding = padding;
+        self
+    }
+
+    /// Set how many mipmap levels to generate
+    pub fn mipmaps(mut self, mipmaps: Mipmaps) -> AtlasBuilder {
+        self.mipmaps = mipmaps;
+        self
+    }
+
+    /// Set the filter used to generate mipmap levels (default: Triangle)
+    pub fn filter(mut self, filter: FilterType) -> AtlasBuilder {
+        self.filter = filter;
+        self
+    }
+
+    /// Put the pages in the layers of a single texture array, rather than in
+    /// separate textures
+    pub fn array(mut self, is_array: bool) -> AtlasBuilder {
+        self.is_array = is_array;
+        self
+    }
+
+    /// Load the sources, pack them, and encode the pages
+    pub fn build(self) -> Result<Atlas, Error> {
+        let layout = match Layout::from_dxgi(self.format) {
+            Some(layout) => layout,
+            None => return Err(Error::UnsupportedFormat),
+        };
+        if self.sources.is_empty() {
+            return Err(Error::InvalidField {
+                field: "images",
+                value: 0,
+                offset: None,
+                expected: Expected::Description("at least one"),
+            });
+        }
+        let (width, height) = (self.width, self.height);
+        let full = 32 - width.max(height).leading_zeros();
+        let mipmap_levels = match self.mipmaps {
+            Mipmaps::None => 1,
+            Mipmaps::Full => full,
+            Mipmaps::Levels(levels) => levels.clamp(1, full),
+        };
+        let srgb = self.format.is_srgb();
+
+        // The top level of every entry, linear for sRGB formats, and the
+        // texture it came from
+        let mut names = Vec::with_capacity(self.sources.len());
+        let mut images = Vec::with_capacity(self.sources.len());
+        let mut textures = Vec::with_capacity(self.sources.len());
+        for (name, source) in self.sources {
+            let (image, texture) = match source {
+                Source::Image(image) => (image.into_rgba32f(), None),
+                Source::Path(path) => (image::open(&path)?.into_rgba32f(), None),
+                Source::Texture(texture) => (level_image(&texture, 0, srgb)?, Some(texture)),
+            };
+            let mut image = image;
+            if srgb && texture.is_none() {
+                linearize(image.as_mut());
+            }
+            names.push(name);
+            images.push(image);
+            textures.push(texture);
+        }
+
+        // Keep every entry, and its gutter, on whole texels of every mipmap
+        // level at which the smallest entry is still a texel wide, and on
+        // whole blocks of the top level. When there are blocks to copy, keep
+        // them on whole blocks of as many levels as the smallest entry allows.
+        let smallest = images
+            .iter()
+            .map(|image| image.width().min(image.height()).max(1))
+            .min()
+            .unwrap_or(1);
+        let cap = 1 << (31 - smallest.leading_zeros());
+        let texel_align: u32 = 1 << (mipmap_levels.min(32 - smallest.leading_zeros()) - 1);
+        let copies_blocks = textures
+            .iter()
+            .flatten()
+            .any(|t| compatible_blocks(layout, t));
+        let align = match layout.block_size() {
+            Some(_) if copies_blocks => texel_align.saturating_mul(4).min(cap).max(4),
+            Some(_) => texel_align.max(4),
+            None => texel_align,
+        };
+        let padding = self.padding.next_multiple_of(align);
+        let mut cells = Vec::with_capacity(images.len());
+        for image in &images {
+            let (w, h) = image.dimensions();
+            let cell = (
+                w.next_multiple_of(align) + 2 * padding,
+                h.next_multiple_of(align) + 2 * padding,
+            );
+            for (field, value, size) in [("width", cell.0, width), ("height", cell.1, height)] {
+                if value > size {
+                    return Err(Error::InvalidField {
+                        field,
+                        value: value as u64,
+                        offset: None,
+                        expected: Expected::Range(1, size as u64),
+                    });
+                }
+            }
+            cells.push(cell);
+        }
+        let (places, pages) = pack(&cells, width, height);
+
+        let entries: Vec<AtlasEntry> = names
+            .into_iter()
+            .zip(images.iter().zip(&places))
+            .map(|(name, (image, &(page, x, y)))| {
+                let (w, h) = image.dimensions();
+                let (x, y) = (x + padding, y + padding);
+                AtlasEntry {
+                    name,
+                    page,
+                    x,
+                    y,
+                    width: w,
+                    height: h,
+                    uv: [
+                        x as f32 / width as f32,
+                        y as f32 / height as f32,
+                        (x + w) as f32 / width as f32,
+                        (y + h) as f32 / height as f32,
+                    ],
+                }
+            })
+            .collect();
+
+        let new_page = |array_layers| {
+            Dds::new_dxgi(NewDxgiParams {
+                height,
+                width,
+                depth: None,
+                format: self.format,
+                mipmap_levels: Some(mipmap_levels),
+                array_layers: Some(array_layers),
+                caps2: None,
+                is_cubemap: false,
+                resource_dimension: D3D10ResourceDimension::Texture2D,
+                alpha_mode: AlphaMode::Unknown,
+            })
+        };
+        let mut dds = match self.is_array {
+            true => vec![new_page(pages)?],
+            false => (0..pages).map(|_| new_page(1)).collect::<Result<_, _>>()?,
+        };
+
+        for mipmap_level in 0..mipmap_levels {
+            let (level_width, level_height) = (
+                (width >> mipmap_level).max(1),
+                (height >> mipmap_level).max(1),
+            );
+            for page in 0..pages {
+                let (texture, array_layer) = match self.is_array {
+                    true => (&mut dds[0], page),
+                    false => (&mut dds[page as usize], 0),
+                };
+                let mut canvas = vec![0.0; level_width as usize * level_height as usize * 4];
+                let on_page = entries.iter().enumerate().filter(|(_, e)| e.page == page);
+                for (i, entry) in on_page.clone() {
+                    let (w, h) = (
+                        (entry.width >> mipmap_level).max(1),
+                        (entry.height >> mipmap_level).max(1),
+                    );
+                    let image = match textures[i] {
+                        Some(ref t)
+                            if t.get_mipmap_dimensions(mipmap_level) == (w, h, 1)
+                                && mipmap_level > 0
+                                && mipmap_level < t.get_num_mipmap_levels() =>
+                        {
+                            level_image(t, mipmap_level, srgb)?
+                        }
+                        _ if mipmap_level == 0 => images[i].clone(),
+                        _ => imageops::resize(&images[i], w, h, self.filter),
+                    };
+                    place(
+                        &mut canvas,
+                        level_width,
+                        &image,
+                        entry.x >> mipmap_level,
+                        entry.y >> mipmap_level,
+                        padding >> mipmap_level,
+                    );
+                }
+                if srgb {
+                    for texel in canvas.chunks_exact_mut(4) {
+                        for c in &mut texel[..3] {
+                            *c = linear_to_srgb(*c);
+                        }
+                    }
+                }
+                texture.encode_rgba32f(array_layer, mipmap_level, &canvas)?;
+
+                // Copy the blocks of compressed sources over what was encoded
+                for (i, entry) in on_page {
+                    let source = match textures[i] {
+                        Some(ref t) => t,
+                        None => continue,
+                    };
+                    if let Some(region) = block_region(layout, source, entry, mipmap_level) {
+                        texture.copy_blocks(
+                            source,
+                            &region,
+                            array_layer,
+                            mipmap_level,
+                            (entry.x >> mipmap_level) / 4,
+                            (entry.y >> mipmap_level) / 4,
+                        )?;
+                    }
+                }
+            }
+        }
+
+        Ok(Atlas {
+            textures: dds,
+            entries,
+            width,
+            height,
+        })
+    }
+}
+
+impl Atlas {
+    /// The entry with the given name, if there is one
+    pub fn get_entry(&self, name: &str) -> Option<&AtlasEntry> {
+        self.entries.iter().find(|e| e.name == name)
+    }
+
+    /// A JSON manifest of the page size and of where every entry was placed
+    pub fn to_json(&self) -> String {
+        let mut s = String::new();
+        let _ = writeln!(s, "{{");
+        let _ = writeln!(s, "  \"width\": {},", self.width);
+        let _ = writeln!(s, "  \"height\": {},", self.height);
+        let _ = writeln!(s, "  \"pages\": {},", self.pages());
+        let _ = writeln!(s, "  \"entries\": [");
+        for (i, e) in self.entries.iter().enumerate() {
+            let _ = writeln!(
+                s,
+                "    {{\"name\": {}, \"page\": {}, \"x\": {}, \"y\": {}, \"width\": {}, \
+                 \"height\": {}, \"uv\": [{:?}, {:?}, {:?}, {:?}]}}{}",
+                quote(&e.name, true),
+                e.page,
+                e.x,
+                e.y,
+                e.width,
+                e.height,
+                e.uv[0],
+                e.uv[1],
+                e.uv[2],
+                e.uv[3],
+                if i + 1 < self.entries.len() { "," } else { "" }
+            );
+        }
+        let _ = writeln!(s, "  ]");
+        let _ = writeln!(s, "}}");
+        s
+    }
+
+    /// A RON manifest with the same contents as `to_json`
+    pub fn to_ron(&self) -> String {
+        let mut s = String::new();
+        let _ = writeln!(s, "(");
+        let _ = writeln!(s, "    width: {},", self.width);
+        let _ = writeln!(s, "    height: {},", self.height);
+        let _ = writeln!(s, "    pages: {},", self.pages());
+        let _ = writeln!(s, "    entries: [");
+        for e in &self.entries {
+            let _ = writeln!(
+                s,
+                "        (name: {}, page: {}, x: {}, y: {}, width: {}, height: {}, \
+                 uv: ({:?}, {:?}, {:?}, {:?})),",
+                quote(&e.name, false),
+                e.page,
+                e.x,
+                e.y,
+                e.width,
+                e.height,
+                e.uv[0],
+                e.uv[1],
+                e.uv[2],
+                e.uv[3]
+            );
+        }
+        let _ = writeln!(s, "    ],");
+        let _ = writeln!(s, ")");
+        s
+    }
+
+    fn pages(&self) -> u32 {
+        self.entries.iter().map(|e| e.page + 1).max().unwrap_or(0)
+    }
+}
+
+// Pack cells onto shelves, tallest first, returning the page, left and top of
+// each cell and the number of pages
+fn pack(cells: &[(u32, u32)], width: u32, height: u32) -> (Vec<(u32, u32, u32)>, u32) {
+    struct Shelf {
+        page: u32,
+        top: u32,
+        height: u32,
+        used: u32,
+    }
+    let mut order: Vec<usize> = (0..cells.len()).collect();
+    order.sort_by_key(|&i| (u32::MAX - cells[i].1, u32::MAX - cells[i].0));
+
+    let mut shelves: Vec<Shelf> = Vec::new();
+    let mut page_heights: Vec<u32> = Vec::new();
+    let mut places = vec![(0, 0, 0); cells.len()];
+    for i in order {
+        let (w, h) = cells[i];
+        if let Some(shelf) = shelves
+            .iter_mut()
+            .find(|s| s.height >= h && s.used + w <= width)
+        {
+            places[i] = (shelf.page, shelf.used, shelf.top);
+            shelf.used += w;
+            continue;
+        }
+        let page = match page_heights.iter().position(|&used| used + h <= height) {
+            Some(page) => page,
+            None => {
+                page_heights.push(0);
+                page_heights.len() - 1
+            }
+        };
+        let top = page_heights[page];
+        page_heights[page] += h;
+        shelves.push(Shelf {
+            page: page as u32,
+            top,
+            height: h,
+            used: w,
+        });
+        places[i] = (page as u32, 0, top);
+    }
+    (places, page_heights.len() as u32)
+}
+
+// Decode a mipmap level of the first layer of a texture, linear for sRGB
+fn level_image(texture: &Dds, mipmap_level: u32, srgb: bool) -> Result<Rgba32FImage, Error> {
+    let (width, height, _) = texture.get_mipmap_dimensions(mipmap_level);
+    let mut rgba = texture.decode_rgba32f(0, mipmap_level)?;
+    rgba.truncate(width as usize * height as usize * 4);
+    if srgb {
+        linearize(&mut rgba);
+    }
+    match Rgba32FImage::from_raw(width, height, rgba) {
+        Some(image) => Ok(image),
+        None => Err(Error::OutOfBounds),
+    }
+}
+
+fn linearize(rgba: &mut [f32]) {
+    for texel in rgba.chunks_exact_mut(4) {
+        for c in &mut texel[..3] {
+            *c = srgb_to_linear(*c);
+        }
+    }
+}
+
+// Copy an image into a canvas with its top left texel at x, y, extending its
+// edge texels outwards across the gutter
+fn place(canvas: &mut [f32], canvas_width: u32, image: &Rgba32FImage, x: u32, y: u32, gutter: u32) {
+    let (w, h) = image.dimensions();
+    let canvas_height = (canvas.len() / 4) as u32 / canvas_width;
+    for cy in y.saturating_sub(gutter)..(y + h + gutter).min(canvas_height) {
+        for cx in x.saturating_sub(gutter)..(x + w + gutter).min(canvas_width) {
+            let texel = image.get_pixel(cx.clamp(x, x + w - 1) - x, cy.clamp(y, y + h - 1) - y);
+            let offset = (cy as usize * canvas_width as usize + cx as usize) * 4;
+            canvas[offset..offset + 4].copy_from_slice(&texel.0);
+        }
+    }
+}
+
+// The blocks of a source texture that can be copied into an atlas as they are,
+// when it stores the same kind of blocks and the entry covers whole blocks at
+// this mipmap level
+fn block_region(
+    layout: Layout,
+    source: &Dds,
+    entry: &AtlasEntry,
+    mipmap_level: u32,
+) -> Option<BlockRegion> {
+    if !compatible_blocks(layout, source) || mipmap_level >= source.get_num_mipmap_levels() {
+        return None;
+    }
+    let (w, h, _) = source.get_mipmap_dimensions(mipmap_level);
+    let expected = (
+        (entry.width >> mipmap_level).max(1),
+        (entry.height >> mipmap_level).max(1),
+    );
+    let aligned = [w, h, entry.x >> mipmap_level, entry.y >> mipmap_level]
+        .iter()
+        .all(|v| v.is_multiple_of(4));
+    if (w, h) != expected || !aligned {
+        return None;
+    }
+    Some(BlockRegion {
+        array_layer: 0,
+        mipmap_level,
+        x: 0,
+        y: 0,
+        width: w / 4,
+        height: h / 4,
+    })
+}
+
+// Whether a texture stores the same kind of compressed blocks as an atlas
+fn compatible_blocks(layout: Layout, texture: &Dds) -> bool {
+    match Layout::of(texture) {
+        Some(source) => {
+            layout.block_size().is_some()
+                && mem::discriminant(&layout) == mem::discriminant(&source)
+        }
+        None => false,
+    }
+}
+
+// A string literal, escaped for JSON or for RON
+fn quote(s: &str, json: bool) -> String {
+    let mut quoted = String::with_capacity(s.len() + 2);
+    quoted.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c if (c as u32) < 0x20 && json => {
+                let _ = write!(quoted, "\\u{:04x}", c as u32);
+            }
+            c if (c as u32) < 0x20 => {
+                let _ = write!(quoted, "\\u{{{:x}}}", c as u32);
+            }
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
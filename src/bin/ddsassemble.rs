@@ -0,0 +1,108 @@
+// The MIT License (MIT)
+//
+// Copyright (c) 2018 Michael Dilger
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+extern crate ddsfile;
+use ddsfile::*;
+
+use std::env;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Write};
+use std::process;
+
+const USAGE: &str = "\
+Usage: ddsassemble [--array | --cubemap | --volume] -o <output.dds> <input.dds>...
+
+Assembles several 2D DDS files of the same format, dimensions and number of
+mipmap levels into one texture.
+
+Options:
+  --array             one array layer per input (the default)
+  --cubemap           cubemap faces in the order +X, -X, +Y, -Y, +Z, -Z, with
+                      a multiple of six inputs making an array of cubemaps
+  --volume            one depth slice per input (inputs without mipmaps)
+  -o, --output <file> the DDS file to write";
+
+fn main() {
+    let mut assembly = Assembly::Array;
+    let mut output = None;
+    let mut inputs = Vec::new();
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match &*arg {
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                process::exit(0);
+            }
+            "--array" => assembly = Assembly::Array,
+            "--cubemap" => assembly = Assembly::Cubemap,
+            "--volume" => assembly = Assembly::Volume,
+            "-o" | "--output" => match args.next() {
+                Some(v) => output = Some(v),
+                None => usage_error(&format!("{} needs a value", arg)),
+            },
+            _ if arg.starts_with('-') => usage_error(&format!("unknown option {}", arg)),
+            _ => inputs.push(arg),
+        }
+    }
+    let output = match output {
+        Some(output) => output,
+        None => usage_error("no output file given"),
+    };
+    if inputs.is_empty() {
+        usage_error("no input files given");
+    }
+
+    let mut parts = Vec::new();
+    for input in &inputs {
+        match File::open(input)
+            .map_err(Error::from)
+            .and_then(|f| Dds::read(BufReader::new(f)))
+        {
+            Ok(dds) => parts.push(dds),
+            Err(e) => fail(&format!("{}: {}", input, e)),
+        }
+    }
+
+    let dds = match Dds::assemble(&parts, assembly) {
+        Ok(dds) => dds,
+        Err(e) => fail(&e),
+    };
+    let result = File::create(&output).map_err(Error::from).and_then(|f| {
+        let mut w = BufWriter::new(f);
+        dds.write(&mut w)?;
+        w.flush()?;
+        Ok(())
+    });
+    if let Err(e) = result {
+        fail(&format!("{}: {}", output, e));
+    }
+}
+
+fn fail(message: &dyn std::fmt::Display) -> ! {
+    eprintln!("ddsassemble: {}", message);
+    process::exit(1);
+}
+
+fn usage_error(message: &str) -> ! {
+    eprintln!("ddsassemble: {}\n\n{}", message, USAGE);
+    process::exit(2);
+}
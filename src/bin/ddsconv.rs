@@ -0,0 +1,663 @@
+// The MIT License (MIT)
+//
+// Copyright (c) 2018 Michael Dilger
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+extern crate ddsfile;
+use ddsfile::*;
+use image::imageops::{self, FilterType};
+use image::DynamicImage;
+
+use std::env;
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+
+const USAGE: &str = "\
+Usage: ddsconv [options] <file or directory>...
+
+Converts textures between DDS formats, and to and from KTX, KTX2, PNG, TGA,
+Radiance HDR and OpenEXR files. The options follow texconv where they overlap.
+
+Options:
+  -f <format>     output DxgiFormat (default: the input format)
+  -ft <type>      output file type: dds, ktx, ktx2, png, tga, hdr or exr
+                  (default: dds)
+  -m <levels>     number of mipmap levels to generate, 0 for a full chain
+                  (default: as many as the input has, or a full chain for
+                  images)
+  -w <width>      resize to this width
+  -h <height>     resize to this height
+  -if <filter>    resize and mipmap filter: point, linear, cubic, box,
+                  gaussian or lanczos (default: linear)
+  -hflip          flip horizontally
+  -vflip          flip vertically
+  -rotate <deg>   rotate clockwise by 90, 180 or 270 degrees
+  -dx10           write a DX10 header even when the format has a legacy one
+  -o <dir>        output directory (default: next to each input)
+  -sx <suffix>    add a suffix to output file names
+  -y              overwrite existing output files
+  -r              search directories recursively
+  -j <jobs>       number of files to convert at once (default: CPU count)
+  -nologo         accepted for texconv compatibility";
+
+const INPUT_EXTENSIONS: &[&str] = &["dds", "ktx", "ktx2", "png", "tga", "hdr", "exr"];
+
+#[derive(Clone, Copy, PartialEq)]
+enum FileType {
+    Dds,
+    Ktx,
+    Ktx2,
+    Image(ExportFormat),
+}
+
+impl FileType {
+    fn from_extension(extension: &str) -> Option<FileType> {
+        match &*extension.to_ascii_lowercase() {
+            "dds" => Some(FileType::Dds),
+            "ktx" => Some(FileType::Ktx),
+            "ktx2" => Some(FileType::Ktx2),
+            e => ExportFormat::from_extension(e).map(FileType::Image),
+        }
+    }
+
+    fn extension(&self) -> &'static str {
+        match *self {
+            FileType::Dds => "dds",
+            FileType::Ktx => "ktx",
+            FileType::Ktx2 => "ktx2",
+            FileType::Image(format) => format.extension(),
+        }
+    }
+}
+
+struct Options {
+    format: Option<DxgiFormat>,
+    file_type: FileType,
+    mipmaps: Option<u32>,
+    width: Option<u32>,
+    height: Option<u32>,
+    filter: FilterType,
+    hflip: bool,
+    vflip: bool,
+    rotate: u32,
+    dx10: bool,
+    output_dir: Option<PathBuf>,
+    suffix: String,
+    overwrite: bool,
+    recursive: bool,
+    jobs: usize,
+}
+
+impl Options {
+    // Whether the texels have to be decoded and encoded again
+    fn reencodes(&self) -> bool {
+        self.format.is_some()
+            || self.mipmaps.is_some()
+            || self.width.is_some()
+            || self.height.is_some()
+            || self.hflip
+            || self.vflip
+            || self.rotate != 0
+    }
+
+    // Whether flipping and rotating is all there is to do to a texture, which
+    // can then be done without decoding most formats
+    fn only_reorients(&self, dds: &Dds) -> bool {
+        self.mipmaps.is_none()
+            && self.width.is_none()
+            && self.height.is_none()
+            && (self.format.is_none() || self.format == source_format(dds))
+    }
+}
+
+fn main() {
+    let (options, inputs) = parse_args();
+
+    let mut files = Vec::new();
+    for input in &inputs {
+        let path = Path::new(input);
+        if path.is_dir() {
+            if let Err(e) = collect(path, options.recursive, &mut files) {
+                eprintln!("ddsconv: {}: {}", input, e);
+                process::exit(1);
+            }
+        } else {
+            files.push(path.to_path_buf());
+        }
+    }
+
+    // Convert on a pool of threads, each taking the next file in turn
+    let next = AtomicUsize::new(0);
+    let failed = AtomicBool::new(false);
+    let stdout = Mutex::new(());
+    thread::scope(|scope| {
+        for _ in 0..options.jobs.min(files.len()).max(1) {
+            scope.spawn(|| loop {
+                let i = next.fetch_add(1, Ordering::Relaxed);
+                let input = match files.get(i) {
+                    Some(input) => input,
+                    None => break,
+                };
+                let result = convert(input, &options);
+                let _lock = stdout.lock();
+                match result {
+                    Ok(output) => println!("{} -> {}", input.display(), output.display()),
+                    Err(e) => {
+                        eprintln!("ddsconv: {}: {}", input.display(), e);
+                        failed.store(true, Ordering::Relaxed);
+                    }
+                }
+            });
+        }
+    });
+
+    if failed.load(Ordering::Relaxed) {
+        process::exit(1);
+    }
+}
+
+fn usage_error(message: &str) -> ! {
+    eprintln!("ddsconv: {}\n\n{}", message, USAGE);
+    process::exit(2);
+}
+
+fn parse_args() -> (Options, Vec<String>) {
+    let mut options = Options {
+        format: None,
+        file_type: FileType::Dds,
+        mipmaps: None,
+        width: None,
+        height: None,
+        filter: FilterType::Triangle,
+        hflip: false,
+        vflip: false,
+        rotate: 0,
+        dx10: false,
+        output_dir: None,
+        suffix: String::new(),
+        overwrite: false,
+        recursive: false,
+        jobs: thread::available_parallelism().map_or(1, |n| n.get()),
+    };
+    let mut inputs = Vec::new();
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || match args.next() {
+            Some(v) => v,
+            None => usage_error(&format!("{} needs a value", arg)),
+        };
+        match &*arg.to_ascii_lowercase() {
+            "-?" | "--help" => {
+                println!("{}", USAGE);
+                process::exit(0);
+            }
+            "-f" => {
+                let v = value();
+                options.format = match v.parse() {
+                    Ok(f) => Some(f),
+                    Err(_) => usage_error(&format!("unknown format {}", v)),
+                }
+            }
+            "-ft" => {
+                let v = value();
+                options.file_type = match FileType::from_extension(&v) {
+                    Some(t) => t,
+                    None => usage_error(&format!("unknown file type {}", v)),
+                }
+            }
+            "-m" => options.mipmaps = Some(parse_number(&value())),
+            "-w" => options.width = Some(parse_number(&value())),
+            "-h" => options.height = Some(parse_number(&value())),
+            "-if" => options.filter = parse_filter(&value()),
+            "-hflip" => options.hflip = true,
+            "-vflip" => options.vflip = true,
+            "-rotate" => {
+                options.rotate = match parse_number(&value()) {
+                    r @ (0 | 90 | 180 | 270) => r,
+                    _ => usage_error("-rotate takes 90, 180 or 270"),
+                }
+            }
+            "-dx10" => options.dx10 = true,
+            "-o" => options.output_dir = Some(PathBuf::from(value())),
+            "-sx" => options.suffix = value(),
+            "-y" => options.overwrite = true,
+            "-r" => options.recursive = true,
+            "-j" => options.jobs = parse_number(&value()).max(1) as usize,
+            "-nologo" => {}
+            _ if arg.starts_with('-') => usage_error(&format!("unknown option {}", arg)),
+            _ => inputs.push(arg),
+        }
+    }
+    if inputs.is_empty() {
+        usage_error("no input files");
+    }
+    (options, inputs)
+}
+
+fn parse_number(s: &str) -> u32 {
+    match s.parse() {
+        Ok(n) => n,
+        Err(_) => usage_error(&format!("expected a number, not {}", s)),
+    }
+}
+
+fn parse_filter(s: &str) -> FilterType {
+    match &*s.to_ascii_lowercase() {
+        "point" => FilterType::Nearest,
+        "linear" | "box" | "fant" => FilterType::Triangle,
+        "cubic" => FilterType::CatmullRom,
+        "gaussian" => FilterType::Gaussian,
+        "lanczos" => FilterType::Lanczos3,
+        _ => usage_error(&format!("unknown filter {}", s)),
+    }
+}
+
+// The convertible files in a directory
+fn collect(dir: &Path, recursive: bool, files: &mut Vec<PathBuf>) -> Result<(), Error> {
+    let mut entries = fs::read_dir(dir)?
+        .map(|e| e.map(|e| e.path()))
+        .collect::<Result<Vec<_>, _>>()?;
+    entries.sort();
+    for path in entries {
+        if path.is_dir() {
+            if recursive {
+                collect(&path, recursive, files)?;
+            }
+        } else if path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(|e| INPUT_EXTENSIONS.contains(&&*e.to_ascii_lowercase()))
+            .unwrap_or(false)
+        {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+fn output_path(input: &Path, options: &Options) -> PathBuf {
+    let dir = match options.output_dir {
+        Some(ref dir) => dir.clone(),
+        None => input.parent().map(Path::to_path_buf).unwrap_or_default(),
+    };
+    let stem = input.file_stem().unwrap_or_default().to_string_lossy();
+    dir.join(format!(
+        "{}{}.{}",
+        stem,
+        options.suffix,
+        options.file_type.extension()
+    ))
+}
+
+// A texture as read from disk: either a container, or a plain image
+enum Source {
+    Texture(Dds),
+    Image(DynamicImage),
+}
+
+fn read(path: &Path) -> Result<Source, Error> {
+    let file_type = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .and_then(FileType::from_extension);
+    let r = || File::open(path).map(BufReader::new);
+    Ok(match file_type {
+        Some(FileType::Dds) => Source::Texture(Dds::read(r()?)?),
+        Some(FileType::Ktx) => Source::Texture(Dds::read_ktx1(r()?)?),
+        Some(FileType::Ktx2) => Source::Texture(Dds::read_ktx2(r()?)?),
+        _ => Source::Image(image::open(path)?),
+    })
+}
+
+fn convert(input: &Path, options: &Options) -> Result<PathBuf, Error> {
+    let output = output_path(input, options);
+    if output.exists() && !options.overwrite {
+        return Err(Error::Io(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("{} already exists (use -y to overwrite)", output.display()),
+        )));
+    }
+
+    let mut dds = match read(input)? {
+        Source::Texture(dds) if !options.reencodes() => dds,
+        Source::Texture(dds) if options.only_reorients(&dds) => reorient(dds, options)?,
+        Source::Texture(dds) => rebuild(&dds, options)?,
+        Source::Image(image) => build(image, options)?,
+    };
+    if options.dx10 {
+        dds.upgrade_to_dx10()?;
+    }
+
+    let mut w = BufWriter::new(File::create(&output)?);
+    match options.file_type {
+        FileType::Dds => dds.write(&mut w)?,
+        FileType::Ktx => dds.write_ktx1(&mut w)?,
+        FileType::Ktx2 => dds.write_ktx2(&mut w)?,
+        FileType::Image(format) => format.write(&dds.to_image(0, 0)?, &mut w)?,
+    }
+    w.flush()?;
+    Ok(output)
+}
+
+// Apply the flips, rotation and resize to one image
+fn transform(mut image: DynamicImage, options: &Options) -> DynamicImage {
+    if options.hflip {
+        image = image.fliph();
+    }
+    if options.vflip {
+        image = image.flipv();
+    }
+    image = match options.rotate {
+        90 => image.rotate90(),
+        180 => image.rotate180(),
+        270 => image.rotate270(),
+        _ => image,
+    };
+    if options.width.is_some() || options.height.is_some() {
+        let width = options.width.unwrap_or(image.width());
+        let height = options.height.unwrap_or(image.height());
+        image = DynamicImage::ImageRgba32F(imageops::resize(
+            &image.to_rgba32f(),
+            width,
+            height,
+            options.filter,
+        ));
+    }
+    image
+}
+
+fn mipmaps(levels: u32) -> Mipmaps {
+    match levels {
+        0 => Mipmaps::Full,
+        1 => Mipmaps::None,
+        n => Mipmaps::Levels(n),
+    }
+}
+
+fn build(image: DynamicImage, options: &Options) -> Result<Dds, Error> {
+    let format = options.format.unwrap_or(match image {
+        DynamicImage::ImageRgb32F(_) | DynamicImage::ImageRgba32F(_) => {
+            DxgiFormat::R32G32B32A32_Float
+        }
+        DynamicImage::ImageLuma16(_)
+        | DynamicImage::ImageLumaA16(_)
+        | DynamicImage::ImageRgb16(_)
+        | DynamicImage::ImageRgba16(_) => DxgiFormat::R16G16B16A16_UNorm,
+        _ => DxgiFormat::R8G8B8A8_UNorm,
+    });
+    TextureBuilder::new(format)
+        .image(transform(image, options))
+        .mipmaps(mipmaps(options.mipmaps.unwrap_or(0)))
+        .filter(options.filter)
+        .build()
+}
+
+// The format a texture is stored in, as a DxgiFormat
+fn source_format(dds: &Dds) -> Option<DxgiFormat> {
+    dds.header10
+        .as_ref()
+        .map(|h10| h10.dxgi_format)
+        .or_else(|| dds.get_d3d_format().and_then(|f| f.to_dxgi_format()))
+        .or_else(|| dds.get_dxgi_format())
+}
+
+// Flip and rotate every subresource of a texture in place of rebuilding it,
+// in the same order as transform()
+fn reorient(mut dds: Dds, options: &Options) -> Result<Dds, Error> {
+    if options.hflip {
+        dds = dds.flip_horizontal()?;
+    }
+    if options.vflip {
+        dds = dds.flip_vertical()?;
+    }
+    let rotation = match options.rotate {
+        90 => Some(Rotation::Rotate90),
+        180 => Some(Rotation::Rotate180),
+        270 => Some(Rotation::Rotate270),
+        _ => None,
+    };
+    if let Some(rotation) = rotation {
+        dds = dds.rotate(rotation)?;
+    }
+    Ok(dds)
+}
+
+// Decode the main image of every layer (or every slice of a volume), and
+// build the texture again from them
+fn rebuild(dds: &Dds, options: &Options) -> Result<Dds, Error> {
+    let format = match options.format.or_else(|| source_format(dds)) {
+        Some(f) => f,
+        None => return Err(Error::UnsupportedFormat),
+    };
+    // Decoded texels come out as stored, so premultiplied ones are not
+    // premultiplied again
+    let alpha_mode = dds.get_alpha_mode();
+
+    let mut images = Vec::new();
+    for array_layer in 0..dds.get_num_array_layers() {
+        let image = dds.to_image(array_layer, 0)?;
+        if dds.is_volume() {
+            let (width, height) = (dds.get_width(), dds.get_height());
+            for slice in 0..dds.get_depth() {
+                images.push(image.crop_imm(0, slice * height, width, height));
+            }
+        } else {
+            images.push(image);
+        }
+    }
+
+    TextureBuilder::new(format)
+        .images(images.into_iter().map(|i| transform(i, options)))
+        .mipmaps(mipmaps(
+            options.mipmaps.unwrap_or(dds.get_num_mipmap_levels()),
+        ))
+        .filter(options.filter)
+        .cubemap(dds.is_cubemap())
+        .volume(dds.is_volume())
+        .alpha_mode(alpha_mode)
+        .premultiplied_sources(alpha_mode == AlphaMode::PreMultiplied)
+        .build()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::RgbaImage;
+
+    fn options() -> Options {
+        Options {
+            format: None,
+            file_type: FileType::Dds,
+            mipmaps: None,
+            width: None,
+            height: None,
+            filter: FilterType::Triangle,
+            hflip: false,
+            vflip: false,
+            rotate: 0,
+            dx10: false,
+            output_dir: None,
+            suffix: String::new(),
+            overwrite: false,
+            recursive: false,
+            jobs: 1,
+        }
+    }
+
+    #[test]
+    fn file_types() {
+        for extension in INPUT_EXTENSIONS {
+            let file_type = FileType::from_extension(extension).unwrap();
+            assert_eq!(file_type.extension(), *extension);
+        }
+        assert!(FileType::from_extension("KTX2") == Some(FileType::Ktx2));
+        assert!(FileType::from_extension("jpg").is_none());
+    }
+
+    #[test]
+    fn output_paths() {
+        let mut options = options();
+        let input = Path::new("textures").join("wall.png");
+        assert_eq!(
+            output_path(&input, &options),
+            Path::new("textures").join("wall.dds")
+        );
+
+        options.file_type = FileType::Image(ExportFormat::Tga);
+        options.suffix = "_small".to_owned();
+        options.output_dir = Some(PathBuf::from("out"));
+        assert_eq!(
+            output_path(&input, &options),
+            Path::new("out").join("wall_small.tga")
+        );
+    }
+
+    #[test]
+    fn flips_rotate_and_resize() {
+        // A 2x1 image of a red and a green texel
+        let mut image = RgbaImage::new(2, 1);
+        image.put_pixel(0, 0, image::Rgba([255, 0, 0, 255]));
+        image.put_pixel(1, 0, image::Rgba([0, 255, 0, 255]));
+        let image = DynamicImage::ImageRgba8(image);
+
+        let mut options = options();
+        options.hflip = true;
+        options.rotate = 90;
+        let rotated = transform(image.clone(), &options).to_rgba8();
+        assert_eq!(rotated.dimensions(), (1, 2));
+        assert_eq!(rotated.get_pixel(0, 0).0, [0, 255, 0, 255]);
+        assert_eq!(rotated.get_pixel(0, 1).0, [255, 0, 0, 255]);
+
+        let mut options = self::options();
+        options.width = Some(4);
+        let resized = transform(image, &options);
+        assert_eq!((resized.width(), resized.height()), (4, 1));
+    }
+
+    #[test]
+    fn reorient_without_reencoding() {
+        let mut dds = Dds::new_d3d(NewD3dParams {
+            height: 8,
+            width: 4,
+            depth: None,
+            format: D3DFormat::DXT1,
+            mipmap_levels: Some(2),
+            caps2: None,
+        })
+        .unwrap();
+        for (i, byte) in dds.data.iter_mut().enumerate() {
+            *byte = i as u8;
+        }
+
+        let mut options = options();
+        options.hflip = true;
+        options.rotate = 90;
+        assert!(options.only_reorients(&dds));
+        let expected = dds
+            .flip_horizontal()
+            .unwrap()
+            .rotate(Rotation::Rotate90)
+            .unwrap();
+        let reoriented = reorient(dds.clone(), &options).unwrap();
+        assert_eq!(reoriented.get_d3d_format(), Some(D3DFormat::DXT1));
+        assert_eq!((reoriented.get_width(), reoriented.get_height()), (8, 4));
+        assert_eq!(reoriented.data, expected.data);
+
+        options.format = Some(DxgiFormat::BC1_UNorm);
+        assert!(options.only_reorients(&dds));
+        options.format = Some(DxgiFormat::BC3_UNorm);
+        assert!(!options.only_reorients(&dds));
+        options.format = None;
+        options.width = Some(2);
+        assert!(!options.only_reorients(&dds));
+    }
+
+    #[test]
+    fn rebuild_keeps_the_shape() {
+        let mut cubemap = Dds::new_dxgi(NewDxgiParams {
+            height: 8,
+            width: 8,
+            depth: None,
+            format: DxgiFormat::R8G8B8A8_UNorm,
+            mipmap_levels: Some(4),
+            array_layers: Some(6),
+            caps2: None,
+            is_cubemap: true,
+            resource_dimension: D3D10ResourceDimension::Texture2D,
+            alpha_mode: AlphaMode::PreMultiplied,
+        })
+        .unwrap();
+        for face in 0..6 {
+            let value = face as u8 * 40;
+            for texel in cubemap
+                .get_mut_mipmap_data(face, 0)
+                .unwrap()
+                .chunks_exact_mut(4)
+            {
+                texel.copy_from_slice(&[value, value, value, 255]);
+            }
+        }
+
+        let mut options = options();
+        options.format = Some(DxgiFormat::BC1_UNorm);
+        let rebuilt = rebuild(&cubemap, &options).unwrap();
+        assert_eq!(rebuilt.get_dxgi_format(), Some(DxgiFormat::BC1_UNorm));
+        assert!(rebuilt.is_cubemap());
+        assert_eq!(rebuilt.get_num_array_layers(), 6);
+        assert_eq!(rebuilt.get_num_mipmap_levels(), 4);
+        assert_eq!(rebuilt.get_alpha_mode(), AlphaMode::PreMultiplied);
+        // Each face keeps its own flat color
+        for face in 0..6 {
+            let texel = &rebuilt.decode_rgba32f(face, 0).unwrap()[..4];
+            let expected = (face * 40) as f32 / 255.0;
+            assert!((texel[0] - expected).abs() < 0.02, "{:?}", texel);
+        }
+    }
+
+    #[test]
+    fn rebuild_keeps_premultiplied_alpha() {
+        // DXT4 declares premultiplied alpha without a DX10 header
+        let mut dds = Dds::new_d3d(NewD3dParams {
+            height: 4,
+            width: 4,
+            depth: None,
+            format: D3DFormat::DXT4,
+            mipmap_levels: None,
+            caps2: None,
+        })
+        .unwrap();
+        let texel = [0.25, 0.25, 0.25, 0.5];
+        dds.encode_rgba32f(0, 0, &texel.repeat(16)).unwrap();
+
+        let mut options = options();
+        options.format = Some(DxgiFormat::R8G8B8A8_UNorm);
+        let rebuilt = rebuild(&dds, &options).unwrap();
+        assert_eq!(rebuilt.get_alpha_mode(), AlphaMode::PreMultiplied);
+        let rebuilt_texel = &rebuilt.decode_rgba32f(0, 0).unwrap()[..4];
+        for (found, expected) in rebuilt_texel.iter().zip(texel) {
+            assert!((found - expected).abs() < 0.02, "{:?}", rebuilt_texel);
+        }
+    }
+}
@@ -0,0 +1,240 @@
+// The MIT License (MIT)
+//
+// Copyright (c) 2018 Michael Dilger
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+extern crate ddsfile;
+use ddsfile::*;
+
+use std::env;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Write};
+use std::path::Path;
+use std::process;
+
+const USAGE: &str = "\
+Usage: ddsdiff [options] <left.dds> <right.dds>
+
+Compares two DDS files field by field and texel by texel. Exits with 0 when
+they match (or are within every threshold given), 1 when they differ, and 2
+on errors.
+
+Options:
+  --psnr <dB>         fail if any subresource has a lower PSNR
+  --ssim <value>      fail if any subresource has a lower SSIM
+  --max-error <value> fail if any channel differs by more than this
+  --ignore-headers    do not fail because of header differences
+  --diff <file>       write the absolute differences to a texture (.dds, or
+                      .png/.tga/.hdr/.exr with the export feature)
+  --scale <factor>    multiply the differences written by --diff (default 1)";
+
+struct Options {
+    left: String,
+    right: String,
+    psnr: Option<f64>,
+    ssim: Option<f64>,
+    max_error: Option<f32>,
+    ignore_headers: bool,
+    diff: Option<String>,
+    scale: f32,
+}
+
+fn main() {
+    let options = parse_args();
+    let left = read(&options.left);
+    let right = read(&options.right);
+
+    let comparison = match left.compare(&right) {
+        Ok(c) => c,
+        Err(e) => fail(&e),
+    };
+    report(&comparison);
+
+    if let Some(ref path) = options.diff {
+        if let Err(e) = left
+            .difference(&right, options.scale)
+            .and_then(|diff| write_diff(&diff, Path::new(path)))
+        {
+            fail(&format!("{}: {}", path, e));
+        }
+    }
+
+    let thresholds =
+        options.psnr.is_some() || options.ssim.is_some() || options.max_error.is_some();
+    let mut pass = true;
+    if !options.ignore_headers && !comparison.header_differences.is_empty() {
+        pass = false;
+    }
+    // Textures with no subresources in common differ, even when their headers
+    // are not compared
+    let has_subresources =
+        |dds: &Dds| dds.get_num_array_layers() > 0 && dds.get_num_mipmap_levels() > 0;
+    if comparison.subresources.is_empty() && (has_subresources(&left) || has_subresources(&right)) {
+        pass = false;
+    }
+    if thresholds {
+        if let Some(psnr) = options.psnr {
+            pass &= comparison.min_psnr() >= psnr;
+        }
+        if let Some(ssim) = options.ssim {
+            pass &= comparison.min_ssim() >= ssim;
+        }
+        if let Some(max_error) = options.max_error {
+            pass &= comparison.max_abs_error() <= max_error;
+        }
+    } else {
+        pass &= comparison.max_abs_error() == 0.0;
+    }
+
+    if !pass {
+        process::exit(1);
+    }
+}
+
+fn fail(message: &dyn std::fmt::Display) -> ! {
+    eprintln!("ddsdiff: {}", message);
+    process::exit(2);
+}
+
+fn usage_error(message: &str) -> ! {
+    eprintln!("ddsdiff: {}\n\n{}", message, USAGE);
+    process::exit(2);
+}
+
+fn parse_args() -> Options {
+    let mut positional = Vec::new();
+    let mut options = Options {
+        left: String::new(),
+        right: String::new(),
+        psnr: None,
+        ssim: None,
+        max_error: None,
+        ignore_headers: false,
+        diff: None,
+        scale: 1.0,
+    };
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || match args.next() {
+            Some(v) => v,
+            None => usage_error(&format!("{} needs a value", arg)),
+        };
+        match &*arg {
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                process::exit(0);
+            }
+            "--psnr" => options.psnr = Some(parse_number(&value())),
+            "--ssim" => options.ssim = Some(parse_number(&value())),
+            "--max-error" => options.max_error = Some(parse_number(&value()) as f32),
+            "--ignore-headers" => options.ignore_headers = true,
+            "--diff" => options.diff = Some(value()),
+            "--scale" => options.scale = parse_number(&value()) as f32,
+            _ if arg.starts_with('-') => usage_error(&format!("unknown option {}", arg)),
+            _ => positional.push(arg),
+        }
+    }
+    if positional.len() != 2 {
+        usage_error("expected two files");
+    }
+    options.right = positional.pop().unwrap_or_default();
+    options.left = positional.pop().unwrap_or_default();
+    options
+}
+
+fn parse_number(s: &str) -> f64 {
+    match s.parse() {
+        Ok(n) => n,
+        Err(_) => usage_error(&format!("expected a number, not {}", s)),
+    }
+}
+
+fn read(filename: &str) -> Dds {
+    match File::open(filename)
+        .map_err(Error::from)
+        .and_then(|f| Dds::read(BufReader::new(f)))
+    {
+        Ok(dds) => dds,
+        Err(e) => fail(&format!("{}: {}", filename, e)),
+    }
+}
+
+fn report(comparison: &Comparison) {
+    if comparison.header_differences.is_empty() {
+        println!("Headers match");
+    } else {
+        println!("Header differences:");
+        for d in &comparison.header_differences {
+            println!("  {}", d);
+        }
+    }
+
+    if comparison.subresources.is_empty() {
+        println!("\nNo subresources in common");
+        return;
+    }
+
+    println!(
+        "\n{:>5} {:>4} {:>10} {:>8} {:>9}  RMSE (R G B A)",
+        "layer", "mip", "PSNR (dB)", "SSIM", "max error"
+    );
+    for s in &comparison.subresources {
+        println!(
+            "{:>5} {:>4} {:>10.2} {:>8.5} {:>9.5}  {:.5} {:.5} {:.5} {:.5}",
+            s.array_layer,
+            s.mipmap_level,
+            s.psnr,
+            s.ssim,
+            s.max_abs_error,
+            s.channel_rmse[0],
+            s.channel_rmse[1],
+            s.channel_rmse[2],
+            s.channel_rmse[3]
+        );
+    }
+    println!(
+        "\nMin PSNR {:.2} dB, min SSIM {:.5}, max error {:.5}",
+        comparison.min_psnr(),
+        comparison.min_ssim(),
+        comparison.max_abs_error()
+    );
+}
+
+fn write_diff(diff: &Dds, path: &Path) -> Result<(), Error> {
+    let extension = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+    if extension == "dds" {
+        let mut w = BufWriter::new(File::create(path)?);
+        diff.write(&mut w)?;
+        w.flush()?;
+        return Ok(());
+    }
+    #[cfg(feature = "export")]
+    if ExportFormat::from_extension(&extension).is_some() {
+        return diff.export_contact_sheet_to_path(path);
+    }
+    Err(Error::Parse {
+        kind: "difference image extension",
+        input: extension,
+    })
+}
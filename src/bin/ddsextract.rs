@@ -0,0 +1,190 @@
+// The MIT License (MIT)
+//
+// Copyright (c) 2018 Michael Dilger
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+extern crate ddsfile;
+use ddsfile::*;
+
+use std::env;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+
+const USAGE: &str = "\
+Usage: ddsextract [options] <input.dds>
+
+Writes array layers, cubemap faces or mipmap levels of a DDS file out as
+standalone DDS files named after the input, such as sky_face2.dds,
+array_layer3.dds or array_layer3_mip1.dds.
+
+Options:
+  --layer <n>         extract only this array layer (or cubemap face)
+  --mip <n>           extract only this mipmap level, without the rest of the
+                      mipmap chain
+  --split-mips        write every mipmap level to its own file
+  -o, --output <dir>  directory to write to (default: next to the input)";
+
+struct Options {
+    input: PathBuf,
+    layer: Option<u32>,
+    mip: Option<u32>,
+    split_mips: bool,
+    output: Option<PathBuf>,
+}
+
+fn main() {
+    let options = parse_args();
+    let dds = match File::open(&options.input)
+        .map_err(Error::from)
+        .and_then(|f| Dds::read(BufReader::new(f)))
+    {
+        Ok(dds) => dds,
+        Err(e) => fail(&format!("{}: {}", options.input.display(), e)),
+    };
+
+    let layers = match options.layer {
+        Some(layer) if layer >= dds.get_num_array_layers() => fail(&format!(
+            "layer {} is out of range, the texture has {}",
+            layer,
+            dds.get_num_array_layers()
+        )),
+        Some(layer) => layer..layer + 1,
+        None => 0..dds.get_num_array_layers(),
+    };
+    let levels = match options.mip {
+        Some(mip) if mip >= dds.get_num_mipmap_levels() => fail(&format!(
+            "mipmap level {} is out of range, the texture has {}",
+            mip,
+            dds.get_num_mipmap_levels()
+        )),
+        Some(mip) => Some(mip..mip + 1),
+        None if options.split_mips => Some(0..dds.get_num_mipmap_levels()),
+        None => None,
+    };
+
+    let directory = match options.output {
+        Some(ref dir) => dir.clone(),
+        None => options
+            .input
+            .parent()
+            .map(Path::to_path_buf)
+            .unwrap_or_default(),
+    };
+    let stem = options
+        .input
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "texture".to_owned());
+
+    for array_layer in layers {
+        let name = format!("{}_{}", stem, layer_name(&dds, array_layer));
+        match levels {
+            Some(ref levels) => {
+                for mipmap_level in levels.clone() {
+                    let path = directory.join(format!("{}_mip{}.dds", name, mipmap_level));
+                    let result = dds.extract_subresource(array_layer, mipmap_level);
+                    write(result, &path);
+                }
+            }
+            None => {
+                let path = directory.join(format!("{}.dds", name));
+                write(dds.extract_layer(array_layer), &path);
+            }
+        }
+    }
+}
+
+// Cubemap faces are named by face (and cube, in arrays of cubemaps)
+fn layer_name(dds: &Dds, array_layer: u32) -> String {
+    if !dds.is_cubemap() {
+        format!("layer{}", array_layer)
+    } else if dds.get_num_array_layers() == 6 {
+        format!("face{}", array_layer)
+    } else {
+        format!("cube{}_face{}", array_layer / 6, array_layer % 6)
+    }
+}
+
+fn write(dds: Result<Dds, Error>, path: &Path) {
+    let result = dds.and_then(|dds| {
+        let mut w = BufWriter::new(File::create(path)?);
+        dds.write(&mut w)?;
+        w.flush()?;
+        Ok(())
+    });
+    match result {
+        Ok(()) => println!("{}", path.display()),
+        Err(e) => fail(&format!("{}: {}", path.display(), e)),
+    }
+}
+
+fn fail(message: &dyn std::fmt::Display) -> ! {
+    eprintln!("ddsextract: {}", message);
+    process::exit(1);
+}
+
+fn usage_error(message: &str) -> ! {
+    eprintln!("ddsextract: {}\n\n{}", message, USAGE);
+    process::exit(2);
+}
+
+fn parse_args() -> Options {
+    let mut positional = Vec::new();
+    let mut options = Options {
+        input: PathBuf::new(),
+        layer: None,
+        mip: None,
+        split_mips: false,
+        output: None,
+    };
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || match args.next() {
+            Some(v) => v,
+            None => usage_error(&format!("{} needs a value", arg)),
+        };
+        match &*arg {
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                process::exit(0);
+            }
+            "--layer" => options.layer = Some(parse_number(&value())),
+            "--mip" => options.mip = Some(parse_number(&value())),
+            "--split-mips" => options.split_mips = true,
+            "-o" | "--output" => options.output = Some(PathBuf::from(value())),
+            _ if arg.starts_with('-') => usage_error(&format!("unknown option {}", arg)),
+            _ => positional.push(arg),
+        }
+    }
+    if positional.len() != 1 {
+        usage_error("expected one input file");
+    }
+    options.input = PathBuf::from(positional.remove(0));
+    options
+}
+
+fn parse_number(s: &str) -> u32 {
+    match s.parse() {
+        Ok(n) => n,
+        Err(_) => usage_error(&format!("expected a number, not {}", s)),
+    }
+}
@@ -1,24 +1,842 @@
+// The MIT License (MIT)
+//
+// Copyright (c) 2018 Michael Dilger
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
 extern crate ddsfile;
 use ddsfile::*;
 
+use std::collections::BTreeMap;
 use std::env;
-use std::fs::File;
+use std::fmt::Write;
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use std::process;
+
+const USAGE: &str = "\
+Usage: ddsinfo [--json | --toml | --csv] [--summary] <path>...
+
+A single file is described in detail. Several files, directories (searched
+recursively for .dds files) and glob patterns (*, ?, and ** to span
+directories) are described one line per file followed by a summary.";
+
+#[derive(PartialEq)]
+enum Output {
+    Text,
+    Json,
+    Toml,
+    Csv,
+}
 
 fn main() {
-    let filename = match env::args().nth(1) {
-        Some(arg) => arg,
-        None => panic!("Usage: ddsinfo <filename>"),
-    };
+    let mut output = Output::Text;
+    let mut summary_only = false;
+    let mut inputs = Vec::new();
+    for arg in env::args().skip(1) {
+        match &*arg {
+            "--json" => output = Output::Json,
+            "--toml" => output = Output::Toml,
+            "--csv" => output = Output::Csv,
+            "--summary" => summary_only = true,
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return;
+            }
+            _ if arg.starts_with('-') => usage_error(),
+            _ => inputs.push(arg),
+        }
+    }
+    if inputs.is_empty() {
+        usage_error();
+    }
 
-    let mut file = match File::open(&*filename) {
-        Ok(f) => f,
-        Err(e) => panic!("{}", e),
-    };
+    let single = inputs.len() == 1
+        && !is_glob(&inputs[0])
+        && !Path::new(&inputs[0]).is_dir()
+        && output != Output::Csv
+        && !summary_only;
+    if single {
+        describe_one(&inputs[0], &output);
+    } else {
+        let mut files = Vec::new();
+        for input in &inputs {
+            if let Err(e) = collect(input, &mut files) {
+                eprintln!("ddsinfo: {}: {}", input, e);
+                process::exit(1);
+            }
+        }
+        if !batch(&files, &output, summary_only) {
+            process::exit(1);
+        }
+    }
+}
 
-    let dds = match Dds::read(&mut file) {
+fn usage_error() -> ! {
+    eprintln!("{}", USAGE);
+    process::exit(2);
+}
+
+fn describe_one(filename: &str, output: &Output) {
+    let dds = match read(Path::new(filename)) {
         Ok(dds) => dds,
-        Err(e) => panic!("{}", e),
+        Err(e) => {
+            eprintln!("ddsinfo: {}: {}", filename, e);
+            process::exit(1);
+        }
     };
 
+    match *output {
+        Output::Text | Output::Csv => print_text(&dds),
+        Output::Json => {
+            let mut s = String::new();
+            describe(filename, &dds).write_json(&mut s, 0);
+            println!("{}", s);
+        }
+        Output::Toml => {
+            let mut s = String::new();
+            describe(filename, &dds).write_toml(&mut s, "");
+            print!("{}", s);
+        }
+    }
+}
+
+fn read(path: &Path) -> Result<Dds, Error> {
+    Dds::read(File::open(path)?)
+}
+
+fn is_glob(s: &str) -> bool {
+    s.contains(['*', '?'])
+}
+
+// Expand a command line argument into the files it names
+fn collect(input: &str, files: &mut Vec<PathBuf>) -> Result<(), Error> {
+    if is_glob(input) {
+        // Walk from the longest directory prefix without wildcards
+        let normalized = input.replace('\\', "/");
+        let start = match normalized.find(['*', '?']) {
+            Some(i) => normalized[..i].rfind('/').map(|j| j + 1).unwrap_or(0),
+            None => 0,
+        };
+        let base = match &normalized[..start] {
+            "" => Path::new("."),
+            b => Path::new(b),
+        };
+        let mut found = Vec::new();
+        walk(base, &mut found)?;
+        for path in found {
+            let text = path.to_string_lossy().replace('\\', "/");
+            let text = match start {
+                0 => text.trim_start_matches("./").to_owned(),
+                _ => text,
+            };
+            if glob_match(normalized.as_bytes(), text.as_bytes()) {
+                files.push(path);
+            }
+        }
+    } else if Path::new(input).is_dir() {
+        let mut found = Vec::new();
+        walk(Path::new(input), &mut found)?;
+        files.extend(found.into_iter().filter(|p| {
+            p.extension()
+                .map(|e| e.eq_ignore_ascii_case("dds"))
+                .unwrap_or(false)
+        }));
+    } else {
+        files.push(PathBuf::from(input));
+    }
+    Ok(())
+}
+
+// Every file under a directory, recursively, in a stable order
+fn walk(dir: &Path, files: &mut Vec<PathBuf>) -> Result<(), Error> {
+    let mut entries = fs::read_dir(dir)?
+        .map(|e| e.map(|e| e.path()))
+        .collect::<Result<Vec<_>, _>>()?;
+    entries.sort();
+    for path in entries {
+        if path.is_dir() {
+            walk(&path, files)?;
+        } else {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+// Match a glob pattern where `*` and `?` stay within a path component and
+// `**` spans any number of them
+fn glob_match(pattern: &[u8], text: &[u8]) -> bool {
+    match pattern {
+        [] => text.is_empty(),
+        [b'*', b'*', b'/', rest @ ..] => {
+            glob_match(rest, text)
+                || (0..text.len()).any(|i| text[i] == b'/' && glob_match(rest, &text[i + 1..]))
+        }
+        [b'*', b'*', rest @ ..] => (0..=text.len()).any(|i| glob_match(rest, &text[i..])),
+        [b'*', rest @ ..] => (0..=text.len())
+            .take_while(|&i| i == 0 || text[i - 1] != b'/')
+            .any(|i| glob_match(rest, &text[i..])),
+        [b'?', rest @ ..] => match text {
+            [c, tail @ ..] if *c != b'/' => glob_match(rest, tail),
+            _ => false,
+        },
+        [p, rest @ ..] => match text {
+            [c, tail @ ..] if c == p => glob_match(rest, tail),
+            _ => false,
+        },
+    }
+}
+
+// One row of a batch report
+struct Entry {
+    path: PathBuf,
+    bytes: u64,
+    result: Result<Dds, Error>,
+}
+
+#[derive(Default)]
+struct Summary {
+    files: usize,
+    failed: usize,
+    bytes: u64,
+    legacy: usize,
+    dx10: usize,
+    without_mipmaps: usize,
+    non_power_of_two: usize,
+    by_format: BTreeMap<String, (usize, u64)>,
+}
+
+impl Summary {
+    fn add(&mut self, entry: &Entry) {
+        self.files += 1;
+        self.bytes += entry.bytes;
+        let dds = match entry.result {
+            Ok(ref dds) => dds,
+            Err(_) => {
+                self.failed += 1;
+                return;
+            }
+        };
+        if dds.header10.is_some() {
+            self.dx10 += 1;
+        } else {
+            self.legacy += 1;
+        }
+        if lacks_mipmaps(dds) {
+            self.without_mipmaps += 1;
+        }
+        if !is_power_of_two(dds) {
+            self.non_power_of_two += 1;
+        }
+        let format = self.by_format.entry(format_name(dds)).or_default();
+        format.0 += 1;
+        format.1 += entry.bytes;
+    }
+
+    fn to_value(&self) -> Value {
+        let by_format = self
+            .by_format
+            .iter()
+            .map(|(format, &(count, bytes))| {
+                Value::Table(vec![
+                    ("format", format.clone().into()),
+                    ("count", count.into()),
+                    ("bytes", bytes.into()),
+                ])
+            })
+            .collect();
+        Value::Table(vec![
+            ("files", self.files.into()),
+            ("failed", self.failed.into()),
+            ("bytes", self.bytes.into()),
+            ("legacy_headers", self.legacy.into()),
+            ("dx10_headers", self.dx10.into()),
+            ("without_mipmaps", self.without_mipmaps.into()),
+            ("non_power_of_two", self.non_power_of_two.into()),
+            ("formats", Value::Array(by_format)),
+        ])
+    }
+
+    fn print(&self) {
+        println!("Files:            {}", self.files);
+        println!("Failed to parse:  {}", self.failed);
+        println!("Total bytes:      {}", self.bytes);
+        println!("Legacy headers:   {}", self.legacy);
+        println!("DX10 headers:     {}", self.dx10);
+        println!("Without mipmaps:  {}", self.without_mipmaps);
+        println!("Non-power-of-two: {}", self.non_power_of_two);
+        println!("Formats:");
+        for (format, (count, bytes)) in &self.by_format {
+            println!("  {:<28} {:>8} {:>14}", format, count, bytes);
+        }
+    }
+}
+
+// A texture larger than 1x1 with only its main image
+fn lacks_mipmaps(dds: &Dds) -> bool {
+    dds.get_num_mipmap_levels() <= 1 && dds.get_width().max(dds.get_height()) > 1
+}
+
+fn is_power_of_two(dds: &Dds) -> bool {
+    dds.get_width().is_power_of_two()
+        && dds.get_height().is_power_of_two()
+        && dds.get_depth().is_power_of_two()
+}
+
+fn format_name(dds: &Dds) -> String {
+    if let Some(value) = dds
+        .header10
+        .as_ref()
+        .and_then(|h10| h10.get_unknown_dxgi_format())
+    {
+        format!("DXGI_FORMAT {}", value)
+    } else if let Some(format) = dds.get_d3d_format() {
+        format!("{:?}", format)
+    } else if let Some(format) = dds.get_dxgi_format() {
+        format!("{:?}", format)
+    } else if let Some(ref fourcc) = dds.header.spf.fourcc {
+        fourcc.to_string()
+    } else {
+        "Unknown".to_owned()
+    }
+}
+
+// Describe many files, returning false if any failed to parse
+fn batch(files: &[PathBuf], output: &Output, summary_only: bool) -> bool {
+    let mut summary = Summary::default();
+    let mut described = Vec::new();
+    if *output == Output::Csv && !summary_only {
+        println!("file,status,format,header,width,height,depth,mipmap_levels,array_layers,cubemap,volume,bytes,error");
+    } else if *output == Output::Text && !summary_only {
+        println!(
+            "{:<28} {:>6} {:>16} {:>5} {:>6} {:>12}  file",
+            "format", "header", "dimensions", "mips", "layers", "bytes"
+        );
+    }
+
+    for path in files {
+        let entry = Entry {
+            path: path.clone(),
+            bytes: fs::metadata(path).map(|m| m.len()).unwrap_or(0),
+            result: read(path),
+        };
+        summary.add(&entry);
+        let name = entry.path.display().to_string();
+        if summary_only {
+            if let Err(ref e) = entry.result {
+                eprintln!("ddsinfo: {}: {}", name, e);
+            }
+            continue;
+        }
+        match *output {
+            Output::Text => match entry.result {
+                Ok(ref dds) => println!(
+                    "{:<28} {:>6} {:>16} {:>5} {:>6} {:>12}  {}",
+                    format_name(dds),
+                    header_kind(dds),
+                    format!(
+                        "{}x{}x{}",
+                        dds.get_width(),
+                        dds.get_height(),
+                        dds.get_depth()
+                    ),
+                    dds.get_num_mipmap_levels(),
+                    dds.get_num_array_layers(),
+                    entry.bytes,
+                    name
+                ),
+                Err(ref e) => eprintln!("ddsinfo: {}: {}", name, e),
+            },
+            Output::Csv => match entry.result {
+                Ok(ref dds) => println!(
+                    "{},ok,{},{},{},{},{},{},{},{},{},{},",
+                    csv_field(&name),
+                    format_name(dds),
+                    header_kind(dds),
+                    dds.get_width(),
+                    dds.get_height(),
+                    dds.get_depth(),
+                    dds.get_num_mipmap_levels(),
+                    dds.get_num_array_layers(),
+                    dds.is_cubemap(),
+                    dds.is_volume(),
+                    entry.bytes
+                ),
+                Err(ref e) => println!(
+                    "{},error,,,,,,,,,,{},{}",
+                    csv_field(&name),
+                    entry.bytes,
+                    csv_field(&e.to_string())
+                ),
+            },
+            Output::Json | Output::Toml => described.push(match entry.result {
+                Ok(ref dds) => describe(&name, dds),
+                Err(ref e) => {
+                    Value::Table(vec![("file", name.into()), ("error", e.to_string().into())])
+                }
+            }),
+        }
+    }
+
+    match *output {
+        Output::Text => {
+            if !summary_only {
+                println!();
+            }
+            summary.print();
+        }
+        Output::Csv => {
+            if summary_only {
+                println!("format,count,bytes");
+                for (format, (count, bytes)) in &summary.by_format {
+                    println!("{},{},{}", csv_field(format), count, bytes);
+                }
+            }
+        }
+        Output::Json | Output::Toml => {
+            let report = Value::Table(vec![
+                ("summary", summary.to_value()),
+                ("files", Value::Array(described)),
+            ]);
+            let mut s = String::new();
+            if *output == Output::Json {
+                report.write_json(&mut s, 0);
+                println!("{}", s);
+            } else {
+                report.write_toml(&mut s, "");
+                print!("{}", s);
+            }
+        }
+    }
+
+    summary.failed == 0
+}
+
+fn header_kind(dds: &Dds) -> &'static str {
+    match dds.header10 {
+        Some(_) => "DX10",
+        None => "legacy",
+    }
+}
+
+fn csv_field(s: &str) -> String {
+    if s.contains([',', '"', '\n']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_owned()
+    }
+}
+
+fn print_text(dds: &Dds) {
     println!("{:?}", dds);
+    let subresources = match subresources(dds) {
+        Some(s) => s,
+        None => {
+            println!("Subresources: (unknown layout)");
+            return;
+        }
+    };
+    println!("Subresources:");
+    println!(
+        "  {:>5} {:>4} {:>18} {:>12} {:>12}",
+        "layer", "mip", "dimensions", "offset", "size"
+    );
+    for s in subresources {
+        println!(
+            "  {:>5} {:>4} {:>18} {:>12} {:>12}",
+            s.array_layer,
+            s.mipmap_level,
+            format!("{}x{}x{}", s.width, s.height, s.depth),
+            s.offset,
+            s.size
+        );
+    }
+}
+
+struct Subresource {
+    array_layer: u32,
+    mipmap_level: u32,
+    width: u32,
+    height: u32,
+    depth: u32,
+    offset: usize,
+    size: u32,
+}
+
+// The location of every mipmap level of every array layer in the file, if
+// the format is understood well enough to tell
+fn subresources(dds: &Dds) -> Option<Vec<Subresource>> {
+    let mut list = Vec::new();
+    for array_layer in 0..dds.get_num_array_layers() {
+        for mipmap_level in 0..dds.get_num_mipmap_levels() {
+            let (offset, size) = dds
+                .get_mipmap_offset_and_size(array_layer, mipmap_level)
+                .ok()?;
+            let (width, height, depth) = dds.get_mipmap_dimensions(mipmap_level);
+            list.push(Subresource {
+                array_layer,
+                mipmap_level,
+                width,
+                height,
+                depth,
+                offset: dds.get_data_offset() + offset as usize,
+                size,
+            });
+        }
+    }
+    Some(list)
+}
+
+// A minimal document model, enough to write both JSON and TOML
+enum Value {
+    Null,
+    Bool(bool),
+    Number(u64),
+    String(String),
+    Array(Vec<Value>),
+    Table(Vec<(&'static str, Value)>),
+}
+
+impl From<bool> for Value {
+    fn from(b: bool) -> Value {
+        Value::Bool(b)
+    }
+}
+
+impl From<u32> for Value {
+    fn from(n: u32) -> Value {
+        Value::Number(n as u64)
+    }
+}
+
+impl From<u64> for Value {
+    fn from(n: u64) -> Value {
+        Value::Number(n)
+    }
+}
+
+impl From<usize> for Value {
+    fn from(n: usize) -> Value {
+        Value::Number(n as u64)
+    }
+}
+
+impl From<String> for Value {
+    fn from(s: String) -> Value {
+        Value::String(s)
+    }
+}
+
+impl<T: Into<Value>> From<Option<T>> for Value {
+    fn from(o: Option<T>) -> Value {
+        match o {
+            Some(v) => v.into(),
+            None => Value::Null,
+        }
+    }
+}
+
+fn flag_names<I: Iterator<Item = (&'static str, F)>, F>(names: I) -> Value {
+    Value::Array(names.map(|(n, _)| Value::String(n.to_owned())).collect())
+}
+
+fn describe(filename: &str, dds: &Dds) -> Value {
+    let format = dds.get_format();
+    let spf = &dds.header.spf;
+    let pixel_format = Value::Table(vec![
+        ("flags", flag_names(spf.flags.iter_names())),
+        ("fourcc", spf.fourcc.as_ref().map(|f| f.to_string()).into()),
+        ("rgb_bit_count", spf.rgb_bit_count.into()),
+        ("r_bit_mask", spf.r_bit_mask.into()),
+        ("g_bit_mask", spf.g_bit_mask.into()),
+        ("b_bit_mask", spf.b_bit_mask.into()),
+        ("a_bit_mask", spf.a_bit_mask.into()),
+    ]);
+    let header = Value::Table(vec![
+        ("flags", flag_names(dds.header.get_flags().iter_names())),
+        ("height", dds.header.height.into()),
+        ("width", dds.header.width.into()),
+        ("pitch", dds.header.pitch.into()),
+        ("linear_size", dds.header.linear_size.into()),
+        ("depth", dds.header.depth.into()),
+        ("mip_map_count", dds.header.mip_map_count.into()),
+        ("caps", flag_names(dds.header.caps.iter_names())),
+        ("caps2", flag_names(dds.header.caps2.iter_names())),
+        ("pixel_format", pixel_format),
+    ]);
+    let header10 = match dds.header10 {
+        Some(ref h10) => Value::Table(vec![
+            ("dxgi_format", format!("{:?}", h10.dxgi_format).into()),
+            ("dxgi_format_value", h10.get_dxgi_format_value().into()),
+            (
+                "resource_dimension",
+                format!("{:?}", h10.resource_dimension).into(),
+            ),
+            ("misc_flag", flag_names(h10.misc_flag.iter_names())),
+            ("array_size", h10.array_size.into()),
+            ("alpha_mode", format!("{:?}", h10.alpha_mode).into()),
+        ]),
+        None => Value::Null,
+    };
+    let subresources = subresources(dds).map(|list| {
+        Value::Array(
+            list.into_iter()
+                .map(|s| {
+                    Value::Table(vec![
+                        ("array_layer", s.array_layer.into()),
+                        ("mipmap_level", s.mipmap_level.into()),
+                        ("width", s.width.into()),
+                        ("height", s.height.into()),
+                        ("depth", s.depth.into()),
+                        ("offset", s.offset.into()),
+                        ("size", s.size.into()),
+                    ])
+                })
+                .collect(),
+        )
+    });
+
+    Value::Table(vec![
+        ("file", filename.to_owned().into()),
+        (
+            "dxgi_format",
+            dds.get_dxgi_format().map(|f| format!("{:?}", f)).into(),
+        ),
+        (
+            "d3d_format",
+            dds.get_d3d_format().map(|f| format!("{:?}", f)).into(),
+        ),
+        ("width", dds.get_width().into()),
+        ("height", dds.get_height().into()),
+        ("depth", dds.get_depth().into()),
+        ("mipmap_levels", dds.get_num_mipmap_levels().into()),
+        ("array_layers", dds.get_num_array_layers().into()),
+        ("is_cubemap", dds.is_cubemap().into()),
+        ("is_volume", dds.is_volume().into()),
+        ("bits_per_pixel", dds.get_bits_per_pixel().into()),
+        (
+            "block_size",
+            format.as_ref().and_then(|f| f.get_block_size()).into(),
+        ),
+        ("pitch", dds.get_pitch().into()),
+        ("pitch_height", dds.get_pitch_height().into()),
+        ("main_texture_size", dds.get_main_texture_size().into()),
+        ("array_stride", dds.get_array_stride().ok().into()),
+        ("data_offset", dds.get_data_offset().into()),
+        ("data_size", dds.data.len().into()),
+        ("header", header),
+        ("header10", header10),
+        ("subresources", subresources.into()),
+    ])
+}
+
+fn write_string(out: &mut String, s: &str) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\t' => out.push_str("\\t"),
+            c if c.is_control() => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+impl Value {
+    fn write_json(&self, out: &mut String, indent: usize) {
+        match *self {
+            Value::Null => out.push_str("null"),
+            Value::Bool(b) => {
+                let _ = write!(out, "{}", b);
+            }
+            Value::Number(n) => {
+                let _ = write!(out, "{}", n);
+            }
+            Value::String(ref s) => write_string(out, s),
+            Value::Array(ref items) => {
+                if items.is_empty() {
+                    out.push_str("[]");
+                    return;
+                }
+                out.push_str("[\n");
+                for (i, item) in items.iter().enumerate() {
+                    out.push_str(&"  ".repeat(indent + 1));
+                    item.write_json(out, indent + 1);
+                    out.push_str(if i + 1 < items.len() { ",\n" } else { "\n" });
+                }
+                out.push_str(&"  ".repeat(indent));
+                out.push(']');
+            }
+            Value::Table(ref entries) => {
+                out.push_str("{\n");
+                for (i, (key, value)) in entries.iter().enumerate() {
+                    out.push_str(&"  ".repeat(indent + 1));
+                    write_string(out, key);
+                    out.push_str(": ");
+                    value.write_json(out, indent + 1);
+                    out.push_str(if i + 1 < entries.len() { ",\n" } else { "\n" });
+                }
+                out.push_str(&"  ".repeat(indent));
+                out.push('}');
+            }
+        }
+    }
+
+    fn is_table_like(&self) -> bool {
+        match *self {
+            Value::Table(_) => true,
+            Value::Array(ref items) => {
+                !items.is_empty() && items.iter().all(|i| matches!(i, Value::Table(_)))
+            }
+            _ => false,
+        }
+    }
+
+    // Write the entries of a table, plain values first and then sub-tables
+    // and arrays of tables under their dotted path. TOML has no null, so
+    // missing values are left out.
+    fn write_toml(&self, out: &mut String, path: &str) {
+        let entries = match *self {
+            Value::Table(ref entries) => entries,
+            _ => return,
+        };
+        for (key, value) in entries {
+            if matches!(value, Value::Null) || value.is_table_like() {
+                continue;
+            }
+            let _ = write!(out, "{} = ", key);
+            value.write_toml_inline(out);
+            out.push('\n');
+        }
+        for (key, value) in entries {
+            let path = if path.is_empty() {
+                key.to_string()
+            } else {
+                format!("{}.{}", path, key)
+            };
+            match *value {
+                Value::Table(_) => {
+                    let _ = write!(out, "\n[{}]\n", path);
+                    value.write_toml(out, &path);
+                }
+                Value::Array(ref items) if value.is_table_like() => {
+                    for item in items {
+                        let _ = write!(out, "\n[[{}]]\n", path);
+                        item.write_toml(out, &path);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn write_toml_inline(&self, out: &mut String) {
+        match *self {
+            Value::Array(ref items) => {
+                out.push('[');
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        out.push_str(", ");
+                    }
+                    item.write_toml_inline(out);
+                }
+                out.push(']');
+            }
+            _ => self.write_json(out, 0),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn glob_patterns() {
+        let matches = |pattern: &str, text: &str| glob_match(pattern.as_bytes(), text.as_bytes());
+        assert!(matches("*.dds", "a.dds"));
+        assert!(!matches("*.dds", "textures/a.dds"));
+        assert!(matches("textures/?.dds", "textures/a.dds"));
+        assert!(!matches("textures/?.dds", "textures/ab.dds"));
+        assert!(matches("**/*.dds", "a.dds"));
+        assert!(matches("**/*.dds", "textures/ui/a.dds"));
+        assert!(matches("textures/**/a.dds", "textures/ui/icons/a.dds"));
+        assert!(!matches("textures/**/a.dds", "models/a.dds"));
+    }
+
+    #[test]
+    fn csv_fields() {
+        assert_eq!(csv_field("a.dds"), "a.dds");
+        assert_eq!(csv_field("a,b.dds"), "\"a,b.dds\"");
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+    }
+
+    fn entry(bytes: u64, result: Result<Dds, Error>) -> Entry {
+        Entry {
+            path: PathBuf::from("a.dds"),
+            bytes,
+            result,
+        }
+    }
+
+    #[test]
+    fn summary_counts() {
+        let legacy = Dds::new_d3d(NewD3dParams {
+            height: 3,
+            width: 5,
+            depth: None,
+            format: D3DFormat::DXT1,
+            mipmap_levels: None,
+            caps2: None,
+        })
+        .unwrap();
+        let dx10 = Dds::new_dxgi(NewDxgiParams {
+            height: 4,
+            width: 4,
+            depth: None,
+            format: DxgiFormat::BC7_UNorm,
+            mipmap_levels: Some(3),
+            array_layers: None,
+            caps2: None,
+            is_cubemap: false,
+            resource_dimension: D3D10ResourceDimension::Texture2D,
+            alpha_mode: AlphaMode::Unknown,
+        })
+        .unwrap();
+
+        let mut summary = Summary::default();
+        summary.add(&entry(100, Ok(legacy)));
+        summary.add(&entry(200, Ok(dx10.clone())));
+        summary.add(&entry(300, Ok(dx10)));
+        summary.add(&entry(4, Err(Error::BadMagicNumber)));
+
+        assert_eq!((summary.files, summary.failed, summary.bytes), (4, 1, 604));
+        assert_eq!((summary.legacy, summary.dx10), (1, 2));
+        assert_eq!(summary.without_mipmaps, 1);
+        assert_eq!(summary.non_power_of_two, 1);
+        assert_eq!(summary.by_format["DXT1"], (1, 100));
+        assert_eq!(summary.by_format["BC7_UNorm"], (2, 500));
+    }
 }
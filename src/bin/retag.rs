@@ -1,60 +1,342 @@
-// This is for re-tagging the format of a file that is tagged wrong.
-// The data is not converted in any way.
+// The MIT License (MIT)
+//
+// Copyright (c) 2018 Michael Dilger
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
 
 extern crate ddsfile;
-
 use ddsfile::*;
 
 use std::env;
-use std::fs::OpenOptions;
+use std::fs::{File, OpenOptions};
 use std::io::{Seek, SeekFrom};
+use std::process;
 
-fn main() {
-    let filename = match env::args().nth(1) {
-        Some(arg) => arg,
-        None => panic!("Usage: retag <ddsfile> <format>"),
-    };
+const USAGE: &str = "\
+Usage: retag [options] <filename> [format]
 
-    let tag = match env::args().nth(2) {
-        Some(arg) => arg,
-        None => panic!("Usage: retag <ddsfile> <format>"),
-    };
-    // Rather than impl FromStr for dxgi and d3d formats, I'm hackily just adding
-    // the ones I care about here.
-    let format: DxgiFormat = match &*tag {
-        "BC7_UNorm" => DxgiFormat::BC7_UNorm,
-        "BC7_UNorm_sRGB" => DxgiFormat::BC7_UNorm_sRGB,
-        _ => panic!("format not implemented"),
-    };
+Changes what the header of a DDS file says about its contents, without
+touching the texel data. The format may be any DxgiFormat or D3DFormat name.
+
+Options:
+  --force              allow a format with a different texel or block size
+  --dx10               upgrade a legacy header to a DX10 header
+  --alpha-mode <mode>  unknown, straight, premultiplied, opaque or custom
+  --cubemap <yes|no>   mark the texture (array) as a cubemap, or not
+  --srgb <yes|no>      switch to the sRGB (or the linear) variant of the format";
+
+enum Target {
+    Dxgi(DxgiFormat),
+    D3d(D3DFormat),
+}
+
+struct Options {
+    filename: String,
+    target: Option<Target>,
+    force: bool,
+    dx10: bool,
+    alpha_mode: Option<AlphaMode>,
+    cubemap: Option<bool>,
+    srgb: Option<bool>,
+}
+
+fn main() {
+    let options = parse_args();
 
     let mut file = match OpenOptions::new()
         .read(true)
         .write(true)
         .create(false)
-        .open(&*filename)
+        .open(&*options.filename)
     {
         Ok(f) => f,
-        Err(e) => panic!("{}", e),
+        Err(e) => fail(&options.filename, e),
     };
 
     let mut dds = match Dds::read(&mut file) {
         Ok(dds) => dds,
-        Err(e) => panic!("{}", e),
+        Err(e) => fail(&options.filename, e),
     };
 
-    if let Some(ref mut h10) = dds.header10 {
-        h10.dxgi_format = format;
+    let before = format_name(&dds);
+    if let Err(e) = retag(&mut dds, &options) {
+        fail(&options.filename, e);
+    }
+
+    if let Err(e) = write(&mut file, &dds) {
+        fail(&options.filename, format!("Error writing file: {}", e));
+    }
+
+    println!("{}: {} -> {}", options.filename, before, format_name(&dds));
+}
+
+fn fail<E: std::fmt::Display>(filename: &str, e: E) -> ! {
+    eprintln!("retag: {}: {}", filename, e);
+    process::exit(1);
+}
+
+fn usage_error(message: &str) -> ! {
+    eprintln!("retag: {}\n\n{}", message, USAGE);
+    process::exit(2);
+}
+
+fn parse_args() -> Options {
+    let mut positional = Vec::new();
+    let mut options = Options {
+        filename: String::new(),
+        target: None,
+        force: false,
+        dx10: false,
+        alpha_mode: None,
+        cubemap: None,
+        srgb: None,
+    };
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || match args.next() {
+            Some(v) => v,
+            None => usage_error(&format!("{} needs a value", arg)),
+        };
+        match &*arg {
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                process::exit(0);
+            }
+            "--force" => options.force = true,
+            "--dx10" => options.dx10 = true,
+            "--alpha-mode" => options.alpha_mode = Some(parse_alpha_mode(&value())),
+            "--cubemap" => options.cubemap = Some(parse_yes_no(&value())),
+            "--srgb" => options.srgb = Some(parse_yes_no(&value())),
+            _ if arg.starts_with('-') => usage_error(&format!("unknown option {}", arg)),
+            _ => positional.push(arg),
+        }
+    }
+
+    let mut positional = positional.into_iter();
+    options.filename = match positional.next() {
+        Some(f) => f,
+        None => usage_error("missing filename"),
+    };
+    options.target = positional.next().map(|name| {
+        if let Ok(f) = name.parse::<DxgiFormat>() {
+            Target::Dxgi(f)
+        } else if let Ok(f) = name.parse::<D3DFormat>() {
+            Target::D3d(f)
+        } else {
+            usage_error(&format!("unknown format {}", name))
+        }
+    });
+    if positional.next().is_some() {
+        usage_error("too many arguments");
+    }
+    options
+}
+
+fn parse_alpha_mode(s: &str) -> AlphaMode {
+    match &*s.to_ascii_lowercase() {
+        "unknown" => AlphaMode::Unknown,
+        "straight" => AlphaMode::Straight,
+        "premultiplied" => AlphaMode::PreMultiplied,
+        "opaque" => AlphaMode::Opaque,
+        "custom" => AlphaMode::Custom,
+        _ => usage_error(&format!("unknown alpha mode {}", s)),
+    }
+}
+
+fn parse_yes_no(s: &str) -> bool {
+    match &*s.to_ascii_lowercase() {
+        "yes" | "on" | "true" => true,
+        "no" | "off" | "false" => false,
+        _ => usage_error(&format!("expected yes or no, not {}", s)),
+    }
+}
+
+fn format_name(dds: &Dds) -> String {
+    if let Some(ref h10) = dds.header10 {
+        format!("{:?}", h10.dxgi_format)
+    } else if let Some(format) = dds.get_d3d_format() {
+        format!("{:?} (legacy header)", format)
+    } else if let Some(format) = dds.get_dxgi_format() {
+        format!("{:?} (legacy header)", format)
     } else {
-        panic!("d3d formats not implemented");
+        "unknown format".to_owned()
+    }
+}
+
+fn retag(dds: &mut Dds, options: &Options) -> Result<(), Error> {
+    let old_format = dds.get_format();
+
+    match options.target {
+        Some(Target::Dxgi(format)) => {
+            // The upgrade keeps what the legacy header says about the layout
+            // and the alpha mode
+            dds.upgrade_to_dx10()?;
+            if let Some(h10) = dds.header10.as_mut() {
+                h10.set_dxgi_format_value(format as u32);
+            }
+        }
+        Some(Target::D3d(format)) => {
+            if let Some(h10) = dds.header10.take() {
+                if h10.array_size > 1 {
+                    return Err(Error::InvalidField {
+                        field: "arraySize",
+                        value: h10.array_size as u64,
+                        offset: None,
+                        expected: Expected::Description("1 without a DX10 header"),
+                    });
+                }
+                if h10.misc_flag.contains(MiscFlag::TEXTURECUBE) {
+                    dds.header.caps2 |= Caps2::CUBEMAP | Caps2::CUBEMAP_ALLFACES;
+                }
+                if h10.resource_dimension == D3D10ResourceDimension::Texture3D {
+                    dds.header.caps2 |= Caps2::VOLUME;
+                }
+            }
+            dds.header.spf = From::from(format);
+        }
+        None => {}
+    }
+
+    if options.dx10 || options.alpha_mode.is_some() || options.srgb.is_some() {
+        dds.upgrade_to_dx10()?;
+    }
+
+    if let Some(h10) = dds.header10.as_mut() {
+        if let Some(srgb) = options.srgb {
+            h10.dxgi_format = match h10.dxgi_format.with_srgb(srgb) {
+                Some(f) => f,
+                None => {
+                    return Err(Error::InvalidField {
+                        field: "dxgiFormat",
+                        value: h10.get_dxgi_format_value() as u64,
+                        offset: None,
+                        expected: Expected::Description(if srgb {
+                            "a format with an sRGB variant"
+                        } else {
+                            "a format with a linear variant"
+                        }),
+                    })
+                }
+            };
+        }
+        if let Some(alpha_mode) = options.alpha_mode {
+            h10.alpha_mode = alpha_mode;
+        }
+    }
+
+    if let Some(cubemap) = options.cubemap {
+        set_cubemap(dds, cubemap)?;
+    }
+
+    let new_format = dds.get_format();
+    if !options.force {
+        if let (Some(old), Some(new)) = (&old_format, &new_format) {
+            if old.get_bits_per_pixel() != new.get_bits_per_pixel()
+                || old.get_block_size() != new.get_block_size()
+                || old.get_pitch_height() != new.get_pitch_height()
+            {
+                return Err(Error::Mismatch {
+                    index: 0,
+                    property: "texel layout (use --force)",
+                    found: texel_layout(&**new),
+                    expected: texel_layout(&**old),
+                });
+            }
+        }
+    }
+    if let Some(format) = new_format {
+        dds.header.set_pitch_or_linear_size(&*format)?;
     }
 
-    if let Err(e) = file.seek(SeekFrom::Start(0)) {
-        panic!("Error seeking to start of output file: {:?}", e);
+    if !options.force {
+        if let Ok(stride) = dds.get_array_stride() {
+            let expected = stride as usize * dds.get_num_array_layers() as usize;
+            if expected != dds.data.len() {
+                return Err(Error::Mismatch {
+                    index: 0,
+                    property: "data length (use --force)",
+                    found: format!("{} bytes", dds.data.len()),
+                    expected: format!("{} bytes", expected),
+                });
+            }
+        }
     }
 
-    if let Err(e) = dds.write(&mut file) {
-        panic!("Error writing file: {:?}", e);
+    Ok(())
+}
+
+fn set_cubemap(dds: &mut Dds, cubemap: bool) -> Result<(), Error> {
+    if cubemap == dds.is_cubemap() {
+        return Ok(());
     }
+    if cubemap && dds.get_width() != dds.get_height() {
+        return Err(Error::Mismatch {
+            index: 0,
+            property: "cubemap face width",
+            found: dds.get_width().to_string(),
+            expected: dds.get_height().to_string(),
+        });
+    }
+    if let Some(ref mut h10) = dds.header10 {
+        if cubemap {
+            if h10.array_size % 6 != 0 {
+                return Err(Error::InvalidField {
+                    field: "arraySize",
+                    value: h10.array_size as u64,
+                    offset: None,
+                    expected: Expected::Description("a multiple of six for a cubemap"),
+                });
+            }
+            h10.array_size /= 6;
+            h10.misc_flag.insert(MiscFlag::TEXTURECUBE);
+        } else {
+            h10.array_size *= 6;
+            h10.misc_flag.remove(MiscFlag::TEXTURECUBE);
+        }
+    }
+    if cubemap {
+        dds.header.caps2 |= Caps2::CUBEMAP | Caps2::CUBEMAP_ALLFACES;
+        dds.header.caps |= Caps::COMPLEX;
+    } else {
+        dds.header.caps2 -= Caps2::CUBEMAP | Caps2::CUBEMAP_ALLFACES;
+    }
+    Ok(())
+}
+
+// Describe the texel or block size of a format, for mismatch errors
+fn texel_layout(format: &dyn DataFormat) -> String {
+    match (format.get_block_size(), format.get_bits_per_pixel()) {
+        (Some(block_size), _) => format!("{}-byte blocks", block_size),
+        (None, Some(bits)) => format!(
+            "{} bits per pixel, {} rows per pitch",
+            bits,
+            format.get_pitch_height()
+        ),
+        (None, None) => "an unknown texel size".to_owned(),
+    }
+}
 
-    println!("Done.");
+fn write(file: &mut File, dds: &Dds) -> Result<(), Error> {
+    file.seek(SeekFrom::Start(0))?;
+    dds.write(file)?;
+    // The header may have shrunk
+    let len = file.stream_position()?;
+    file.set_len(len)?;
+    Ok(())
 }
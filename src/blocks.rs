@@ -0,0 +1,234 @@
+// The MIT License (MIT)
+//
+// Copyright (c) 2018 Michael Dilger
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+// Working with the blocks of block compressed data directly
+
+use crate::codec::Layout;
+use crate::{Dds, Error, Expected};
+use std::mem;
+
+/// A block of compressed data, `N` bytes long, with its position in blocks
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Block<'a, const N: usize> {
+    /// The column, counting blocks from the left
+    pub x: u32,
+    /// The row, counting blocks from the top
+    pub y: u32,
+    /// The depth slice, always 0 except in volume textures
+    pub z: u32,
+    /// The bytes of the block
+    pub data: &'a [u8; N],
+}
+
+/// A mutable block of compressed data, `N` bytes long, with its position in
+/// blocks
+#[derive(Debug, PartialEq, Eq)]
+pub struct BlockMut<'a, const N: usize> {
+    /// The column, counting blocks from the left
+    pub x: u32,
+    /// The row, counting blocks from the top
+    pub y: u32,
+    /// The depth slice, always 0 except in volume textures
+    pub z: u32,
+    /// The bytes of the block
+    pub data: &'a mut [u8; N],
+}
+
+/// A rectangle of blocks within a subresource, for `Dds::copy_blocks`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BlockRegion {
+    /// The array layer (or cubemap face) of the subresource
+    pub array_layer: u32,
+    /// The mipmap level of the subresource
+    pub mipmap_level: u32,
+    /// The left column, in blocks
+    pub x: u32,
+    /// The top row, in blocks
+    pub y: u32,
+    /// The width, in blocks
+    pub width: u32,
+    /// The height, in blocks
+    pub height: u32,
+}
+
+impl Dds {
+    /// This gets the number of blocks across, down and deep in a mipmap level,
+    /// if the format is block compressed. Blocks at the right and bottom edges
+    /// may be only partly covered by the texture.
+    pub fn get_block_count(&self, mipmap_level: u32) -> Option<(u32, u32, u32)> {
+        let layout = Layout::of(self)?;
+        layout.block_size()?;
+        let (width, height, depth) = self.get_mipmap_dimensions(mipmap_level);
+        Some((width.div_ceil(4), height.div_ceil(4), depth))
+    }
+
+    /// Iterate over the blocks of a single mipmap level of an array layer, row
+    /// by row (and slice by slice for volume textures). `N` is the size of a
+    /// block in bytes: 8 for BC1 and BC4, and 16 for the others.
+    pub fn blocks<const N: usize>(
+        &self,
+        array_layer: u32,
+        mipmap_level: u32,
+    ) -> Result<impl Iterator<Item = Block<'_, N>>, Error> {
+        let (wide, high) = self.check_block_size::<N>(mipmap_level)?;
+        let data = self.get_mipmap_data(array_layer, mipmap_level)?;
+        Ok(data
+            .chunks_exact(N)
+            .enumerate()
+            .filter_map(move |(i, block)| {
+                let (x, y, z) = position(i, wide, high);
+                Some(Block {
+                    x,
+                    y,
+                    z,
+                    data: block.try_into().ok()?,
+                })
+            }))
+    }
+
+    /// Iterate mutably over the blocks of a single mipmap level of an array
+    /// layer, so they can be patched in place. See `blocks`.
+    pub fn blocks_mut<const N: usize>(
+        &mut self,
+        array_layer: u32,
+        mipmap_level: u32,
+    ) -> Result<impl Iterator<Item = BlockMut<'_, N>>, Error> {
+        let (wide, high) = self.check_block_size::<N>(mipmap_level)?;
+        let data = self.get_mut_mipmap_data(array_layer, mipmap_level)?;
+        Ok(data
+            .chunks_exact_mut(N)
+            .enumerate()
+            .filter_map(move |(i, block)| {
+                let (x, y, z) = position(i, wide, high);
+                Some(BlockMut {
+                    x,
+                    y,
+                    z,
+                    data: block.try_into().ok()?,
+                })
+            }))
+    }
+
+    /// Copy a rectangle of blocks from a subresource of another texture into
+    /// a subresource of this one, with its top left block at column `x` and row
+    /// `y`, without decoding them. Volume textures copy the rectangle in every
+    /// depth slice.
+    ///
+    /// The formats must store the same kind of blocks, although they may
+    /// differ in signedness or sRGB, such as BC1_UNorm and BC1_UNorm_sRGB.
+    pub fn copy_blocks(
+        &mut self,
+        source: &Dds,
+        region: &BlockRegion,
+        array_layer: u32,
+        mipmap_level: u32,
+        x: u32,
+        y: u32,
+    ) -> Result<(), Error> {
+        let (layout, source_layout) = match (Layout::of(self), Layout::of(source)) {
+            (Some(l), Some(s)) if l.block_size().is_some() => (l, s),
+            _ => return Err(Error::UnsupportedFormat),
+        };
+        if mem::discriminant(&layout) != mem::discriminant(&source_layout) {
+            return Err(Error::Mismatch {
+                index: 0,
+                property: "format",
+                found: source.format_name(),
+                expected: self.format_name(),
+            });
+        }
+        let block_size = layout.unit().2;
+
+        let (source_wide, source_high, source_deep) =
+            match source.get_block_count(region.mipmap_level) {
+                Some(count) => count,
+                None => return Err(Error::UnsupportedFormat),
+            };
+        let (wide, high, deep) = match self.get_block_count(mipmap_level) {
+            Some(count) => count,
+            None => return Err(Error::UnsupportedFormat),
+        };
+        if source_deep != deep {
+            return Err(Error::Mismatch {
+                index: 0,
+                property: "depth",
+                found: source_deep.to_string(),
+                expected: deep.to_string(),
+            });
+        }
+        let fits = |x: u32, y: u32, wide: u32, high: u32| {
+            x as u64 + region.width as u64 <= wide as u64
+                && y as u64 + region.height as u64 <= high as u64
+        };
+        if region.mipmap_level >= source.get_num_mipmap_levels()
+            || mipmap_level >= self.get_num_mipmap_levels()
+            || !fits(region.x, region.y, source_wide, source_high)
+            || !fits(x, y, wide, high)
+        {
+            return Err(Error::OutOfBounds);
+        }
+
+        let src = source.get_mipmap_data(region.array_layer, region.mipmap_level)?;
+        let dst = self.get_mut_mipmap_data(array_layer, mipmap_level)?;
+        let length = region.width as usize * block_size;
+        for z in 0..deep as usize {
+            for row in 0..region.height as usize {
+                let from = ((z * source_high as usize + region.y as usize + row)
+                    * source_wide as usize
+                    + region.x as usize)
+                    * block_size;
+                let to = ((z * high as usize + y as usize + row) * wide as usize + x as usize)
+                    * block_size;
+                dst[to..to + length].copy_from_slice(&src[from..from + length]);
+            }
+        }
+        Ok(())
+    }
+
+    // The blocks across and down a mipmap level, checking that blocks are N
+    // bytes long
+    fn check_block_size<const N: usize>(&self, mipmap_level: u32) -> Result<(u32, u32), Error> {
+        let block_size = match Layout::of(self).and_then(|l| l.block_size()) {
+            Some(block_size) => block_size,
+            None => return Err(Error::UnsupportedFormat),
+        };
+        if block_size != N {
+            return Err(Error::InvalidField {
+                field: "block size",
+                value: N as u64,
+                offset: None,
+                expected: Expected::Range(block_size as u64, block_size as u64),
+            });
+        }
+        if mipmap_level >= self.get_num_mipmap_levels() {
+            return Err(Error::OutOfBounds);
+        }
+        let (width, height, _) = self.get_mipmap_dimensions(mipmap_level);
+        Ok((width.div_ceil(4), height.div_ceil(4)))
+    }
+}
+
+// The column, row and slice of the block at an index
+fn position(index: usize, wide: u32, high: u32) -> (u32, u32, u32) {
+    let index = index as u32;
+    (index % wide, index / wide % high, index / (wide * high))
+}
@@ -0,0 +1,366 @@
+// The MIT License (MIT)
+//
+// Copyright (c) 2018 Michael Dilger
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+// Building textures from source images
+
+use crate::alpha::{premultiply, unpremultiply};
+use crate::codec::{linear_to_srgb, srgb_to_linear, Layout};
+use crate::{
+    AlphaMode, Caps2, D3D10ResourceDimension, Dds, DxgiFormat, Error, Expected, NewDxgiParams,
+};
+use image::imageops::{self, FilterType};
+use image::{DynamicImage, Rgba32FImage};
+use std::path::PathBuf;
+
+/// How many mipmap levels a `TextureBuilder` generates
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mipmaps {
+    /// Only the main image
+    None,
+    /// Every level down to 1x1(x1)
+    Full,
+    /// Up to this many levels, including the main image
+    Levels(u32),
+}
+
+#[derive(Debug, Clone)]
+enum Source {
+    Image(DynamicImage),
+    Path(PathBuf),
+}
+
+/// Builds a finished `Dds` from one or more source images, generating the
+/// mipmap levels and encoding everything into the target format.
+///
+/// Each source image becomes an array layer, a cubemap face (in the order +X,
+/// -X, +Y, -Y, +Z, -Z, repeated for cubemap arrays), or a volume slice.
+///
+/// ```no_run
+/// # use ddsfile::{AlphaMode, DxgiFormat, Mipmaps, TextureBuilder};
+/// let dds = TextureBuilder::new(DxgiFormat::BC7_UNorm_sRGB)
+///     .path("albedo.png")
+///     .mipmaps(Mipmaps::Full)
+///     .alpha_mode(AlphaMode::Straight)
+///     .build()?;
+/// # Ok::<(), ddsfile::Error>(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct TextureBuilder {
+    format: DxgiFormat,
+    sources: Vec<Source>,
+    mipmaps: Mipmaps,
+    filter: FilterType,
+    is_cubemap: bool,
+    is_volume: bool,
+    alpha_mode: AlphaMode,
+    premultiplied_sources: bool,
+}
+
+impl TextureBuilder {
+    /// Start building a 2D texture in the given format, with no mipmaps
+    pub fn new(format: DxgiFormat) -> TextureBuilder {
+        TextureBuilder {
+            format,
+            sources: Vec::new(),
+            mipmaps: Mipmaps::None,
+            filter: FilterType::Triangle,
+            is_cubemap: false,
+            is_volume: false,
+            alpha_mode: AlphaMode::Unknown,
+            premultiplied_sources: false,
+        }
+    }
+
+    /// Add a source image as the next layer, face or slice
+    pub fn image(mut self, image: DynamicImage) -> TextureBuilder {
+        self.sources.push(Source::Image(image));
+        self
+    }
+
+    /// Add several source images, in order
+    pub fn images<I: IntoIterator<Item = DynamicImage>>(mut self, images: I) -> TextureBuilder {
+        self.sources.extend(images.into_iter().map(Source::Image));
+        self
+    }
+
+    /// Add a source image file as the next layer, face or slice. The file is
+    /// opened by `build()`, so which file formats can be read depends on the
+    /// features enabled on the `image` crate.
+    pub fn path<P: Into<PathBuf>>(mut self, path: P) -> TextureBuilder {
+        self.sources.push(Source::Path(path.into()));
+        self
+    }
+
+    /// Add several source image files, in order
+    pub fn paths<P: Into<PathBuf>, I: IntoIterator<Item = P>>(
+        mut self,
+        paths: I,
+    ) -> TextureBuilder {
+        self.sources
+            .extend(paths.into_iter().map(|p| Source::Path(p.into())));
+        self
+    }
+
+    /// Set how many mipmap levels to generate
+    pub fn mipmaps(mut self, mipmaps: Mipmaps) -> TextureBuilder {
+        self.mipmaps = mipmaps;
+        self
+    }
+
+    /// Set the filter used to generate mipmap levels (default: Triangle)
+    pub fn filter(mut self, filter: FilterType) -> TextureBuilder {
+        self.filter = filter;
+        self
+    }
+
+    /// Build a cubemap (or cubemap array) from groups of six faces
+    pub fn cubemap(mut self, is_cubemap: bool) -> TextureBuilder {
+        self.is_cubemap = is_cubemap;
+        self
+    }
+
+    /// Build a volume texture, with each source image as a depth slice
+    pub fn volume(mut self, is_volume: bool) -> TextureBuilder {
+        self.is_volume = is_volume;
+        self
+    }
+
+    /// Set the alpha mode written to the DX10 header. The source images are
+    /// taken as straight alpha; with `AlphaMode::PreMultiplied` they are
+    /// premultiplied before encoding. Mipmaps of straight or premultiplied
+    /// textures are filtered with premultiplied alpha.
+    pub fn alpha_mode(mut self, alpha_mode: AlphaMode) -> TextureBuilder {
+        self.alpha_mode = alpha_mode;
+        self
+    }
+
+    /// Take the source images as premultiplied alpha, as `Dds::to_image`
+    /// returns them for premultiplied textures, so that they are not
+    /// premultiplied a second time (default: false)
+    pub fn premultiplied_sources(mut self, premultiplied_sources: bool) -> TextureBuilder {
+        self.premultiplied_sources = premultiplied_sources;
+        self
+    }
+
+    /// Load the sources, generate mipmaps, and encode the texture
+    pub fn build(self) -> Result<Dds, Error> {
+        if Layout::from_dxgi(self.format).is_none() {
+            return Err(Error::UnsupportedFormat);
+        }
+        if self.sources.is_empty() {
+            return Err(Error::InvalidField {
+                field: "images",
+                value: 0,
+                offset: None,
+                expected: Expected::Description("at least one"),
+            });
+        }
+        if self.is_cubemap && self.is_volume {
+            return Err(Error::Conflict {
+                first: "cubemap",
+                second: "volume",
+            });
+        }
+
+        let mut images = Vec::with_capacity(self.sources.len());
+        for source in self.sources {
+            let image = match source {
+                Source::Image(image) => image,
+                Source::Path(path) => image::open(&path)?,
+            };
+            images.push(image.into_rgba32f());
+        }
+
+        let (width, height) = images[0].dimensions();
+        if let Some(index) = images
+            .iter()
+            .position(|i| i.dimensions() != (width, height))
+        {
+            let (w, h) = images[index].dimensions();
+            return Err(Error::Mismatch {
+                index,
+                property: "dimensions",
+                found: format!("{}x{}", w, h),
+                expected: format!("{}x{}", width, height),
+            });
+        }
+        if self.is_cubemap && width != height {
+            return Err(Error::InvalidField {
+                field: "height",
+                value: height as u64,
+                offset: None,
+                expected: Expected::Description("the same as the width, for a cubemap"),
+            });
+        }
+        if self.is_cubemap && images.len() % 6 != 0 {
+            return Err(Error::InvalidField {
+                field: "faces",
+                value: images.len() as u64,
+                offset: None,
+                expected: Expected::Description("a multiple of six"),
+            });
+        }
+
+        let depth = if self.is_volume {
+            images.len() as u32
+        } else {
+            1
+        };
+        let full = 32 - width.max(height).max(depth).leading_zeros();
+        let mipmap_levels = match self.mipmaps {
+            Mipmaps::None => 1,
+            Mipmaps::Full => full,
+            Mipmaps::Levels(levels) => levels.clamp(1, full),
+        };
+
+        let (depth, array_layers, caps2, resource_dimension) = if self.is_volume {
+            (
+                Some(depth),
+                None,
+                Some(Caps2::VOLUME),
+                D3D10ResourceDimension::Texture3D,
+            )
+        } else if self.is_cubemap {
+            (
+                None,
+                Some(images.len() as u32),
+                Some(Caps2::CUBEMAP | Caps2::CUBEMAP_ALLFACES),
+                D3D10ResourceDimension::Texture2D,
+            )
+        } else {
+            (
+                None,
+                Some(images.len() as u32),
+                None,
+                D3D10ResourceDimension::Texture2D,
+            )
+        };
+
+        let mut dds = Dds::new_dxgi(NewDxgiParams {
+            height,
+            width,
+            depth,
+            format: self.format,
+            mipmap_levels: Some(mipmap_levels),
+            array_layers,
+            caps2,
+            is_cubemap: self.is_cubemap,
+            resource_dimension,
+            alpha_mode: self.alpha_mode,
+        })?;
+
+        // Filter in linear space for sRGB formats
+        let srgb = self.format.is_srgb();
+        if srgb {
+            for image in images.iter_mut() {
+                for v in image.pixels_mut() {
+                    for c in &mut v.0[..3] {
+                        *c = srgb_to_linear(*c);
+                    }
+                }
+            }
+        }
+
+        // Filter with premultiplied alpha so transparent texels do not bleed
+        // their color into the mipmaps. The sources are straight alpha unless
+        // told otherwise, and stay premultiplied only when that is the
+        // declared alpha mode.
+        let premultiplied = matches!(
+            self.alpha_mode,
+            AlphaMode::Straight | AlphaMode::PreMultiplied
+        );
+        if premultiplied != self.premultiplied_sources {
+            for image in images.iter_mut() {
+                if premultiplied {
+                    premultiply(image.as_mut());
+                } else {
+                    unpremultiply(image.as_mut());
+                }
+            }
+        }
+
+        // Each chain holds the slices of a volume, or a single layer
+        let chains: Vec<Vec<Rgba32FImage>> = if self.is_volume {
+            vec![images]
+        } else {
+            images.into_iter().map(|i| vec![i]).collect()
+        };
+
+        for (array_layer, mut slices) in chains.into_iter().enumerate() {
+            for mipmap_level in 0..mipmap_levels {
+                if mipmap_level > 0 {
+                    slices = downsample(&slices, self.filter);
+                }
+                let mut rgba = Vec::new();
+                for slice in &slices {
+                    rgba.extend_from_slice(slice.as_raw());
+                }
+                if self.alpha_mode == AlphaMode::Straight {
+                    unpremultiply(&mut rgba);
+                }
+                if srgb {
+                    for v in rgba.chunks_exact_mut(4) {
+                        for c in &mut v[..3] {
+                            *c = linear_to_srgb(*c);
+                        }
+                    }
+                }
+                dds.encode_rgba32f(array_layer as u32, mipmap_level, &rgba)?;
+            }
+        }
+
+        Ok(dds)
+    }
+}
+
+// Halve every dimension of a stack of slices (stopping at 1), averaging pairs
+// of slices for volume textures. An odd slice out joins the last pair.
+fn downsample(slices: &[Rgba32FImage], filter: FilterType) -> Vec<Rgba32FImage> {
+    let (width, height) = slices[0].dimensions();
+    let (width, height) = ((width / 2).max(1), (height / 2).max(1));
+    let resized: Vec<Rgba32FImage> = slices
+        .iter()
+        .map(|s| imageops::resize(s, width, height, filter))
+        .collect();
+    let depth = (resized.len() / 2).max(1);
+    (0..depth)
+        .map(|slice| {
+            let end = if slice + 1 == depth {
+                resized.len()
+            } else {
+                slice * 2 + 2
+            };
+            let group = &resized[slice * 2..end];
+            let mut out = group[0].clone();
+            for other in &group[1..] {
+                for (o, v) in out.iter_mut().zip(other.iter()) {
+                    *o += v;
+                }
+            }
+            let scale = 1.0 / group.len() as f32;
+            for o in out.iter_mut() {
+                *o *= scale;
+            }
+            out
+        })
+        .collect()
+}
@@ -0,0 +1,219 @@
+// The MIT License (MIT)
+//
+// Copyright (c) 2018 Michael Dilger
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+// Swizzling, packing and splitting the channels of textures
+
+use crate::{ComponentType, Dds, DxgiFormat, Error, Expected};
+use std::ptr;
+
+/// Where a channel of a swizzled or packed texture comes from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChannelSource {
+    R,
+    G,
+    B,
+    A,
+    /// The constant 0.0
+    Zero,
+    /// The constant 1.0
+    One,
+    /// The Z of a unit length normal with X in red and Y in green, in the same
+    /// range as them: -1.0 to 1.0 for signed formats, and 0.0 to 1.0 otherwise
+    ReconstructZ,
+}
+
+impl ChannelSource {
+    fn select(self, texel: &[f32], signed: bool) -> f32 {
+        match self {
+            ChannelSource::R => texel[0],
+            ChannelSource::G => texel[1],
+            ChannelSource::B => texel[2],
+            ChannelSource::A => texel[3],
+            ChannelSource::Zero => 0.0,
+            ChannelSource::One => 1.0,
+            ChannelSource::ReconstructZ if signed => {
+                (1.0 - texel[0] * texel[0] - texel[1] * texel[1])
+                    .max(0.0)
+                    .sqrt()
+            }
+            ChannelSource::ReconstructZ => {
+                let (x, y) = (texel[0] * 2.0 - 1.0, texel[1] * 2.0 - 1.0);
+                (1.0 - x * x - y * y).max(0.0).sqrt() * 0.5 + 0.5
+            }
+        }
+    }
+}
+
+impl Dds {
+    /// Rearrange the channels of every subresource, into a new texture in the
+    /// same format. Each channel of the result, in RGBA order, takes the given
+    /// channel of this texture: `[B, G, R, A]` swaps red and blue.
+    ///
+    /// Values are moved as they decode, without converting color spaces, and
+    /// every subresource is encoded again.
+    pub fn swizzle(&self, swizzle: [ChannelSource; 4]) -> Result<Dds, Error> {
+        let mut dds = self.clone();
+        compose(&mut dds, &swizzle.map(|s| Some((self, s))))?;
+        Ok(dds)
+    }
+
+    /// Build a texture in the given format by taking each channel, in RGBA
+    /// order, from a channel of another texture, such as roughness, metallic
+    /// and occlusion from three separate maps. Channels without a source are
+    /// 0.0, except alpha which is 1.0.
+    ///
+    /// The sources must have the same dimensions, array layers and mipmap
+    /// levels. See `swizzle`.
+    pub fn pack_channels(
+        format: DxgiFormat,
+        channels: [Option<(&Dds, ChannelSource)>; 4],
+    ) -> Result<Dds, Error> {
+        let first = match channels.iter().flatten().next() {
+            Some(&(first, _)) => first,
+            None => {
+                return Err(Error::InvalidField {
+                    field: "channels",
+                    value: 0,
+                    offset: None,
+                    expected: Expected::Description("at least one with a source"),
+                })
+            }
+        };
+        for (index, &(source, _)) in channels
+            .iter()
+            .enumerate()
+            .filter_map(|(i, c)| c.as_ref().map(|c| (i, c)))
+        {
+            let mismatch = |property, found: String, expected: String| Error::Mismatch {
+                index,
+                property,
+                found,
+                expected,
+            };
+            let dimensions =
+                |t: &Dds| format!("{}x{}x{}", t.get_width(), t.get_height(), t.get_depth());
+            if dimensions(source) != dimensions(first) || source.is_volume() != first.is_volume() {
+                return Err(mismatch(
+                    "dimensions",
+                    dimensions(source),
+                    dimensions(first),
+                ));
+            }
+            if source.get_num_array_layers() != first.get_num_array_layers()
+                || source.is_cubemap() != first.is_cubemap()
+            {
+                return Err(mismatch(
+                    "array layers",
+                    source.get_num_array_layers().to_string(),
+                    first.get_num_array_layers().to_string(),
+                ));
+            }
+            if source.get_num_mipmap_levels() != first.get_num_mipmap_levels() {
+                return Err(mismatch(
+                    "mipmap levels",
+                    source.get_num_mipmap_levels().to_string(),
+                    first.get_num_mipmap_levels().to_string(),
+                ));
+            }
+        }
+
+        let depth = match first.is_volume() {
+            true => Some(first.get_depth()),
+            false => None,
+        };
+        let mut dds = first.new_dxgi_like(
+            format,
+            (first.get_width(), first.get_height(), depth),
+            first.get_num_mipmap_levels(),
+            first.get_num_array_layers(),
+            first.is_cubemap(),
+        )?;
+        compose(&mut dds, &channels)?;
+        Ok(dds)
+    }
+
+    /// Copy a single channel into the red channel of a new texture in the
+    /// given format, usually a single channel one such as R8_UNorm
+    pub fn extract_channel(
+        &self,
+        channel: ChannelSource,
+        format: DxgiFormat,
+    ) -> Result<Dds, Error> {
+        Dds::pack_channels(format, [Some((self, channel)), None, None, None])
+    }
+
+    /// Split the red, green, blue and alpha channels into four new textures in
+    /// the given format. See `extract_channel`.
+    pub fn split_channels(&self, format: DxgiFormat) -> Result<Vec<Dds>, Error> {
+        [
+            ChannelSource::R,
+            ChannelSource::G,
+            ChannelSource::B,
+            ChannelSource::A,
+        ]
+        .into_iter()
+        .map(|channel| self.extract_channel(channel, format))
+        .collect()
+    }
+}
+
+// Encode every subresource of a texture from the channels of others, decoding
+// each source once per subresource
+fn compose(target: &mut Dds, channels: &[Option<(&Dds, ChannelSource)>; 4]) -> Result<(), Error> {
+    for array_layer in 0..target.get_num_array_layers() {
+        for mipmap_level in 0..target.get_num_mipmap_levels() {
+            let mut decoded: Vec<(&Dds, Vec<f32>)> = Vec::new();
+            for &(source, _) in channels.iter().flatten() {
+                if !decoded.iter().any(|(d, _)| ptr::eq(*d, source)) {
+                    decoded.push((source, source.decode_rgba32f(array_layer, mipmap_level)?));
+                }
+            }
+
+            let (width, height, depth) = target.get_mipmap_dimensions(mipmap_level);
+            let mut rgba = vec![0.0; width as usize * height as usize * depth as usize * 4];
+            for (c, channel) in channels.iter().enumerate() {
+                let (source, select) = match *channel {
+                    Some(channel) => channel,
+                    None => {
+                        if c == 3 {
+                            rgba.chunks_exact_mut(4).for_each(|texel| texel[3] = 1.0);
+                        }
+                        continue;
+                    }
+                };
+                let signed = match source.get_format_info() {
+                    Some(info) => info.component_type == ComponentType::SNorm,
+                    None => false,
+                };
+                let data = match decoded.iter().find(|(d, _)| ptr::eq(*d, source)) {
+                    Some((_, data)) => data,
+                    None => continue,
+                };
+                for (out, texel) in rgba.chunks_exact_mut(4).zip(data.chunks_exact(4)) {
+                    out[c] = select.select(texel, signed);
+                }
+            }
+            target.encode_rgba32f(array_layer, mipmap_level, &rgba)?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,329 @@
+// The MIT License (MIT)
+//
+// Copyright (c) 2018 Michael Dilger
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+// BC1 through BC5 (the DXTn and RGTC families)
+
+fn unpack_565(c: u16) -> [f32; 3] {
+    [
+        ((c >> 11) & 0x1f) as f32 / 31.0,
+        ((c >> 5) & 0x3f) as f32 / 63.0,
+        (c & 0x1f) as f32 / 31.0,
+    ]
+}
+
+fn pack_565(rgb: [f32; 3]) -> u16 {
+    let r = (rgb[0].clamp(0.0, 1.0) * 31.0).round() as u16;
+    let g = (rgb[1].clamp(0.0, 1.0) * 63.0).round() as u16;
+    let b = (rgb[2].clamp(0.0, 1.0) * 31.0).round() as u16;
+    (r << 11) | (g << 5) | b
+}
+
+fn lerp3(a: [f32; 3], b: [f32; 3], wa: f32, wb: f32, d: f32) -> [f32; 3] {
+    [
+        (a[0] * wa + b[0] * wb) / d,
+        (a[1] * wa + b[1] * wb) / d,
+        (a[2] * wa + b[2] * wb) / d,
+    ]
+}
+
+fn color_palette(c0: u16, c1: u16, four_color: bool) -> [[f32; 4]; 4] {
+    let e0 = unpack_565(c0);
+    let e1 = unpack_565(c1);
+    let rgba = |c: [f32; 3], a: f32| [c[0], c[1], c[2], a];
+    if four_color || c0 > c1 {
+        [
+            rgba(e0, 1.0),
+            rgba(e1, 1.0),
+            rgba(lerp3(e0, e1, 2.0, 1.0, 3.0), 1.0),
+            rgba(lerp3(e0, e1, 1.0, 2.0, 3.0), 1.0),
+        ]
+    } else {
+        [
+            rgba(e0, 1.0),
+            rgba(e1, 1.0),
+            rgba(lerp3(e0, e1, 1.0, 1.0, 2.0), 1.0),
+            [0.0, 0.0, 0.0, 0.0],
+        ]
+    }
+}
+
+/// Decode a BC1 style color block. BC2 and BC3 always use four colors.
+pub fn decode_color(block: &[u8], four_color: bool, out: &mut [[f32; 4]; 16]) {
+    let c0 = u16::from_le_bytes([block[0], block[1]]);
+    let c1 = u16::from_le_bytes([block[2], block[3]]);
+    let palette = color_palette(c0, c1, four_color);
+    let indices = u32::from_le_bytes([block[4], block[5], block[6], block[7]]);
+    for (i, texel) in out.iter_mut().enumerate() {
+        *texel = palette[((indices >> (2 * i)) & 3) as usize];
+    }
+}
+
+/// Decode a BC2 explicit alpha block into the alpha channel
+pub fn decode_explicit_alpha(block: &[u8], out: &mut [[f32; 4]; 16]) {
+    let alpha = u64::from_le_bytes([
+        block[0], block[1], block[2], block[3], block[4], block[5], block[6], block[7],
+    ]);
+    for (i, texel) in out.iter_mut().enumerate() {
+        texel[3] = ((alpha >> (4 * i)) & 0xf) as f32 / 15.0;
+    }
+}
+
+fn single_palette(e0: f32, e1: f32, eight_values: bool, signed: bool) -> [f32; 8] {
+    let mut palette = [e0, e1, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0];
+    if eight_values {
+        for (j, value) in palette.iter_mut().enumerate().skip(2) {
+            let w = (j - 1) as f32;
+            *value = ((7.0 - w) * e0 + w * e1) / 7.0;
+        }
+    } else {
+        for (j, value) in palette.iter_mut().enumerate().take(6).skip(2) {
+            let w = (j - 1) as f32;
+            *value = ((5.0 - w) * e0 + w * e1) / 5.0;
+        }
+        palette[6] = if signed { -1.0 } else { 0.0 };
+        palette[7] = 1.0;
+    }
+    palette
+}
+
+/// Decode a BC4 style single channel block (also the BC3 alpha block)
+pub fn decode_single(block: &[u8], signed: bool, out: &mut [f32; 16]) {
+    let (e0, e1, eight_values) = if signed {
+        let e0 = (block[0] as i8).max(-127);
+        let e1 = (block[1] as i8).max(-127);
+        (e0 as f32 / 127.0, e1 as f32 / 127.0, e0 > e1)
+    } else {
+        (
+            block[0] as f32 / 255.0,
+            block[1] as f32 / 255.0,
+            block[0] > block[1],
+        )
+    };
+    let palette = single_palette(e0, e1, eight_values, signed);
+    let indices = u64::from_le_bytes([
+        block[2], block[3], block[4], block[5], block[6], block[7], 0, 0,
+    ]);
+    for (i, value) in out.iter_mut().enumerate() {
+        *value = palette[((indices >> (3 * i)) & 7) as usize];
+    }
+}
+
+/// Mirror the indices of a block, packed row by row from the least
+/// significant bit with the given number of bits per texel
+pub fn mirror_indices(indices: &mut [u8], bits: u32, horizontal: bool, vertical: bool) {
+    let mut packed = 0u64;
+    for (i, byte) in indices.iter().enumerate() {
+        packed |= (*byte as u64) << (8 * i);
+    }
+    let mask = (1u64 << bits) - 1;
+    let mut mirrored = 0u64;
+    for i in 0..16 {
+        let x = if horizontal { 3 - i % 4 } else { i % 4 };
+        let y = if vertical { 3 - i / 4 } else { i / 4 };
+        mirrored |= ((packed >> (bits * i)) & mask) << (bits * (y * 4 + x));
+    }
+    for (i, byte) in indices.iter_mut().enumerate() {
+        *byte = (mirrored >> (8 * i)) as u8;
+    }
+}
+
+fn distance(a: &[f32], b: &[f32]) -> f32 {
+    a.iter().zip(b).map(|(x, y)| (x - y) * (x - y)).sum()
+}
+
+fn nearest(palette: &[[f32; 4]], texel: &[f32]) -> u32 {
+    let mut best = 0;
+    let mut best_distance = f32::MAX;
+    for (i, entry) in palette.iter().enumerate() {
+        let d = distance(&entry[..3], &texel[..3]);
+        if d < best_distance {
+            best = i;
+            best_distance = d;
+        }
+    }
+    best as u32
+}
+
+/// Find the endpoints of the line that best fits the texels, by projecting
+/// them onto their principal axis.
+pub fn fit_line<const N: usize>(texels: &[[f32; N]]) -> ([f32; N], [f32; N]) {
+    if texels.is_empty() {
+        return ([0.0; N], [0.0; N]);
+    }
+    let count = texels.len() as f32;
+    let mut mean = [0.0; N];
+    for texel in texels {
+        for c in 0..N {
+            mean[c] += texel[c] / count;
+        }
+    }
+    let mut covariance = [[0.0_f32; N]; N];
+    for texel in texels {
+        for i in 0..N {
+            for j in 0..N {
+                covariance[i][j] += (texel[i] - mean[i]) * (texel[j] - mean[j]);
+            }
+        }
+    }
+    // Power iteration, starting from the channel with the most variance
+    let mut widest = 0;
+    for c in 1..N {
+        if covariance[c][c] > covariance[widest][widest] {
+            widest = c;
+        }
+    }
+    // Start from a unit vector, so that the products stay in range for large
+    // values such as the unquantized BC6H ones
+    let mut axis = covariance[widest];
+    let length = axis.iter().map(|v| v * v).sum::<f32>().sqrt();
+    if length < 1e-12 {
+        return (mean, mean);
+    }
+    for value in axis.iter_mut() {
+        *value /= length;
+    }
+    for _ in 0..8 {
+        let mut next = [0.0; N];
+        for i in 0..N {
+            for j in 0..N {
+                next[i] += covariance[i][j] * axis[j];
+            }
+        }
+        let length = next.iter().map(|v| v * v).sum::<f32>().sqrt();
+        if length < 1e-12 {
+            break;
+        }
+        for i in 0..N {
+            axis[i] = next[i] / length;
+        }
+    }
+    let length = axis.iter().map(|v| v * v).sum::<f32>().sqrt();
+    if length < 1e-12 {
+        return (mean, mean);
+    }
+    for value in axis.iter_mut() {
+        *value /= length;
+    }
+    let mut min_t = f32::MAX;
+    let mut max_t = f32::MIN;
+    for texel in texels {
+        let t: f32 = (0..N).map(|c| (texel[c] - mean[c]) * axis[c]).sum();
+        min_t = min_t.min(t);
+        max_t = max_t.max(t);
+    }
+    // Keep the endpoints inside the bounding box of the texels
+    let mut low = mean;
+    let mut high = mean;
+    for c in 0..N {
+        let min = texels.iter().map(|t| t[c]).fold(f32::MAX, f32::min);
+        let max = texels.iter().map(|t| t[c]).fold(f32::MIN, f32::max);
+        low[c] = (low[c] + axis[c] * min_t).clamp(min, max);
+        high[c] = (high[c] + axis[c] * max_t).clamp(min, max);
+    }
+    (low, high)
+}
+
+/// Encode a BC1 style color block. With `punch_through`, texels with alpha
+/// below one half become transparent black.
+pub fn encode_color(texels: &[[f32; 4]; 16], punch_through: bool) -> [u8; 8] {
+    let transparent = |t: &[f32; 4]| punch_through && t[3] < 0.5;
+    let opaque: Vec<[f32; 3]> = texels
+        .iter()
+        .filter(|t| !transparent(t))
+        .map(|t| [t[0], t[1], t[2]])
+        .collect();
+    let has_transparent = opaque.len() < 16;
+    let (low, high) = fit_line(&opaque);
+    let mut c0 = pack_565(high);
+    let mut c1 = pack_565(low);
+    if has_transparent {
+        // Three colors plus transparent black needs c0 <= c1
+        if c0 > c1 {
+            std::mem::swap(&mut c0, &mut c1);
+        }
+    } else if c0 < c1 {
+        std::mem::swap(&mut c0, &mut c1);
+    }
+    let palette = color_palette(c0, c1, false);
+    let mut indices = 0_u32;
+    for (i, texel) in texels.iter().enumerate() {
+        let index = if transparent(texel) {
+            3
+        } else if has_transparent {
+            nearest(&palette[..3], texel)
+        } else if c0 == c1 {
+            0
+        } else {
+            nearest(&palette, texel)
+        };
+        indices |= index << (2 * i);
+    }
+    let mut block = [0_u8; 8];
+    block[0..2].copy_from_slice(&c0.to_le_bytes());
+    block[2..4].copy_from_slice(&c1.to_le_bytes());
+    block[4..8].copy_from_slice(&indices.to_le_bytes());
+    block
+}
+
+/// Encode a BC2 explicit alpha block from the alpha channel
+pub fn encode_explicit_alpha(texels: &[[f32; 4]; 16]) -> [u8; 8] {
+    let mut alpha = 0_u64;
+    for (i, texel) in texels.iter().enumerate() {
+        alpha |= ((texel[3].clamp(0.0, 1.0) * 15.0).round() as u64) << (4 * i);
+    }
+    alpha.to_le_bytes()
+}
+
+/// Encode a BC4 style single channel block (also the BC3 alpha block)
+pub fn encode_single(values: &[f32; 16], signed: bool) -> [u8; 8] {
+    let min = values.iter().copied().fold(f32::MAX, f32::min);
+    let max = values.iter().copied().fold(f32::MIN, f32::max);
+    let (b0, b1, e0, e1) = if signed {
+        let e0 = (max.clamp(-1.0, 1.0) * 127.0).round() as i8;
+        let e1 = (min.clamp(-1.0, 1.0) * 127.0).round() as i8;
+        (e0 as u8, e1 as u8, e0 as f32 / 127.0, e1 as f32 / 127.0)
+    } else {
+        let e0 = (max.clamp(0.0, 1.0) * 255.0).round() as u8;
+        let e1 = (min.clamp(0.0, 1.0) * 255.0).round() as u8;
+        (e0, e1, e0 as f32 / 255.0, e1 as f32 / 255.0)
+    };
+    // Equal endpoints select the six value mode, where index 0 is still e0
+    let palette = single_palette(e0, e1, b0 != b1, signed);
+    let mut indices = 0_u64;
+    for (i, value) in values.iter().enumerate() {
+        let mut best = 0;
+        let mut best_distance = f32::MAX;
+        for (j, entry) in palette.iter().enumerate() {
+            let d = (entry - value).abs();
+            if d < best_distance {
+                best = j;
+                best_distance = d;
+            }
+        }
+        indices |= (best as u64) << (3 * i);
+    }
+    let mut block = [0_u8; 8];
+    block[0] = b0;
+    block[1] = b1;
+    block[2..8].copy_from_slice(&indices.to_le_bytes()[..6]);
+    block
+}
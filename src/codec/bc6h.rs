@@ -0,0 +1,379 @@
+// The MIT License (MIT)
+//
+// Copyright (c) 2018 Michael Dilger
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+// BC6H (BPTC float)
+
+use super::bc::fit_line;
+use super::bc7::{Bits, ANCHORS2, PARTITIONS2, WEIGHTS3, WEIGHTS4};
+use super::{f16_to_f32, f32_to_f16};
+
+// Endpoint fields: w and x are the first region, y and z the second
+const RW: u8 = 0;
+const GW: u8 = 1;
+const BW: u8 = 2;
+const RX: u8 = 3;
+const GX: u8 = 4;
+const BX: u8 = 5;
+const RY: u8 = 6;
+const GY: u8 = 7;
+const BY: u8 = 8;
+const RZ: u8 = 9;
+const GZ: u8 = 10;
+const BZ: u8 = 11;
+const D: u8 = 12;
+
+struct ModeInfo {
+    mode: u32,
+    transformed: bool,
+    endpoint_bits: u32,
+    delta_bits: [u32; 3],
+    /// (field, first bit, bit count) in stream order, after the mode bits
+    layout: &'static [(u8, u8, u8)],
+}
+
+#[rustfmt::skip]
+const MODES: [ModeInfo; 14] = [
+    ModeInfo { mode: 0, transformed: true, endpoint_bits: 10, delta_bits: [5, 5, 5], layout: &[
+        (GY, 4, 1), (BY, 4, 1), (BZ, 4, 1), (RW, 0, 10), (GW, 0, 10), (BW, 0, 10), (RX, 0, 5),
+        (GZ, 4, 1), (GY, 0, 4), (GX, 0, 5), (BZ, 0, 1), (GZ, 0, 4), (BX, 0, 5), (BZ, 1, 1),
+        (BY, 0, 4), (RY, 0, 5), (BZ, 2, 1), (RZ, 0, 5), (BZ, 3, 1), (D, 0, 5)] },
+    ModeInfo { mode: 1, transformed: true, endpoint_bits: 7, delta_bits: [6, 6, 6], layout: &[
+        (GY, 5, 1), (GZ, 4, 1), (GZ, 5, 1), (RW, 0, 7), (BZ, 0, 1), (BZ, 1, 1), (BY, 4, 1),
+        (GW, 0, 7), (BY, 5, 1), (BZ, 2, 1), (GY, 4, 1), (BW, 0, 7), (BZ, 3, 1), (BZ, 5, 1),
+        (BZ, 4, 1), (RX, 0, 6), (GY, 0, 4), (GX, 0, 6), (GZ, 0, 4), (BX, 0, 6), (BY, 0, 4),
+        (RY, 0, 6), (RZ, 0, 6), (D, 0, 5)] },
+    ModeInfo { mode: 2, transformed: true, endpoint_bits: 11, delta_bits: [5, 4, 4], layout: &[
+        (RW, 0, 10), (GW, 0, 10), (BW, 0, 10), (RX, 0, 5), (RW, 10, 1), (GY, 0, 4), (GX, 0, 4),
+        (GW, 10, 1), (BZ, 0, 1), (GZ, 0, 4), (BX, 0, 4), (BW, 10, 1), (BZ, 1, 1), (BY, 0, 4),
+        (RY, 0, 5), (BZ, 2, 1), (RZ, 0, 5), (BZ, 3, 1), (D, 0, 5)] },
+    ModeInfo { mode: 6, transformed: true, endpoint_bits: 11, delta_bits: [4, 5, 4], layout: &[
+        (RW, 0, 10), (GW, 0, 10), (BW, 0, 10), (RX, 0, 4), (RW, 10, 1), (GZ, 4, 1), (GY, 0, 4),
+        (GX, 0, 5), (GW, 10, 1), (GZ, 0, 4), (BX, 0, 4), (BW, 10, 1), (BZ, 1, 1), (BY, 0, 4),
+        (RY, 0, 4), (BZ, 0, 1), (BZ, 2, 1), (RZ, 0, 4), (GY, 4, 1), (BZ, 3, 1), (D, 0, 5)] },
+    ModeInfo { mode: 10, transformed: true, endpoint_bits: 11, delta_bits: [4, 4, 5], layout: &[
+        (RW, 0, 10), (GW, 0, 10), (BW, 0, 10), (RX, 0, 4), (RW, 10, 1), (BY, 4, 1), (GY, 0, 4),
+        (GX, 0, 4), (GW, 10, 1), (BZ, 0, 1), (GZ, 0, 4), (BX, 0, 5), (BW, 10, 1), (BY, 0, 4),
+        (RY, 0, 4), (BZ, 1, 1), (BZ, 2, 1), (RZ, 0, 4), (BZ, 4, 1), (BZ, 3, 1), (D, 0, 5)] },
+    ModeInfo { mode: 14, transformed: true, endpoint_bits: 9, delta_bits: [5, 5, 5], layout: &[
+        (RW, 0, 9), (BY, 4, 1), (GW, 0, 9), (GY, 4, 1), (BW, 0, 9), (BZ, 4, 1), (RX, 0, 5),
+        (GZ, 4, 1), (GY, 0, 4), (GX, 0, 5), (BZ, 0, 1), (GZ, 0, 4), (BX, 0, 5), (BZ, 1, 1),
+        (BY, 0, 4), (RY, 0, 5), (BZ, 2, 1), (RZ, 0, 5), (BZ, 3, 1), (D, 0, 5)] },
+    ModeInfo { mode: 18, transformed: true, endpoint_bits: 8, delta_bits: [6, 5, 5], layout: &[
+        (RW, 0, 8), (GZ, 4, 1), (BY, 4, 1), (GW, 0, 8), (BZ, 2, 1), (GY, 4, 1), (BW, 0, 8),
+        (BZ, 3, 1), (BZ, 4, 1), (RX, 0, 6), (GY, 0, 4), (GX, 0, 5), (BZ, 0, 1), (GZ, 0, 4),
+        (BX, 0, 5), (BZ, 1, 1), (BY, 0, 4), (RY, 0, 6), (RZ, 0, 6), (D, 0, 5)] },
+    ModeInfo { mode: 22, transformed: true, endpoint_bits: 8, delta_bits: [5, 6, 5], layout: &[
+        (RW, 0, 8), (BZ, 0, 1), (BY, 4, 1), (GW, 0, 8), (GY, 5, 1), (GY, 4, 1), (BW, 0, 8),
+        (GZ, 5, 1), (BZ, 4, 1), (RX, 0, 5), (GZ, 4, 1), (GY, 0, 4), (GX, 0, 6), (GZ, 0, 4),
+        (BX, 0, 5), (BZ, 1, 1), (BY, 0, 4), (RY, 0, 5), (BZ, 2, 1), (RZ, 0, 5), (BZ, 3, 1),
+        (D, 0, 5)] },
+    ModeInfo { mode: 26, transformed: true, endpoint_bits: 8, delta_bits: [5, 5, 6], layout: &[
+        (RW, 0, 8), (BZ, 1, 1), (BY, 4, 1), (GW, 0, 8), (BY, 5, 1), (GY, 4, 1), (BW, 0, 8),
+        (BZ, 5, 1), (BZ, 4, 1), (RX, 0, 5), (GZ, 4, 1), (GY, 0, 4), (GX, 0, 5), (BZ, 0, 1),
+        (GZ, 0, 4), (BX, 0, 6), (BY, 0, 4), (RY, 0, 5), (BZ, 2, 1), (RZ, 0, 5), (BZ, 3, 1),
+        (D, 0, 5)] },
+    ModeInfo { mode: 30, transformed: false, endpoint_bits: 6, delta_bits: [6, 6, 6], layout: &[
+        (RW, 0, 6), (GZ, 4, 1), (BZ, 0, 1), (BZ, 1, 1), (BY, 4, 1), (GW, 0, 6), (GY, 5, 1),
+        (BY, 5, 1), (BZ, 2, 1), (GY, 4, 1), (BW, 0, 6), (GZ, 5, 1), (BZ, 3, 1), (BZ, 5, 1),
+        (BZ, 4, 1), (RX, 0, 6), (GY, 0, 4), (GX, 0, 6), (GZ, 0, 4), (BX, 0, 6), (BY, 0, 4),
+        (RY, 0, 6), (RZ, 0, 6), (D, 0, 5)] },
+    ModeInfo { mode: 3, transformed: false, endpoint_bits: 10, delta_bits: [10, 10, 10], layout: &[
+        (RW, 0, 10), (GW, 0, 10), (BW, 0, 10), (RX, 0, 10), (GX, 0, 10), (BX, 0, 10)] },
+    ModeInfo { mode: 7, transformed: true, endpoint_bits: 11, delta_bits: [9, 9, 9], layout: &[
+        (RW, 0, 10), (GW, 0, 10), (BW, 0, 10), (RX, 0, 9), (RW, 10, 1), (GX, 0, 9), (GW, 10, 1),
+        (BX, 0, 9), (BW, 10, 1)] },
+    ModeInfo { mode: 11, transformed: true, endpoint_bits: 12, delta_bits: [8, 8, 8], layout: &[
+        (RW, 0, 10), (GW, 0, 10), (BW, 0, 10), (RX, 0, 8), (RW, 11, 1), (RW, 10, 1), (GX, 0, 8),
+        (GW, 11, 1), (GW, 10, 1), (BX, 0, 8), (BW, 11, 1), (BW, 10, 1)] },
+    ModeInfo { mode: 15, transformed: true, endpoint_bits: 16, delta_bits: [4, 4, 4], layout: &[
+        (RW, 0, 10), (GW, 0, 10), (BW, 0, 10), (RX, 0, 4), (RW, 15, 1), (RW, 14, 1), (RW, 13, 1),
+        (RW, 12, 1), (RW, 11, 1), (RW, 10, 1), (GX, 0, 4), (GW, 15, 1), (GW, 14, 1), (GW, 13, 1),
+        (GW, 12, 1), (GW, 11, 1), (GW, 10, 1), (BX, 0, 4), (BW, 15, 1), (BW, 14, 1), (BW, 13, 1),
+        (BW, 12, 1), (BW, 11, 1), (BW, 10, 1)] },
+];
+
+fn sign_extend(value: u32, bits: u32) -> i32 {
+    let shift = 32 - bits;
+    ((value << shift) as i32) >> shift
+}
+
+fn unquantize(value: i32, bits: u32, signed: bool) -> i32 {
+    if signed {
+        if bits >= 16 {
+            return value;
+        }
+        let (negative, magnitude) = (value < 0, value.abs());
+        let unquantized = if magnitude == 0 {
+            0
+        } else if magnitude >= (1 << (bits - 1)) - 1 {
+            0x7fff
+        } else {
+            ((magnitude << 15) + 0x4000) >> (bits - 1)
+        };
+        if negative {
+            -unquantized
+        } else {
+            unquantized
+        }
+    } else if bits >= 15 {
+        value
+    } else if value == 0 {
+        0
+    } else if value == (1 << bits) - 1 {
+        0xffff
+    } else {
+        ((value << 16) + 0x8000) >> bits
+    }
+}
+
+// Scale an interpolated value into the bits of a half float
+fn finish(value: i32, signed: bool) -> u16 {
+    if signed {
+        if value < 0 {
+            0x8000 | ((-value * 31) >> 5) as u16
+        } else {
+            ((value * 31) >> 5) as u16
+        }
+    } else {
+        ((value * 31) >> 6) as u16
+    }
+}
+
+/// Decode a BC6H block
+pub fn decode(block: &[u8], signed: bool, out: &mut [[f32; 4]; 16]) {
+    let mut bits = Bits::new(block);
+    let mut mode = bits.read(2);
+    if mode > 1 {
+        mode |= bits.read(3) << 2;
+    }
+    let info = match MODES.iter().find(|m| m.mode == mode) {
+        Some(info) => info,
+        None => {
+            // Reserved modes decode to black
+            *out = [[0.0, 0.0, 0.0, 1.0]; 16];
+            return;
+        }
+    };
+
+    let mut fields = [0_u32; 13];
+    for &(field, first, count) in info.layout {
+        fields[field as usize] |= bits.read(count as u32) << first;
+    }
+    let regions = if info.layout.iter().any(|&(field, _, _)| field == D) {
+        2
+    } else {
+        1
+    };
+    let partition = fields[D as usize] as usize;
+
+    let mut endpoints = [[0_i32; 3]; 4];
+    let mask = ((1_u64 << info.endpoint_bits) - 1) as i32;
+    for c in 0..3 {
+        let w = fields[c] as i32;
+        endpoints[0][c] = if signed {
+            sign_extend(w as u32, info.endpoint_bits)
+        } else {
+            w
+        };
+        for e in 1..regions * 2 {
+            let raw = fields[e * 3 + c];
+            endpoints[e][c] = if info.transformed {
+                let delta = sign_extend(raw, info.delta_bits[c]);
+                let value = (w + delta) & mask;
+                if signed {
+                    sign_extend(value as u32, info.endpoint_bits)
+                } else {
+                    value
+                }
+            } else if signed {
+                sign_extend(raw, info.endpoint_bits)
+            } else {
+                raw as i32
+            };
+        }
+    }
+    for endpoint in endpoints.iter_mut() {
+        for value in endpoint.iter_mut() {
+            *value = unquantize(*value, info.endpoint_bits, signed);
+        }
+    }
+
+    let (index_bits, weights): (u32, &[u32]) = if regions == 2 {
+        (3, &WEIGHTS3)
+    } else {
+        (4, &WEIGHTS4)
+    };
+    for (texel, rgba) in out.iter_mut().enumerate() {
+        let region = if regions == 2 {
+            ((PARTITIONS2[partition] >> texel) & 1) as usize
+        } else {
+            0
+        };
+        let anchor = texel == 0 || (regions == 2 && ANCHORS2[partition] as usize == texel);
+        let weight = weights[bits.read(index_bits - anchor as u32) as usize] as i32;
+        for c in 0..3 {
+            let e0 = endpoints[region * 2][c];
+            let e1 = endpoints[region * 2 + 1][c];
+            let value = ((64 - weight) * e0 + weight * e1 + 32) >> 6;
+            rgba[c] = f16_to_f32(finish(value, signed));
+        }
+        rgba[3] = 1.0;
+    }
+}
+
+// Map a float into the linear domain the endpoints are interpolated in
+fn to_unquantized(value: f32, signed: bool) -> f32 {
+    let half = f32_to_f16(value);
+    let magnitude = ((half & 0x7fff) as u32).min(0x7bff) as f32;
+    let negative = half & 0x8000 != 0 && !value.is_nan();
+    if signed {
+        let unquantized = magnitude * 32.0 / 31.0;
+        if negative {
+            -unquantized
+        } else {
+            unquantized
+        }
+    } else if negative {
+        0.0
+    } else {
+        magnitude * 64.0 / 31.0
+    }
+}
+
+// Find the 10 bit endpoint whose unquantized value is closest
+fn quantize(value: f32, signed: bool) -> i32 {
+    let estimate = if signed {
+        ((value.abs() - 32.0) / 64.0).round() as i32 * value.signum() as i32
+    } else {
+        (value / 64.0 - 0.5).round() as i32
+    };
+    let (min, max) = if signed { (-511, 511) } else { (0, 1023) };
+    let mut best = estimate.clamp(min, max);
+    let mut best_error = f32::MAX;
+    for candidate in estimate - 1..=estimate + 1 {
+        let candidate = candidate.clamp(min, max);
+        let error = (unquantize(candidate, 10, signed) as f32 - value).abs();
+        if error < best_error {
+            best = candidate;
+            best_error = error;
+        }
+    }
+    best
+}
+
+// Pick the index of each texel, returning the indices and the squared error
+fn select_indices(
+    texels: &[[f32; 4]; 16],
+    endpoints: &[[i32; 3]; 2],
+    signed: bool,
+) -> ([u32; 16], f32) {
+    let e0 = endpoints[0].map(|v| unquantize(v, 10, signed));
+    let e1 = endpoints[1].map(|v| unquantize(v, 10, signed));
+    let mut palette = [[0.0_f32; 3]; 16];
+    for (entry, &weight) in palette.iter_mut().zip(WEIGHTS4.iter()) {
+        let w = weight as i32;
+        for c in 0..3 {
+            let value = ((64 - w) * e0[c] + w * e1[c] + 32) >> 6;
+            entry[c] = f16_to_f32(finish(value, signed));
+        }
+    }
+    let mut indices = [0_u32; 16];
+    let mut total = 0.0;
+    for (texel, index) in texels.iter().zip(indices.iter_mut()) {
+        let mut best_distance = f32::MAX;
+        for (i, entry) in palette.iter().enumerate() {
+            let d: f32 = (0..3).map(|c| (entry[c] - texel[c]).powi(2)).sum();
+            if d < best_distance {
+                *index = i as u32;
+                best_distance = d;
+            }
+        }
+        total += best_distance;
+    }
+    (indices, total)
+}
+
+/// Encode a BC6H block, using mode 11 (one region with 10 bit endpoints)
+pub fn encode(texels: &[[f32; 4]; 16], signed: bool) -> [u8; 16] {
+    let values: Vec<[f32; 3]> = texels
+        .iter()
+        .map(|t| {
+            [
+                to_unquantized(t[0], signed),
+                to_unquantized(t[1], signed),
+                to_unquantized(t[2], signed),
+            ]
+        })
+        .collect();
+
+    // Try both the best fit line and the diagonal of the bounding box, as the
+    // interpolation is not linear in the value of the texels
+    let (low, high) = fit_line(&values);
+    let mut min = values[0];
+    let mut max = values[0];
+    for value in values.iter() {
+        for c in 0..3 {
+            min[c] = min[c].min(value[c]);
+            max[c] = max[c].max(value[c]);
+        }
+    }
+    let mut best = None;
+    for (low, high) in [(low, high), (min, max)] {
+        let endpoints = [
+            low.map(|v| quantize(v, signed)),
+            high.map(|v| quantize(v, signed)),
+        ];
+        let (indices, error) = select_indices(texels, &endpoints, signed);
+        match best {
+            Some((_, _, best_error)) if best_error <= error => {}
+            _ => best = Some((endpoints, indices, error)),
+        }
+    }
+    let (mut endpoints, mut indices, _) = match best {
+        Some(best) => best,
+        None => return [0; 16],
+    };
+
+    // The anchor index is stored without its high bit
+    if indices[0] >= 8 {
+        endpoints.swap(0, 1);
+        for index in indices.iter_mut() {
+            *index = 15 - *index;
+        }
+    }
+
+    let mut value = 3_u128;
+    let mut position = 5;
+    let mut write = |count: u32, bits: u32| {
+        value |= ((bits & ((1 << count) - 1)) as u128) << position;
+        position += count;
+    };
+    for endpoint in endpoints.iter() {
+        for c in endpoint.iter() {
+            write(10, *c as u32);
+        }
+    }
+    for (texel, index) in indices.iter().enumerate() {
+        write(if texel == 0 { 3 } else { 4 }, *index);
+    }
+    value.to_le_bytes()
+}
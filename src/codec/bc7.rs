@@ -0,0 +1,379 @@
+// The MIT License (MIT)
+//
+// Copyright (c) 2018 Michael Dilger
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+// BC7 (BPTC)
+
+use super::bc::fit_line;
+
+pub struct Bits {
+    value: u128,
+    position: u32,
+}
+
+impl Bits {
+    pub fn new(block: &[u8]) -> Bits {
+        let mut raw = [0_u8; 16];
+        raw.copy_from_slice(&block[..16]);
+        Bits {
+            value: u128::from_le_bytes(raw),
+            position: 0,
+        }
+    }
+
+    pub fn read(&mut self, count: u32) -> u32 {
+        let value = (self.value >> self.position) as u32 & ((1_u64 << count) - 1) as u32;
+        self.position += count;
+        value
+    }
+}
+
+struct BitWriter {
+    value: u128,
+    position: u32,
+}
+
+impl BitWriter {
+    fn write(&mut self, count: u32, value: u32) {
+        self.value |= (value as u128 & ((1_u128 << count) - 1)) << self.position;
+        self.position += count;
+    }
+}
+
+struct ModeInfo {
+    subsets: usize,
+    partition_bits: u32,
+    rotation_bits: u32,
+    index_selection_bits: u32,
+    color_bits: u32,
+    alpha_bits: u32,
+    endpoint_pbits: bool,
+    shared_pbits: bool,
+    index_bits: u32,
+    index2_bits: u32,
+}
+
+macro_rules! mode {
+    ($ns:expr, $pb:expr, $rb:expr, $isb:expr, $cb:expr, $ab:expr, $epb:expr, $spb:expr, $ib:expr, $ib2:expr) => {
+        ModeInfo {
+            subsets: $ns,
+            partition_bits: $pb,
+            rotation_bits: $rb,
+            index_selection_bits: $isb,
+            color_bits: $cb,
+            alpha_bits: $ab,
+            endpoint_pbits: $epb,
+            shared_pbits: $spb,
+            index_bits: $ib,
+            index2_bits: $ib2,
+        }
+    };
+}
+
+#[rustfmt::skip]
+const MODES: [ModeInfo; 8] = [
+    mode!(3, 4, 0, 0, 4, 0, true,  false, 3, 0),
+    mode!(2, 6, 0, 0, 6, 0, false, true,  3, 0),
+    mode!(3, 6, 0, 0, 5, 0, false, false, 2, 0),
+    mode!(2, 6, 0, 0, 7, 0, true,  false, 2, 0),
+    mode!(1, 0, 2, 1, 5, 6, false, false, 2, 3),
+    mode!(1, 0, 2, 0, 7, 8, false, false, 2, 2),
+    mode!(1, 0, 0, 0, 7, 7, true,  false, 4, 0),
+    mode!(2, 6, 0, 0, 5, 5, true,  false, 2, 0),
+];
+
+pub const WEIGHTS2: [u32; 4] = [0, 21, 43, 64];
+pub const WEIGHTS3: [u32; 8] = [0, 9, 18, 27, 37, 46, 55, 64];
+pub const WEIGHTS4: [u32; 16] = [0, 4, 9, 13, 17, 21, 26, 30, 34, 38, 43, 47, 51, 55, 60, 64];
+
+pub fn weights(index_bits: u32) -> &'static [u32] {
+    match index_bits {
+        2 => &WEIGHTS2,
+        3 => &WEIGHTS3,
+        _ => &WEIGHTS4,
+    }
+}
+
+/// Two subset partitions, one bit per texel (shared with BC6H)
+#[rustfmt::skip]
+pub const PARTITIONS2: [u16; 64] = [
+    0xcccc, 0x8888, 0xeeee, 0xecc8, 0xc880, 0xfeec, 0xfec8, 0xec80,
+    0xc800, 0xffec, 0xfe80, 0xe800, 0xffe8, 0xff00, 0xfff0, 0xf000,
+    0xf710, 0x008e, 0x7100, 0x08ce, 0x008c, 0x7310, 0x3100, 0x8cce,
+    0x088c, 0x3110, 0x6666, 0x366c, 0x17e8, 0x0ff0, 0x718e, 0x399c,
+    0xaaaa, 0xf0f0, 0x5a5a, 0x33cc, 0x3c3c, 0x55aa, 0x9696, 0xa55a,
+    0x73ce, 0x13c8, 0x324c, 0x3bdc, 0x6996, 0xc33c, 0x9966, 0x0660,
+    0x0272, 0x04e4, 0x4e40, 0x2720, 0xc936, 0x936c, 0x39c6, 0x639c,
+    0x9336, 0x9cc6, 0x817e, 0xe718, 0xccf0, 0x0fcc, 0x7744, 0xee22,
+];
+
+/// Three subset partitions, two bits per texel
+#[rustfmt::skip]
+const PARTITIONS3: [u32; 64] = [
+    0xaa685050, 0x6a5a5040, 0x5a5a4200, 0x5450a0a8, 0xa5a50000, 0xa0a05050, 0x5555a0a0, 0x5a5a5050,
+    0xaa550000, 0xaa555500, 0xaaaa5500, 0x90909090, 0x94949494, 0xa4a4a4a4, 0xa9a59450, 0x2a0a4250,
+    0xa5945040, 0x0a425054, 0xa5a5a500, 0x55a0a0a0, 0xa8a85454, 0x6a6a4040, 0xa4a45000, 0x1a1a0500,
+    0x0050a4a4, 0xaaa59090, 0x14696914, 0x69691400, 0xa08585a0, 0xaa821414, 0x50a4a450, 0x6a5a0200,
+    0xa9a58000, 0x5090a0a8, 0xa8a09050, 0x24242424, 0x00aa5500, 0x24924924, 0x24499224, 0x50a50a50,
+    0x500aa550, 0xaaaa4444, 0x66660000, 0xa5a0a5a0, 0x50a050a0, 0x69286928, 0x44aaaa44, 0x66666600,
+    0xaa444444, 0x54a854a8, 0x95809580, 0x96969600, 0xa85454a8, 0x80959580, 0xaa141414, 0x96960000,
+    0xaaaa1414, 0xa05050a0, 0xa0a5a5a0, 0x96000000, 0x40804080, 0xa9a8a9a8, 0xaaaaaa44, 0x2a4a5254,
+];
+
+/// Anchor texel of the second subset in two subset partitions
+#[rustfmt::skip]
+pub const ANCHORS2: [u8; 64] = [
+    15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15,
+    15,  2,  8,  2,  2,  8,  8, 15,  2,  8,  2,  2,  8,  8,  2,  2,
+    15, 15,  6,  8,  2,  8, 15, 15,  2,  8,  2,  2,  2, 15, 15,  6,
+     6,  2,  6,  8, 15, 15,  2,  2, 15, 15, 15, 15, 15,  2,  2, 15,
+];
+
+/// Anchor texels of the second and third subsets in three subset partitions
+#[rustfmt::skip]
+const ANCHORS3: [[u8; 2]; 64] = [
+    [ 3, 15], [ 3,  8], [15,  8], [15,  3], [ 8, 15], [ 3, 15], [15,  3], [15,  8],
+    [ 8, 15], [ 8, 15], [ 6, 15], [ 6, 15], [ 6, 15], [ 5, 15], [ 3, 15], [ 3,  8],
+    [ 3, 15], [ 3,  8], [ 8, 15], [15,  3], [ 3, 15], [ 3,  8], [ 6, 15], [10,  8],
+    [ 5,  3], [ 8, 15], [ 8,  6], [ 6, 10], [ 8, 15], [ 5, 15], [15, 10], [15,  8],
+    [ 8, 15], [15,  3], [ 3, 15], [ 5, 10], [ 6, 10], [10,  8], [ 8,  9], [15, 10],
+    [15,  6], [ 3, 15], [15,  8], [ 5, 15], [15,  3], [15,  6], [15,  6], [15,  8],
+    [ 3, 15], [15,  3], [ 5, 15], [ 5, 15], [ 5, 15], [ 8, 15], [ 5, 15], [10, 15],
+    [ 5, 15], [10, 15], [ 8, 15], [13, 15], [15,  3], [12, 15], [ 3, 15], [ 3,  8],
+];
+
+fn subset_of(subsets: usize, partition: usize, texel: usize) -> usize {
+    match subsets {
+        2 => ((PARTITIONS2[partition] >> texel) & 1) as usize,
+        3 => ((PARTITIONS3[partition] >> (2 * texel)) & 3) as usize,
+        _ => 0,
+    }
+}
+
+fn is_anchor(subsets: usize, partition: usize, texel: usize) -> bool {
+    texel == 0
+        || match subsets {
+            2 => ANCHORS2[partition] as usize == texel,
+            3 => ANCHORS3[partition].contains(&(texel as u8)),
+            _ => false,
+        }
+}
+
+pub fn interpolate(e0: u32, e1: u32, weight: u32) -> u32 {
+    ((64 - weight) * e0 + weight * e1 + 32) >> 6
+}
+
+fn expand(value: u32, bits: u32) -> u32 {
+    (value << (8 - bits)) | (value >> (2 * bits - 8))
+}
+
+/// Decode a BC7 block
+pub fn decode(block: &[u8], out: &mut [[f32; 4]; 16]) {
+    let mut bits = Bits::new(block);
+    let mode = block[0].trailing_zeros() as usize;
+    if mode >= 8 {
+        // Reserved mode
+        *out = [[0.0; 4]; 16];
+        return;
+    }
+    bits.read(mode as u32 + 1);
+    let info = &MODES[mode];
+    let partition = bits.read(info.partition_bits) as usize;
+    let rotation = bits.read(info.rotation_bits);
+    let index_selection = bits.read(info.index_selection_bits);
+
+    let endpoints = info.subsets * 2;
+    let mut colors = [[0_u32; 4]; 6];
+    for c in 0..3 {
+        for color in colors.iter_mut().take(endpoints) {
+            color[c] = bits.read(info.color_bits);
+        }
+    }
+    if info.alpha_bits > 0 {
+        for color in colors.iter_mut().take(endpoints) {
+            color[3] = bits.read(info.alpha_bits);
+        }
+    }
+    let mut pbits = [0_u32; 6];
+    if info.endpoint_pbits {
+        for pbit in pbits.iter_mut().take(endpoints) {
+            *pbit = bits.read(1);
+        }
+    } else if info.shared_pbits {
+        for subset in 0..info.subsets {
+            let pbit = bits.read(1);
+            pbits[subset * 2] = pbit;
+            pbits[subset * 2 + 1] = pbit;
+        }
+    }
+    let has_pbits = info.endpoint_pbits || info.shared_pbits;
+    for (color, pbit) in colors.iter_mut().zip(pbits.iter()).take(endpoints) {
+        for (c, value) in color.iter_mut().enumerate() {
+            let mut precision = if c < 3 {
+                info.color_bits
+            } else {
+                info.alpha_bits
+            };
+            if precision == 0 {
+                *value = 255;
+                continue;
+            }
+            if has_pbits {
+                *value = (*value << 1) | pbit;
+                precision += 1;
+            }
+            *value = expand(*value, precision);
+        }
+    }
+
+    let mut indices = [0_u32; 16];
+    for (texel, index) in indices.iter_mut().enumerate() {
+        let anchor = is_anchor(info.subsets, partition, texel);
+        *index = bits.read(info.index_bits - anchor as u32);
+    }
+    let mut indices2 = [0_u32; 16];
+    if info.index2_bits > 0 {
+        for (texel, index) in indices2.iter_mut().enumerate() {
+            *index = bits.read(info.index2_bits - (texel == 0) as u32);
+        }
+    }
+
+    for (texel, rgba) in out.iter_mut().enumerate() {
+        let subset = subset_of(info.subsets, partition, texel);
+        let e0 = colors[subset * 2];
+        let e1 = colors[subset * 2 + 1];
+        let (color_weight, alpha_weight) = if info.index2_bits == 0 {
+            let w = weights(info.index_bits)[indices[texel] as usize];
+            (w, w)
+        } else if index_selection == 0 {
+            (
+                weights(info.index_bits)[indices[texel] as usize],
+                weights(info.index2_bits)[indices2[texel] as usize],
+            )
+        } else {
+            (
+                weights(info.index2_bits)[indices2[texel] as usize],
+                weights(info.index_bits)[indices[texel] as usize],
+            )
+        };
+        let mut value = [0_u32; 4];
+        for c in 0..3 {
+            value[c] = interpolate(e0[c], e1[c], color_weight);
+        }
+        value[3] = interpolate(e0[3], e1[3], alpha_weight);
+        match rotation {
+            1 => value.swap(0, 3),
+            2 => value.swap(1, 3),
+            3 => value.swap(2, 3),
+            _ => {}
+        }
+        for c in 0..4 {
+            rgba[c] = value[c] as f32 / 255.0;
+        }
+    }
+}
+
+// Quantize an 8 bit endpoint to 7 bits plus a p-bit, choosing the p-bit
+// with the least error
+fn quantize_endpoint(value: [f32; 4]) -> ([u32; 4], u32) {
+    let mut best = ([0; 4], 0);
+    let mut best_error = f32::MAX;
+    for pbit in 0..2 {
+        let mut quantized = [0_u32; 4];
+        let mut error = 0.0;
+        for c in 0..4 {
+            let q = ((value[c] - pbit as f32) / 2.0).round().clamp(0.0, 127.0) as u32;
+            quantized[c] = q;
+            let restored = ((q << 1) | pbit) as f32;
+            error += (restored - value[c]) * (restored - value[c]);
+        }
+        if error < best_error {
+            best = (quantized, pbit);
+            best_error = error;
+        }
+    }
+    best
+}
+
+/// Encode a BC7 block, using mode 6 (one subset, RGBA with 4 bit indices)
+pub fn encode(texels: &[[f32; 4]; 16]) -> [u8; 16] {
+    let scaled: Vec<[f32; 4]> = texels
+        .iter()
+        .map(|t| {
+            [
+                t[0].clamp(0.0, 1.0) * 255.0,
+                t[1].clamp(0.0, 1.0) * 255.0,
+                t[2].clamp(0.0, 1.0) * 255.0,
+                t[3].clamp(0.0, 1.0) * 255.0,
+            ]
+        })
+        .collect();
+    let (low, high) = fit_line(&scaled);
+    let mut endpoints = [quantize_endpoint(low), quantize_endpoint(high)];
+
+    let select = |endpoints: &[([u32; 4], u32); 2]| {
+        let restore = |(q, p): &([u32; 4], u32)| q.map(|v| (v << 1) | p);
+        let e0 = restore(&endpoints[0]);
+        let e1 = restore(&endpoints[1]);
+        let mut palette = [[0_f32; 4]; 16];
+        for (i, entry) in palette.iter_mut().enumerate() {
+            for c in 0..4 {
+                entry[c] = interpolate(e0[c], e1[c], WEIGHTS4[i]) as f32;
+            }
+        }
+        let mut indices = [0_u32; 16];
+        for (texel, index) in scaled.iter().zip(indices.iter_mut()) {
+            let mut best_distance = f32::MAX;
+            for (i, entry) in palette.iter().enumerate() {
+                let d: f32 = (0..4).map(|c| (entry[c] - texel[c]).powi(2)).sum();
+                if d < best_distance {
+                    *index = i as u32;
+                    best_distance = d;
+                }
+            }
+        }
+        indices
+    };
+    let mut indices = select(&endpoints);
+    // The anchor index is stored without its high bit
+    if indices[0] >= 8 {
+        endpoints.swap(0, 1);
+        for index in indices.iter_mut() {
+            *index = 15 - *index;
+        }
+    }
+
+    let mut bits = BitWriter {
+        value: 0,
+        position: 0,
+    };
+    bits.write(7, 1 << 6);
+    for c in 0..4 {
+        bits.write(7, endpoints[0].0[c]);
+        bits.write(7, endpoints[1].0[c]);
+    }
+    bits.write(1, endpoints[0].1);
+    bits.write(1, endpoints[1].1);
+    for (texel, index) in indices.iter().enumerate() {
+        bits.write(if texel == 0 { 3 } else { 4 }, *index);
+    }
+    bits.value.to_le_bytes()
+}
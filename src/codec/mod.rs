@@ -0,0 +1,711 @@
+// The MIT License (MIT)
+//
+// Copyright (c) 2018 Michael Dilger
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+// Decoding and encoding of texel data, to and from RGBA floats
+
+mod bc;
+mod bc6h;
+mod bc7;
+mod packed;
+
+use self::packed::{ch, Channel, Kind};
+use crate::{D3DFormat, DataFormat, Dds, DxgiFormat, Error, PixelFormatFlags};
+
+/// How the texels of a format are stored
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum Layout {
+    /// Uncompressed texels of the given size, with the given channels
+    Packed(usize, &'static [Channel]),
+    /// Legacy texels of the given size, with RGBA bit masks and a luminance flag
+    Masks(usize, [u32; 4], bool),
+    SharedExp,
+    /// 8-bit YUV 4:2:2, with the byte offsets of Y0, U, Y1 and V within each
+    /// pair of texels
+    Yuv422([usize; 4]),
+    /// Two signed components of a normal, the third being computed
+    CxV8U8,
+    Bc1,
+    Bc2,
+    Bc3,
+    Bc4(bool),
+    Bc5(bool),
+    Bc6h(bool),
+    Bc7,
+}
+
+const fn uniform<const N: usize>(bits: u32, kind: Kind) -> [Channel; N] {
+    let mut channels = [ch(0, 0, bits, kind); N];
+    let mut i = 0;
+    while i < N {
+        channels[i] = ch(i, i as u32 * bits, bits, kind);
+        i += 1;
+    }
+    channels
+}
+
+const RGBA32F: [Channel; 4] = uniform(32, Kind::Float);
+const RGBA32UI: [Channel; 4] = uniform(32, Kind::UInt);
+const RGBA32I: [Channel; 4] = uniform(32, Kind::SInt);
+const RGB32F: [Channel; 3] = uniform(32, Kind::Float);
+const RGB32UI: [Channel; 3] = uniform(32, Kind::UInt);
+const RGB32I: [Channel; 3] = uniform(32, Kind::SInt);
+const RG32F: [Channel; 2] = uniform(32, Kind::Float);
+const RG32UI: [Channel; 2] = uniform(32, Kind::UInt);
+const RG32I: [Channel; 2] = uniform(32, Kind::SInt);
+const R32F: [Channel; 1] = uniform(32, Kind::Float);
+const R32UI: [Channel; 1] = uniform(32, Kind::UInt);
+const R32I: [Channel; 1] = uniform(32, Kind::SInt);
+const RGBA16F: [Channel; 4] = uniform(16, Kind::Float);
+const RGBA16: [Channel; 4] = uniform(16, Kind::UNorm);
+const RGBA16UI: [Channel; 4] = uniform(16, Kind::UInt);
+const RGBA16_SNORM: [Channel; 4] = uniform(16, Kind::SNorm);
+const RGBA16I: [Channel; 4] = uniform(16, Kind::SInt);
+const RG16F: [Channel; 2] = uniform(16, Kind::Float);
+const RG16: [Channel; 2] = uniform(16, Kind::UNorm);
+const RG16UI: [Channel; 2] = uniform(16, Kind::UInt);
+const RG16_SNORM: [Channel; 2] = uniform(16, Kind::SNorm);
+const RG16I: [Channel; 2] = uniform(16, Kind::SInt);
+const R16F: [Channel; 1] = uniform(16, Kind::Float);
+const R16: [Channel; 1] = uniform(16, Kind::UNorm);
+const R16UI: [Channel; 1] = uniform(16, Kind::UInt);
+const R16_SNORM: [Channel; 1] = uniform(16, Kind::SNorm);
+const R16I: [Channel; 1] = uniform(16, Kind::SInt);
+const RGBA8: [Channel; 4] = uniform(8, Kind::UNorm);
+const RGBA8UI: [Channel; 4] = uniform(8, Kind::UInt);
+const RGBA8_SNORM: [Channel; 4] = uniform(8, Kind::SNorm);
+const RGBA8I: [Channel; 4] = uniform(8, Kind::SInt);
+const RG8: [Channel; 2] = uniform(8, Kind::UNorm);
+const RG8UI: [Channel; 2] = uniform(8, Kind::UInt);
+const RG8_SNORM: [Channel; 2] = uniform(8, Kind::SNorm);
+const RG8I: [Channel; 2] = uniform(8, Kind::SInt);
+const R8: [Channel; 1] = uniform(8, Kind::UNorm);
+const R8UI: [Channel; 1] = uniform(8, Kind::UInt);
+const R8_SNORM: [Channel; 1] = uniform(8, Kind::SNorm);
+const R8I: [Channel; 1] = uniform(8, Kind::SInt);
+
+#[rustfmt::skip]
+const RGB10A2: [Channel; 4] = [
+    ch(0, 0, 10, Kind::UNorm), ch(1, 10, 10, Kind::UNorm), ch(2, 20, 10, Kind::UNorm), ch(3, 30, 2, Kind::UNorm),
+];
+#[rustfmt::skip]
+const RGB10A2UI: [Channel; 4] = [
+    ch(0, 0, 10, Kind::UInt), ch(1, 10, 10, Kind::UInt), ch(2, 20, 10, Kind::UInt), ch(3, 30, 2, Kind::UInt),
+];
+#[rustfmt::skip]
+const RG11B10F: [Channel; 3] = [
+    ch(0, 0, 11, Kind::UFloat), ch(1, 11, 11, Kind::UFloat), ch(2, 22, 10, Kind::UFloat),
+];
+const D32F_S8: [Channel; 2] = [ch(0, 0, 32, Kind::Float), ch(1, 32, 8, Kind::UInt)];
+const X32_G8: [Channel; 1] = [ch(1, 32, 8, Kind::UInt)];
+const D24_S8: [Channel; 2] = [ch(0, 0, 24, Kind::UNorm), ch(1, 24, 8, Kind::UInt)];
+const R24_X8: [Channel; 1] = [ch(0, 0, 24, Kind::UNorm)];
+const X24_G8: [Channel; 1] = [ch(1, 24, 8, Kind::UInt)];
+const A8: [Channel; 1] = [ch(3, 0, 8, Kind::UNorm)];
+#[rustfmt::skip]
+const B5G6R5: [Channel; 3] = [
+    ch(2, 0, 5, Kind::UNorm), ch(1, 5, 6, Kind::UNorm), ch(0, 11, 5, Kind::UNorm),
+];
+#[rustfmt::skip]
+const B5G5R5A1: [Channel; 4] = [
+    ch(2, 0, 5, Kind::UNorm), ch(1, 5, 5, Kind::UNorm), ch(0, 10, 5, Kind::UNorm), ch(3, 15, 1, Kind::UNorm),
+];
+#[rustfmt::skip]
+const BGRA8: [Channel; 4] = [
+    ch(2, 0, 8, Kind::UNorm), ch(1, 8, 8, Kind::UNorm), ch(0, 16, 8, Kind::UNorm), ch(3, 24, 8, Kind::UNorm),
+];
+#[rustfmt::skip]
+const BGRX8: [Channel; 3] = [
+    ch(2, 0, 8, Kind::UNorm), ch(1, 8, 8, Kind::UNorm), ch(0, 16, 8, Kind::UNorm),
+];
+#[rustfmt::skip]
+const BGRA4: [Channel; 4] = [
+    ch(2, 0, 4, Kind::UNorm), ch(1, 4, 4, Kind::UNorm), ch(0, 8, 4, Kind::UNorm), ch(3, 12, 4, Kind::UNorm),
+];
+
+#[rustfmt::skip]
+const L6V5U5: [Channel; 3] = [
+    ch(0, 0, 5, Kind::SNorm), ch(1, 5, 5, Kind::SNorm), ch(2, 10, 6, Kind::UNorm),
+];
+#[rustfmt::skip]
+const X8L8V8U8: [Channel; 3] = [
+    ch(0, 0, 8, Kind::SNorm), ch(1, 8, 8, Kind::SNorm), ch(2, 16, 8, Kind::UNorm),
+];
+#[rustfmt::skip]
+const A2W10V10U10: [Channel; 4] = [
+    ch(0, 0, 10, Kind::SNorm), ch(1, 10, 10, Kind::SNorm), ch(2, 20, 10, Kind::SNorm), ch(3, 30, 2, Kind::UNorm),
+];
+
+#[rustfmt::skip]
+const ABGR4: [Channel; 4] = [
+    ch(3, 0, 4, Kind::UNorm), ch(2, 4, 4, Kind::UNorm), ch(1, 8, 4, Kind::UNorm), ch(0, 12, 4, Kind::UNorm),
+];
+
+// The byte offsets of Y0, U, Y1 and V in a pair of texels
+const YUY2: [usize; 4] = [0, 1, 2, 3];
+const UYVY: [usize; 4] = [1, 0, 3, 2];
+
+impl Layout {
+    pub(crate) fn from_dxgi(format: DxgiFormat) -> Option<Layout> {
+        use self::Layout::*;
+        let layout = match format {
+            DxgiFormat::R32G32B32A32_Float => Packed(16, &RGBA32F),
+            DxgiFormat::R32G32B32A32_UInt => Packed(16, &RGBA32UI),
+            DxgiFormat::R32G32B32A32_SInt => Packed(16, &RGBA32I),
+            DxgiFormat::R32G32B32_Float => Packed(12, &RGB32F),
+            DxgiFormat::R32G32B32_UInt => Packed(12, &RGB32UI),
+            DxgiFormat::R32G32B32_SInt => Packed(12, &RGB32I),
+            DxgiFormat::R16G16B16A16_Float => Packed(8, &RGBA16F),
+            DxgiFormat::R16G16B16A16_UNorm => Packed(8, &RGBA16),
+            DxgiFormat::R16G16B16A16_UInt => Packed(8, &RGBA16UI),
+            DxgiFormat::R16G16B16A16_SNorm => Packed(8, &RGBA16_SNORM),
+            DxgiFormat::R16G16B16A16_SInt => Packed(8, &RGBA16I),
+            DxgiFormat::R32G32_Float => Packed(8, &RG32F),
+            DxgiFormat::R32G32_UInt => Packed(8, &RG32UI),
+            DxgiFormat::R32G32_SInt => Packed(8, &RG32I),
+            DxgiFormat::D32_Float_S8X24_UInt => Packed(8, &D32F_S8),
+            DxgiFormat::R32_Float_X8X24_Typeless => Packed(8, &R32F),
+            DxgiFormat::X32_Typeless_G8X24_UInt => Packed(8, &X32_G8),
+            DxgiFormat::R10G10B10A2_UNorm => Packed(4, &RGB10A2),
+            DxgiFormat::R10G10B10A2_UInt => Packed(4, &RGB10A2UI),
+            DxgiFormat::R11G11B10_Float => Packed(4, &RG11B10F),
+            DxgiFormat::R8G8B8A8_UNorm | DxgiFormat::R8G8B8A8_UNorm_sRGB => Packed(4, &RGBA8),
+            DxgiFormat::R8G8B8A8_UInt => Packed(4, &RGBA8UI),
+            DxgiFormat::R8G8B8A8_SNorm => Packed(4, &RGBA8_SNORM),
+            DxgiFormat::R8G8B8A8_SInt => Packed(4, &RGBA8I),
+            DxgiFormat::R16G16_Float => Packed(4, &RG16F),
+            DxgiFormat::R16G16_UNorm => Packed(4, &RG16),
+            DxgiFormat::R16G16_UInt => Packed(4, &RG16UI),
+            DxgiFormat::R16G16_SNorm => Packed(4, &RG16_SNORM),
+            DxgiFormat::R16G16_SInt => Packed(4, &RG16I),
+            DxgiFormat::D32_Float | DxgiFormat::R32_Float => Packed(4, &R32F),
+            DxgiFormat::R32_UInt => Packed(4, &R32UI),
+            DxgiFormat::R32_SInt => Packed(4, &R32I),
+            DxgiFormat::D24_UNorm_S8_UInt => Packed(4, &D24_S8),
+            DxgiFormat::R24_UNorm_X8_Typeless => Packed(4, &R24_X8),
+            DxgiFormat::X24_Typeless_G8_UInt => Packed(4, &X24_G8),
+            DxgiFormat::R8G8_UNorm => Packed(2, &RG8),
+            DxgiFormat::R8G8_UInt => Packed(2, &RG8UI),
+            DxgiFormat::R8G8_SNorm => Packed(2, &RG8_SNORM),
+            DxgiFormat::R8G8_SInt => Packed(2, &RG8I),
+            DxgiFormat::R16_Float => Packed(2, &R16F),
+            DxgiFormat::D16_UNorm | DxgiFormat::R16_UNorm => Packed(2, &R16),
+            DxgiFormat::R16_UInt => Packed(2, &R16UI),
+            DxgiFormat::R16_SNorm => Packed(2, &R16_SNORM),
+            DxgiFormat::R16_SInt => Packed(2, &R16I),
+            DxgiFormat::R8_UNorm => Packed(1, &R8),
+            DxgiFormat::R8_UInt => Packed(1, &R8UI),
+            DxgiFormat::R8_SNorm => Packed(1, &R8_SNORM),
+            DxgiFormat::R8_SInt => Packed(1, &R8I),
+            DxgiFormat::A8_UNorm => Packed(1, &A8),
+            DxgiFormat::R9G9B9E5_SharedExp => SharedExp,
+            DxgiFormat::BC1_UNorm | DxgiFormat::BC1_UNorm_sRGB => Bc1,
+            DxgiFormat::BC2_UNorm | DxgiFormat::BC2_UNorm_sRGB => Bc2,
+            DxgiFormat::BC3_UNorm | DxgiFormat::BC3_UNorm_sRGB => Bc3,
+            DxgiFormat::BC4_UNorm => Bc4(false),
+            DxgiFormat::BC4_SNorm => Bc4(true),
+            DxgiFormat::BC5_UNorm => Bc5(false),
+            DxgiFormat::BC5_SNorm => Bc5(true),
+            DxgiFormat::B5G6R5_UNorm => Packed(2, &B5G6R5),
+            DxgiFormat::B5G5R5A1_UNorm => Packed(2, &B5G5R5A1),
+            DxgiFormat::B8G8R8A8_UNorm | DxgiFormat::B8G8R8A8_UNorm_sRGB => Packed(4, &BGRA8),
+            DxgiFormat::B8G8R8X8_UNorm | DxgiFormat::B8G8R8X8_UNorm_sRGB => Packed(4, &BGRX8),
+            DxgiFormat::BC6H_UF16 => Bc6h(false),
+            DxgiFormat::BC6H_SF16 => Bc6h(true),
+            DxgiFormat::BC7_UNorm | DxgiFormat::BC7_UNorm_sRGB => Bc7,
+            DxgiFormat::B4G4R4A4_UNorm => Packed(2, &BGRA4),
+            DxgiFormat::A4B4G4R4_UNorm => Packed(2, &ABGR4),
+            DxgiFormat::YUY2 => Yuv422(YUY2),
+            _ => return None,
+        };
+        Some(layout)
+    }
+
+    pub(crate) fn from_d3d(format: D3DFormat) -> Option<Layout> {
+        use self::Layout::*;
+        let layout = match format {
+            D3DFormat::DXT1 => Bc1,
+            D3DFormat::DXT2 | D3DFormat::DXT3 => Bc2,
+            D3DFormat::DXT4 | D3DFormat::DXT5 => Bc3,
+            D3DFormat::A16B16G16R16 => Packed(8, &RGBA16),
+            D3DFormat::Q16W16V16U16 => Packed(8, &RGBA16_SNORM),
+            D3DFormat::R16F => Packed(2, &R16F),
+            D3DFormat::G16R16F => Packed(4, &RG16F),
+            D3DFormat::A16B16G16R16F => Packed(8, &RGBA16F),
+            D3DFormat::R32F => Packed(4, &R32F),
+            D3DFormat::G32R32F => Packed(8, &RG32F),
+            D3DFormat::A32B32G32R32F => Packed(16, &RGBA32F),
+            D3DFormat::UYVY => Yuv422(UYVY),
+            D3DFormat::YUY2 => Yuv422(YUY2),
+            D3DFormat::CXV8U8 => CxV8U8,
+            // Bump maps decode U, V and W (or luminance) to red, green and blue
+            D3DFormat::V8U8 => Packed(2, &RG8_SNORM),
+            D3DFormat::Q8W8V8U8 => Packed(4, &RGBA8_SNORM),
+            D3DFormat::V16U16 => Packed(4, &RG16_SNORM),
+            D3DFormat::L6V5U5 => Packed(2, &L6V5U5),
+            D3DFormat::X8L8V8U8 => Packed(4, &X8L8V8U8),
+            D3DFormat::A2W10V10U10 => Packed(4, &A2W10V10U10),
+            D3DFormat::R8G8_B8G8 | D3DFormat::G8R8_G8B8 => return None,
+            _ => {
+                let bits = format.get_bits_per_pixel()?;
+                let masks = [
+                    format.r_bit_mask().unwrap_or(0),
+                    format.g_bit_mask().unwrap_or(0),
+                    format.b_bit_mask().unwrap_or(0),
+                    format.a_bit_mask().unwrap_or(0),
+                ];
+                let luminance = matches!(
+                    format,
+                    D3DFormat::A8L8 | D3DFormat::L16 | D3DFormat::L8 | D3DFormat::A4L4
+                );
+                Masks(bits as usize / 8, masks, luminance)
+            }
+        };
+        Some(layout)
+    }
+
+    /// The layout of a surface, falling back to the bit masks of its pixel
+    /// format when the format is not recognized
+    pub(crate) fn of(dds: &Dds) -> Option<Layout> {
+        if let Some(format) = dds.get_dxgi_format() {
+            return Layout::from_dxgi(format);
+        }
+        if let Some(format) = dds.get_d3d_format() {
+            return Layout::from_d3d(format);
+        }
+        let spf = &dds.header.spf;
+        let luminance = spf.flags.contains(PixelFormatFlags::LUMINANCE);
+        if !spf.flags.contains(PixelFormatFlags::RGB) && !luminance {
+            return None;
+        }
+        let bits = spf.rgb_bit_count?;
+        if bits == 0 || bits > 32 || bits % 8 != 0 {
+            return None;
+        }
+        let masks = [
+            spf.r_bit_mask.unwrap_or(0),
+            spf.g_bit_mask.unwrap_or(0),
+            spf.b_bit_mask.unwrap_or(0),
+            spf.a_bit_mask.unwrap_or(0),
+        ];
+        Some(Layout::Masks(bits as usize / 8, masks, luminance))
+    }
+
+    pub(crate) fn block_size(&self) -> Option<usize> {
+        match *self {
+            Layout::Bc1 | Layout::Bc4(_) => Some(8),
+            Layout::Bc2 | Layout::Bc3 | Layout::Bc5(_) | Layout::Bc6h(_) | Layout::Bc7 => Some(16),
+            _ => None,
+        }
+    }
+
+    /// The width and height in texels, and the size in bytes, of the smallest
+    /// unit of storage: a block, a pair of texels sharing chroma, or a texel
+    pub(crate) fn unit(&self) -> (u32, u32, usize) {
+        match self.block_size() {
+            Some(block_size) => (4, 4, block_size),
+            None if matches!(self, Layout::Yuv422(_)) => (2, 1, 4),
+            None => (1, 1, self.texel_size()),
+        }
+    }
+
+    /// Whether the texels within a unit of storage can be mirrored without
+    /// decoding them, which BC6H and BC7 do not allow
+    pub(crate) fn can_mirror_unit(&self) -> bool {
+        !matches!(self, Layout::Bc6h(_) | Layout::Bc7)
+    }
+
+    /// Mirror the texels within a unit of storage, see `can_mirror_unit`
+    pub(crate) fn mirror_unit(&self, unit: &mut [u8], horizontal: bool, vertical: bool) {
+        match *self {
+            Layout::Yuv422(order) if horizontal => unit.swap(order[0], order[2]),
+            Layout::Bc1 => bc::mirror_indices(&mut unit[4..8], 2, horizontal, vertical),
+            Layout::Bc2 => {
+                bc::mirror_indices(&mut unit[..8], 4, horizontal, vertical);
+                bc::mirror_indices(&mut unit[12..], 2, horizontal, vertical);
+            }
+            Layout::Bc3 => {
+                bc::mirror_indices(&mut unit[2..8], 3, horizontal, vertical);
+                bc::mirror_indices(&mut unit[12..], 2, horizontal, vertical);
+            }
+            Layout::Bc4(_) => bc::mirror_indices(&mut unit[2..8], 3, horizontal, vertical),
+            Layout::Bc5(_) => {
+                bc::mirror_indices(&mut unit[2..8], 3, horizontal, vertical);
+                bc::mirror_indices(&mut unit[10..], 3, horizontal, vertical);
+            }
+            _ => {}
+        }
+    }
+
+    fn texel_size(&self) -> usize {
+        match *self {
+            Layout::Packed(size, _) | Layout::Masks(size, _, _) => size,
+            Layout::Yuv422(_) | Layout::CxV8U8 => 2,
+            _ => 4,
+        }
+    }
+
+    /// If every channel is a normalized unsigned integer, the largest number
+    /// of bits in a channel
+    #[cfg(feature = "image")]
+    pub(crate) fn unorm_bits(&self) -> Option<u32> {
+        match *self {
+            Layout::Packed(_, channels) => {
+                if channels.iter().all(|c| c.kind == Kind::UNorm) {
+                    channels.iter().map(|c| c.bits).max()
+                } else {
+                    None
+                }
+            }
+            Layout::Masks(_, masks, _) => masks.iter().map(|m| m.count_ones()).max(),
+            Layout::Bc1 | Layout::Bc2 | Layout::Bc3 | Layout::Bc7 => Some(8),
+            Layout::Bc4(signed) | Layout::Bc5(signed) => {
+                if signed {
+                    None
+                } else {
+                    Some(8)
+                }
+            }
+            Layout::Yuv422(_) => Some(8),
+            Layout::SharedExp | Layout::CxV8U8 | Layout::Bc6h(_) => None,
+        }
+    }
+
+    /// The size in bytes of a surface of the given dimensions
+    pub(crate) fn surface_size(&self, width: u32, height: u32, depth: u32) -> usize {
+        let (width, height, depth) = (width as usize, height as usize, depth as usize);
+        match self.block_size() {
+            Some(block_size) => width.div_ceil(4) * height.div_ceil(4) * depth * block_size,
+            // Texels are stored in pairs, even when the width is odd
+            None if matches!(self, Layout::Yuv422(_)) => width.div_ceil(2) * 4 * height * depth,
+            None => width * height * depth * self.texel_size(),
+        }
+    }
+
+    /// Decode a surface into RGBA floats, four per texel, row by row and
+    /// slice by slice
+    pub(crate) fn decode(
+        &self,
+        width: u32,
+        height: u32,
+        depth: u32,
+        data: &[u8],
+    ) -> Result<Vec<f32>, Error> {
+        let size = self.surface_size(width, height, depth);
+        if data.len() < size {
+            return Err(Error::ShortFile {
+                expected: size as u64,
+                actual: data.len() as u64,
+            });
+        }
+        let (width, height, depth) = (width as usize, height as usize, depth as usize);
+        let mut rgba = vec![0.0; width * height * depth * 4];
+        let block_size = match self.block_size() {
+            Some(block_size) => block_size,
+            None => {
+                match *self {
+                    Layout::Packed(size, channels) => {
+                        packed::decode(size, channels, data, &mut rgba)
+                    }
+                    Layout::Masks(size, masks, luminance) => {
+                        packed::decode_masks(size, &masks, luminance, data, &mut rgba)
+                    }
+                    Layout::Yuv422(order) => packed::decode_yuv422(width, &order, data, &mut rgba),
+                    Layout::CxV8U8 => packed::decode_cxv8u8(data, &mut rgba),
+                    _ => packed::decode_shared_exp(data, &mut rgba),
+                }
+                return Ok(rgba);
+            }
+        };
+
+        let blocks_wide = width.div_ceil(4);
+        let blocks_high = height.div_ceil(4);
+        let mut texels = [[0.0; 4]; 16];
+        let mut single = [0.0; 16];
+        for (i, block) in data
+            .chunks_exact(block_size)
+            .take(blocks_wide * blocks_high * depth)
+            .enumerate()
+        {
+            match *self {
+                Layout::Bc1 => bc::decode_color(block, false, &mut texels),
+                Layout::Bc2 => {
+                    bc::decode_color(&block[8..], true, &mut texels);
+                    bc::decode_explicit_alpha(block, &mut texels);
+                }
+                Layout::Bc3 => {
+                    bc::decode_color(&block[8..], true, &mut texels);
+                    bc::decode_single(block, false, &mut single);
+                    for (texel, alpha) in texels.iter_mut().zip(single.iter()) {
+                        texel[3] = *alpha;
+                    }
+                }
+                Layout::Bc4(signed) => {
+                    bc::decode_single(block, signed, &mut single);
+                    for (texel, red) in texels.iter_mut().zip(single.iter()) {
+                        *texel = [*red, 0.0, 0.0, 1.0];
+                    }
+                }
+                Layout::Bc5(signed) => {
+                    bc::decode_single(block, signed, &mut single);
+                    for (texel, red) in texels.iter_mut().zip(single.iter()) {
+                        *texel = [*red, 0.0, 0.0, 1.0];
+                    }
+                    bc::decode_single(&block[8..], signed, &mut single);
+                    for (texel, green) in texels.iter_mut().zip(single.iter()) {
+                        texel[1] = *green;
+                    }
+                }
+                Layout::Bc6h(signed) => bc6h::decode(block, signed, &mut texels),
+                _ => bc7::decode(block, &mut texels),
+            }
+            let slice = i / (blocks_wide * blocks_high);
+            let block_y = i / blocks_wide % blocks_high;
+            let block_x = i % blocks_wide;
+            for (j, texel) in texels.iter().enumerate() {
+                let x = block_x * 4 + j % 4;
+                let y = block_y * 4 + j / 4;
+                if x < width && y < height {
+                    let offset = ((slice * height + y) * width + x) * 4;
+                    rgba[offset..offset + 4].copy_from_slice(texel);
+                }
+            }
+        }
+        Ok(rgba)
+    }
+
+    /// Encode a surface from RGBA floats, four per texel, row by row and slice
+    /// by slice
+    pub(crate) fn encode(
+        &self,
+        width: u32,
+        height: u32,
+        depth: u32,
+        rgba: &[f32],
+        data: &mut [u8],
+    ) -> Result<(), Error> {
+        let (w, h, d) = (width as usize, height as usize, depth as usize);
+        let count = (w * h * d * 4) as u64;
+        if rgba.len() as u64 != count {
+            return Err(Error::Mismatch {
+                index: 0,
+                property: "RGBA float count",
+                found: rgba.len().to_string(),
+                expected: count.to_string(),
+            });
+        }
+        let size = self.surface_size(width, height, depth);
+        if data.len() < size {
+            return Err(Error::ShortFile {
+                expected: size as u64,
+                actual: data.len() as u64,
+            });
+        }
+        let block_size = match self.block_size() {
+            Some(block_size) => block_size,
+            None => {
+                match *self {
+                    Layout::Packed(size, channels) => packed::encode(size, channels, rgba, data),
+                    Layout::Masks(size, masks, luminance) => {
+                        packed::encode_masks(size, &masks, luminance, rgba, data)
+                    }
+                    Layout::Yuv422(order) => packed::encode_yuv422(w, &order, rgba, data),
+                    Layout::CxV8U8 => packed::encode_cxv8u8(rgba, data),
+                    _ => packed::encode_shared_exp(rgba, data),
+                }
+                return Ok(());
+            }
+        };
+
+        let blocks_wide = w.div_ceil(4);
+        let blocks_high = h.div_ceil(4);
+        let mut texels = [[0.0; 4]; 16];
+        for (i, block) in data
+            .chunks_exact_mut(block_size)
+            .take(blocks_wide * blocks_high * d)
+            .enumerate()
+        {
+            let slice = i / (blocks_wide * blocks_high);
+            let block_y = i / blocks_wide % blocks_high;
+            let block_x = i % blocks_wide;
+            // Partial blocks repeat the texels along the edge
+            for (j, texel) in texels.iter_mut().enumerate() {
+                let x = (block_x * 4 + j % 4).min(w - 1);
+                let y = (block_y * 4 + j / 4).min(h - 1);
+                let offset = ((slice * h + y) * w + x) * 4;
+                texel.copy_from_slice(&rgba[offset..offset + 4]);
+            }
+            let channel = |c: usize| -> [f32; 16] {
+                let mut values = [0.0; 16];
+                for (value, texel) in values.iter_mut().zip(texels.iter()) {
+                    *value = texel[c];
+                }
+                values
+            };
+            match *self {
+                Layout::Bc1 => block.copy_from_slice(&bc::encode_color(&texels, true)),
+                Layout::Bc2 => {
+                    block[..8].copy_from_slice(&bc::encode_explicit_alpha(&texels));
+                    block[8..].copy_from_slice(&bc::encode_color(&texels, false));
+                }
+                Layout::Bc3 => {
+                    block[..8].copy_from_slice(&bc::encode_single(&channel(3), false));
+                    block[8..].copy_from_slice(&bc::encode_color(&texels, false));
+                }
+                Layout::Bc4(signed) => {
+                    block.copy_from_slice(&bc::encode_single(&channel(0), signed))
+                }
+                Layout::Bc5(signed) => {
+                    block[..8].copy_from_slice(&bc::encode_single(&channel(0), signed));
+                    block[8..].copy_from_slice(&bc::encode_single(&channel(1), signed));
+                }
+                Layout::Bc6h(signed) => block.copy_from_slice(&bc6h::encode(&texels, signed)),
+                _ => block.copy_from_slice(&bc7::encode(&texels)),
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Convert an sRGB encoded value to linear
+pub(crate) fn srgb_to_linear(v: f32) -> f32 {
+    if v <= 0.04045 {
+        v / 12.92
+    } else {
+        ((v + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// Convert a linear value to sRGB encoding
+pub(crate) fn linear_to_srgb(v: f32) -> f32 {
+    if v <= 0.0031308 {
+        v * 12.92
+    } else {
+        1.055 * v.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+/// Convert the bits of an IEEE half float into a float
+pub(crate) fn f16_to_f32(half: u16) -> f32 {
+    let sign = ((half >> 15) & 1) as u32;
+    let exponent = ((half >> 10) & 0x1f) as u32;
+    let mantissa = (half & 0x3ff) as u32;
+    let magnitude = if exponent == 0 {
+        // zero or subnormal
+        mantissa as f32 / (1 << 24) as f32
+    } else if exponent == 31 {
+        f32::from_bits(0x7f80_0000 | (mantissa << 13))
+    } else {
+        f32::from_bits(((exponent + 127 - 15) << 23) | (mantissa << 13))
+    };
+    if sign == 1 {
+        -magnitude
+    } else {
+        magnitude
+    }
+}
+
+/// Convert a float into the bits of an IEEE half float, rounding to nearest even
+pub(crate) fn f32_to_f16(value: f32) -> u16 {
+    let bits = value.to_bits();
+    let sign = ((bits >> 16) & 0x8000) as u16;
+    let exponent = ((bits >> 23) & 0xff) as i32;
+    let mantissa = bits & 0x7f_ffff;
+    if exponent == 0xff {
+        return sign | 0x7c00 | if mantissa != 0 { 0x200 } else { 0 };
+    }
+    let half_exponent = exponent - 127 + 15;
+    if half_exponent >= 31 {
+        return sign | 0x7c00;
+    }
+    if half_exponent <= 0 {
+        if half_exponent < -10 {
+            return sign;
+        }
+        let mantissa = mantissa | 0x80_0000;
+        let shift = (14 - half_exponent) as u32;
+        let mut half = mantissa >> shift;
+        let remainder = mantissa & ((1 << shift) - 1);
+        let halfway = 1 << (shift - 1);
+        if remainder > halfway || (remainder == halfway && half & 1 == 1) {
+            half += 1;
+        }
+        return sign | half as u16;
+    }
+    let mut half = ((half_exponent as u32) << 10) | (mantissa >> 13);
+    let remainder = mantissa & 0x1fff;
+    // A carry out of the mantissa correctly bumps the exponent
+    if remainder > 0x1000 || (remainder == 0x1000 && half & 1 == 1) {
+        half += 1;
+    }
+    sign | half as u16
+}
+
+impl Dds {
+    /// This gets the width, height and depth of the given mipmap level. The
+    /// depth is 1 unless this is a volume texture.
+    pub fn get_mipmap_dimensions(&self, mipmap_level: u32) -> (u32, u32, u32) {
+        let depth = if self.is_volume() {
+            (self.get_depth() >> mipmap_level).max(1)
+        } else {
+            1
+        };
+        (
+            (self.get_width() >> mipmap_level).max(1),
+            (self.get_height() >> mipmap_level).max(1),
+            depth,
+        )
+    }
+
+    /// Decode a single mipmap level of an array layer into RGBA floats, four
+    /// per texel, row by row (and slice by slice for volume textures).
+    ///
+    /// Normalized formats decode to the 0.0 to 1.0 (or -1.0 to 1.0) range and
+    /// integer formats to their integer values. Missing color channels are 0.0
+    /// and a missing alpha channel is 1.0. sRGB data is not linearized.
+    pub fn decode_rgba32f(&self, array_layer: u32, mipmap_level: u32) -> Result<Vec<f32>, Error> {
+        let layout = match Layout::of(self) {
+            Some(layout) => layout,
+            None => return Err(Error::UnsupportedFormat),
+        };
+        let (width, height, depth) = self.get_mipmap_dimensions(mipmap_level);
+        let data = self.get_mipmap_data(array_layer, mipmap_level)?;
+        layout.decode(width, height, depth, data)
+    }
+
+    /// Encode RGBA floats, four per texel, into a single mipmap level of an
+    /// array layer, in the format of this texture. This is the inverse of
+    /// `decode_rgba32f`.
+    pub fn encode_rgba32f(
+        &mut self,
+        array_layer: u32,
+        mipmap_level: u32,
+        rgba: &[f32],
+    ) -> Result<(), Error> {
+        let layout = match Layout::of(self) {
+            Some(layout) => layout,
+            None => return Err(Error::UnsupportedFormat),
+        };
+        let (width, height, depth) = self.get_mipmap_dimensions(mipmap_level);
+        let data = self.get_mut_mipmap_data(array_layer, mipmap_level)?;
+        layout.encode(width, height, depth, rgba, data)
+    }
+}
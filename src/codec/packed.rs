@@ -0,0 +1,334 @@
+// The MIT License (MIT)
+//
+// Copyright (c) 2018 Michael Dilger
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+use super::{f16_to_f32, f32_to_f16};
+use crate::yuv::{rgb_to_yuv, yuv_to_rgb};
+use crate::YuvMatrix;
+
+/// How a stored channel value is interpreted
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    UNorm,
+    SNorm,
+    UInt,
+    SInt,
+    /// IEEE float (16 or 32 bits)
+    Float,
+    /// Unsigned small float (10 or 11 bits, 5 of them exponent)
+    UFloat,
+}
+
+/// One channel of an uncompressed texel
+#[derive(Debug, Clone, Copy)]
+pub struct Channel {
+    /// Destination component (0 = red ... 3 = alpha)
+    pub target: usize,
+    pub offset: u32,
+    pub bits: u32,
+    pub kind: Kind,
+}
+
+pub const fn ch(target: usize, offset: u32, bits: u32, kind: Kind) -> Channel {
+    Channel {
+        target,
+        offset,
+        bits,
+        kind,
+    }
+}
+
+fn read_texel(bytes: &[u8]) -> u128 {
+    let mut raw = [0_u8; 16];
+    raw[..bytes.len()].copy_from_slice(bytes);
+    u128::from_le_bytes(raw)
+}
+
+fn write_texel(bytes: &mut [u8], value: u128) {
+    let len = bytes.len();
+    bytes.copy_from_slice(&value.to_le_bytes()[..len]);
+}
+
+fn sign_extend(value: u32, bits: u32) -> i32 {
+    let shift = 32 - bits;
+    ((value << shift) as i32) >> shift
+}
+
+fn decode_channel(value: u32, channel: &Channel) -> f32 {
+    let bits = channel.bits;
+    match channel.kind {
+        Kind::UNorm => value as f32 / ((1_u64 << bits) - 1) as f32,
+        Kind::SNorm => {
+            let max = ((1_u32 << (bits - 1)) - 1) as f32;
+            (sign_extend(value, bits) as f32 / max).max(-1.0)
+        }
+        Kind::UInt => value as f32,
+        Kind::SInt => sign_extend(value, bits) as f32,
+        Kind::Float => {
+            if bits == 16 {
+                f16_to_f32(value as u16)
+            } else {
+                f32::from_bits(value)
+            }
+        }
+        Kind::UFloat => {
+            // Same exponent as a half, with a shorter mantissa and no sign
+            f16_to_f32((value << (15 - bits)) as u16)
+        }
+    }
+}
+
+fn encode_channel(value: f32, channel: &Channel) -> u32 {
+    let bits = channel.bits;
+    let mask = ((1_u64 << bits) - 1) as u32;
+    match channel.kind {
+        Kind::UNorm => (value.clamp(0.0, 1.0) * mask as f32).round() as u32,
+        Kind::SNorm => {
+            let max = ((1_u32 << (bits - 1)) - 1) as f32;
+            ((value.clamp(-1.0, 1.0) * max).round() as i32 as u32) & mask
+        }
+        Kind::UInt => value.round().clamp(0.0, mask as f32) as u32,
+        Kind::SInt => {
+            let max = ((1_u32 << (bits - 1)) - 1) as f32;
+            (value.round().clamp(-max - 1.0, max) as i32 as u32) & mask
+        }
+        Kind::Float => {
+            if bits == 16 {
+                f32_to_f16(value) as u32
+            } else {
+                value.to_bits()
+            }
+        }
+        Kind::UFloat => {
+            if value.is_nan() || value <= 0.0 {
+                return 0;
+            }
+            // Round the half to the shorter mantissa, letting the carry
+            // ripple into the exponent
+            let shift = 15 - bits;
+            let half = f32_to_f16(value) as u32 & 0x7fff;
+            let rounded = (half + (1 << (shift - 1))) >> shift;
+            // Clamp infinities to the largest finite value
+            let max_finite = (0x1f << (bits - 5)) - 1;
+            rounded.min(max_finite)
+        }
+    }
+}
+
+/// Decode texels described by a list of channels into RGBA
+pub fn decode(bytes_per_texel: usize, channels: &[Channel], src: &[u8], dst: &mut [f32]) {
+    for (texel, rgba) in src
+        .chunks_exact(bytes_per_texel)
+        .zip(dst.chunks_exact_mut(4))
+    {
+        rgba.copy_from_slice(&[0.0, 0.0, 0.0, 1.0]);
+        let raw = read_texel(texel);
+        for channel in channels {
+            let value = (raw >> channel.offset) as u32 & ((1_u64 << channel.bits) - 1) as u32;
+            rgba[channel.target] = decode_channel(value, channel);
+        }
+    }
+}
+
+/// Encode RGBA into texels described by a list of channels
+pub fn encode(bytes_per_texel: usize, channels: &[Channel], src: &[f32], dst: &mut [u8]) {
+    for (rgba, texel) in src
+        .chunks_exact(4)
+        .zip(dst.chunks_exact_mut(bytes_per_texel))
+    {
+        let mut raw = 0_u128;
+        for channel in channels {
+            raw |= (encode_channel(rgba[channel.target], channel) as u128) << channel.offset;
+        }
+        write_texel(texel, raw);
+    }
+}
+
+/// Decode texels described by legacy RGBA (or luminance) bit masks
+pub fn decode_masks(
+    bytes_per_texel: usize,
+    masks: &[u32; 4],
+    luminance: bool,
+    src: &[u8],
+    dst: &mut [f32],
+) {
+    for (texel, rgba) in src
+        .chunks_exact(bytes_per_texel)
+        .zip(dst.chunks_exact_mut(4))
+    {
+        rgba.copy_from_slice(&[0.0, 0.0, 0.0, 1.0]);
+        let raw = read_texel(texel) as u32;
+        for (i, &mask) in masks.iter().enumerate() {
+            if mask != 0 {
+                let shift = mask.trailing_zeros();
+                rgba[i] = ((raw & mask) >> shift) as f32 / (mask >> shift) as f32;
+            }
+        }
+        if luminance {
+            rgba[1] = rgba[0];
+            rgba[2] = rgba[0];
+        }
+    }
+}
+
+/// Encode texels described by legacy RGBA (or luminance) bit masks
+pub fn encode_masks(
+    bytes_per_texel: usize,
+    masks: &[u32; 4],
+    luminance: bool,
+    src: &[f32],
+    dst: &mut [u8],
+) {
+    for (rgba, texel) in src
+        .chunks_exact(4)
+        .zip(dst.chunks_exact_mut(bytes_per_texel))
+    {
+        let mut rgba = [rgba[0], rgba[1], rgba[2], rgba[3]];
+        if luminance {
+            // Rec. 709 luma
+            rgba[0] = 0.2126 * rgba[0] + 0.7152 * rgba[1] + 0.0722 * rgba[2];
+        }
+        let mut raw = 0_u32;
+        for (i, &mask) in masks.iter().enumerate() {
+            if mask != 0 {
+                let shift = mask.trailing_zeros();
+                let max = (mask >> shift) as f32;
+                raw |= (((rgba[i].clamp(0.0, 1.0) * max).round() as u32) << shift) & mask;
+            }
+        }
+        write_texel(texel, raw as u128);
+    }
+}
+
+/// Decode R9G9B9E5 shared exponent texels
+pub fn decode_shared_exp(src: &[u8], dst: &mut [f32]) {
+    for (texel, rgba) in src.chunks_exact(4).zip(dst.chunks_exact_mut(4)) {
+        let raw = u32::from_le_bytes([texel[0], texel[1], texel[2], texel[3]]);
+        let scale = 2_f32.powi((raw >> 27) as i32 - 15 - 9);
+        rgba[0] = (raw & 0x1ff) as f32 * scale;
+        rgba[1] = ((raw >> 9) & 0x1ff) as f32 * scale;
+        rgba[2] = ((raw >> 18) & 0x1ff) as f32 * scale;
+        rgba[3] = 1.0;
+    }
+}
+
+/// Encode R9G9B9E5 shared exponent texels
+pub fn encode_shared_exp(src: &[f32], dst: &mut [u8]) {
+    const MAX: f32 = 511.0 / 512.0 * 65536.0;
+    for (rgba, texel) in src.chunks_exact(4).zip(dst.chunks_exact_mut(4)) {
+        let rgb = [
+            rgba[0].clamp(0.0, MAX),
+            rgba[1].clamp(0.0, MAX),
+            rgba[2].clamp(0.0, MAX),
+        ];
+        let max = rgb[0].max(rgb[1]).max(rgb[2]);
+        let mut exp = (max.log2().floor() as i32).max(-16) + 1 + 15;
+        if (max / 2_f32.powi(exp - 15 - 9)).round() >= 512.0 {
+            exp += 1;
+        }
+        let exp = exp.clamp(0, 31);
+        let scale = 2_f32.powi(exp - 15 - 9);
+        let mut raw = (exp as u32) << 27;
+        for (i, value) in rgb.iter().enumerate() {
+            raw |= ((value / scale).round() as u32).min(511) << (9 * i);
+        }
+        texel.copy_from_slice(&raw.to_le_bytes());
+    }
+}
+
+// 8-bit YUV in the limited range of BT.601, which D3D9 assumed for its video
+// formats
+fn yuv_to_rgba(y: u8, u: u8, v: u8) -> [f32; 4] {
+    let [r, g, b] = yuv_to_rgb(
+        YuvMatrix::Bt601,
+        (y as f32 - 16.0) / 219.0,
+        (u as f32 - 128.0) / 224.0,
+        (v as f32 - 128.0) / 224.0,
+    );
+    [r.clamp(0.0, 1.0), g.clamp(0.0, 1.0), b.clamp(0.0, 1.0), 1.0]
+}
+
+fn rgba_to_yuv(rgba: &[f32]) -> (f32, f32, f32) {
+    let (y, u, v) = rgb_to_yuv(YuvMatrix::Bt601, &rgba[..3]);
+    (16.0 + 219.0 * y, 128.0 + 224.0 * u, 128.0 + 224.0 * v)
+}
+
+fn to_byte(value: f32) -> u8 {
+    value.round().clamp(0.0, 255.0) as u8
+}
+
+/// Decode pairs of texels that share their chroma (4:2:2), four bytes per
+/// pair with Y0, U, Y1 and V at the given offsets
+pub fn decode_yuv422(width: usize, order: &[usize; 4], src: &[u8], dst: &mut [f32]) {
+    let pairs = width.div_ceil(2);
+    for (row, texels) in src
+        .chunks_exact(pairs * 4)
+        .zip(dst.chunks_exact_mut(width * 4))
+    {
+        for (x, rgba) in texels.chunks_exact_mut(4).enumerate() {
+            let pair = &row[x / 2 * 4..x / 2 * 4 + 4];
+            let y = pair[order[if x % 2 == 0 { 0 } else { 2 }]];
+            rgba.copy_from_slice(&yuv_to_rgba(y, pair[order[1]], pair[order[3]]));
+        }
+    }
+}
+
+/// Encode pairs of texels that share their chroma (4:2:2), averaging the
+/// chroma of each pair
+pub fn encode_yuv422(width: usize, order: &[usize; 4], src: &[f32], dst: &mut [u8]) {
+    let pairs = width.div_ceil(2);
+    for (texels, row) in src
+        .chunks_exact(width * 4)
+        .zip(dst.chunks_exact_mut(pairs * 4))
+    {
+        for (x, pair) in row.chunks_exact_mut(4).enumerate() {
+            let (y0, u0, v0) = rgba_to_yuv(&texels[x * 8..]);
+            // An odd width repeats the last texel
+            let (y1, u1, v1) = match texels.get(x * 8 + 4..x * 8 + 8) {
+                Some(rgba) => rgba_to_yuv(rgba),
+                None => (y0, u0, v0),
+            };
+            pair[order[0]] = to_byte(y0);
+            pair[order[1]] = to_byte((u0 + u1) / 2.0);
+            pair[order[2]] = to_byte(y1);
+            pair[order[3]] = to_byte((v0 + v1) / 2.0);
+        }
+    }
+}
+
+/// Decode CxV8U8 normal maps, whose third component is computed from the two
+/// stored ones so that the normal has unit length
+pub fn decode_cxv8u8(src: &[u8], dst: &mut [f32]) {
+    for (texel, rgba) in src.chunks_exact(2).zip(dst.chunks_exact_mut(4)) {
+        let u = (texel[0] as i8 as f32 / 127.0).max(-1.0);
+        let v = (texel[1] as i8 as f32 / 127.0).max(-1.0);
+        let c = (1.0 - u * u - v * v).max(0.0).sqrt();
+        rgba.copy_from_slice(&[u, v, c, 1.0]);
+    }
+}
+
+/// Encode CxV8U8 normal maps, dropping the computed component
+pub fn encode_cxv8u8(src: &[f32], dst: &mut [u8]) {
+    for (rgba, texel) in src.chunks_exact(4).zip(dst.chunks_exact_mut(2)) {
+        texel[0] = (rgba[0].clamp(-1.0, 1.0) * 127.0).round() as i8 as u8;
+        texel[1] = (rgba[1].clamp(-1.0, 1.0) * 127.0).round() as i8 as u8;
+    }
+}
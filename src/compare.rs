@@ -0,0 +1,451 @@
+// The MIT License (MIT)
+//
+// Copyright (c) 2018 Michael Dilger
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+// Comparing two textures, header by header and texel by texel
+
+use crate::{
+    AlphaMode, D3D10ResourceDimension, Dds, DxgiFormat, Error, FourCC, Header10, NewDxgiParams,
+    PixelFormat,
+};
+use std::fmt;
+
+/// A header field that differs between two textures
+#[derive(Debug, Clone, PartialEq)]
+pub struct HeaderDifference {
+    pub field: &'static str,
+    pub left: String,
+    pub right: String,
+}
+
+impl fmt::Display for HeaderDifference {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {} != {}", self.field, self.left, self.right)
+    }
+}
+
+/// Error metrics for one subresource (a mipmap level of an array layer),
+/// computed on decoded RGBA floats
+#[derive(Debug, Clone, PartialEq)]
+pub struct SubresourceMetrics {
+    pub array_layer: u32,
+    pub mipmap_level: u32,
+    /// Peak signal-to-noise ratio of the RGB channels in decibels, taking 1.0 as
+    /// the peak value. This is infinite when the channels are identical.
+    pub psnr: f64,
+    /// Mean structural similarity of the RGB channels, from -1 to 1
+    pub ssim: f64,
+    /// The largest absolute difference in any channel
+    pub max_abs_error: f32,
+    /// Root mean square error of each RGBA channel
+    pub channel_rmse: [f64; 4],
+    /// The largest absolute difference in each RGBA channel
+    pub channel_max_abs_error: [f32; 4],
+}
+
+/// The result of comparing two textures
+#[derive(Debug, Clone, PartialEq)]
+pub struct Comparison {
+    pub header_differences: Vec<HeaderDifference>,
+    /// Metrics for every subresource the textures have in common
+    pub subresources: Vec<SubresourceMetrics>,
+}
+
+impl Comparison {
+    /// True when the headers match and every subresource decodes identically
+    pub fn is_identical(&self) -> bool {
+        self.header_differences.is_empty()
+            && self.subresources.iter().all(|s| s.max_abs_error == 0.0)
+    }
+
+    /// The lowest PSNR of any subresource
+    pub fn min_psnr(&self) -> f64 {
+        self.subresources
+            .iter()
+            .map(|s| s.psnr)
+            .fold(f64::INFINITY, f64::min)
+    }
+
+    /// The lowest SSIM of any subresource
+    pub fn min_ssim(&self) -> f64 {
+        self.subresources.iter().map(|s| s.ssim).fold(1.0, f64::min)
+    }
+
+    /// The largest absolute error of any subresource
+    pub fn max_abs_error(&self) -> f32 {
+        self.subresources
+            .iter()
+            .map(|s| s.max_abs_error)
+            .fold(0.0, f32::max)
+    }
+}
+
+impl Dds {
+    /// List the header fields that differ between two textures
+    pub fn compare_headers(&self, other: &Dds) -> Vec<HeaderDifference> {
+        let mut differences = Vec::new();
+        let mut check = |field: &'static str, left: String, right: String| {
+            if left != right {
+                differences.push(HeaderDifference { field, left, right });
+            }
+        };
+        let format = |dds: &Dds| match (dds.get_d3d_format(), dds.get_dxgi_format()) {
+            (_, Some(f)) if dds.header10.is_some() => f.to_string(),
+            (Some(f), _) => f.to_string(),
+            (None, Some(f)) => f.to_string(),
+            (None, None) => "unknown".to_owned(),
+        };
+
+        check("format", format(self), format(other));
+        check(
+            "width",
+            self.get_width().to_string(),
+            other.get_width().to_string(),
+        );
+        check(
+            "height",
+            self.get_height().to_string(),
+            other.get_height().to_string(),
+        );
+        check(
+            "depth",
+            self.get_depth().to_string(),
+            other.get_depth().to_string(),
+        );
+        check(
+            "mipmap_levels",
+            self.get_num_mipmap_levels().to_string(),
+            other.get_num_mipmap_levels().to_string(),
+        );
+        check(
+            "array_layers",
+            self.get_num_array_layers().to_string(),
+            other.get_num_array_layers().to_string(),
+        );
+        check(
+            "cubemap",
+            self.is_cubemap().to_string(),
+            other.is_cubemap().to_string(),
+        );
+        check(
+            "volume",
+            self.is_volume().to_string(),
+            other.is_volume().to_string(),
+        );
+
+        let (h, o) = (&self.header, &other.header);
+        check(
+            "header.flags",
+            format!("{:?}", h.get_flags()),
+            format!("{:?}", o.get_flags()),
+        );
+        check(
+            "header.pitch",
+            format!("{:?}", h.pitch),
+            format!("{:?}", o.pitch),
+        );
+        check(
+            "header.linear_size",
+            format!("{:?}", h.linear_size),
+            format!("{:?}", o.linear_size),
+        );
+        check(
+            "header.caps",
+            format!("{:?}", h.caps),
+            format!("{:?}", o.caps),
+        );
+        check(
+            "header.caps2",
+            format!("{:?}", h.caps2),
+            format!("{:?}", o.caps2),
+        );
+
+        let (h, o) = (&self.header.spf, &other.header.spf);
+        check(
+            "pixel_format.flags",
+            format!("{:?}", h.flags),
+            format!("{:?}", o.flags),
+        );
+        let fourcc = |f: &Option<FourCC>| match f {
+            Some(f) => f.to_string(),
+            None => "None".to_owned(),
+        };
+        check("pixel_format.fourcc", fourcc(&h.fourcc), fourcc(&o.fourcc));
+        check(
+            "pixel_format.rgb_bit_count",
+            format!("{:?}", h.rgb_bit_count),
+            format!("{:?}", o.rgb_bit_count),
+        );
+        let masks = |p: &PixelFormat| {
+            format!(
+                "{:?}",
+                [p.r_bit_mask, p.g_bit_mask, p.b_bit_mask, p.a_bit_mask]
+            )
+        };
+        check("pixel_format.bit_masks", masks(h), masks(o));
+
+        let dxgi_format = |h: &Header10| match h.get_unknown_dxgi_format() {
+            Some(value) => value.to_string(),
+            None => h.dxgi_format.to_string(),
+        };
+        match (&self.header10, &other.header10) {
+            (Some(h), Some(o)) => {
+                check("header10.dxgi_format", dxgi_format(h), dxgi_format(o));
+                check(
+                    "header10.resource_dimension",
+                    format!("{:?}", h.resource_dimension),
+                    format!("{:?}", o.resource_dimension),
+                );
+                check(
+                    "header10.misc_flag",
+                    format!("{:?}", h.misc_flag),
+                    format!("{:?}", o.misc_flag),
+                );
+                check(
+                    "header10.array_size",
+                    h.array_size.to_string(),
+                    o.array_size.to_string(),
+                );
+                check(
+                    "header10.alpha_mode",
+                    format!("{:?}", h.alpha_mode),
+                    format!("{:?}", o.alpha_mode),
+                );
+            }
+            (h, o) => check(
+                "header10",
+                if h.is_some() { "present" } else { "absent" }.to_owned(),
+                if o.is_some() { "present" } else { "absent" }.to_owned(),
+            ),
+        }
+
+        check(
+            "data_size",
+            self.data.len().to_string(),
+            other.data.len().to_string(),
+        );
+        differences
+    }
+
+    /// Compare the headers of two textures, and decode and measure every
+    /// subresource they have in common: those in the first array layers and
+    /// mipmap levels of the texture that has fewer, with the same dimensions in
+    /// both.
+    pub fn compare(&self, other: &Dds) -> Result<Comparison, Error> {
+        let mut subresources = Vec::new();
+        let layers = self
+            .get_num_array_layers()
+            .min(other.get_num_array_layers());
+        let levels = self
+            .get_num_mipmap_levels()
+            .min(other.get_num_mipmap_levels());
+        for array_layer in 0..layers {
+            for mipmap_level in 0..levels {
+                if self.get_mipmap_dimensions(mipmap_level)
+                    != other.get_mipmap_dimensions(mipmap_level)
+                {
+                    continue;
+                }
+                subresources.push(self.compare_subresource(other, array_layer, mipmap_level)?);
+            }
+        }
+        Ok(Comparison {
+            header_differences: self.compare_headers(other),
+            subresources,
+        })
+    }
+
+    /// Decode and measure a single subresource of both textures
+    pub fn compare_subresource(
+        &self,
+        other: &Dds,
+        array_layer: u32,
+        mipmap_level: u32,
+    ) -> Result<SubresourceMetrics, Error> {
+        self.check_mipmap_dimensions(other, mipmap_level)?;
+        let (width, height, depth) = self.get_mipmap_dimensions(mipmap_level);
+        let left = self.decode_rgba32f(array_layer, mipmap_level)?;
+        let right = other.decode_rgba32f(array_layer, mipmap_level)?;
+
+        let mut squared = [0.0_f64; 4];
+        let mut channel_max_abs_error = [0.0_f32; 4];
+        for (l, r) in left.chunks_exact(4).zip(right.chunks_exact(4)) {
+            for c in 0..4 {
+                let d = l[c] - r[c];
+                squared[c] += (d as f64) * (d as f64);
+                channel_max_abs_error[c] = channel_max_abs_error[c].max(d.abs());
+            }
+        }
+        let texels = (left.len() / 4).max(1) as f64;
+        let channel_rmse = squared.map(|s| (s / texels).sqrt());
+        let rgb_mse = (squared[0] + squared[1] + squared[2]) / (3.0 * texels);
+        let psnr = if rgb_mse == 0.0 {
+            f64::INFINITY
+        } else {
+            -10.0 * rgb_mse.log10()
+        };
+
+        // SSIM of each slice and color channel, averaged
+        let slice = (width * height * 4) as usize;
+        let mut ssim_total = 0.0;
+        for z in 0..depth as usize {
+            let l = &left[z * slice..(z + 1) * slice];
+            let r = &right[z * slice..(z + 1) * slice];
+            for c in 0..3 {
+                ssim_total += ssim(width as usize, height as usize, l, r, c);
+            }
+        }
+        let ssim = ssim_total / (3 * depth) as f64;
+
+        Ok(SubresourceMetrics {
+            array_layer,
+            mipmap_level,
+            psnr,
+            ssim,
+            max_abs_error: channel_max_abs_error.iter().cloned().fold(0.0, f32::max),
+            channel_rmse,
+            channel_max_abs_error,
+        })
+    }
+
+    /// Build a texture holding the absolute difference of every channel, times
+    /// `scale`, for every subresource two textures have in common. The result
+    /// is R32G32B32A32_Float, with the layout of this texture.
+    pub fn difference(&self, other: &Dds, scale: f32) -> Result<Dds, Error> {
+        let layers = self
+            .get_num_array_layers()
+            .min(other.get_num_array_layers());
+        let levels = self
+            .get_num_mipmap_levels()
+            .min(other.get_num_mipmap_levels());
+        let mut diff = Dds::new_dxgi(NewDxgiParams {
+            height: self.get_height(),
+            width: self.get_width(),
+            depth: if self.is_volume() {
+                Some(self.get_depth())
+            } else {
+                None
+            },
+            format: DxgiFormat::R32G32B32A32_Float,
+            mipmap_levels: Some(levels),
+            array_layers: Some(layers),
+            caps2: Some(self.header.caps2),
+            is_cubemap: self.is_cubemap() && layers.is_multiple_of(6),
+            resource_dimension: match self.header10 {
+                Some(ref h10) => h10.resource_dimension,
+                None if self.is_volume() => D3D10ResourceDimension::Texture3D,
+                None => D3D10ResourceDimension::Texture2D,
+            },
+            alpha_mode: AlphaMode::Unknown,
+        })?;
+        for array_layer in 0..layers {
+            for mipmap_level in 0..levels {
+                self.check_mipmap_dimensions(other, mipmap_level)?;
+                let left = self.decode_rgba32f(array_layer, mipmap_level)?;
+                let right = other.decode_rgba32f(array_layer, mipmap_level)?;
+                let rgba: Vec<f32> = left
+                    .iter()
+                    .zip(right.iter())
+                    .map(|(l, r)| (l - r).abs() * scale)
+                    .collect();
+                diff.encode_rgba32f(array_layer, mipmap_level, &rgba)?;
+            }
+        }
+        Ok(diff)
+    }
+
+    // The other texture, as the second one compared, must have the same
+    // dimensions at the given mipmap level
+    fn check_mipmap_dimensions(&self, other: &Dds, mipmap_level: u32) -> Result<(), Error> {
+        let dimensions = |dds: &Dds| {
+            let (width, height, depth) = dds.get_mipmap_dimensions(mipmap_level);
+            format!("{}x{}x{}", width, height, depth)
+        };
+        let (expected, found) = (dimensions(self), dimensions(other));
+        if found != expected {
+            return Err(Error::Mismatch {
+                index: 1,
+                property: "mipmap dimensions",
+                found,
+                expected,
+            });
+        }
+        Ok(())
+    }
+}
+
+// Structural similarity of one channel of two RGBA images, using an 11x11
+// Gaussian window with a standard deviation of 1.5 and a dynamic range of 1.0
+fn ssim(width: usize, height: usize, left: &[f32], right: &[f32], channel: usize) -> f64 {
+    const C1: f64 = 0.01 * 0.01;
+    const C2: f64 = 0.03 * 0.03;
+    let n = width * height;
+    let x: Vec<f64> = (0..n).map(|i| left[i * 4 + channel] as f64).collect();
+    let y: Vec<f64> = (0..n).map(|i| right[i * 4 + channel] as f64).collect();
+    let xx: Vec<f64> = x.iter().map(|v| v * v).collect();
+    let yy: Vec<f64> = y.iter().map(|v| v * v).collect();
+    let xy: Vec<f64> = x.iter().zip(y.iter()).map(|(a, b)| a * b).collect();
+
+    let mu_x = blur(width, height, &x);
+    let mu_y = blur(width, height, &y);
+    let sigma_xx = blur(width, height, &xx);
+    let sigma_yy = blur(width, height, &yy);
+    let sigma_xy = blur(width, height, &xy);
+
+    let mut total = 0.0;
+    for i in 0..n {
+        let (mx, my) = (mu_x[i], mu_y[i]);
+        let vx = sigma_xx[i] - mx * mx;
+        let vy = sigma_yy[i] - my * my;
+        let cov = sigma_xy[i] - mx * my;
+        total +=
+            ((2.0 * mx * my + C1) * (2.0 * cov + C2)) / ((mx * mx + my * my + C1) * (vx + vy + C2));
+    }
+    total / n.max(1) as f64
+}
+
+// Separable Gaussian blur, with the window renormalized at the edges
+fn blur(width: usize, height: usize, values: &[f64]) -> Vec<f64> {
+    const RADIUS: isize = 5;
+    let weights: Vec<f64> = (-RADIUS..=RADIUS)
+        .map(|i| (-((i * i) as f64) / (2.0 * 1.5 * 1.5)).exp())
+        .collect();
+    let pass = |values: &[f64], step: usize, len: usize, lines: usize, stride: usize| {
+        let mut out = vec![0.0; values.len()];
+        for line in 0..lines {
+            for i in 0..len as isize {
+                let (mut sum, mut weight) = (0.0, 0.0);
+                for k in -RADIUS..=RADIUS {
+                    let j = i + k;
+                    if j >= 0 && j < len as isize {
+                        let w = weights[(k + RADIUS) as usize];
+                        sum += w * values[line * stride + j as usize * step];
+                        weight += w;
+                    }
+                }
+                out[line * stride + i as usize * step] = sum / weight;
+            }
+        }
+        out
+    };
+    let rows = pass(values, 1, width, height, width);
+    pass(&rows, width, height, width, 1)
+}
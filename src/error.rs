@@ -26,12 +26,81 @@ use std::fmt;
 pub enum Error {
     Fmt(fmt::Error),
     Io(std::io::Error),
-    General(String),
+    /// An image could not be decoded or encoded
+    #[cfg(feature = "image")]
+    Image(image::ImageError),
     BadMagicNumber,
-    InvalidField(String),
-    ShortFile,
+    /// A field holds a value that is not allowed
+    InvalidField {
+        /// The name of the field, as in the file format specification
+        field: &'static str,
+        /// The offending value
+        value: u64,
+        /// The byte offset of the field within the file, when it came from one
+        offset: Option<u64>,
+        /// What the field should have held
+        expected: Expected,
+    },
+    /// The data ends before it should: `expected` bytes were needed, but only
+    /// `actual` bytes were there
+    ShortFile {
+        expected: u64,
+        actual: u64,
+    },
     UnsupportedFormat,
     OutOfBounds,
+    /// One of several textures or images being combined does not match the
+    /// first one
+    Mismatch {
+        /// The position of the mismatching texture or image
+        index: usize,
+        /// The property that differs, such as "format" or "dimensions"
+        property: &'static str,
+        found: String,
+        expected: String,
+    },
+    /// A string does not name any value of the given kind
+    Parse {
+        kind: &'static str,
+        input: String,
+    },
+    /// Two options were requested that cannot be combined, such as a texture
+    /// that is both a cubemap and a volume
+    Conflict {
+        first: &'static str,
+        second: &'static str,
+    },
+}
+
+/// The values a field was expected to hold
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Expected {
+    /// A value from the first to the second, inclusive
+    Range(u64, u64),
+    /// One of a list of values
+    OneOf(&'static [u64]),
+    /// A value meeting a condition that is not a simple range
+    Description(&'static str),
+}
+
+impl fmt::Display for Expected {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Expected::Range(min, max) if min == max => write!(f, "{}", min),
+            Expected::Range(min, max) => write!(f, "{} to {}", min, max),
+            Expected::OneOf(values) => {
+                write!(f, "one of ")?;
+                for (i, value) in values.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", value)?;
+                }
+                Ok(())
+            }
+            Expected::Description(s) => write!(f, "{}", s),
+        }
+    }
 }
 
 impl fmt::Display for Error {
@@ -39,14 +108,44 @@ impl fmt::Display for Error {
         match *self {
             Error::Fmt(ref e) => write!(f, "{}", e),
             Error::Io(ref e) => write!(f, "{}", e),
-            Error::General(ref s) => write!(f, "General Error: {}", s),
+            #[cfg(feature = "image")]
+            Error::Image(ref e) => write!(f, "{}", e),
             Error::BadMagicNumber => write!(f, "Bad Magic Number"),
-            Error::InvalidField(ref s) => write!(f, "Invalid Field: {}", s),
-            Error::ShortFile => write!(f, "File is cut short"),
+            Error::InvalidField {
+                field,
+                value,
+                offset,
+                expected,
+            } => {
+                write!(f, "Invalid Field: {} is {}", field, value)?;
+                if let Some(offset) = offset {
+                    write!(f, " at byte {}", offset)?;
+                }
+                write!(f, ", expected {}", expected)
+            }
+            Error::ShortFile { expected, actual } => write!(
+                f,
+                "File is cut short: needed {} bytes but found {}",
+                expected, actual
+            ),
             Error::UnsupportedFormat => {
                 write!(f, "Format is not supported well enough for this operation")
             }
             Error::OutOfBounds => write!(f, "Request is out of bounds"),
+            Error::Mismatch {
+                index,
+                property,
+                ref found,
+                ref expected,
+            } => write!(
+                f,
+                "Mismatch: item {} has {} {}, expected {}",
+                index, property, found, expected
+            ),
+            Error::Parse { kind, ref input } => write!(f, "Unknown {}: {}", kind, input),
+            Error::Conflict { first, second } => {
+                write!(f, "Conflict: {} and {} cannot be combined", first, second)
+            }
         }
     }
 }
@@ -56,6 +155,8 @@ impl std::error::Error for Error {
         match *self {
             Error::Fmt(ref e) => Some(e),
             Error::Io(ref e) => Some(e),
+            #[cfg(feature = "image")]
+            Error::Image(ref e) => Some(e),
             _ => None,
         }
     }
@@ -73,14 +174,12 @@ impl From<std::io::Error> for Error {
     }
 }
 
-impl From<&str> for Error {
-    fn from(s: &str) -> Error {
-        Error::General(s.to_owned())
-    }
-}
-
-impl From<String> for Error {
-    fn from(s: String) -> Error {
-        Error::General(s)
+#[cfg(feature = "image")]
+impl From<image::ImageError> for Error {
+    fn from(e: image::ImageError) -> Error {
+        match e {
+            image::ImageError::IoError(e) => Error::Io(e),
+            e => Error::Image(e),
+        }
     }
 }
@@ -0,0 +1,215 @@
+// The MIT License (MIT)
+//
+// Copyright (c) 2018 Michael Dilger
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+// Exporting subresources to common image file formats
+
+use crate::codec::Layout;
+use crate::{Dds, Error};
+use image::{imageops, DynamicImage, ImageFormat};
+use std::fs::File;
+use std::io::{BufWriter, Seek, Write};
+use std::path::{Path, PathBuf};
+
+/// An image file format that subresources can be exported to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// PNG, 8 or 16 bits per channel
+    Png,
+    /// Truevision TGA, 8 bits per channel
+    Tga,
+    /// Radiance HDR, RGB only
+    Hdr,
+    /// OpenEXR, 32 bit float RGBA
+    Exr,
+}
+
+impl ExportFormat {
+    /// Pick the format from a file extension, ignoring case
+    pub fn from_extension(extension: &str) -> Option<ExportFormat> {
+        match extension.to_ascii_lowercase().as_str() {
+            "png" => Some(ExportFormat::Png),
+            "tga" => Some(ExportFormat::Tga),
+            "hdr" => Some(ExportFormat::Hdr),
+            "exr" => Some(ExportFormat::Exr),
+            _ => None,
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match *self {
+            ExportFormat::Png => "png",
+            ExportFormat::Tga => "tga",
+            ExportFormat::Hdr => "hdr",
+            ExportFormat::Exr => "exr",
+        }
+    }
+
+    /// Convert the image to a color type this format can hold, and write it
+    pub fn write<W: Write + Seek>(&self, image: &DynamicImage, w: &mut W) -> Result<(), Error> {
+        match *self {
+            ExportFormat::Png => match image {
+                DynamicImage::ImageRgba8(_) | DynamicImage::ImageRgba16(_) => {
+                    image.write_to(w, ImageFormat::Png)?
+                }
+                _ => DynamicImage::ImageRgba16(image.to_rgba16()).write_to(w, ImageFormat::Png)?,
+            },
+            ExportFormat::Tga => {
+                DynamicImage::ImageRgba8(image.to_rgba8()).write_to(w, ImageFormat::Tga)?
+            }
+            ExportFormat::Hdr => {
+                DynamicImage::ImageRgb32F(image.to_rgb32f()).write_to(w, ImageFormat::Hdr)?
+            }
+            ExportFormat::Exr => {
+                DynamicImage::ImageRgba32F(image.to_rgba32f()).write_to(w, ImageFormat::OpenExr)?
+            }
+        }
+        Ok(())
+    }
+
+    /// Write the image to a file
+    pub fn save(&self, image: &DynamicImage, path: &Path) -> Result<(), Error> {
+        let mut w = BufWriter::new(File::create(path)?);
+        self.write(image, &mut w)?;
+        w.flush()?;
+        Ok(())
+    }
+}
+
+impl Dds {
+    /// The best suited export format for this texture: PNG for low dynamic range
+    /// formats, and OpenEXR for float, signed, integer and BC6H formats.
+    pub fn get_export_format(&self) -> ExportFormat {
+        match Layout::of(self).and_then(|layout| layout.unorm_bits()) {
+            Some(_) => ExportFormat::Png,
+            None => ExportFormat::Exr,
+        }
+    }
+
+    /// Export a single mipmap level of an array layer. The slices of a volume
+    /// texture are stacked vertically.
+    pub fn export_subresource<W: Write + Seek>(
+        &self,
+        array_layer: u32,
+        mipmap_level: u32,
+        format: ExportFormat,
+        w: &mut W,
+    ) -> Result<(), Error> {
+        let image = self.to_image(array_layer, mipmap_level)?;
+        format.write(&image, w)
+    }
+
+    /// Export a single mipmap level of an array layer to a file, picking the
+    /// format from the file extension.
+    pub fn export_subresource_to_path<P: AsRef<Path>>(
+        &self,
+        array_layer: u32,
+        mipmap_level: u32,
+        path: P,
+    ) -> Result<(), Error> {
+        let path = path.as_ref();
+        let format = export_format_of(path)?;
+        let image = self.to_image(array_layer, mipmap_level)?;
+        format.save(&image, path)
+    }
+
+    /// Export every mipmap level of every array layer (and every slice of a
+    /// volume texture) into `directory`, as `<name>_layer<L>_mip<M>.<ext>` (with
+    /// `_slice<S>` appended for volume textures). Returns the paths written.
+    pub fn export_all<P: AsRef<Path>>(
+        &self,
+        directory: P,
+        name: &str,
+        format: ExportFormat,
+    ) -> Result<Vec<PathBuf>, Error> {
+        let directory = directory.as_ref();
+        let mut paths = Vec::new();
+        for array_layer in 0..self.get_num_array_layers() {
+            for mipmap_level in 0..self.get_num_mipmap_levels() {
+                let image = self.to_image(array_layer, mipmap_level)?;
+                let base = format!("{}_layer{}_mip{}", name, array_layer, mipmap_level);
+                if self.is_volume() {
+                    let (width, height, depth) = self.get_mipmap_dimensions(mipmap_level);
+                    for slice in 0..depth {
+                        let path = directory.join(format!(
+                            "{}_slice{}.{}",
+                            base,
+                            slice,
+                            format.extension()
+                        ));
+                        format.save(&image.crop_imm(0, slice * height, width, height), &path)?;
+                        paths.push(path);
+                    }
+                } else {
+                    let path = directory.join(format!("{}.{}", base, format.extension()));
+                    format.save(&image, &path)?;
+                    paths.push(path);
+                }
+            }
+        }
+        Ok(paths)
+    }
+
+    /// Lay out every subresource in a single image: one row per array layer
+    /// (cubemap face), with the mipmap levels from left to right.
+    pub fn get_contact_sheet(&self) -> Result<DynamicImage, Error> {
+        let first = self.to_image(0, 0)?;
+        let levels = self.get_num_mipmap_levels();
+        let width = (0..levels)
+            .map(|level| self.get_mipmap_dimensions(level).0)
+            .sum();
+        let row_height = first.height();
+        let height = row_height * self.get_num_array_layers();
+        let mut sheet = DynamicImage::new(width, height, first.color());
+        for array_layer in 0..self.get_num_array_layers() {
+            let mut x = 0;
+            for mipmap_level in 0..levels {
+                let image = self.to_image(array_layer, mipmap_level)?;
+                let y = array_layer * row_height;
+                imageops::replace(&mut sheet, &image, x as i64, y as i64);
+                x += image.width();
+            }
+        }
+        Ok(sheet)
+    }
+
+    /// Export a contact sheet of every subresource to a file, picking the
+    /// format from the file extension.
+    pub fn export_contact_sheet_to_path<P: AsRef<Path>>(&self, path: P) -> Result<(), Error> {
+        let path = path.as_ref();
+        let format = export_format_of(path)?;
+        format.save(&self.get_contact_sheet()?, path)
+    }
+}
+
+fn export_format_of(path: &Path) -> Result<ExportFormat, Error> {
+    match path
+        .extension()
+        .and_then(|e| e.to_str())
+        .and_then(ExportFormat::from_extension)
+    {
+        Some(format) => Ok(format),
+        None => Err(Error::Parse {
+            kind: "export file extension",
+            input: path.display().to_string(),
+        }),
+    }
+}
@@ -20,8 +20,13 @@
 // OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
 // THE SOFTWARE.
 
+use super::dxgi::strip_prefix_ignore_case;
+use super::info::d3d_format_info;
 use super::pixel_format::{FourCC, PixelFormat, PixelFormatFlags};
-use super::DataFormat;
+use super::{DataFormat, DxgiFormat, FormatInfo};
+use crate::error::Error;
+use std::fmt;
+use std::str::FromStr;
 
 #[allow(non_camel_case_types)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -63,22 +68,76 @@ pub enum D3DFormat {
     UYVY,
     YUY2,
     CXV8U8,
+    V8U8,
+    L6V5U5,
+    X8L8V8U8,
+    Q8W8V8U8,
+    V16U16,
+    A2W10V10U10,
 }
 
+// Every format, for looking them up by name
+const D3D_FORMATS: &[D3DFormat] = &[
+    D3DFormat::A8B8G8R8,
+    D3DFormat::G16R16,
+    D3DFormat::A2B10G10R10,
+    D3DFormat::A1R5G5B5,
+    D3DFormat::R5G6B5,
+    D3DFormat::A8,
+    D3DFormat::A8R8G8B8,
+    D3DFormat::X8R8G8B8,
+    D3DFormat::X8B8G8R8,
+    D3DFormat::A2R10G10B10,
+    D3DFormat::R8G8B8,
+    D3DFormat::X1R5G5B5,
+    D3DFormat::A4R4G4B4,
+    D3DFormat::X4R4G4B4,
+    D3DFormat::A8R3G3B2,
+    D3DFormat::A8L8,
+    D3DFormat::L16,
+    D3DFormat::L8,
+    D3DFormat::A4L4,
+    D3DFormat::DXT1,
+    D3DFormat::DXT3,
+    D3DFormat::DXT5,
+    D3DFormat::R8G8_B8G8,
+    D3DFormat::G8R8_G8B8,
+    D3DFormat::A16B16G16R16,
+    D3DFormat::Q16W16V16U16,
+    D3DFormat::R16F,
+    D3DFormat::G16R16F,
+    D3DFormat::A16B16G16R16F,
+    D3DFormat::R32F,
+    D3DFormat::G32R32F,
+    D3DFormat::A32B32G32R32F,
+    D3DFormat::DXT2,
+    D3DFormat::DXT4,
+    D3DFormat::UYVY,
+    D3DFormat::YUY2,
+    D3DFormat::CXV8U8,
+    D3DFormat::V8U8,
+    D3DFormat::L6V5U5,
+    D3DFormat::X8L8V8U8,
+    D3DFormat::Q8W8V8U8,
+    D3DFormat::V16U16,
+    D3DFormat::A2W10V10U10,
+];
+
 impl DataFormat for D3DFormat {
     fn get_pitch(&self, width: u32) -> Option<u32> {
         // see https://msdn.microsoft.com/en-us/library/bb943991.aspx
         match *self {
-            D3DFormat::R8G8_B8G8 | D3DFormat::G8R8_G8B8 => {
-                return Some(((width + 1) >> 1) * 4);
+            D3DFormat::R8G8_B8G8 | D3DFormat::G8R8_G8B8 | D3DFormat::UYVY | D3DFormat::YUY2 => {
+                return width.div_ceil(2).checked_mul(4);
             }
             _ => {}
         };
 
+        // Widths whose pitch does not fit in a u32 have none
         if let Some(bpp) = self.get_bits_per_pixel() {
-            Some((width * bpp as u32 + 7) / 8)
+            Some(width.checked_mul(bpp as u32)?.div_ceil(8))
         } else {
-            self.get_block_size().map(|bs| 1.max((width + 3) / 4) * bs)
+            1.max(width.div_ceil(4)).checked_mul(self.get_block_size()?)
         }
     }
 
@@ -106,8 +165,8 @@ impl DataFormat for D3DFormat {
             D3DFormat::DXT1 => None,
             D3DFormat::DXT3 => None,
             D3DFormat::DXT5 => None,
-            D3DFormat::R8G8_B8G8 => Some(32),
-            D3DFormat::G8R8_G8B8 => Some(32),
+            D3DFormat::R8G8_B8G8 => Some(16),
+            D3DFormat::G8R8_G8B8 => Some(16),
             D3DFormat::A16B16G16R16 => Some(64),
             D3DFormat::Q16W16V16U16 => Some(64),
             D3DFormat::R16F => Some(16),
@@ -121,6 +180,12 @@ impl DataFormat for D3DFormat {
             D3DFormat::UYVY => Some(16),
             D3DFormat::YUY2 => Some(16),
             D3DFormat::CXV8U8 => Some(16),
+            D3DFormat::V8U8 => Some(16),
+            D3DFormat::L6V5U5 => Some(16),
+            D3DFormat::X8L8V8U8 => Some(32),
+            D3DFormat::Q8W8V8U8 => Some(32),
+            D3DFormat::V16U16 => Some(32),
+            D3DFormat::A2W10V10U10 => Some(32),
         }
     }
 
@@ -171,12 +236,22 @@ impl DataFormat for D3DFormat {
             D3DFormat::UYVY => Some(FourCC(FourCC::UYVY)),
             D3DFormat::YUY2 => Some(FourCC(FourCC::YUY2)),
             D3DFormat::CXV8U8 => Some(FourCC(FourCC::CXV8U8)),
+            D3DFormat::V8U8 => None,
+            D3DFormat::L6V5U5 => None,
+            D3DFormat::X8L8V8U8 => None,
+            D3DFormat::Q8W8V8U8 => None,
+            D3DFormat::V16U16 => None,
+            D3DFormat::A2W10V10U10 => None,
         }
     }
 
     fn requires_extension(&self) -> bool {
         false
     }
+
+    fn get_format_info(&self) -> Option<FormatInfo> {
+        d3d_format_info(*self)
+    }
 }
 
 impl D3DFormat {
@@ -202,6 +277,12 @@ impl D3DFormat {
             D3DFormat::L16 => Some(0xffff),
             D3DFormat::L8 => Some(0xff),
             D3DFormat::A4L4 => Some(0x0f),
+            D3DFormat::V8U8 => Some(0x00ff),
+            D3DFormat::L6V5U5 => Some(0x001f),
+            D3DFormat::X8L8V8U8 => Some(0x0000_00ff),
+            D3DFormat::Q8W8V8U8 => Some(0x0000_00ff),
+            D3DFormat::V16U16 => Some(0x0000_ffff),
+            D3DFormat::A2W10V10U10 => Some(0x0000_03ff),
             _ => None,
         }
     }
@@ -228,6 +309,12 @@ impl D3DFormat {
             D3DFormat::L16 => None,
             D3DFormat::L8 => None,
             D3DFormat::A4L4 => None,
+            D3DFormat::V8U8 => Some(0xff00),
+            D3DFormat::L6V5U5 => Some(0x03e0),
+            D3DFormat::X8L8V8U8 => Some(0x0000_ff00),
+            D3DFormat::Q8W8V8U8 => Some(0x0000_ff00),
+            D3DFormat::V16U16 => Some(0xffff_0000),
+            D3DFormat::A2W10V10U10 => Some(0x000f_fc00),
             _ => None,
         }
     }
@@ -254,6 +341,10 @@ impl D3DFormat {
             D3DFormat::L16 => None,
             D3DFormat::L8 => None,
             D3DFormat::A4L4 => None,
+            D3DFormat::L6V5U5 => Some(0xfc00),
+            D3DFormat::X8L8V8U8 => Some(0x00ff_0000),
+            D3DFormat::Q8W8V8U8 => Some(0x00ff_0000),
+            D3DFormat::A2W10V10U10 => Some(0x3ff0_0000),
             _ => None,
         }
     }
@@ -280,6 +371,47 @@ impl D3DFormat {
             D3DFormat::L16 => None,
             D3DFormat::L8 => None,
             D3DFormat::A4L4 => Some(0xf0),
+            D3DFormat::Q8W8V8U8 => Some(0xff00_0000),
+            D3DFormat::A2W10V10U10 => Some(0xc000_0000),
+            _ => None,
+        }
+    }
+
+    /// The DxgiFormat with the same memory layout, if there is one. The DXT2 and
+    /// DXT4 formats map to BC2 and BC3, which do not record that the color is
+    /// premultiplied by alpha; `Dds::upgrade_to_dx10` records it in the alpha
+    /// mode instead.
+    pub fn to_dxgi_format(&self) -> Option<DxgiFormat> {
+        match *self {
+            D3DFormat::A8B8G8R8 => Some(DxgiFormat::R8G8B8A8_UNorm),
+            D3DFormat::G16R16 => Some(DxgiFormat::R16G16_UNorm),
+            D3DFormat::A2B10G10R10 => Some(DxgiFormat::R10G10B10A2_UNorm),
+            D3DFormat::A1R5G5B5 => Some(DxgiFormat::B5G5R5A1_UNorm),
+            D3DFormat::R5G6B5 => Some(DxgiFormat::B5G6R5_UNorm),
+            D3DFormat::A8 => Some(DxgiFormat::A8_UNorm),
+            D3DFormat::A8R8G8B8 => Some(DxgiFormat::B8G8R8A8_UNorm),
+            D3DFormat::X8R8G8B8 => Some(DxgiFormat::B8G8R8X8_UNorm),
+            D3DFormat::A4R4G4B4 => Some(DxgiFormat::B4G4R4A4_UNorm),
+            D3DFormat::A8L8 => Some(DxgiFormat::R8G8_UNorm),
+            D3DFormat::L16 => Some(DxgiFormat::R16_UNorm),
+            D3DFormat::L8 => Some(DxgiFormat::R8_UNorm),
+            D3DFormat::DXT1 => Some(DxgiFormat::BC1_UNorm),
+            D3DFormat::DXT2 | D3DFormat::DXT3 => Some(DxgiFormat::BC2_UNorm),
+            D3DFormat::DXT4 | D3DFormat::DXT5 => Some(DxgiFormat::BC3_UNorm),
+            D3DFormat::R8G8_B8G8 => Some(DxgiFormat::R8G8_B8G8_UNorm),
+            D3DFormat::G8R8_G8B8 => Some(DxgiFormat::G8R8_G8B8_UNorm),
+            D3DFormat::A16B16G16R16 => Some(DxgiFormat::R16G16B16A16_UNorm),
+            D3DFormat::Q16W16V16U16 => Some(DxgiFormat::R16G16B16A16_SNorm),
+            D3DFormat::R16F => Some(DxgiFormat::R16_Float),
+            D3DFormat::G16R16F => Some(DxgiFormat::R16G16_Float),
+            D3DFormat::A16B16G16R16F => Some(DxgiFormat::R16G16B16A16_Float),
+            D3DFormat::R32F => Some(DxgiFormat::R32_Float),
+            D3DFormat::G32R32F => Some(DxgiFormat::R32G32_Float),
+            D3DFormat::A32B32G32R32F => Some(DxgiFormat::R32G32B32A32_Float),
+            D3DFormat::YUY2 => Some(DxgiFormat::YUY2),
+            D3DFormat::V8U8 => Some(DxgiFormat::R8G8_SNorm),
+            D3DFormat::Q8W8V8U8 => Some(DxgiFormat::R8G8B8A8_SNorm),
+            D3DFormat::V16U16 => Some(DxgiFormat::R16G16_SNorm),
             _ => None,
         }
     }
@@ -310,6 +442,32 @@ impl D3DFormat {
                 FourCC::DX10 => None, // should use try_from_header10
                 _ => None,
             }
+        } else if pixel_format
+            .flags
+            .intersects(PixelFormatFlags::BUMPDUDV | PixelFormatFlags::BUMPLUMINANCE)
+        {
+            let luminance = pixel_format.flags.contains(PixelFormatFlags::BUMPLUMINANCE);
+            #[rustfmt::skip]
+            let format = match (
+                luminance,
+                pixel_format.rgb_bit_count,
+                pixel_format.r_bit_mask,
+                pixel_format.g_bit_mask,
+                pixel_format.b_bit_mask,
+                pixel_format.a_bit_mask.unwrap_or(0)
+            ) {
+                // lum    rgb cnt   u (r) bitmask     v (g) bitmask     w or l (b) bitmask  q or a bitmask
+                ( false, Some(16), Some(    0x00ff), Some(    0xff00), None | Some(0),    0         ) => Some(D3DFormat::V8U8),
+                ( false, Some(32), Some(0x000000ff), Some(0x0000ff00), Some(0x00ff0000),  0xff000000) => Some(D3DFormat::Q8W8V8U8),
+                ( false, Some(32), Some(0x0000ffff), Some(0xffff0000), None | Some(0),    0         ) => Some(D3DFormat::V16U16),
+                ( false, Some(32), Some(0x000003ff), Some(0x000ffc00), Some(0x3ff00000),  0xc0000000) => Some(D3DFormat::A2W10V10U10),
+                // as written by D3DX, with the U and W masks swapped
+                ( false, Some(32), Some(0x3ff00000), Some(0x000ffc00), Some(0x000003ff),  0xc0000000) => Some(D3DFormat::A2W10V10U10),
+                (  true, Some(16), Some(    0x001f), Some(    0x03e0), Some(    0xfc00),  0         ) => Some(D3DFormat::L6V5U5),
+                (  true, Some(32), Some(0x000000ff), Some(0x0000ff00), Some(0x00ff0000),  0         ) => Some(D3DFormat::X8L8V8U8),
+                _ => None
+            };
+            format
         } else {
             let rgb = pixel_format.flags.contains(PixelFormatFlags::RGB);
             let alpha = pixel_format.flags.contains(PixelFormatFlags::ALPHA)
@@ -352,3 +510,30 @@ impl D3DFormat {
         }
     }
 }
+
+impl fmt::Display for D3DFormat {
+    /// Shows the canonical name without the `D3DFMT_` prefix, e.g. "A8R8G8B8"
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(self, f)
+    }
+}
+
+impl FromStr for D3DFormat {
+    type Err = Error;
+
+    /// Parse a format from its name, ignoring case and an optional `D3DFMT_`
+    /// prefix (e.g. "A8R8G8B8", "D3DFMT_DXT5" or "dxt5")
+    fn from_str(s: &str) -> Result<D3DFormat, Error> {
+        let name = strip_prefix_ignore_case(s, "D3DFMT_");
+        match D3D_FORMATS
+            .iter()
+            .find(|f| f.to_string().eq_ignore_ascii_case(name))
+        {
+            Some(format) => Ok(*format),
+            None => Err(Error::Parse {
+                kind: "D3DFormat",
+                input: s.to_owned(),
+            }),
+        }
+    }
+}
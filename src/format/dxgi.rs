@@ -20,9 +20,14 @@
 // OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
 // THE SOFTWARE.
 
+use super::info::dxgi_format_info;
 use super::pixel_format::{FourCC, PixelFormat};
-use super::DataFormat;
+use super::{DataFormat, FormatInfo, PlaneLayout};
+use crate::error::Error;
 use enum_primitive_derive::Primitive;
+use num_traits::FromPrimitive;
+use std::fmt;
+use std::str::FromStr;
 
 #[rustfmt::skip]
 #[allow(non_camel_case_types)]
@@ -148,23 +153,45 @@ pub enum DxgiFormat {
     P208                        = 130,
     V208                        = 131,
     V408                        = 132,
+    Sampler_Feedback_Min_Mip_Opaque         = 189,
+    Sampler_Feedback_Mip_Region_Used_Opaque = 190,
+    A4B4G4R4_UNorm              = 191,
     Force_UInt                  = 0xffffffff_u32
 }
 
 impl DataFormat for DxgiFormat {
     fn get_pitch(&self, width: u32) -> Option<u32> {
         // see https://msdn.microsoft.com/en-us/library/bb943991.aspx
+        // Formats that pack texels in pairs, or whose pitch is that of the
+        // luma plane, are padded out to whole pairs (or quads)
         match *self {
-            DxgiFormat::R8G8_B8G8_UNorm | DxgiFormat::G8R8_G8B8_UNorm => {
-                return Some(((width + 1) >> 1) * 4);
+            DxgiFormat::R8G8_B8G8_UNorm
+            | DxgiFormat::G8R8_G8B8_UNorm
+            | DxgiFormat::YUY2
+            | DxgiFormat::P010
+            | DxgiFormat::P016 => {
+                return width.div_ceil(2).checked_mul(4);
+            }
+            DxgiFormat::Y210 | DxgiFormat::Y216 => {
+                return width.div_ceil(2).checked_mul(8);
+            }
+            DxgiFormat::NV12 | DxgiFormat::Format_420_Opaque | DxgiFormat::P208 => {
+                return width.div_ceil(2).checked_mul(2);
+            }
+            DxgiFormat::NV11 => {
+                return width.div_ceil(4).checked_mul(4);
+            }
+            DxgiFormat::V208 | DxgiFormat::V408 => {
+                return Some(width);
             }
             _ => {}
         };
 
+        // Widths whose pitch does not fit in a u32 have none
         if let Some(bpp) = self.get_bits_per_pixel() {
-            Some((width * bpp as u32 + 7) / 8)
+            Some(width.checked_mul(bpp as u32)?.div_ceil(8))
         } else {
-            self.get_block_size().map(|bs| 1.max((width + 3) / 4) * bs)
+            1.max(width.div_ceil(4)).checked_mul(self.get_block_size()?)
         }
     }
 
@@ -260,25 +287,27 @@ impl DataFormat for DxgiFormat {
             | DxgiFormat::B8G8R8X8_UNorm_sRGB => Some(32),
 
             DxgiFormat::AYUV => Some(32),
-            DxgiFormat::Y410 => Some(10),
-            DxgiFormat::Y416 => Some(16),
+            DxgiFormat::Y410 => Some(32),
+            DxgiFormat::Y416 => Some(64),
             DxgiFormat::NV12 => Some(12),
-            DxgiFormat::P010 => Some(10),
-            DxgiFormat::P016 => Some(16),
-            DxgiFormat::Format_420_Opaque => Some(20),
+            DxgiFormat::P010 => Some(24),
+            DxgiFormat::P016 => Some(24),
+            DxgiFormat::Format_420_Opaque => Some(12),
             DxgiFormat::YUY2 => Some(16),
-            DxgiFormat::Y210 => Some(10),
-            DxgiFormat::Y216 => Some(16),
-            DxgiFormat::NV11 => Some(11),
-            DxgiFormat::AI44 => Some(44),
-            DxgiFormat::IA44 => Some(44),
+            DxgiFormat::Y210 => Some(32),
+            DxgiFormat::Y216 => Some(32),
+            DxgiFormat::NV11 => Some(12),
+            DxgiFormat::AI44 => Some(8),
+            DxgiFormat::IA44 => Some(8),
             DxgiFormat::P8 => Some(8),
             DxgiFormat::A8P8 => Some(16),
             DxgiFormat::B4G4R4A4_UNorm => Some(16),
-            DxgiFormat::P208 => Some(8),
-            DxgiFormat::V208 => Some(8),
-            DxgiFormat::V408 => Some(8),
+            DxgiFormat::P208 => Some(16),
+            DxgiFormat::V208 => Some(16),
+            DxgiFormat::V408 => Some(24),
+            DxgiFormat::A4B4G4R4_UNorm => Some(16),
 
+            // The layout of sampler feedback maps is opaque
             _ => None,
         }
     }
@@ -312,6 +341,50 @@ impl DataFormat for DxgiFormat {
         }
     }
 
+    fn get_num_planes(&self) -> u32 {
+        match *self {
+            DxgiFormat::NV12
+            | DxgiFormat::P010
+            | DxgiFormat::P016
+            | DxgiFormat::Format_420_Opaque
+            | DxgiFormat::NV11
+            | DxgiFormat::P208 => 2,
+            DxgiFormat::V208 | DxgiFormat::V408 => 3,
+            _ => 1,
+        }
+    }
+
+    fn get_plane_layout(&self, plane: u32, width: u32, height: u32) -> Option<PlaneLayout> {
+        // see https://learn.microsoft.com/en-us/windows/win32/medfound/recommended-8-bit-yuv-formats-for-video-rendering
+        let pitch = self.get_pitch(width)?;
+        let half_height = height.div_ceil(2);
+        // The rows of each plane, in order
+        let rows: &[u32] = match *self {
+            DxgiFormat::NV12
+            | DxgiFormat::P010
+            | DxgiFormat::P016
+            | DxgiFormat::Format_420_Opaque => &[height, half_height],
+            // The chroma plane of NV11 holds a quarter of the samples at twice
+            // the size, and so has the same pitch as the luma plane
+            DxgiFormat::NV11 | DxgiFormat::P208 => &[height, height],
+            DxgiFormat::V208 => &[height, half_height, half_height],
+            DxgiFormat::V408 => &[height, height, height],
+            _ => &[height.div_ceil(self.get_pitch_height())],
+        };
+        if plane as usize >= rows.len() {
+            return None;
+        }
+        let offset = rows[..plane as usize]
+            .iter()
+            .try_fold(0_u32, |sum, &rows| sum.checked_add(rows))?
+            .checked_mul(pitch)?;
+        Some(PlaneLayout {
+            offset,
+            pitch,
+            rows: rows[plane as usize],
+        })
+    }
+
     fn get_fourcc(&self) -> Option<FourCC> {
         // note: we never use this. For Dxgi formats, we set FourCC to DX10 and
         // set the format in the header10 field. But these were the FourCCs that
@@ -408,9 +481,16 @@ impl DataFormat for DxgiFormat {
             DxgiFormat::A8P8 |
             DxgiFormat::P208 |
             DxgiFormat::V208 |
-            DxgiFormat::V408
+            DxgiFormat::V408 |
+            // Opaque
+            DxgiFormat::Sampler_Feedback_Min_Mip_Opaque |
+            DxgiFormat::Sampler_Feedback_Mip_Region_Used_Opaque
         )
     }
+
+    fn get_format_info(&self) -> Option<FormatInfo> {
+        dxgi_format_info(*self)
+    }
 }
 
 impl DxgiFormat {
@@ -428,4 +508,404 @@ impl DxgiFormat {
             None
         }
     }
+
+    /// This gets the raw `MTLPixelFormat` value that can hold this format's data
+    /// without any transcoding, or `None` if Metal has no such format.
+    ///
+    /// Metal has no typeless formats, so typeless formats yield `None`; map them to
+    /// a typed variant first. Metal's ASTC, ETC2 and PVRTC formats have no DXGI
+    /// equivalent and are never returned.
+    pub fn to_mtl_pixel_format(&self) -> Option<u32> {
+        match *self {
+            DxgiFormat::A8_UNorm => Some(1), // MTLPixelFormatA8Unorm
+
+            DxgiFormat::R8_UNorm => Some(10), // MTLPixelFormatR8Unorm
+            DxgiFormat::R8_SNorm => Some(12), // MTLPixelFormatR8Snorm
+            DxgiFormat::R8_UInt => Some(13),  // MTLPixelFormatR8Uint
+            DxgiFormat::R8_SInt => Some(14),  // MTLPixelFormatR8Sint
+
+            DxgiFormat::R16_UNorm => Some(20), // MTLPixelFormatR16Unorm
+            DxgiFormat::R16_SNorm => Some(22), // MTLPixelFormatR16Snorm
+            DxgiFormat::R16_UInt => Some(23),  // MTLPixelFormatR16Uint
+            DxgiFormat::R16_SInt => Some(24),  // MTLPixelFormatR16Sint
+            DxgiFormat::R16_Float => Some(25), // MTLPixelFormatR16Float
+
+            DxgiFormat::R8G8_UNorm => Some(30), // MTLPixelFormatRG8Unorm
+            DxgiFormat::R8G8_SNorm => Some(32), // MTLPixelFormatRG8Snorm
+            DxgiFormat::R8G8_UInt => Some(33),  // MTLPixelFormatRG8Uint
+            DxgiFormat::R8G8_SInt => Some(34),  // MTLPixelFormatRG8Sint
+
+            // DXGI packed formats are named from the least significant bit, so
+            // these have the same bit layout as Metal's
+            DxgiFormat::B5G6R5_UNorm => Some(40), // MTLPixelFormatB5G6R5Unorm
+            DxgiFormat::A4B4G4R4_UNorm => Some(42), // MTLPixelFormatABGR4Unorm
+            DxgiFormat::B5G5R5A1_UNorm => Some(43), // MTLPixelFormatBGR5A1Unorm
+
+            DxgiFormat::R32_UInt => Some(53), // MTLPixelFormatR32Uint
+            DxgiFormat::R32_SInt => Some(54), // MTLPixelFormatR32Sint
+            DxgiFormat::R32_Float => Some(55), // MTLPixelFormatR32Float
+
+            DxgiFormat::R16G16_UNorm => Some(60), // MTLPixelFormatRG16Unorm
+            DxgiFormat::R16G16_SNorm => Some(62), // MTLPixelFormatRG16Snorm
+            DxgiFormat::R16G16_UInt => Some(63),  // MTLPixelFormatRG16Uint
+            DxgiFormat::R16G16_SInt => Some(64),  // MTLPixelFormatRG16Sint
+            DxgiFormat::R16G16_Float => Some(65), // MTLPixelFormatRG16Float
+
+            DxgiFormat::R8G8B8A8_UNorm => Some(70), // MTLPixelFormatRGBA8Unorm
+            DxgiFormat::R8G8B8A8_UNorm_sRGB => Some(71), // MTLPixelFormatRGBA8Unorm_sRGB
+            DxgiFormat::R8G8B8A8_SNorm => Some(72), // MTLPixelFormatRGBA8Snorm
+            DxgiFormat::R8G8B8A8_UInt => Some(73),  // MTLPixelFormatRGBA8Uint
+            DxgiFormat::R8G8B8A8_SInt => Some(74),  // MTLPixelFormatRGBA8Sint
+
+            DxgiFormat::B8G8R8A8_UNorm => Some(80), // MTLPixelFormatBGRA8Unorm
+            DxgiFormat::B8G8R8A8_UNorm_sRGB => Some(81), // MTLPixelFormatBGRA8Unorm_sRGB
+
+            DxgiFormat::R10G10B10A2_UNorm => Some(90), // MTLPixelFormatRGB10A2Unorm
+            DxgiFormat::R10G10B10A2_UInt => Some(91),  // MTLPixelFormatRGB10A2Uint
+            DxgiFormat::R11G11B10_Float => Some(92),   // MTLPixelFormatRG11B10Float
+            DxgiFormat::R9G9B9E5_SharedExp => Some(93), // MTLPixelFormatRGB9E5Float
+
+            DxgiFormat::R32G32_UInt => Some(103), // MTLPixelFormatRG32Uint
+            DxgiFormat::R32G32_SInt => Some(104), // MTLPixelFormatRG32Sint
+            DxgiFormat::R32G32_Float => Some(105), // MTLPixelFormatRG32Float
+
+            DxgiFormat::R16G16B16A16_UNorm => Some(110), // MTLPixelFormatRGBA16Unorm
+            DxgiFormat::R16G16B16A16_SNorm => Some(112), // MTLPixelFormatRGBA16Snorm
+            DxgiFormat::R16G16B16A16_UInt => Some(113),  // MTLPixelFormatRGBA16Uint
+            DxgiFormat::R16G16B16A16_SInt => Some(114),  // MTLPixelFormatRGBA16Sint
+            DxgiFormat::R16G16B16A16_Float => Some(115), // MTLPixelFormatRGBA16Float
+
+            DxgiFormat::R32G32B32A32_UInt => Some(123), // MTLPixelFormatRGBA32Uint
+            DxgiFormat::R32G32B32A32_SInt => Some(124), // MTLPixelFormatRGBA32Sint
+            DxgiFormat::R32G32B32A32_Float => Some(125), // MTLPixelFormatRGBA32Float
+
+            DxgiFormat::BC1_UNorm => Some(130), // MTLPixelFormatBC1_RGBA
+            DxgiFormat::BC1_UNorm_sRGB => Some(131), // MTLPixelFormatBC1_RGBA_sRGB
+            DxgiFormat::BC2_UNorm => Some(132), // MTLPixelFormatBC2_RGBA
+            DxgiFormat::BC2_UNorm_sRGB => Some(133), // MTLPixelFormatBC2_RGBA_sRGB
+            DxgiFormat::BC3_UNorm => Some(134), // MTLPixelFormatBC3_RGBA
+            DxgiFormat::BC3_UNorm_sRGB => Some(135), // MTLPixelFormatBC3_RGBA_sRGB
+            DxgiFormat::BC4_UNorm => Some(140), // MTLPixelFormatBC4_RUnorm
+            DxgiFormat::BC4_SNorm => Some(141), // MTLPixelFormatBC4_RSnorm
+            DxgiFormat::BC5_UNorm => Some(142), // MTLPixelFormatBC5_RGUnorm
+            DxgiFormat::BC5_SNorm => Some(143), // MTLPixelFormatBC5_RGSnorm
+            DxgiFormat::BC6H_SF16 => Some(150), // MTLPixelFormatBC6H_RGBFloat
+            DxgiFormat::BC6H_UF16 => Some(151), // MTLPixelFormatBC6H_RGBUfloat
+            DxgiFormat::BC7_UNorm => Some(152), // MTLPixelFormatBC7_RGBAUnorm
+            DxgiFormat::BC7_UNorm_sRGB => Some(153), // MTLPixelFormatBC7_RGBAUnorm_sRGB
+
+            DxgiFormat::D16_UNorm => Some(250), // MTLPixelFormatDepth16Unorm
+            DxgiFormat::D32_Float => Some(252), // MTLPixelFormatDepth32Float
+            DxgiFormat::D24_UNorm_S8_UInt => Some(255), // MTLPixelFormatDepth24Unorm_Stencil8
+            DxgiFormat::D32_Float_S8X24_UInt => Some(260), // MTLPixelFormatDepth32Float_Stencil8
+
+            // Metal's 4:2:2 formats (GBGR422, BGRG422) store the chroma channels
+            // in the opposite order to R8G8_B8G8 and G8R8_G8B8, and Metal has no
+            // B4G4R4A4 layout, so those need transcoding along with everything else.
+            _ => None,
+        }
+    }
+
+    /// This gets the `VkFormat` value with the same memory layout as this format,
+    /// or `None` if Vulkan has no such format.
+    pub fn to_vk_format(&self) -> Option<u32> {
+        VK_FORMATS
+            .iter()
+            .find(|(dxgi, _)| dxgi == self)
+            .map(|(_, vk)| *vk)
+    }
+
+    /// This attempts to find the `DxgiFormat` with the same memory layout as the
+    /// given `VkFormat` value.
+    pub fn try_from_vk_format(vk_format: u32) -> Option<DxgiFormat> {
+        match vk_format {
+            // BC1 without alpha shares its layout with BC1 (the alpha bit is
+            // simply ignored)
+            131 => Some(DxgiFormat::BC1_UNorm),
+            132 => Some(DxgiFormat::BC1_UNorm_sRGB),
+            _ => VK_FORMATS
+                .iter()
+                .find(|(_, vk)| *vk == vk_format)
+                .map(|(dxgi, _)| *dxgi),
+        }
+    }
+
+    /// Returns true if this is the typeless format at the head of a family of
+    /// formats that share a memory layout, such as `R8G8B8A8_Typeless`
+    pub fn is_typeless(&self) -> bool {
+        TYPELESS_FAMILIES
+            .iter()
+            .any(|(typeless, _)| typeless == self)
+    }
+
+    /// This gets the typeless format of the family this format belongs to, which
+    /// is the format itself if it is typeless, or `None` if it has no family
+    pub fn get_typeless_format(&self) -> Option<DxgiFormat> {
+        TYPELESS_FAMILIES
+            .iter()
+            .find(|(typeless, typed)| typeless == self || typed.contains(self))
+            .map(|(typeless, _)| *typeless)
+    }
+
+    /// This gets the typed formats of the family this format belongs to. The
+    /// depth stencil families also list the formats that view the depth or the
+    /// stencil alone. It is empty if the format has no family.
+    pub fn get_typed_formats(&self) -> &'static [DxgiFormat] {
+        TYPELESS_FAMILIES
+            .iter()
+            .find(|(typeless, typed)| typeless == self || typed.contains(self))
+            .map(|(_, typed)| *typed)
+            .unwrap_or(&[])
+    }
+
+    /// Returns true if texel values are sRGB encoded
+    pub fn is_srgb(&self) -> bool {
+        SRGB_PAIRS.iter().any(|(_, encoded)| encoded == self)
+    }
+
+    /// This gets the variant of this format with (or without) sRGB encoding,
+    /// such as `BC7_UNorm_sRGB` for `BC7_UNorm`, or `None` if there is no such
+    /// variant. A format that already is as asked for is returned unchanged.
+    pub fn with_srgb(&self, srgb: bool) -> Option<DxgiFormat> {
+        SRGB_PAIRS.iter().find_map(|&(linear, encoded)| {
+            if *self == linear || *self == encoded {
+                Some(if srgb { encoded } else { linear })
+            } else {
+                None
+            }
+        })
+    }
+
+    /// Returns true if a resource of one format can be viewed as the other,
+    /// which requires the two to be the same or to belong to the same typeless
+    /// family
+    pub fn is_view_compatible(&self, other: DxgiFormat) -> bool {
+        *self == other
+            || match self.get_typeless_format() {
+                Some(typeless) => other.get_typeless_format() == Some(typeless),
+                None => false,
+            }
+    }
+}
+
+// Each typeless format with the typed formats that can view its data
+#[rustfmt::skip]
+const TYPELESS_FAMILIES: &[(DxgiFormat, &[DxgiFormat])] = &[
+    (DxgiFormat::R32G32B32A32_Typeless, &[
+        DxgiFormat::R32G32B32A32_Float,
+        DxgiFormat::R32G32B32A32_UInt,
+        DxgiFormat::R32G32B32A32_SInt,
+    ]),
+    (DxgiFormat::R32G32B32_Typeless, &[
+        DxgiFormat::R32G32B32_Float,
+        DxgiFormat::R32G32B32_UInt,
+        DxgiFormat::R32G32B32_SInt,
+    ]),
+    (DxgiFormat::R16G16B16A16_Typeless, &[
+        DxgiFormat::R16G16B16A16_Float,
+        DxgiFormat::R16G16B16A16_UNorm,
+        DxgiFormat::R16G16B16A16_UInt,
+        DxgiFormat::R16G16B16A16_SNorm,
+        DxgiFormat::R16G16B16A16_SInt,
+    ]),
+    (DxgiFormat::R32G32_Typeless, &[
+        DxgiFormat::R32G32_Float,
+        DxgiFormat::R32G32_UInt,
+        DxgiFormat::R32G32_SInt,
+    ]),
+    (DxgiFormat::R32G8X24_Typeless, &[
+        DxgiFormat::D32_Float_S8X24_UInt,
+        DxgiFormat::R32_Float_X8X24_Typeless,
+        DxgiFormat::X32_Typeless_G8X24_UInt,
+    ]),
+    (DxgiFormat::R10G10B10A2_Typeless, &[
+        DxgiFormat::R10G10B10A2_UNorm,
+        DxgiFormat::R10G10B10A2_UInt,
+    ]),
+    (DxgiFormat::R8G8B8A8_Typeless, &[
+        DxgiFormat::R8G8B8A8_UNorm,
+        DxgiFormat::R8G8B8A8_UNorm_sRGB,
+        DxgiFormat::R8G8B8A8_UInt,
+        DxgiFormat::R8G8B8A8_SNorm,
+        DxgiFormat::R8G8B8A8_SInt,
+    ]),
+    (DxgiFormat::R16G16_Typeless, &[
+        DxgiFormat::R16G16_Float,
+        DxgiFormat::R16G16_UNorm,
+        DxgiFormat::R16G16_UInt,
+        DxgiFormat::R16G16_SNorm,
+        DxgiFormat::R16G16_SInt,
+    ]),
+    (DxgiFormat::R32_Typeless, &[
+        DxgiFormat::D32_Float,
+        DxgiFormat::R32_Float,
+        DxgiFormat::R32_UInt,
+        DxgiFormat::R32_SInt,
+    ]),
+    (DxgiFormat::R24G8_Typeless, &[
+        DxgiFormat::D24_UNorm_S8_UInt,
+        DxgiFormat::R24_UNorm_X8_Typeless,
+        DxgiFormat::X24_Typeless_G8_UInt,
+    ]),
+    (DxgiFormat::R8G8_Typeless, &[
+        DxgiFormat::R8G8_UNorm,
+        DxgiFormat::R8G8_UInt,
+        DxgiFormat::R8G8_SNorm,
+        DxgiFormat::R8G8_SInt,
+    ]),
+    (DxgiFormat::R16_Typeless, &[
+        DxgiFormat::R16_Float,
+        DxgiFormat::D16_UNorm,
+        DxgiFormat::R16_UNorm,
+        DxgiFormat::R16_UInt,
+        DxgiFormat::R16_SNorm,
+        DxgiFormat::R16_SInt,
+    ]),
+    (DxgiFormat::R8_Typeless, &[
+        DxgiFormat::R8_UNorm,
+        DxgiFormat::R8_UInt,
+        DxgiFormat::R8_SNorm,
+        DxgiFormat::R8_SInt,
+    ]),
+    (DxgiFormat::BC1_Typeless, &[DxgiFormat::BC1_UNorm, DxgiFormat::BC1_UNorm_sRGB]),
+    (DxgiFormat::BC2_Typeless, &[DxgiFormat::BC2_UNorm, DxgiFormat::BC2_UNorm_sRGB]),
+    (DxgiFormat::BC3_Typeless, &[DxgiFormat::BC3_UNorm, DxgiFormat::BC3_UNorm_sRGB]),
+    (DxgiFormat::BC4_Typeless, &[DxgiFormat::BC4_UNorm, DxgiFormat::BC4_SNorm]),
+    (DxgiFormat::BC5_Typeless, &[DxgiFormat::BC5_UNorm, DxgiFormat::BC5_SNorm]),
+    (DxgiFormat::B8G8R8A8_Typeless, &[
+        DxgiFormat::B8G8R8A8_UNorm,
+        DxgiFormat::B8G8R8A8_UNorm_sRGB,
+    ]),
+    (DxgiFormat::B8G8R8X8_Typeless, &[
+        DxgiFormat::B8G8R8X8_UNorm,
+        DxgiFormat::B8G8R8X8_UNorm_sRGB,
+    ]),
+    (DxgiFormat::BC6H_Typeless, &[DxgiFormat::BC6H_UF16, DxgiFormat::BC6H_SF16]),
+    (DxgiFormat::BC7_Typeless, &[DxgiFormat::BC7_UNorm, DxgiFormat::BC7_UNorm_sRGB]),
+];
+
+// Each linear format with its sRGB encoded variant
+const SRGB_PAIRS: &[(DxgiFormat, DxgiFormat)] = &[
+    (DxgiFormat::R8G8B8A8_UNorm, DxgiFormat::R8G8B8A8_UNorm_sRGB),
+    (DxgiFormat::BC1_UNorm, DxgiFormat::BC1_UNorm_sRGB),
+    (DxgiFormat::BC2_UNorm, DxgiFormat::BC2_UNorm_sRGB),
+    (DxgiFormat::BC3_UNorm, DxgiFormat::BC3_UNorm_sRGB),
+    (DxgiFormat::B8G8R8A8_UNorm, DxgiFormat::B8G8R8A8_UNorm_sRGB),
+    (DxgiFormat::B8G8R8X8_UNorm, DxgiFormat::B8G8R8X8_UNorm_sRGB),
+    (DxgiFormat::BC7_UNorm, DxgiFormat::BC7_UNorm_sRGB),
+];
+
+// DXGI names packed formats from the least significant bit while Vulkan's
+// _PACKnn formats are named from the most significant bit, hence the reversals.
+#[rustfmt::skip]
+const VK_FORMATS: &[(DxgiFormat, u32)] = &[
+    (DxgiFormat::R32G32B32A32_Float,    109), // VK_FORMAT_R32G32B32A32_SFLOAT
+    (DxgiFormat::R32G32B32A32_UInt,     107), // VK_FORMAT_R32G32B32A32_UINT
+    (DxgiFormat::R32G32B32A32_SInt,     108), // VK_FORMAT_R32G32B32A32_SINT
+    (DxgiFormat::R32G32B32_Float,       106), // VK_FORMAT_R32G32B32_SFLOAT
+    (DxgiFormat::R32G32B32_UInt,        104), // VK_FORMAT_R32G32B32_UINT
+    (DxgiFormat::R32G32B32_SInt,        105), // VK_FORMAT_R32G32B32_SINT
+    (DxgiFormat::R16G16B16A16_Float,     97), // VK_FORMAT_R16G16B16A16_SFLOAT
+    (DxgiFormat::R16G16B16A16_UNorm,     91), // VK_FORMAT_R16G16B16A16_UNORM
+    (DxgiFormat::R16G16B16A16_UInt,      95), // VK_FORMAT_R16G16B16A16_UINT
+    (DxgiFormat::R16G16B16A16_SNorm,     92), // VK_FORMAT_R16G16B16A16_SNORM
+    (DxgiFormat::R16G16B16A16_SInt,      96), // VK_FORMAT_R16G16B16A16_SINT
+    (DxgiFormat::R32G32_Float,          103), // VK_FORMAT_R32G32_SFLOAT
+    (DxgiFormat::R32G32_UInt,           101), // VK_FORMAT_R32G32_UINT
+    (DxgiFormat::R32G32_SInt,           102), // VK_FORMAT_R32G32_SINT
+    (DxgiFormat::R10G10B10A2_UNorm,      64), // VK_FORMAT_A2B10G10R10_UNORM_PACK32
+    (DxgiFormat::R10G10B10A2_UInt,       68), // VK_FORMAT_A2B10G10R10_UINT_PACK32
+    (DxgiFormat::R11G11B10_Float,       122), // VK_FORMAT_B10G11R11_UFLOAT_PACK32
+    (DxgiFormat::R8G8B8A8_UNorm,         37), // VK_FORMAT_R8G8B8A8_UNORM
+    (DxgiFormat::R8G8B8A8_UNorm_sRGB,    43), // VK_FORMAT_R8G8B8A8_SRGB
+    (DxgiFormat::R8G8B8A8_UInt,          41), // VK_FORMAT_R8G8B8A8_UINT
+    (DxgiFormat::R8G8B8A8_SNorm,         38), // VK_FORMAT_R8G8B8A8_SNORM
+    (DxgiFormat::R8G8B8A8_SInt,          42), // VK_FORMAT_R8G8B8A8_SINT
+    (DxgiFormat::R16G16_Float,           83), // VK_FORMAT_R16G16_SFLOAT
+    (DxgiFormat::R16G16_UNorm,           77), // VK_FORMAT_R16G16_UNORM
+    (DxgiFormat::R16G16_UInt,            81), // VK_FORMAT_R16G16_UINT
+    (DxgiFormat::R16G16_SNorm,           78), // VK_FORMAT_R16G16_SNORM
+    (DxgiFormat::R16G16_SInt,            82), // VK_FORMAT_R16G16_SINT
+    (DxgiFormat::D32_Float,             126), // VK_FORMAT_D32_SFLOAT
+    (DxgiFormat::R32_Float,             100), // VK_FORMAT_R32_SFLOAT
+    (DxgiFormat::R32_UInt,               98), // VK_FORMAT_R32_UINT
+    (DxgiFormat::R32_SInt,               99), // VK_FORMAT_R32_SINT
+    (DxgiFormat::D24_UNorm_S8_UInt,     129), // VK_FORMAT_D24_UNORM_S8_UINT
+    (DxgiFormat::R8G8_UNorm,             16), // VK_FORMAT_R8G8_UNORM
+    (DxgiFormat::R8G8_UInt,              20), // VK_FORMAT_R8G8_UINT
+    (DxgiFormat::R8G8_SNorm,             17), // VK_FORMAT_R8G8_SNORM
+    (DxgiFormat::R8G8_SInt,              21), // VK_FORMAT_R8G8_SINT
+    (DxgiFormat::R16_Float,              76), // VK_FORMAT_R16_SFLOAT
+    (DxgiFormat::D16_UNorm,             124), // VK_FORMAT_D16_UNORM
+    (DxgiFormat::R16_UNorm,              70), // VK_FORMAT_R16_UNORM
+    (DxgiFormat::R16_UInt,               74), // VK_FORMAT_R16_UINT
+    (DxgiFormat::R16_SNorm,              71), // VK_FORMAT_R16_SNORM
+    (DxgiFormat::R16_SInt,               75), // VK_FORMAT_R16_SINT
+    (DxgiFormat::R8_UNorm,                9), // VK_FORMAT_R8_UNORM
+    (DxgiFormat::R8_UInt,                13), // VK_FORMAT_R8_UINT
+    (DxgiFormat::R8_SNorm,               10), // VK_FORMAT_R8_SNORM
+    (DxgiFormat::R8_SInt,                14), // VK_FORMAT_R8_SINT
+    (DxgiFormat::A8_UNorm,       1000470001), // VK_FORMAT_A8_UNORM_KHR
+    (DxgiFormat::R9G9B9E5_SharedExp,    123), // VK_FORMAT_E5B9G9R9_UFLOAT_PACK32
+    (DxgiFormat::BC1_UNorm,             133), // VK_FORMAT_BC1_RGBA_UNORM_BLOCK
+    (DxgiFormat::BC1_UNorm_sRGB,        134), // VK_FORMAT_BC1_RGBA_SRGB_BLOCK
+    (DxgiFormat::BC2_UNorm,             135), // VK_FORMAT_BC2_UNORM_BLOCK
+    (DxgiFormat::BC2_UNorm_sRGB,        136), // VK_FORMAT_BC2_SRGB_BLOCK
+    (DxgiFormat::BC3_UNorm,             137), // VK_FORMAT_BC3_UNORM_BLOCK
+    (DxgiFormat::BC3_UNorm_sRGB,        138), // VK_FORMAT_BC3_SRGB_BLOCK
+    (DxgiFormat::BC4_UNorm,             139), // VK_FORMAT_BC4_UNORM_BLOCK
+    (DxgiFormat::BC4_SNorm,             140), // VK_FORMAT_BC4_SNORM_BLOCK
+    (DxgiFormat::BC5_UNorm,             141), // VK_FORMAT_BC5_UNORM_BLOCK
+    (DxgiFormat::BC5_SNorm,             142), // VK_FORMAT_BC5_SNORM_BLOCK
+    (DxgiFormat::B5G6R5_UNorm,            4), // VK_FORMAT_R5G6B5_UNORM_PACK16
+    (DxgiFormat::B5G5R5A1_UNorm,          8), // VK_FORMAT_A1R5G5B5_UNORM_PACK16
+    (DxgiFormat::B8G8R8A8_UNorm,         44), // VK_FORMAT_B8G8R8A8_UNORM
+    (DxgiFormat::B8G8R8A8_UNorm_sRGB,    50), // VK_FORMAT_B8G8R8A8_SRGB
+    (DxgiFormat::BC6H_UF16,             143), // VK_FORMAT_BC6H_UFLOAT_BLOCK
+    (DxgiFormat::BC6H_SF16,             144), // VK_FORMAT_BC6H_SFLOAT_BLOCK
+    (DxgiFormat::BC7_UNorm,             145), // VK_FORMAT_BC7_UNORM_BLOCK
+    (DxgiFormat::BC7_UNorm_sRGB,        146), // VK_FORMAT_BC7_SRGB_BLOCK
+    (DxgiFormat::B4G4R4A4_UNorm, 1000340000), // VK_FORMAT_A4R4G4B4_UNORM_PACK16
+    (DxgiFormat::A4B4G4R4_UNorm,          2), // VK_FORMAT_R4G4B4A4_UNORM_PACK16
+];
+
+impl fmt::Display for DxgiFormat {
+    /// Shows the canonical name without the `DXGI_FORMAT_` prefix, e.g.
+    /// "BC7_UNORM_SRGB"
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            DxgiFormat::Format_420_Opaque => write!(f, "420_OPAQUE"),
+            _ => write!(f, "{}", format!("{:?}", self).to_ascii_uppercase()),
+        }
+    }
+}
+
+impl FromStr for DxgiFormat {
+    type Err = Error;
+
+    /// Parse a format from its name, ignoring case and an optional
+    /// `DXGI_FORMAT_` prefix (e.g. "BC7_UNORM_SRGB", "DXGI_FORMAT_BC7_UNORM_SRGB"
+    /// or "BC7_UNorm_sRGB")
+    fn from_str(s: &str) -> Result<DxgiFormat, Error> {
+        let name = strip_prefix_ignore_case(s, "DXGI_FORMAT_");
+        match (0..=u8::MAX as u32)
+            .filter_map(DxgiFormat::from_u32)
+            .find(|f| {
+                f.to_string().eq_ignore_ascii_case(name)
+                    || format!("{:?}", f).eq_ignore_ascii_case(name)
+            }) {
+            Some(format) => Ok(format),
+            None => Err(Error::Parse {
+                kind: "DxgiFormat",
+                input: s.to_owned(),
+            }),
+        }
+    }
+}
+
+pub(super) fn strip_prefix_ignore_case<'a>(s: &'a str, prefix: &str) -> &'a str {
+    match s.get(..prefix.len()) {
+        Some(p) if p.eq_ignore_ascii_case(prefix) => &s[prefix.len()..],
+        _ => s,
+    }
 }
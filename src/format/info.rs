@@ -0,0 +1,534 @@
+// The MIT License (MIT)
+//
+// Copyright (c) 2018 Michael Dilger
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+use super::{D3DFormat, DxgiFormat};
+
+/// What a channel of a format holds
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Channel {
+    R,
+    G,
+    B,
+    A,
+    /// Unused bits
+    X,
+    Depth,
+    Stencil,
+    Luminance,
+    /// The exponent shared by the other channels
+    Exponent,
+    /// Luma
+    Y,
+    /// Blue-difference chroma, or the first component of a bump map
+    U,
+    /// Red-difference chroma, or the second component of a bump map
+    V,
+    /// The third component of a bump map
+    W,
+    /// The fourth component of a bump map
+    Q,
+    /// An index into a palette
+    PaletteIndex,
+}
+
+/// How the bits of a channel are interpreted
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ComponentType {
+    /// Unsigned integers mapped to 0.0 to 1.0
+    UNorm,
+    /// Signed integers mapped to -1.0 to 1.0
+    SNorm,
+    UInt,
+    SInt,
+    Float,
+    /// Bits whose type is chosen by a view of the data
+    Typeless,
+    /// A mantissa scaled by an exponent shared with the other channels
+    SharedExp,
+}
+
+/// A channel of a format
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ChannelInfo {
+    pub channel: Channel,
+    /// The number of significant bits, which for some video formats is fewer
+    /// than the bits the channel occupies. This is 0 for block compressed
+    /// formats, which have no fixed width per channel.
+    pub bits: u8,
+    pub component_type: ComponentType,
+}
+
+/// A description of a format beyond its size
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FormatInfo {
+    /// The channels in memory order, starting from the lowest bits. Formats
+    /// that store texels in pairs (such as YUY2) list the channels of both, and
+    /// planar formats list each channel once.
+    pub channels: &'static [ChannelInfo],
+    /// The component type of the first channel that is not `Channel::X`
+    pub component_type: ComponentType,
+    /// Color values are sRGB encoded
+    pub srgb: bool,
+    /// This is a depth and/or stencil format
+    pub depth_stencil: bool,
+    /// Color is held as luma and chroma
+    pub yuv: bool,
+    /// Channels are stored in separate planes
+    pub planar: bool,
+    /// The width in texels of a block (4 for block compressed formats, 2 for
+    /// formats that store texels in pairs, and 1 for most others)
+    pub block_width: u32,
+    /// The height in texels of a block
+    pub block_height: u32,
+    /// The format can be rendered to, as a render target or depth stencil
+    pub renderable: bool,
+    /// The format can be read by shaders as it is, without a view of another
+    /// format
+    pub sampleable: bool,
+}
+
+macro_rules! channels {
+    ($($channel:ident $bits:literal $ty:ident),* $(,)?) => {
+        const {
+            &[$(ChannelInfo {
+                channel: Channel::$channel,
+                bits: $bits,
+                component_type: ComponentType::$ty,
+            }),*]
+        }
+    };
+}
+
+impl FormatInfo {
+    // A plain color format that can be rendered to and sampled
+    fn new(channels: &'static [ChannelInfo]) -> FormatInfo {
+        let component_type = channels
+            .iter()
+            .find(|c| c.channel != Channel::X)
+            .unwrap_or(&channels[0])
+            .component_type;
+        FormatInfo {
+            channels,
+            component_type,
+            srgb: false,
+            depth_stencil: false,
+            yuv: false,
+            planar: false,
+            block_width: 1,
+            block_height: 1,
+            renderable: true,
+            sampleable: true,
+        }
+    }
+
+    fn srgb(mut self) -> FormatInfo {
+        self.srgb = true;
+        self
+    }
+
+    fn depth_stencil(mut self) -> FormatInfo {
+        self.depth_stencil = true;
+        self
+    }
+
+    fn yuv(mut self) -> FormatInfo {
+        self.yuv = true;
+        self.renderable = false;
+        self
+    }
+
+    // Planes are read through views of other formats
+    fn planar(mut self) -> FormatInfo {
+        self.planar = true;
+        self.sampleable = false;
+        self
+    }
+
+    fn block(mut self, width: u32, height: u32) -> FormatInfo {
+        self.block_width = width;
+        self.block_height = height;
+        self
+    }
+
+    // Block compressed formats can be sampled but not rendered to
+    fn compressed(self) -> FormatInfo {
+        self.block(4, 4).not_renderable()
+    }
+
+    // Typeless formats need a view of a typed format for everything
+    fn typeless(self) -> FormatInfo {
+        self.not_renderable().not_sampleable()
+    }
+
+    fn not_renderable(mut self) -> FormatInfo {
+        self.renderable = false;
+        self
+    }
+
+    fn not_sampleable(mut self) -> FormatInfo {
+        self.sampleable = false;
+        self
+    }
+}
+
+pub(super) fn dxgi_format_info(format: DxgiFormat) -> Option<FormatInfo> {
+    use self::FormatInfo as F;
+    let info = match format {
+        DxgiFormat::Unknown | DxgiFormat::Force_UInt => return None,
+
+        DxgiFormat::R32G32B32A32_Typeless => {
+            F::new(channels![R 32 Typeless, G 32 Typeless, B 32 Typeless, A 32 Typeless]).typeless()
+        }
+        DxgiFormat::R32G32B32A32_Float => {
+            F::new(channels![R 32 Float, G 32 Float, B 32 Float, A 32 Float])
+        }
+        DxgiFormat::R32G32B32A32_UInt => {
+            F::new(channels![R 32 UInt, G 32 UInt, B 32 UInt, A 32 UInt])
+        }
+        DxgiFormat::R32G32B32A32_SInt => {
+            F::new(channels![R 32 SInt, G 32 SInt, B 32 SInt, A 32 SInt])
+        }
+        // Rendering to 96-bit formats is optional
+        DxgiFormat::R32G32B32_Typeless => {
+            F::new(channels![R 32 Typeless, G 32 Typeless, B 32 Typeless]).typeless()
+        }
+        DxgiFormat::R32G32B32_Float => {
+            F::new(channels![R 32 Float, G 32 Float, B 32 Float]).not_renderable()
+        }
+        DxgiFormat::R32G32B32_UInt => {
+            F::new(channels![R 32 UInt, G 32 UInt, B 32 UInt]).not_renderable()
+        }
+        DxgiFormat::R32G32B32_SInt => {
+            F::new(channels![R 32 SInt, G 32 SInt, B 32 SInt]).not_renderable()
+        }
+        DxgiFormat::R16G16B16A16_Typeless => {
+            F::new(channels![R 16 Typeless, G 16 Typeless, B 16 Typeless, A 16 Typeless]).typeless()
+        }
+        DxgiFormat::R16G16B16A16_Float => {
+            F::new(channels![R 16 Float, G 16 Float, B 16 Float, A 16 Float])
+        }
+        DxgiFormat::R16G16B16A16_UNorm => {
+            F::new(channels![R 16 UNorm, G 16 UNorm, B 16 UNorm, A 16 UNorm])
+        }
+        DxgiFormat::R16G16B16A16_UInt => {
+            F::new(channels![R 16 UInt, G 16 UInt, B 16 UInt, A 16 UInt])
+        }
+        DxgiFormat::R16G16B16A16_SNorm => {
+            F::new(channels![R 16 SNorm, G 16 SNorm, B 16 SNorm, A 16 SNorm])
+        }
+        DxgiFormat::R16G16B16A16_SInt => {
+            F::new(channels![R 16 SInt, G 16 SInt, B 16 SInt, A 16 SInt])
+        }
+        DxgiFormat::R32G32_Typeless => F::new(channels![R 32 Typeless, G 32 Typeless]).typeless(),
+        DxgiFormat::R32G32_Float => F::new(channels![R 32 Float, G 32 Float]),
+        DxgiFormat::R32G32_UInt => F::new(channels![R 32 UInt, G 32 UInt]),
+        DxgiFormat::R32G32_SInt => F::new(channels![R 32 SInt, G 32 SInt]),
+        DxgiFormat::R32G8X24_Typeless => {
+            F::new(channels![R 32 Typeless, G 8 Typeless, X 24 Typeless])
+                .depth_stencil()
+                .typeless()
+        }
+        DxgiFormat::D32_Float_S8X24_UInt => {
+            F::new(channels![Depth 32 Float, Stencil 8 UInt, X 24 Typeless])
+                .depth_stencil()
+                .not_sampleable()
+        }
+        DxgiFormat::R32_Float_X8X24_Typeless => F::new(channels![Depth 32 Float, X 32 Typeless])
+            .depth_stencil()
+            .not_renderable(),
+        DxgiFormat::X32_Typeless_G8X24_UInt => {
+            F::new(channels![X 32 Typeless, Stencil 8 UInt, X 24 Typeless])
+                .depth_stencil()
+                .not_renderable()
+        }
+        DxgiFormat::R10G10B10A2_Typeless => {
+            F::new(channels![R 10 Typeless, G 10 Typeless, B 10 Typeless, A 2 Typeless]).typeless()
+        }
+        DxgiFormat::R10G10B10A2_UNorm => {
+            F::new(channels![R 10 UNorm, G 10 UNorm, B 10 UNorm, A 2 UNorm])
+        }
+        DxgiFormat::R10G10B10A2_UInt => {
+            F::new(channels![R 10 UInt, G 10 UInt, B 10 UInt, A 2 UInt])
+        }
+        DxgiFormat::R11G11B10_Float => F::new(channels![R 11 Float, G 11 Float, B 10 Float]),
+        DxgiFormat::R8G8B8A8_Typeless => {
+            F::new(channels![R 8 Typeless, G 8 Typeless, B 8 Typeless, A 8 Typeless]).typeless()
+        }
+        DxgiFormat::R8G8B8A8_UNorm => F::new(channels![R 8 UNorm, G 8 UNorm, B 8 UNorm, A 8 UNorm]),
+        DxgiFormat::R8G8B8A8_UNorm_sRGB => {
+            F::new(channels![R 8 UNorm, G 8 UNorm, B 8 UNorm, A 8 UNorm]).srgb()
+        }
+        DxgiFormat::R8G8B8A8_UInt => F::new(channels![R 8 UInt, G 8 UInt, B 8 UInt, A 8 UInt]),
+        DxgiFormat::R8G8B8A8_SNorm => F::new(channels![R 8 SNorm, G 8 SNorm, B 8 SNorm, A 8 SNorm]),
+        DxgiFormat::R8G8B8A8_SInt => F::new(channels![R 8 SInt, G 8 SInt, B 8 SInt, A 8 SInt]),
+        DxgiFormat::R16G16_Typeless => F::new(channels![R 16 Typeless, G 16 Typeless]).typeless(),
+        DxgiFormat::R16G16_Float => F::new(channels![R 16 Float, G 16 Float]),
+        DxgiFormat::R16G16_UNorm => F::new(channels![R 16 UNorm, G 16 UNorm]),
+        DxgiFormat::R16G16_UInt => F::new(channels![R 16 UInt, G 16 UInt]),
+        DxgiFormat::R16G16_SNorm => F::new(channels![R 16 SNorm, G 16 SNorm]),
+        DxgiFormat::R16G16_SInt => F::new(channels![R 16 SInt, G 16 SInt]),
+        DxgiFormat::R32_Typeless => F::new(channels![R 32 Typeless]).typeless(),
+        DxgiFormat::D32_Float => F::new(channels![Depth 32 Float])
+            .depth_stencil()
+            .not_sampleable(),
+        DxgiFormat::R32_Float => F::new(channels![R 32 Float]),
+        DxgiFormat::R32_UInt => F::new(channels![R 32 UInt]),
+        DxgiFormat::R32_SInt => F::new(channels![R 32 SInt]),
+        DxgiFormat::R24G8_Typeless => F::new(channels![R 24 Typeless, G 8 Typeless])
+            .depth_stencil()
+            .typeless(),
+        DxgiFormat::D24_UNorm_S8_UInt => F::new(channels![Depth 24 UNorm, Stencil 8 UInt])
+            .depth_stencil()
+            .not_sampleable(),
+        DxgiFormat::R24_UNorm_X8_Typeless => F::new(channels![Depth 24 UNorm, X 8 Typeless])
+            .depth_stencil()
+            .not_renderable(),
+        DxgiFormat::X24_Typeless_G8_UInt => F::new(channels![X 24 Typeless, Stencil 8 UInt])
+            .depth_stencil()
+            .not_renderable(),
+        DxgiFormat::R8G8_Typeless => F::new(channels![R 8 Typeless, G 8 Typeless]).typeless(),
+        DxgiFormat::R8G8_UNorm => F::new(channels![R 8 UNorm, G 8 UNorm]),
+        DxgiFormat::R8G8_UInt => F::new(channels![R 8 UInt, G 8 UInt]),
+        DxgiFormat::R8G8_SNorm => F::new(channels![R 8 SNorm, G 8 SNorm]),
+        DxgiFormat::R8G8_SInt => F::new(channels![R 8 SInt, G 8 SInt]),
+        DxgiFormat::R16_Typeless => F::new(channels![R 16 Typeless]).typeless(),
+        DxgiFormat::R16_Float => F::new(channels![R 16 Float]),
+        DxgiFormat::D16_UNorm => F::new(channels![Depth 16 UNorm])
+            .depth_stencil()
+            .not_sampleable(),
+        DxgiFormat::R16_UNorm => F::new(channels![R 16 UNorm]),
+        DxgiFormat::R16_UInt => F::new(channels![R 16 UInt]),
+        DxgiFormat::R16_SNorm => F::new(channels![R 16 SNorm]),
+        DxgiFormat::R16_SInt => F::new(channels![R 16 SInt]),
+        DxgiFormat::R8_Typeless => F::new(channels![R 8 Typeless]).typeless(),
+        DxgiFormat::R8_UNorm => F::new(channels![R 8 UNorm]),
+        DxgiFormat::R8_UInt => F::new(channels![R 8 UInt]),
+        DxgiFormat::R8_SNorm => F::new(channels![R 8 SNorm]),
+        DxgiFormat::R8_SInt => F::new(channels![R 8 SInt]),
+        DxgiFormat::A8_UNorm => F::new(channels![A 8 UNorm]),
+        DxgiFormat::R1_UNorm => F::new(channels![R 1 UNorm]).block(8, 1).not_renderable(),
+        DxgiFormat::R9G9B9E5_SharedExp => {
+            F::new(channels![R 9 SharedExp, G 9 SharedExp, B 9 SharedExp, Exponent 5 UInt])
+                .not_renderable()
+        }
+        DxgiFormat::R8G8_B8G8_UNorm => {
+            F::new(channels![R 8 UNorm, G 8 UNorm, B 8 UNorm, G 8 UNorm])
+                .block(2, 1)
+                .not_renderable()
+        }
+        DxgiFormat::G8R8_G8B8_UNorm => {
+            F::new(channels![G 8 UNorm, R 8 UNorm, G 8 UNorm, B 8 UNorm])
+                .block(2, 1)
+                .not_renderable()
+        }
+        DxgiFormat::BC1_Typeless | DxgiFormat::BC2_Typeless | DxgiFormat::BC3_Typeless => {
+            F::new(channels![R 0 Typeless, G 0 Typeless, B 0 Typeless, A 0 Typeless])
+                .compressed()
+                .typeless()
+        }
+        DxgiFormat::BC1_UNorm | DxgiFormat::BC2_UNorm | DxgiFormat::BC3_UNorm => {
+            F::new(channels![R 0 UNorm, G 0 UNorm, B 0 UNorm, A 0 UNorm]).compressed()
+        }
+        DxgiFormat::BC1_UNorm_sRGB | DxgiFormat::BC2_UNorm_sRGB | DxgiFormat::BC3_UNorm_sRGB => {
+            F::new(channels![R 0 UNorm, G 0 UNorm, B 0 UNorm, A 0 UNorm])
+                .compressed()
+                .srgb()
+        }
+        DxgiFormat::BC4_Typeless => F::new(channels![R 0 Typeless]).compressed().typeless(),
+        DxgiFormat::BC4_UNorm => F::new(channels![R 0 UNorm]).compressed(),
+        DxgiFormat::BC4_SNorm => F::new(channels![R 0 SNorm]).compressed(),
+        DxgiFormat::BC5_Typeless => F::new(channels![R 0 Typeless, G 0 Typeless])
+            .compressed()
+            .typeless(),
+        DxgiFormat::BC5_UNorm => F::new(channels![R 0 UNorm, G 0 UNorm]).compressed(),
+        DxgiFormat::BC5_SNorm => F::new(channels![R 0 SNorm, G 0 SNorm]).compressed(),
+        DxgiFormat::B5G6R5_UNorm => F::new(channels![B 5 UNorm, G 6 UNorm, R 5 UNorm]),
+        DxgiFormat::B5G5R5A1_UNorm => F::new(channels![B 5 UNorm, G 5 UNorm, R 5 UNorm, A 1 UNorm]),
+        DxgiFormat::B8G8R8A8_UNorm => F::new(channels![B 8 UNorm, G 8 UNorm, R 8 UNorm, A 8 UNorm]),
+        DxgiFormat::B8G8R8X8_UNorm => {
+            F::new(channels![B 8 UNorm, G 8 UNorm, R 8 UNorm, X 8 Typeless])
+        }
+        // Only for display, with an extended range
+        DxgiFormat::R10G10B10_XR_Bias_A2_UNorm => {
+            F::new(channels![R 10 UNorm, G 10 UNorm, B 10 UNorm, A 2 UNorm]).not_renderable()
+        }
+        DxgiFormat::B8G8R8A8_Typeless => {
+            F::new(channels![B 8 Typeless, G 8 Typeless, R 8 Typeless, A 8 Typeless]).typeless()
+        }
+        DxgiFormat::B8G8R8A8_UNorm_sRGB => {
+            F::new(channels![B 8 UNorm, G 8 UNorm, R 8 UNorm, A 8 UNorm]).srgb()
+        }
+        DxgiFormat::B8G8R8X8_Typeless => {
+            F::new(channels![B 8 Typeless, G 8 Typeless, R 8 Typeless, X 8 Typeless]).typeless()
+        }
+        DxgiFormat::B8G8R8X8_UNorm_sRGB => {
+            F::new(channels![B 8 UNorm, G 8 UNorm, R 8 UNorm, X 8 Typeless]).srgb()
+        }
+        DxgiFormat::BC6H_Typeless => F::new(channels![R 0 Typeless, G 0 Typeless, B 0 Typeless])
+            .compressed()
+            .typeless(),
+        DxgiFormat::BC6H_UF16 | DxgiFormat::BC6H_SF16 => {
+            F::new(channels![R 0 Float, G 0 Float, B 0 Float]).compressed()
+        }
+        DxgiFormat::BC7_Typeless => {
+            F::new(channels![R 0 Typeless, G 0 Typeless, B 0 Typeless, A 0 Typeless])
+                .compressed()
+                .typeless()
+        }
+        DxgiFormat::BC7_UNorm => {
+            F::new(channels![R 0 UNorm, G 0 UNorm, B 0 UNorm, A 0 UNorm]).compressed()
+        }
+        DxgiFormat::BC7_UNorm_sRGB => F::new(channels![R 0 UNorm, G 0 UNorm, B 0 UNorm, A 0 UNorm])
+            .compressed()
+            .srgb(),
+        DxgiFormat::AYUV => F::new(channels![V 8 UNorm, U 8 UNorm, Y 8 UNorm, A 8 UNorm]).yuv(),
+        DxgiFormat::Y410 => F::new(channels![U 10 UNorm, Y 10 UNorm, V 10 UNorm, A 2 UNorm]).yuv(),
+        DxgiFormat::Y416 => F::new(channels![U 16 UNorm, Y 16 UNorm, V 16 UNorm, A 16 UNorm]).yuv(),
+        DxgiFormat::NV12 | DxgiFormat::Format_420_Opaque => {
+            F::new(channels![Y 8 UNorm, U 8 UNorm, V 8 UNorm])
+                .yuv()
+                .planar()
+                .block(2, 2)
+        }
+        DxgiFormat::P010 => F::new(channels![Y 10 UNorm, U 10 UNorm, V 10 UNorm])
+            .yuv()
+            .planar()
+            .block(2, 2),
+        DxgiFormat::P016 => F::new(channels![Y 16 UNorm, U 16 UNorm, V 16 UNorm])
+            .yuv()
+            .planar()
+            .block(2, 2),
+        DxgiFormat::YUY2 => F::new(channels![Y 8 UNorm, U 8 UNorm, Y 8 UNorm, V 8 UNorm])
+            .yuv()
+            .block(2, 1),
+        DxgiFormat::Y210 => F::new(channels![Y 10 UNorm, U 10 UNorm, Y 10 UNorm, V 10 UNorm])
+            .yuv()
+            .block(2, 1),
+        DxgiFormat::Y216 => F::new(channels![Y 16 UNorm, U 16 UNorm, Y 16 UNorm, V 16 UNorm])
+            .yuv()
+            .block(2, 1),
+        DxgiFormat::NV11 => F::new(channels![Y 8 UNorm, U 8 UNorm, V 8 UNorm])
+            .yuv()
+            .planar()
+            .block(4, 1),
+        DxgiFormat::P208 => F::new(channels![Y 8 UNorm, U 8 UNorm, V 8 UNorm])
+            .yuv()
+            .planar()
+            .block(2, 1),
+        DxgiFormat::V208 => F::new(channels![Y 8 UNorm, U 8 UNorm, V 8 UNorm])
+            .yuv()
+            .planar()
+            .block(1, 2),
+        DxgiFormat::V408 => F::new(channels![Y 8 UNorm, U 8 UNorm, V 8 UNorm])
+            .yuv()
+            .planar(),
+        // Palettized formats are only for video overlays
+        DxgiFormat::AI44 => F::new(channels![A 4 UNorm, PaletteIndex 4 UInt])
+            .not_renderable()
+            .not_sampleable(),
+        DxgiFormat::IA44 => F::new(channels![PaletteIndex 4 UInt, A 4 UNorm])
+            .not_renderable()
+            .not_sampleable(),
+        DxgiFormat::P8 => F::new(channels![PaletteIndex 8 UInt])
+            .not_renderable()
+            .not_sampleable(),
+        DxgiFormat::A8P8 => F::new(channels![A 8 UNorm, PaletteIndex 8 UInt])
+            .not_renderable()
+            .not_sampleable(),
+        DxgiFormat::B4G4R4A4_UNorm => F::new(channels![B 4 UNorm, G 4 UNorm, R 4 UNorm, A 4 UNorm]),
+        // The layout of sampler feedback maps is opaque
+        DxgiFormat::Sampler_Feedback_Min_Mip_Opaque
+        | DxgiFormat::Sampler_Feedback_Mip_Region_Used_Opaque => {
+            F::new(channels![X 0 Typeless]).typeless()
+        }
+        DxgiFormat::A4B4G4R4_UNorm => {
+            F::new(channels![A 4 UNorm, B 4 UNorm, G 4 UNorm, R 4 UNorm]).not_renderable()
+        }
+    };
+    Some(info)
+}
+
+pub(super) fn d3d_format_info(format: D3DFormat) -> Option<FormatInfo> {
+    use self::FormatInfo as F;
+    let info = match format {
+        D3DFormat::X8B8G8R8 => F::new(channels![R 8 UNorm, G 8 UNorm, B 8 UNorm, X 8 Typeless]),
+        D3DFormat::A2R10G10B10 => F::new(channels![B 10 UNorm, G 10 UNorm, R 10 UNorm, A 2 UNorm]),
+        D3DFormat::R8G8B8 => F::new(channels![B 8 UNorm, G 8 UNorm, R 8 UNorm]).not_renderable(),
+        D3DFormat::X1R5G5B5 => F::new(channels![B 5 UNorm, G 5 UNorm, R 5 UNorm, X 1 Typeless]),
+        D3DFormat::X4R4G4B4 => F::new(channels![B 4 UNorm, G 4 UNorm, R 4 UNorm, X 4 Typeless]),
+        D3DFormat::A8R3G3B2 => {
+            F::new(channels![B 2 UNorm, G 3 UNorm, R 3 UNorm, A 8 UNorm]).not_renderable()
+        }
+        D3DFormat::A8L8 => F::new(channels![Luminance 8 UNorm, A 8 UNorm]).not_renderable(),
+        D3DFormat::L16 => F::new(channels![Luminance 16 UNorm]).not_renderable(),
+        D3DFormat::L8 => F::new(channels![Luminance 8 UNorm]).not_renderable(),
+        D3DFormat::A4L4 => F::new(channels![Luminance 4 UNorm, A 4 UNorm]).not_renderable(),
+        D3DFormat::Q16W16V16U16 => {
+            F::new(channels![U 16 SNorm, V 16 SNorm, W 16 SNorm, Q 16 SNorm]).not_renderable()
+        }
+        D3DFormat::CXV8U8 => F::new(channels![U 8 SNorm, V 8 SNorm]).not_renderable(),
+        D3DFormat::V8U8 => F::new(channels![U 8 SNorm, V 8 SNorm]).not_renderable(),
+        D3DFormat::L6V5U5 => {
+            F::new(channels![U 5 SNorm, V 5 SNorm, Luminance 6 UNorm]).not_renderable()
+        }
+        D3DFormat::X8L8V8U8 => {
+            F::new(channels![U 8 SNorm, V 8 SNorm, Luminance 8 UNorm, X 8 Typeless])
+                .not_renderable()
+        }
+        D3DFormat::Q8W8V8U8 => {
+            F::new(channels![U 8 SNorm, V 8 SNorm, W 8 SNorm, Q 8 SNorm]).not_renderable()
+        }
+        D3DFormat::V16U16 => F::new(channels![U 16 SNorm, V 16 SNorm]).not_renderable(),
+        D3DFormat::A2W10V10U10 => {
+            F::new(channels![U 10 SNorm, V 10 SNorm, W 10 SNorm, A 2 UNorm]).not_renderable()
+        }
+        D3DFormat::UYVY => F::new(channels![U 8 UNorm, Y 8 UNorm, V 8 UNorm, Y 8 UNorm])
+            .yuv()
+            .block(2, 1),
+        // The rest have the same layout as a DxgiFormat
+        D3DFormat::A8B8G8R8
+        | D3DFormat::G16R16
+        | D3DFormat::A2B10G10R10
+        | D3DFormat::A1R5G5B5
+        | D3DFormat::R5G6B5
+        | D3DFormat::A8
+        | D3DFormat::A8R8G8B8
+        | D3DFormat::X8R8G8B8
+        | D3DFormat::A4R4G4B4
+        | D3DFormat::DXT1
+        | D3DFormat::DXT3
+        | D3DFormat::DXT5
+        | D3DFormat::R8G8_B8G8
+        | D3DFormat::G8R8_G8B8
+        | D3DFormat::A16B16G16R16
+        | D3DFormat::R16F
+        | D3DFormat::G16R16F
+        | D3DFormat::A16B16G16R16F
+        | D3DFormat::R32F
+        | D3DFormat::G32R32F
+        | D3DFormat::A32B32G32R32F
+        | D3DFormat::DXT2
+        | D3DFormat::DXT4
+        | D3DFormat::YUY2 => return format.to_dxgi_format().and_then(dxgi_format_info),
+    };
+    Some(info)
+}
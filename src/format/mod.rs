@@ -29,6 +29,28 @@ pub use self::d3d::D3DFormat;
 mod dxgi;
 pub use self::dxgi::DxgiFormat;
 
+mod info;
+pub use self::info::{Channel, ChannelInfo, ComponentType, FormatInfo};
+
+/// Where one plane of a subresource lies within its data. Most formats have a
+/// single plane, but planar video formats keep luma and chroma apart.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PlaneLayout {
+    /// The byte offset of the plane from the start of the subresource
+    pub offset: u32,
+    /// The number of bytes in each row of the plane
+    pub pitch: u32,
+    /// The number of rows in the plane
+    pub rows: u32,
+}
+
+impl PlaneLayout {
+    /// The size of the plane in bytes, or None if it does not fit in a u32
+    pub fn size(&self) -> Option<u32> {
+        self.pitch.checked_mul(self.rows)
+    }
+}
+
 pub trait DataFormat {
     /// This gets the number of bytes required to store one row of data
     fn get_pitch(&self, width: u32) -> Option<u32>;
@@ -57,12 +79,45 @@ pub trait DataFormat {
     /// Returns true if the DX10 extention is required to use this format.
     fn requires_extension(&self) -> bool;
 
+    /// This gets the channels, component types and capabilities of the format,
+    /// if known
+    fn get_format_info(&self) -> Option<FormatInfo> {
+        None
+    }
+
+    /// This gets the number of planes the data of each subresource is split
+    /// into. It is 1 for all but planar formats.
+    fn get_num_planes(&self) -> u32 {
+        1
+    }
+
+    /// This gets where the given plane lies within a subresource of the given
+    /// dimensions (one depth slice, for volumes)
+    fn get_plane_layout(&self, plane: u32, width: u32, height: u32) -> Option<PlaneLayout> {
+        if plane != 0 {
+            return None;
+        }
+        Some(PlaneLayout {
+            offset: 0,
+            pitch: self.get_pitch(width)?,
+            rows: height.div_ceil(self.get_pitch_height()),
+        })
+    }
+
+    /// This gets the number of bytes required to store a subresource of the
+    /// given dimensions (one depth slice, for volumes), across all its planes.
+    /// It is None if the size does not fit in a u32.
+    fn get_slice_size(&self, width: u32, height: u32) -> Option<u32> {
+        let last = self.get_plane_layout(self.get_num_planes() - 1, width, height)?;
+        last.offset.checked_add(last.size()?)
+    }
+
     /// This gets the minimum mipmap size in bytes. Even if they go all the way
     /// down to 1x1, there is a minimum number of bytes based on bits per pixel
     /// or blocksize.
     fn get_minimum_mipmap_size_in_bytes(&self) -> Option<u32> {
         if let Some(bpp) = self.get_bits_per_pixel() {
-            Some((bpp as u32 + 7) / 8)
+            Some((bpp as u32).div_ceil(8))
         } else {
             self.get_block_size()
         }
@@ -25,6 +25,7 @@ use crate::error::*;
 use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
 use std::fmt;
 use std::io::{Read, Write};
+use std::str::FromStr;
 
 #[derive(Clone)]
 pub struct PixelFormat {
@@ -62,7 +63,12 @@ impl PixelFormat {
     pub fn read<R: Read>(mut r: R) -> Result<PixelFormat, Error> {
         let size = r.read_u32::<LittleEndian>()?;
         if size != 32 {
-            return Err(Error::InvalidField("Pixel format struct size".to_owned()));
+            return Err(Error::InvalidField {
+                field: "DDS_PIXELFORMAT.dwSize",
+                value: size as u64,
+                offset: Some(76),
+                expected: Expected::Range(32, 32),
+            });
         }
         let flags = PixelFormatFlags::from_bits_truncate(r.read_u32::<LittleEndian>()?);
         let fourcc = r.read_u32::<LittleEndian>()?;
@@ -71,6 +77,8 @@ impl PixelFormat {
         let g_bit_mask = r.read_u32::<LittleEndian>()?;
         let b_bit_mask = r.read_u32::<LittleEndian>()?;
         let a_bit_mask = r.read_u32::<LittleEndian>()?;
+        // Bump maps keep their masks (and bit count) in the color fields
+        let bump = flags.intersects(PixelFormatFlags::BUMPDUDV | PixelFormatFlags::BUMPLUMINANCE);
         Ok(PixelFormat {
             size,
             flags,
@@ -81,28 +89,34 @@ impl PixelFormat {
             },
             rgb_bit_count: if flags.contains(PixelFormatFlags::RGB)
                 || flags.contains(PixelFormatFlags::LUMINANCE)
+                || bump
             {
                 Some(rgb_bit_count)
             } else {
                 None
             },
-            r_bit_mask: if flags.contains(PixelFormatFlags::RGB) {
+            // luminance formats keep their mask in the red channel
+            r_bit_mask: if flags.contains(PixelFormatFlags::RGB)
+                || flags.contains(PixelFormatFlags::LUMINANCE)
+                || bump
+            {
                 Some(r_bit_mask)
             } else {
                 None
             },
-            g_bit_mask: if flags.contains(PixelFormatFlags::RGB) {
+            g_bit_mask: if flags.contains(PixelFormatFlags::RGB) || bump {
                 Some(g_bit_mask)
             } else {
                 None
             },
-            b_bit_mask: if flags.contains(PixelFormatFlags::RGB) {
+            b_bit_mask: if flags.contains(PixelFormatFlags::RGB) || bump {
                 Some(b_bit_mask)
             } else {
                 None
             },
             a_bit_mask: if flags.contains(PixelFormatFlags::ALPHA_PIXELS)
                 || flags.contains(PixelFormatFlags::ALPHA)
+                || bump
             {
                 Some(a_bit_mask)
             } else {
@@ -157,15 +171,35 @@ impl Default for PixelFormat {
 impl From<D3DFormat> for PixelFormat {
     fn from(format: D3DFormat) -> PixelFormat {
         let mut pf: PixelFormat = Default::default();
-        if let Some(bpp) = format.get_bits_per_pixel() {
-            pf.flags.insert(PixelFormatFlags::RGB);
-            pf.rgb_bit_count = Some(bpp as u32)
-        } else if let Some(fourcc) = format.get_fourcc() {
+        if let Some(fourcc) = format.get_fourcc() {
             pf.flags.insert(PixelFormatFlags::FOURCC);
             pf.fourcc = Some(fourcc);
+        } else if let Some(bpp) = format.get_bits_per_pixel() {
+            match format {
+                D3DFormat::A8L8 | D3DFormat::L16 | D3DFormat::L8 | D3DFormat::A4L4 => {
+                    pf.flags.insert(PixelFormatFlags::LUMINANCE)
+                }
+                D3DFormat::A8 => pf.flags.insert(PixelFormatFlags::ALPHA),
+                D3DFormat::V8U8
+                | D3DFormat::Q8W8V8U8
+                | D3DFormat::V16U16
+                | D3DFormat::A2W10V10U10 => pf.flags.insert(PixelFormatFlags::BUMPDUDV),
+                D3DFormat::L6V5U5 | D3DFormat::X8L8V8U8 => {
+                    pf.flags.insert(PixelFormatFlags::BUMPLUMINANCE)
+                }
+                _ => pf.flags.insert(PixelFormatFlags::RGB),
+            }
+            pf.rgb_bit_count = Some(bpp as u32)
         }
         if let Some(abitmask) = format.a_bit_mask() {
-            pf.flags.insert(PixelFormatFlags::ALPHA_PIXELS);
+            // The fourth channel of a bump map is not alpha
+            if !pf.flags.intersects(
+                PixelFormatFlags::ALPHA
+                    | PixelFormatFlags::BUMPDUDV
+                    | PixelFormatFlags::BUMPLUMINANCE,
+            ) {
+                pf.flags.insert(PixelFormatFlags::ALPHA_PIXELS);
+            }
             pf.a_bit_mask = Some(abitmask);
         }
         pf.r_bit_mask = format.r_bit_mask();
@@ -207,6 +241,10 @@ bitflags! {
         const YUV = 0x200;
         /// Single channel color uncompressed data (used in older DDS files)
         const LUMINANCE = 0x20000;
+        /// Bump map data with luminance (used in older DDS files)
+        const BUMPLUMINANCE = 0x40000;
+        /// Bump map data of signed du and dv offsets (used in older DDS files)
+        const BUMPDUDV = 0x80000;
     }
 }
 
@@ -272,3 +310,38 @@ impl FourCC {
     pub const R32G32_FLOAT: u32 = 115;
     pub const R32G32B32A32_FLOAT: u32 = 116;
 }
+
+impl fmt::Display for FourCC {
+    /// Shows the four ASCII characters of the code (e.g. "DXT1"), or the number
+    /// for the numeric D3D codes (e.g. "36" for A16B16G16R16)
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let bytes = self.0.to_le_bytes();
+        if bytes.iter().all(|b| b.is_ascii_graphic() || *b == b' ') {
+            for &b in &bytes {
+                write!(f, "{}", b as char)?;
+            }
+            Ok(())
+        } else {
+            write!(f, "{}", self.0)
+        }
+    }
+}
+
+impl FromStr for FourCC {
+    type Err = Error;
+
+    /// Parse four ASCII characters (e.g. "DXT1"), or a number
+    fn from_str(s: &str) -> Result<FourCC, Error> {
+        if let Ok(n) = s.parse::<u32>() {
+            Ok(FourCC(n))
+        } else if s.len() == 4 && s.is_ascii() {
+            let b = s.as_bytes();
+            Ok(FourCC(u32::from_le_bytes([b[0], b[1], b[2], b[3]])))
+        } else {
+            Err(Error::Parse {
+                kind: "FourCC",
+                input: s.to_owned(),
+            })
+        }
+    }
+}
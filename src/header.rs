@@ -140,23 +140,7 @@ impl Header {
             header.caps2 = c2;
         }
 
-        let compressed: bool = format.get_block_size().is_some();
-        let pitch: u32 = match format.get_pitch(width) {
-            Some(pitch) => pitch,
-            None => return Err(Error::UnsupportedFormat),
-        };
-
-        let depth = depth.unwrap_or(1);
-
-        if compressed {
-            header.flags |= HeaderFlags::LINEARSIZE;
-            let pitch_height = format.get_pitch_height();
-            let raw_height = (height + (pitch_height - 1)) / pitch_height;
-            header.linear_size = Some(pitch * raw_height * depth);
-        } else {
-            header.flags |= HeaderFlags::PITCH;
-            header.pitch = Some(pitch);
-        }
+        header.set_pitch_or_linear_size(&format)?;
 
         Ok(header)
     }
@@ -202,23 +186,7 @@ impl Header {
             header.caps2 = c2;
         }
 
-        let compressed: bool = format.get_block_size().is_some();
-        let pitch: u32 = match format.get_pitch(width) {
-            Some(pitch) => pitch,
-            None => return Err(Error::UnsupportedFormat),
-        };
-
-        let depth = depth.unwrap_or(1);
-
-        if compressed {
-            header.flags |= HeaderFlags::LINEARSIZE;
-            let pitch_height = format.get_pitch_height();
-            let raw_height = (height + (pitch_height - 1)) / pitch_height;
-            header.linear_size = Some(pitch * raw_height * depth);
-        } else {
-            header.flags |= HeaderFlags::PITCH;
-            header.pitch = Some(pitch);
-        }
+        header.set_pitch_or_linear_size(&format)?;
 
         Ok(header)
     }
@@ -226,7 +194,12 @@ impl Header {
     pub fn read<R: Read>(mut r: R) -> Result<Header, Error> {
         let size = r.read_u32::<LittleEndian>()?;
         if size != 124 {
-            return Err(Error::InvalidField("Header struct size".to_owned()));
+            return Err(Error::InvalidField {
+                field: "DDS_HEADER.dwSize",
+                value: size as u64,
+                offset: Some(4),
+                expected: Expected::Range(124, 124),
+            });
         }
         let flags = HeaderFlags::from_bits_truncate(r.read_u32::<LittleEndian>()?);
         let height = r.read_u32::<LittleEndian>()?;
@@ -302,6 +275,45 @@ impl Header {
         w.write_u32::<LittleEndian>(self.reserved2)?;
         Ok(())
     }
+
+    /// Set the pitch (for uncompressed formats) or the linear size of the main
+    /// image (for compressed formats) to suit the given format, along with the
+    /// flags marking which one is valid.
+    pub fn set_pitch_or_linear_size(&mut self, format: &dyn DataFormat) -> Result<(), Error> {
+        let pitch: u32 = match format.get_pitch(self.width) {
+            Some(pitch) => pitch,
+            None => return Err(Error::UnsupportedFormat),
+        };
+
+        let depth = self.depth.unwrap_or(1);
+
+        if format.get_block_size().is_some() {
+            let pitch_height = format.get_pitch_height();
+            let raw_height = self.height.div_ceil(pitch_height);
+            let linear_size = match pitch
+                .checked_mul(raw_height)
+                .and_then(|size| size.checked_mul(depth))
+            {
+                Some(size) => size,
+                None => return Err(Error::UnsupportedFormat),
+            };
+            self.flags.remove(HeaderFlags::PITCH);
+            self.flags |= HeaderFlags::LINEARSIZE;
+            self.pitch = None;
+            self.linear_size = Some(linear_size);
+        } else {
+            self.flags.remove(HeaderFlags::LINEARSIZE);
+            self.flags |= HeaderFlags::PITCH;
+            self.pitch = Some(pitch);
+            self.linear_size = None;
+        }
+        Ok(())
+    }
+
+    /// Flags indicating which members contain valid data
+    pub fn get_flags(&self) -> HeaderFlags {
+        self.flags
+    }
 }
 
 impl fmt::Debug for Header {
@@ -45,12 +45,19 @@ pub struct Header10 {
     pub array_size: u32,
     /// This is called misc_flags2 in the official documentation
     pub alpha_mode: AlphaMode,
+    // The DXGI_FORMAT value read from a file when `DxgiFormat` has no variant
+    // for it, written back in place of `Unknown`
+    unknown_dxgi_format: Option<u32>,
 }
 
 impl fmt::Debug for Header10 {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         writeln!(f, "  Header10:")?;
-        writeln!(f, "    dxgi_format: {:?}", self.dxgi_format)?;
+        if let Some(value) = self.get_unknown_dxgi_format() {
+            writeln!(f, "    dxgi_format: {:?} ({})", self.dxgi_format, value)?;
+        } else {
+            writeln!(f, "    dxgi_format: {:?}", self.dxgi_format)?;
+        }
         writeln!(f, "    resource_dimension: {:?}", self.resource_dimension)?;
         writeln!(f, "    misc_flag: {:?}", self.misc_flag)?;
         writeln!(f, "    array_size: {:?}", self.array_size)?;
@@ -67,6 +74,7 @@ impl Default for Header10 {
             misc_flag: MiscFlag::empty(),
             array_size: 0,
             alpha_mode: AlphaMode::Unknown,
+            unknown_dxgi_format: None,
         }
     }
 }
@@ -89,6 +97,40 @@ impl Header10 {
             misc_flag: flags,
             array_size,
             alpha_mode,
+            unknown_dxgi_format: None,
+        }
+    }
+
+    /// The raw DXGI_FORMAT value, including one that `DxgiFormat` does not know
+    pub fn get_dxgi_format_value(&self) -> u32 {
+        match self.get_unknown_dxgi_format() {
+            Some(value) => value,
+            None => self.dxgi_format as u32,
+        }
+    }
+
+    /// Set the raw DXGI_FORMAT value. A value that `DxgiFormat` does not know
+    /// (such as a vendor format) leaves `dxgi_format` as `Unknown` and is
+    /// written back unchanged.
+    pub fn set_dxgi_format_value(&mut self, value: u32) {
+        match DxgiFormat::from_u32(value) {
+            Some(format) => {
+                self.dxgi_format = format;
+                self.unknown_dxgi_format = None;
+            }
+            None => {
+                self.dxgi_format = DxgiFormat::Unknown;
+                self.unknown_dxgi_format = Some(value);
+            }
+        }
+    }
+
+    /// The raw DXGI_FORMAT value when `DxgiFormat` has no variant for it and
+    /// `dxgi_format` is `Unknown`
+    pub fn get_unknown_dxgi_format(&self) -> Option<u32> {
+        match self.dxgi_format {
+            DxgiFormat::Unknown => self.unknown_dxgi_format,
+            _ => None,
         }
     }
 
@@ -99,26 +141,39 @@ impl Header10 {
         let array_size = r.read_u32::<LittleEndian>()?;
         let alpha_mode = r.read_u32::<LittleEndian>()?;
 
-        let dxgi_format_result: Result<DxgiFormat, Error> = DxgiFormat::from_u32(dxgi_format)
-            .ok_or_else(|| Error::InvalidField("dxgi_format".to_owned()));
+        // Offsets are within the file, where this header follows the magic
+        // number and the main header
         let resource_dimension_result: Result<D3D10ResourceDimension, Error> =
-            D3D10ResourceDimension::from_u32(resource_dimension)
-                .ok_or_else(|| Error::InvalidField("resource_dimension".to_owned()));
+            D3D10ResourceDimension::from_u32(resource_dimension).ok_or(Error::InvalidField {
+                field: "DDS_HEADER_DXT10.resourceDimension",
+                value: resource_dimension as u64,
+                offset: Some(132),
+                expected: Expected::Range(0, 4),
+            });
 
-        let alpha_mode: Result<AlphaMode, Error> = AlphaMode::from_u32(alpha_mode)
-            .ok_or_else(|| Error::InvalidField("alpha mode (misc_flags2)".to_owned()));
+        let alpha_mode: Result<AlphaMode, Error> =
+            AlphaMode::from_u32(alpha_mode).ok_or(Error::InvalidField {
+                field: "DDS_HEADER_DXT10.miscFlags2",
+                value: alpha_mode as u64,
+                offset: Some(144),
+                expected: Expected::Range(0, 4),
+            });
 
-        Ok(Header10 {
-            dxgi_format: dxgi_format_result?,
+        let mut header10 = Header10 {
+            dxgi_format: DxgiFormat::Unknown,
             resource_dimension: resource_dimension_result?,
             misc_flag,
             array_size,
             alpha_mode: alpha_mode?,
-        })
+            unknown_dxgi_format: None,
+        };
+        // Formats we do not know are kept as they are
+        header10.set_dxgi_format_value(dxgi_format);
+        Ok(header10)
     }
 
     pub fn write<W: Write>(&self, w: &mut W) -> Result<(), Error> {
-        w.write_u32::<LittleEndian>(self.dxgi_format as u32)?;
+        w.write_u32::<LittleEndian>(self.get_dxgi_format_value())?;
         w.write_u32::<LittleEndian>(self.resource_dimension as u32)?;
         w.write_u32::<LittleEndian>(self.misc_flag.bits())?;
         w.write_u32::<LittleEndian>(self.array_size)?;
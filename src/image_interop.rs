@@ -0,0 +1,113 @@
+// The MIT License (MIT)
+//
+// Copyright (c) 2018 Michael Dilger
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+// Conversions to and from the `image` crate
+
+use crate::codec::Layout;
+use crate::{AlphaMode, D3D10ResourceDimension, Dds, DxgiFormat, Error, NewDxgiParams};
+use image::{DynamicImage, ImageBuffer, Rgba32FImage, RgbaImage};
+
+impl TryFrom<&Dds> for DynamicImage {
+    type Error = Error;
+
+    /// Decode the main image (the first mipmap level of the first array layer)
+    fn try_from(dds: &Dds) -> Result<DynamicImage, Error> {
+        dds.to_image(0, 0)
+    }
+}
+
+impl Dds {
+    /// Decode a single mipmap level of an array layer into an image. For cubemaps,
+    /// each face is its own array layer, and the slices of a volume texture are
+    /// stacked vertically.
+    ///
+    /// Formats of up to 8 bits per channel give an RGBA8 image, other normalized
+    /// unsigned formats an RGBA16 image, and everything else an RGBA32F image.
+    pub fn to_image(&self, array_layer: u32, mipmap_level: u32) -> Result<DynamicImage, Error> {
+        let layout = match Layout::of(self) {
+            Some(layout) => layout,
+            None => return Err(Error::UnsupportedFormat),
+        };
+        let rgba = self.decode_rgba32f(array_layer, mipmap_level)?;
+        let (width, height, depth) = self.get_mipmap_dimensions(mipmap_level);
+        let height = height * depth;
+        let image = match layout.unorm_bits() {
+            Some(bits) if bits <= 8 => {
+                let texels = rgba
+                    .iter()
+                    .map(|v| (v.clamp(0.0, 1.0) * 255.0).round() as u8)
+                    .collect();
+                ImageBuffer::from_raw(width, height, texels).map(DynamicImage::ImageRgba8)
+            }
+            Some(bits) if bits <= 16 => {
+                let texels = rgba
+                    .iter()
+                    .map(|v| (v.clamp(0.0, 1.0) * 65535.0).round() as u16)
+                    .collect();
+                ImageBuffer::from_raw(width, height, texels).map(DynamicImage::ImageRgba16)
+            }
+            _ => ImageBuffer::from_raw(width, height, rgba).map(DynamicImage::ImageRgba32F),
+        };
+        match image {
+            Some(image) => Ok(image),
+            None => Err(Error::OutOfBounds),
+        }
+    }
+
+    /// Create a new 2D texture with a single mipmap level from an RGBA8 image,
+    /// encoding it into the given format.
+    pub fn from_rgba_image(image: &RgbaImage, format: DxgiFormat) -> Result<Dds, Error> {
+        let rgba: Vec<f32> = image.as_raw().iter().map(|&v| v as f32 / 255.0).collect();
+        Dds::from_rgba32f(image.width(), image.height(), &rgba, format)
+    }
+
+    /// Create a new 2D texture with a single mipmap level from an RGBA32F image,
+    /// encoding it into the given format.
+    pub fn from_rgba32f_image(image: &Rgba32FImage, format: DxgiFormat) -> Result<Dds, Error> {
+        Dds::from_rgba32f(image.width(), image.height(), image.as_raw(), format)
+    }
+
+    fn from_rgba32f(
+        width: u32,
+        height: u32,
+        rgba: &[f32],
+        format: DxgiFormat,
+    ) -> Result<Dds, Error> {
+        if Layout::from_dxgi(format).is_none() {
+            return Err(Error::UnsupportedFormat);
+        }
+        let mut dds = Dds::new_dxgi(NewDxgiParams {
+            height,
+            width,
+            depth: None,
+            format,
+            mipmap_levels: Some(1),
+            array_layers: None,
+            caps2: None,
+            is_cubemap: false,
+            resource_dimension: D3D10ResourceDimension::Texture2D,
+            alpha_mode: AlphaMode::Unknown,
+        })?;
+        dds.encode_rgba32f(0, 0, rgba)?;
+        Ok(dds)
+    }
+}
@@ -0,0 +1,615 @@
+// The MIT License (MIT)
+//
+// Copyright (c) 2018 Michael Dilger
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+use crate::error::*;
+use crate::ktx2::write_key_value;
+use crate::{
+    AlphaMode, Caps2, CountingReader, D3D10ResourceDimension, D3DFormat, DataFormat, Dds,
+    DxgiFormat, NewD3dParams, NewDxgiParams,
+};
+use byteorder::{LittleEndian, WriteBytesExt};
+use std::io::{Read, Write};
+
+// «KTX 11»\r\n\x1A\n
+const IDENTIFIER: [u8; 12] = [
+    0xAB, 0x4B, 0x54, 0x58, 0x20, 0x31, 0x31, 0xBB, 0x0D, 0x0A, 0x1A, 0x0A,
+];
+
+const ENDIANNESS: u32 = 0x04030201;
+
+impl Dds {
+    /// Read a KTX (version 1) file, converting it into a DirectDraw Surface.
+    ///
+    /// Formats with a DXGI equivalent get a DX10 header, and legacy formats such
+    /// as luminance or 24-bit RGB get a D3DFormat header. Formats with no DDS
+    /// equivalent yield `Error::UnsupportedFormat`. Texel data is copied as-is, so
+    /// a `KTXorientation` other than "S=r,T=d" is not applied.
+    pub fn read_ktx1<R: Read>(r: R) -> Result<Dds, Error> {
+        let mut r = CountingReader::new(r);
+        let mut identifier = [0_u8; 12];
+        r.fill(&mut identifier)?;
+        if identifier != IDENTIFIER {
+            return Err(Error::BadMagicNumber);
+        }
+
+        // The writer's endianness applies to every field and to the texel data
+        let endianness = r.read_u32_le()?;
+        let swap = match endianness {
+            ENDIANNESS => false,
+            0x01020304 => true,
+            _ => {
+                return Err(Error::InvalidField {
+                    field: "endianness",
+                    value: endianness as u64,
+                    offset: Some(12),
+                    expected: Expected::OneOf(&[ENDIANNESS as u64, 0x01020304]),
+                })
+            }
+        };
+        let read_u32 = |r: &mut CountingReader<R>| -> Result<u32, Error> {
+            let value = r.read_u32_le()?;
+            Ok(if swap { value.swap_bytes() } else { value })
+        };
+
+        let gl_type = read_u32(&mut r)?;
+        let gl_type_size = read_u32(&mut r)?;
+        let gl_format = read_u32(&mut r)?;
+        let gl_internal_format = read_u32(&mut r)?;
+        let _gl_base_internal_format = read_u32(&mut r)?;
+        let pixel_width = read_u32(&mut r)?;
+        let pixel_height = read_u32(&mut r)?;
+        let pixel_depth = read_u32(&mut r)?;
+        let number_of_array_elements = read_u32(&mut r)?;
+        let number_of_faces = read_u32(&mut r)?;
+        let number_of_mipmap_levels = read_u32(&mut r)?;
+        let bytes_of_key_value_data = read_u32(&mut r)?;
+
+        // Nothing in the key/value data is needed
+        r.skip(bytes_of_key_value_data as u64)?;
+
+        let gl_format = match GlFormat::find(gl_internal_format, gl_format, gl_type) {
+            Some(f) => f,
+            None => return Err(Error::UnsupportedFormat),
+        };
+        if pixel_width == 0 {
+            return Err(Error::InvalidField {
+                field: "pixelWidth",
+                value: 0,
+                offset: Some(36),
+                expected: Expected::Range(1, u32::MAX as u64),
+            });
+        }
+        if number_of_faces != 1 && number_of_faces != 6 {
+            return Err(Error::InvalidField {
+                field: "numberOfFaces",
+                value: number_of_faces as u64,
+                offset: Some(52),
+                expected: Expected::OneOf(&[1, 6]),
+            });
+        }
+        let is_cubemap = number_of_faces == 6;
+        let is_array = number_of_array_elements > 0;
+        let layers = number_of_array_elements.max(1);
+        // Each face of each element is an array layer of the DDS
+        let array_layers = match layers.checked_mul(number_of_faces) {
+            Some(n) => n,
+            None => {
+                return Err(Error::InvalidField {
+                    field: "numberOfArrayElements",
+                    value: number_of_array_elements as u64,
+                    offset: Some(48),
+                    expected: Expected::Range(0, (u32::MAX / number_of_faces) as u64),
+                })
+            }
+        };
+        // DDS cannot hold arrays of volume textures
+        if pixel_depth > 0 && (is_array || is_cubemap) {
+            return Err(Error::UnsupportedFormat);
+        }
+        let max_levels = 32
+            - pixel_width
+                .max(pixel_height)
+                .max(pixel_depth)
+                .leading_zeros();
+        if number_of_mipmap_levels > max_levels {
+            return Err(Error::InvalidField {
+                field: "numberOfMipmapLevels",
+                value: number_of_mipmap_levels as u64,
+                offset: Some(56),
+                expected: Expected::Range(0, max_levels as u64),
+            });
+        }
+        // A level count of zero asks the loader to generate mipmaps, but only
+        // the base level is present in the file.
+        let levels = number_of_mipmap_levels.max(1);
+
+        let format: Box<dyn DataFormat> = match (gl_format.dxgi, gl_format.d3d) {
+            (Some(dxgi), _) => Box::new(dxgi),
+            (None, Some(d3d)) => Box::new(d3d),
+            (None, None) => return Err(Error::UnsupportedFormat),
+        };
+        // Read a whole level, returning the pitch of its rows in the DDS and in
+        // the file, where they are padded to four bytes, and the data. Rows
+        // being padded, cubePadding and mipPadding are always empty.
+        let read_level =
+            |r: &mut CountingReader<R>, level: u32| -> Result<(usize, usize, Vec<u8>), Error> {
+                let image_size_offset = r.position();
+                let image_size = read_u32(r)?;
+                let pitch = match format.get_pitch((pixel_width >> level).max(1)) {
+                    Some(p) => p as u64,
+                    None => return Err(Error::UnsupportedFormat),
+                };
+                let ktx_pitch = pitch.next_multiple_of(4);
+                let height = (pixel_height.max(1) >> level).max(1);
+                let depth = (pixel_depth.max(1) >> level).max(1);
+                let rows = height.div_ceil(format.get_pitch_height()) as u64 * depth as u64;
+                // imageSize counts a single face of a non-array cubemap
+                let images = if is_cubemap && !is_array {
+                    1
+                } else {
+                    array_layers as u64
+                };
+                let expected_size = ktx_pitch
+                    .checked_mul(rows)
+                    .and_then(|face_size| face_size.checked_mul(images));
+                if expected_size != Some(image_size as u64) {
+                    return Err(Error::InvalidField {
+                        field: "imageSize",
+                        value: image_size as u64,
+                        offset: Some(image_size_offset),
+                        expected: match expected_size {
+                            Some(size) => Expected::Range(size, size),
+                            None => Expected::Description("the size of the mipmap level"),
+                        },
+                    });
+                }
+                let data = r.read_vec(image_size as u64 / images * array_layers as u64)?;
+                Ok((pitch as usize, ktx_pitch as usize, data))
+            };
+
+        // The first level is read before the texture is allocated, so that a
+        // header claiming more data than the input holds fails on the short
+        // input rather than on the allocation
+        let (mut pitch, mut ktx_pitch, mut data) = read_level(&mut r, 0)?;
+
+        let depth = if pixel_depth > 0 {
+            Some(pixel_depth)
+        } else {
+            None
+        };
+        let caps2 = if pixel_depth > 0 {
+            Some(Caps2::VOLUME)
+        } else if is_cubemap {
+            Some(Caps2::CUBEMAP | Caps2::CUBEMAP_ALLFACES)
+        } else {
+            None
+        };
+
+        let mut dds = if let Some(format) = gl_format.dxgi {
+            let resource_dimension = if pixel_depth > 0 {
+                D3D10ResourceDimension::Texture3D
+            } else if pixel_height == 0 {
+                D3D10ResourceDimension::Texture1D
+            } else {
+                D3D10ResourceDimension::Texture2D
+            };
+            Dds::new_dxgi(NewDxgiParams {
+                height: pixel_height.max(1),
+                width: pixel_width,
+                depth,
+                format,
+                mipmap_levels: Some(levels),
+                array_layers: Some(array_layers),
+                caps2,
+                is_cubemap,
+                resource_dimension,
+                alpha_mode: AlphaMode::Unknown,
+            })?
+        } else if let Some(format) = gl_format.d3d {
+            // Legacy headers cannot describe texture arrays
+            if is_array {
+                return Err(Error::UnsupportedFormat);
+            }
+            Dds::new_d3d(NewD3dParams {
+                height: pixel_height.max(1),
+                width: pixel_width,
+                depth,
+                format,
+                mipmap_levels: Some(levels),
+                caps2,
+            })?
+        } else {
+            return Err(Error::UnsupportedFormat);
+        };
+
+        for level in 0..levels {
+            if level > 0 {
+                (pitch, ktx_pitch, data) = read_level(&mut r, level)?;
+            }
+            let face_size = data.len() / array_layers as usize;
+            for (layer, face) in data.chunks_exact(face_size).enumerate() {
+                let image = dds.get_mut_mipmap_data(layer as u32, level)?;
+                for (row, ktx_row) in image
+                    .chunks_exact_mut(pitch)
+                    .zip(face.chunks_exact(ktx_pitch))
+                {
+                    row.copy_from_slice(&ktx_row[..pitch]);
+                    if swap {
+                        swap_bytes(row, gl_type_size);
+                    }
+                }
+            }
+        }
+
+        Ok(dds)
+    }
+
+    /// Write as a KTX (version 1) file.
+    ///
+    /// The format must have an OpenGL equivalent, otherwise
+    /// `Error::UnsupportedFormat` is returned.
+    pub fn write_ktx1<W: Write>(&self, w: &mut W) -> Result<(), Error> {
+        let gl_format = if let Some(dxgi) = self.get_dxgi_format() {
+            GL_FORMATS.iter().find(|f| f.dxgi == Some(dxgi))
+        } else if let Some(d3d) = self.get_d3d_format() {
+            GL_FORMATS.iter().find(|f| f.d3d == Some(d3d))
+        } else {
+            None
+        };
+        let gl_format = match gl_format {
+            Some(f) => f,
+            None => return Err(Error::UnsupportedFormat),
+        };
+        let format = match self.get_format() {
+            Some(f) => f,
+            None => return Err(Error::UnsupportedFormat),
+        };
+
+        let is_1d = match self.header10 {
+            Some(ref h10) => h10.resource_dimension == D3D10ResourceDimension::Texture1D,
+            None => false,
+        };
+        let is_volume = self.is_volume();
+        let is_cubemap = self.is_cubemap();
+        let faces = if is_cubemap { 6 } else { 1 };
+        let layers = self.get_num_array_layers() / faces;
+        // A DX10 array of one is written as a plain texture
+        let is_array = layers > 1;
+        let levels = self.get_num_mipmap_levels();
+
+        let orientation = if is_volume { "S=r,T=d,R=i" } else { "S=r,T=d" };
+        let mut key_value_data: Vec<u8> = Vec::new();
+        write_key_value(&mut key_value_data, "KTXorientation", orientation)?;
+
+        w.write_all(&IDENTIFIER)?;
+        w.write_u32::<LittleEndian>(ENDIANNESS)?;
+        w.write_u32::<LittleEndian>(gl_format.gl_type)?;
+        w.write_u32::<LittleEndian>(gl_format.gl_type_size)?;
+        w.write_u32::<LittleEndian>(gl_format.gl_format)?;
+        w.write_u32::<LittleEndian>(gl_format.gl_internal_format)?;
+        w.write_u32::<LittleEndian>(gl_format.gl_base_internal_format)?;
+        w.write_u32::<LittleEndian>(self.get_width())?;
+        w.write_u32::<LittleEndian>(if is_1d { 0 } else { self.get_height() })?;
+        w.write_u32::<LittleEndian>(if is_volume { self.get_depth() } else { 0 })?;
+        w.write_u32::<LittleEndian>(if is_array { layers } else { 0 })?;
+        w.write_u32::<LittleEndian>(faces)?;
+        w.write_u32::<LittleEndian>(levels)?;
+        w.write_u32::<LittleEndian>(key_value_data.len() as u32)?;
+        w.write_all(&key_value_data)?;
+
+        let padding = [0_u8; 4];
+        for level in 0..levels {
+            let pitch = match format.get_pitch((self.get_width() >> level).max(1)) {
+                Some(p) => p as usize,
+                None => return Err(Error::UnsupportedFormat),
+            };
+            let ktx_pitch = pitch.next_multiple_of(4);
+            let face_size = self.get_mipmap_size(level)? as usize / pitch * ktx_pitch;
+            let image_size = if is_cubemap && !is_array {
+                face_size
+            } else {
+                face_size * (layers * faces) as usize
+            };
+            w.write_u32::<LittleEndian>(image_size as u32)?;
+
+            for layer in 0..layers * faces {
+                let image = self.get_mipmap_data(layer, level)?;
+                for row in image.chunks_exact(pitch) {
+                    w.write_all(row)?;
+                    w.write_all(&padding[..ktx_pitch - pitch])?;
+                }
+                // cubePadding, for non-array cubemaps only
+                if is_cubemap && !is_array {
+                    w.write_all(&padding[..face_size.next_multiple_of(4) - face_size])?;
+                }
+            }
+            // mipPadding
+            w.write_all(&padding[..image_size.next_multiple_of(4) - image_size])?;
+        }
+
+        Ok(())
+    }
+}
+
+fn swap_bytes(data: &mut [u8], type_size: u32) {
+    if type_size > 1 {
+        for chunk in data.chunks_exact_mut(type_size as usize) {
+            chunk.reverse();
+        }
+    }
+}
+
+// An OpenGL format and the DDS formats that share its memory layout
+struct GlFormat {
+    gl_internal_format: u32,
+    gl_format: u32,
+    gl_type: u32,
+    gl_base_internal_format: u32,
+    gl_type_size: u32,
+    dxgi: Option<DxgiFormat>,
+    d3d: Option<D3DFormat>,
+}
+
+impl GlFormat {
+    fn find(gl_internal_format: u32, gl_format: u32, gl_type: u32) -> Option<&'static GlFormat> {
+        // Compressed formats are identified by the internal format alone
+        if gl_type == 0 {
+            return GL_FORMATS
+                .iter()
+                .find(|f| f.gl_type == 0 && f.gl_internal_format == gl_internal_format)
+                .or_else(|| match gl_internal_format {
+                    GL_COMPRESSED_RGB_S3TC_DXT1_EXT => {
+                        GlFormat::find(GL_COMPRESSED_RGBA_S3TC_DXT1_EXT, 0, 0)
+                    }
+                    GL_COMPRESSED_SRGB_S3TC_DXT1_EXT => {
+                        GlFormat::find(GL_COMPRESSED_SRGB_ALPHA_S3TC_DXT1_EXT, 0, 0)
+                    }
+                    _ => None,
+                });
+        }
+        GL_FORMATS
+            .iter()
+            .find(|f| {
+                f.gl_internal_format == gl_internal_format
+                    && f.gl_format == gl_format
+                    && f.gl_type == gl_type
+            })
+            .or_else(|| {
+                // Older files may use an unsized internal format
+                GL_FORMATS.iter().find(|f| {
+                    f.gl_base_internal_format == gl_internal_format
+                        && f.gl_format == gl_format
+                        && f.gl_type == gl_type
+                })
+            })
+    }
+}
+
+// Types
+const GL_BYTE: u32 = 0x1400;
+const GL_UNSIGNED_BYTE: u32 = 0x1401;
+const GL_SHORT: u32 = 0x1402;
+const GL_UNSIGNED_SHORT: u32 = 0x1403;
+const GL_INT: u32 = 0x1404;
+const GL_UNSIGNED_INT: u32 = 0x1405;
+const GL_FLOAT: u32 = 0x1406;
+const GL_HALF_FLOAT: u32 = 0x140B;
+const GL_UNSIGNED_SHORT_5_6_5: u32 = 0x8363;
+const GL_UNSIGNED_SHORT_4_4_4_4: u32 = 0x8033;
+const GL_UNSIGNED_SHORT_4_4_4_4_REV: u32 = 0x8365;
+const GL_UNSIGNED_SHORT_1_5_5_5_REV: u32 = 0x8366;
+const GL_UNSIGNED_INT_2_10_10_10_REV: u32 = 0x8368;
+const GL_UNSIGNED_INT_10F_11F_11F_REV: u32 = 0x8C3B;
+const GL_UNSIGNED_INT_5_9_9_9_REV: u32 = 0x8C3E;
+
+// Formats (and base internal formats)
+const GL_DEPTH_COMPONENT: u32 = 0x1902;
+const GL_RED: u32 = 0x1903;
+const GL_ALPHA: u32 = 0x1906;
+const GL_RGB: u32 = 0x1907;
+const GL_RGBA: u32 = 0x1908;
+const GL_LUMINANCE: u32 = 0x1909;
+const GL_LUMINANCE_ALPHA: u32 = 0x190A;
+const GL_BGR: u32 = 0x80E0;
+const GL_BGRA: u32 = 0x80E1;
+const GL_RG: u32 = 0x8227;
+const GL_RG_INTEGER: u32 = 0x8228;
+const GL_RED_INTEGER: u32 = 0x8D94;
+const GL_RGB_INTEGER: u32 = 0x8D98;
+const GL_RGBA_INTEGER: u32 = 0x8D99;
+
+// Sized internal formats
+const GL_RGB4: u32 = 0x804F;
+const GL_RGB5: u32 = 0x8050;
+const GL_RGB8: u32 = 0x8051;
+const GL_RGBA4: u32 = 0x8056;
+const GL_RGB565: u32 = 0x8D62;
+const GL_RGB5_A1: u32 = 0x8057;
+const GL_RGBA8: u32 = 0x8058;
+const GL_RGB10_A2: u32 = 0x8059;
+const GL_RGBA16: u32 = 0x805B;
+const GL_ALPHA8: u32 = 0x803C;
+const GL_LUMINANCE8: u32 = 0x8040;
+const GL_LUMINANCE16: u32 = 0x8042;
+const GL_LUMINANCE8_ALPHA8: u32 = 0x8045;
+const GL_DEPTH_COMPONENT16: u32 = 0x81A5;
+const GL_R8: u32 = 0x8229;
+const GL_R16: u32 = 0x822A;
+const GL_RG8: u32 = 0x822B;
+const GL_RG16: u32 = 0x822C;
+const GL_R16F: u32 = 0x822D;
+const GL_R32F: u32 = 0x822E;
+const GL_RG16F: u32 = 0x822F;
+const GL_RG32F: u32 = 0x8230;
+const GL_R8I: u32 = 0x8231;
+const GL_R8UI: u32 = 0x8232;
+const GL_R16I: u32 = 0x8233;
+const GL_R16UI: u32 = 0x8234;
+const GL_R32I: u32 = 0x8235;
+const GL_R32UI: u32 = 0x8236;
+const GL_RG8I: u32 = 0x8237;
+const GL_RG8UI: u32 = 0x8238;
+const GL_RG16I: u32 = 0x8239;
+const GL_RG16UI: u32 = 0x823A;
+const GL_RG32I: u32 = 0x823B;
+const GL_RG32UI: u32 = 0x823C;
+const GL_RGBA32F: u32 = 0x8814;
+const GL_RGB32F: u32 = 0x8815;
+const GL_RGBA16F: u32 = 0x881A;
+const GL_R11F_G11F_B10F: u32 = 0x8C3A;
+const GL_RGB9_E5: u32 = 0x8C3D;
+const GL_SRGB8: u32 = 0x8C41;
+const GL_SRGB8_ALPHA8: u32 = 0x8C43;
+const GL_DEPTH_COMPONENT32F: u32 = 0x8CAC;
+const GL_RGBA32UI: u32 = 0x8D70;
+const GL_RGB32UI: u32 = 0x8D71;
+const GL_RGBA16UI: u32 = 0x8D76;
+const GL_RGBA8UI: u32 = 0x8D7C;
+const GL_RGBA32I: u32 = 0x8D82;
+const GL_RGB32I: u32 = 0x8D83;
+const GL_RGBA16I: u32 = 0x8D88;
+const GL_RGBA8I: u32 = 0x8D8E;
+const GL_R8_SNORM: u32 = 0x8F94;
+const GL_RG8_SNORM: u32 = 0x8F95;
+const GL_RGBA8_SNORM: u32 = 0x8F97;
+const GL_R16_SNORM: u32 = 0x8F98;
+const GL_RG16_SNORM: u32 = 0x8F99;
+const GL_RGBA16_SNORM: u32 = 0x8F9B;
+const GL_RGB10_A2UI: u32 = 0x906F;
+
+// Compressed internal formats
+const GL_COMPRESSED_RGB_S3TC_DXT1_EXT: u32 = 0x83F0;
+const GL_COMPRESSED_RGBA_S3TC_DXT1_EXT: u32 = 0x83F1;
+const GL_COMPRESSED_RGBA_S3TC_DXT3_EXT: u32 = 0x83F2;
+const GL_COMPRESSED_RGBA_S3TC_DXT5_EXT: u32 = 0x83F3;
+const GL_COMPRESSED_SRGB_S3TC_DXT1_EXT: u32 = 0x8C4C;
+const GL_COMPRESSED_SRGB_ALPHA_S3TC_DXT1_EXT: u32 = 0x8C4D;
+const GL_COMPRESSED_SRGB_ALPHA_S3TC_DXT3_EXT: u32 = 0x8C4E;
+const GL_COMPRESSED_SRGB_ALPHA_S3TC_DXT5_EXT: u32 = 0x8C4F;
+const GL_COMPRESSED_RED_RGTC1: u32 = 0x8DBB;
+const GL_COMPRESSED_SIGNED_RED_RGTC1: u32 = 0x8DBC;
+const GL_COMPRESSED_RG_RGTC2: u32 = 0x8DBD;
+const GL_COMPRESSED_SIGNED_RG_RGTC2: u32 = 0x8DBE;
+const GL_COMPRESSED_RGBA_BPTC_UNORM: u32 = 0x8E8C;
+const GL_COMPRESSED_SRGB_ALPHA_BPTC_UNORM: u32 = 0x8E8D;
+const GL_COMPRESSED_RGB_BPTC_SIGNED_FLOAT: u32 = 0x8E8E;
+const GL_COMPRESSED_RGB_BPTC_UNSIGNED_FLOAT: u32 = 0x8E8F;
+
+macro_rules! gl_format {
+    ($internal:expr, $format:expr, $type:expr, $base:expr, $size:expr, $dxgi:expr, $d3d:expr) => {
+        GlFormat {
+            gl_internal_format: $internal,
+            gl_format: $format,
+            gl_type: $type,
+            gl_base_internal_format: $base,
+            gl_type_size: $size,
+            dxgi: $dxgi,
+            d3d: $d3d,
+        }
+    };
+}
+
+// When reading, the first entry matching the GL format wins, so entries with a
+// DXGI equivalent come before legacy-only ones.
+#[rustfmt::skip]
+const GL_FORMATS: &[GlFormat] = &[
+    gl_format!(GL_RGBA32F,          GL_RGBA,            GL_FLOAT,                        GL_RGBA,            4, Some(DxgiFormat::R32G32B32A32_Float),  Some(D3DFormat::A32B32G32R32F)),
+    gl_format!(GL_RGBA32UI,         GL_RGBA_INTEGER,    GL_UNSIGNED_INT,                 GL_RGBA,            4, Some(DxgiFormat::R32G32B32A32_UInt),   None),
+    gl_format!(GL_RGBA32I,          GL_RGBA_INTEGER,    GL_INT,                          GL_RGBA,            4, Some(DxgiFormat::R32G32B32A32_SInt),   None),
+    gl_format!(GL_RGB32F,           GL_RGB,             GL_FLOAT,                        GL_RGB,             4, Some(DxgiFormat::R32G32B32_Float),     None),
+    gl_format!(GL_RGB32UI,          GL_RGB_INTEGER,     GL_UNSIGNED_INT,                 GL_RGB,             4, Some(DxgiFormat::R32G32B32_UInt),      None),
+    gl_format!(GL_RGB32I,           GL_RGB_INTEGER,     GL_INT,                          GL_RGB,             4, Some(DxgiFormat::R32G32B32_SInt),      None),
+    gl_format!(GL_RGBA16F,          GL_RGBA,            GL_HALF_FLOAT,                   GL_RGBA,            2, Some(DxgiFormat::R16G16B16A16_Float),  Some(D3DFormat::A16B16G16R16F)),
+    gl_format!(GL_RGBA16,           GL_RGBA,            GL_UNSIGNED_SHORT,               GL_RGBA,            2, Some(DxgiFormat::R16G16B16A16_UNorm),  Some(D3DFormat::A16B16G16R16)),
+    gl_format!(GL_RGBA16UI,         GL_RGBA_INTEGER,    GL_UNSIGNED_SHORT,               GL_RGBA,            2, Some(DxgiFormat::R16G16B16A16_UInt),   None),
+    gl_format!(GL_RGBA16_SNORM,     GL_RGBA,            GL_SHORT,                        GL_RGBA,            2, Some(DxgiFormat::R16G16B16A16_SNorm),  Some(D3DFormat::Q16W16V16U16)),
+    gl_format!(GL_RGBA16I,          GL_RGBA_INTEGER,    GL_SHORT,                        GL_RGBA,            2, Some(DxgiFormat::R16G16B16A16_SInt),   None),
+    gl_format!(GL_RG32F,            GL_RG,              GL_FLOAT,                        GL_RG,              4, Some(DxgiFormat::R32G32_Float),        Some(D3DFormat::G32R32F)),
+    gl_format!(GL_RG32UI,           GL_RG_INTEGER,      GL_UNSIGNED_INT,                 GL_RG,              4, Some(DxgiFormat::R32G32_UInt),         None),
+    gl_format!(GL_RG32I,            GL_RG_INTEGER,      GL_INT,                          GL_RG,              4, Some(DxgiFormat::R32G32_SInt),         None),
+    gl_format!(GL_RGB10_A2,         GL_RGBA,            GL_UNSIGNED_INT_2_10_10_10_REV,  GL_RGBA,            4, Some(DxgiFormat::R10G10B10A2_UNorm),   Some(D3DFormat::A2B10G10R10)),
+    gl_format!(GL_RGB10_A2UI,       GL_RGBA_INTEGER,    GL_UNSIGNED_INT_2_10_10_10_REV,  GL_RGBA,            4, Some(DxgiFormat::R10G10B10A2_UInt),    None),
+    gl_format!(GL_R11F_G11F_B10F,   GL_RGB,             GL_UNSIGNED_INT_10F_11F_11F_REV, GL_RGB,             4, Some(DxgiFormat::R11G11B10_Float),     None),
+    gl_format!(GL_RGBA8,            GL_RGBA,            GL_UNSIGNED_BYTE,                GL_RGBA,            1, Some(DxgiFormat::R8G8B8A8_UNorm),      Some(D3DFormat::A8B8G8R8)),
+    gl_format!(GL_SRGB8_ALPHA8,     GL_RGBA,            GL_UNSIGNED_BYTE,                GL_RGBA,            1, Some(DxgiFormat::R8G8B8A8_UNorm_sRGB), None),
+    gl_format!(GL_RGBA8UI,          GL_RGBA_INTEGER,    GL_UNSIGNED_BYTE,                GL_RGBA,            1, Some(DxgiFormat::R8G8B8A8_UInt),       None),
+    gl_format!(GL_RGBA8_SNORM,      GL_RGBA,            GL_BYTE,                         GL_RGBA,            1, Some(DxgiFormat::R8G8B8A8_SNorm),      None),
+    gl_format!(GL_RGBA8I,           GL_RGBA_INTEGER,    GL_BYTE,                         GL_RGBA,            1, Some(DxgiFormat::R8G8B8A8_SInt),       None),
+    gl_format!(GL_RG16F,            GL_RG,              GL_HALF_FLOAT,                   GL_RG,              2, Some(DxgiFormat::R16G16_Float),        Some(D3DFormat::G16R16F)),
+    gl_format!(GL_RG16,             GL_RG,              GL_UNSIGNED_SHORT,               GL_RG,              2, Some(DxgiFormat::R16G16_UNorm),        Some(D3DFormat::G16R16)),
+    gl_format!(GL_RG16UI,           GL_RG_INTEGER,      GL_UNSIGNED_SHORT,               GL_RG,              2, Some(DxgiFormat::R16G16_UInt),         None),
+    gl_format!(GL_RG16_SNORM,       GL_RG,              GL_SHORT,                        GL_RG,              2, Some(DxgiFormat::R16G16_SNorm),        None),
+    gl_format!(GL_RG16I,            GL_RG_INTEGER,      GL_SHORT,                        GL_RG,              2, Some(DxgiFormat::R16G16_SInt),         None),
+    gl_format!(GL_DEPTH_COMPONENT32F, GL_DEPTH_COMPONENT, GL_FLOAT,                      GL_DEPTH_COMPONENT, 4, Some(DxgiFormat::D32_Float),           None),
+    gl_format!(GL_R32F,             GL_RED,             GL_FLOAT,                        GL_RED,             4, Some(DxgiFormat::R32_Float),           Some(D3DFormat::R32F)),
+    gl_format!(GL_R32UI,            GL_RED_INTEGER,     GL_UNSIGNED_INT,                 GL_RED,             4, Some(DxgiFormat::R32_UInt),            None),
+    gl_format!(GL_R32I,             GL_RED_INTEGER,     GL_INT,                          GL_RED,             4, Some(DxgiFormat::R32_SInt),            None),
+    gl_format!(GL_RG8,              GL_RG,              GL_UNSIGNED_BYTE,                GL_RG,              1, Some(DxgiFormat::R8G8_UNorm),          None),
+    gl_format!(GL_RG8UI,            GL_RG_INTEGER,      GL_UNSIGNED_BYTE,                GL_RG,              1, Some(DxgiFormat::R8G8_UInt),           None),
+    gl_format!(GL_RG8_SNORM,        GL_RG,              GL_BYTE,                         GL_RG,              1, Some(DxgiFormat::R8G8_SNorm),          None),
+    gl_format!(GL_RG8I,             GL_RG_INTEGER,      GL_BYTE,                         GL_RG,              1, Some(DxgiFormat::R8G8_SInt),           None),
+    gl_format!(GL_R16F,             GL_RED,             GL_HALF_FLOAT,                   GL_RED,             2, Some(DxgiFormat::R16_Float),           Some(D3DFormat::R16F)),
+    gl_format!(GL_DEPTH_COMPONENT16, GL_DEPTH_COMPONENT, GL_UNSIGNED_SHORT,              GL_DEPTH_COMPONENT, 2, Some(DxgiFormat::D16_UNorm),           None),
+    gl_format!(GL_R16,              GL_RED,             GL_UNSIGNED_SHORT,               GL_RED,             2, Some(DxgiFormat::R16_UNorm),           None),
+    gl_format!(GL_R16UI,            GL_RED_INTEGER,     GL_UNSIGNED_SHORT,               GL_RED,             2, Some(DxgiFormat::R16_UInt),            None),
+    gl_format!(GL_R16_SNORM,        GL_RED,             GL_SHORT,                        GL_RED,             2, Some(DxgiFormat::R16_SNorm),           None),
+    gl_format!(GL_R16I,             GL_RED_INTEGER,     GL_SHORT,                        GL_RED,             2, Some(DxgiFormat::R16_SInt),            None),
+    gl_format!(GL_R8,               GL_RED,             GL_UNSIGNED_BYTE,                GL_RED,             1, Some(DxgiFormat::R8_UNorm),            None),
+    gl_format!(GL_R8UI,             GL_RED_INTEGER,     GL_UNSIGNED_BYTE,                GL_RED,             1, Some(DxgiFormat::R8_UInt),             None),
+    gl_format!(GL_R8_SNORM,         GL_RED,             GL_BYTE,                         GL_RED,             1, Some(DxgiFormat::R8_SNorm),            None),
+    gl_format!(GL_R8I,              GL_RED_INTEGER,     GL_BYTE,                         GL_RED,             1, Some(DxgiFormat::R8_SInt),             None),
+    gl_format!(GL_ALPHA8,           GL_ALPHA,           GL_UNSIGNED_BYTE,                GL_ALPHA,           1, Some(DxgiFormat::A8_UNorm),            Some(D3DFormat::A8)),
+    gl_format!(GL_RGB9_E5,          GL_RGB,             GL_UNSIGNED_INT_5_9_9_9_REV,     GL_RGB,             4, Some(DxgiFormat::R9G9B9E5_SharedExp),  None),
+    gl_format!(GL_RGB565,           GL_RGB,             GL_UNSIGNED_SHORT_5_6_5,         GL_RGB,             2, Some(DxgiFormat::B5G6R5_UNorm),        Some(D3DFormat::R5G6B5)),
+    gl_format!(GL_RGB5_A1,          GL_BGRA,            GL_UNSIGNED_SHORT_1_5_5_5_REV,   GL_RGBA,            2, Some(DxgiFormat::B5G5R5A1_UNorm),      Some(D3DFormat::A1R5G5B5)),
+    gl_format!(GL_RGBA8,            GL_BGRA,            GL_UNSIGNED_BYTE,                GL_RGBA,            1, Some(DxgiFormat::B8G8R8A8_UNorm),      Some(D3DFormat::A8R8G8B8)),
+    gl_format!(GL_SRGB8_ALPHA8,     GL_BGRA,            GL_UNSIGNED_BYTE,                GL_RGBA,            1, Some(DxgiFormat::B8G8R8A8_UNorm_sRGB), None),
+    gl_format!(GL_RGB8,             GL_BGRA,            GL_UNSIGNED_BYTE,                GL_RGB,             1, Some(DxgiFormat::B8G8R8X8_UNorm),      Some(D3DFormat::X8R8G8B8)),
+    gl_format!(GL_SRGB8,            GL_BGRA,            GL_UNSIGNED_BYTE,                GL_RGB,             1, Some(DxgiFormat::B8G8R8X8_UNorm_sRGB), None),
+    gl_format!(GL_RGBA4,            GL_BGRA,            GL_UNSIGNED_SHORT_4_4_4_4_REV,   GL_RGBA,            2, Some(DxgiFormat::B4G4R4A4_UNorm),      Some(D3DFormat::A4R4G4B4)),
+    gl_format!(GL_RGBA4,            GL_RGBA,            GL_UNSIGNED_SHORT_4_4_4_4,       GL_RGBA,            2, Some(DxgiFormat::A4B4G4R4_UNorm),      None),
+
+    gl_format!(GL_COMPRESSED_RGBA_S3TC_DXT1_EXT,        0, 0, GL_RGBA, 1, Some(DxgiFormat::BC1_UNorm),      Some(D3DFormat::DXT1)),
+    gl_format!(GL_COMPRESSED_SRGB_ALPHA_S3TC_DXT1_EXT,  0, 0, GL_RGBA, 1, Some(DxgiFormat::BC1_UNorm_sRGB), None),
+    gl_format!(GL_COMPRESSED_RGBA_S3TC_DXT3_EXT,        0, 0, GL_RGBA, 1, Some(DxgiFormat::BC2_UNorm),      Some(D3DFormat::DXT3)),
+    gl_format!(GL_COMPRESSED_SRGB_ALPHA_S3TC_DXT3_EXT,  0, 0, GL_RGBA, 1, Some(DxgiFormat::BC2_UNorm_sRGB), None),
+    gl_format!(GL_COMPRESSED_RGBA_S3TC_DXT5_EXT,        0, 0, GL_RGBA, 1, Some(DxgiFormat::BC3_UNorm),      Some(D3DFormat::DXT5)),
+    gl_format!(GL_COMPRESSED_SRGB_ALPHA_S3TC_DXT5_EXT,  0, 0, GL_RGBA, 1, Some(DxgiFormat::BC3_UNorm_sRGB), None),
+    gl_format!(GL_COMPRESSED_RED_RGTC1,                 0, 0, GL_RED,  1, Some(DxgiFormat::BC4_UNorm),      None),
+    gl_format!(GL_COMPRESSED_SIGNED_RED_RGTC1,          0, 0, GL_RED,  1, Some(DxgiFormat::BC4_SNorm),      None),
+    gl_format!(GL_COMPRESSED_RG_RGTC2,                  0, 0, GL_RG,   1, Some(DxgiFormat::BC5_UNorm),      None),
+    gl_format!(GL_COMPRESSED_SIGNED_RG_RGTC2,           0, 0, GL_RG,   1, Some(DxgiFormat::BC5_SNorm),      None),
+    gl_format!(GL_COMPRESSED_RGB_BPTC_UNSIGNED_FLOAT,   0, 0, GL_RGB,  1, Some(DxgiFormat::BC6H_UF16),      None),
+    gl_format!(GL_COMPRESSED_RGB_BPTC_SIGNED_FLOAT,     0, 0, GL_RGB,  1, Some(DxgiFormat::BC6H_SF16),      None),
+    gl_format!(GL_COMPRESSED_RGBA_BPTC_UNORM,           0, 0, GL_RGBA, 1, Some(DxgiFormat::BC7_UNorm),      None),
+    gl_format!(GL_COMPRESSED_SRGB_ALPHA_BPTC_UNORM,     0, 0, GL_RGBA, 1, Some(DxgiFormat::BC7_UNorm_sRGB), None),
+
+    // Legacy formats with no DXGI equivalent
+    gl_format!(GL_RGB8,             GL_RGBA,            GL_UNSIGNED_BYTE,                GL_RGB,             1, None, Some(D3DFormat::X8B8G8R8)),
+    gl_format!(GL_RGB10_A2,         GL_BGRA,            GL_UNSIGNED_INT_2_10_10_10_REV,  GL_RGBA,            4, None, Some(D3DFormat::A2R10G10B10)),
+    gl_format!(GL_RGB8,             GL_BGR,             GL_UNSIGNED_BYTE,                GL_RGB,             1, None, Some(D3DFormat::R8G8B8)),
+    gl_format!(GL_RGB5,             GL_BGRA,            GL_UNSIGNED_SHORT_1_5_5_5_REV,   GL_RGB,             2, None, Some(D3DFormat::X1R5G5B5)),
+    gl_format!(GL_RGB4,             GL_BGRA,            GL_UNSIGNED_SHORT_4_4_4_4_REV,   GL_RGB,             2, None, Some(D3DFormat::X4R4G4B4)),
+    gl_format!(GL_LUMINANCE8_ALPHA8, GL_LUMINANCE_ALPHA, GL_UNSIGNED_BYTE,               GL_LUMINANCE_ALPHA, 1, None, Some(D3DFormat::A8L8)),
+    gl_format!(GL_LUMINANCE16,      GL_LUMINANCE,       GL_UNSIGNED_SHORT,               GL_LUMINANCE,       2, None, Some(D3DFormat::L16)),
+    gl_format!(GL_LUMINANCE8,       GL_LUMINANCE,       GL_UNSIGNED_BYTE,                GL_LUMINANCE,       1, None, Some(D3DFormat::L8)),
+];
@@ -0,0 +1,812 @@
+// The MIT License (MIT)
+//
+// Copyright (c) 2018 Michael Dilger
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+use crate::error::*;
+use crate::{
+    AlphaMode, Caps2, CountingReader, D3D10ResourceDimension, DataFormat, Dds, DxgiFormat,
+    NewDxgiParams,
+};
+use byteorder::{LittleEndian, WriteBytesExt};
+use std::io::{Read, Write};
+
+// «KTX 20»\r\n\x1A\n
+const IDENTIFIER: [u8; 12] = [
+    0xAB, 0x4B, 0x54, 0x58, 0x20, 0x32, 0x30, 0xBB, 0x0D, 0x0A, 0x1A, 0x0A,
+];
+
+// identifier, header and index
+const HEADER_SIZE: u64 = 80;
+
+// Size of each entry in the level index
+const LEVEL_INDEX_ENTRY_SIZE: u64 = 24;
+
+impl Dds {
+    /// Read a KTX2 file, converting it into a DirectDraw Surface with a DX10
+    /// header.
+    ///
+    /// Supercompressed files are not supported. Texel data is copied as-is, so a
+    /// `KTXorientation` other than the DDS native "rd" (top-left origin) is not
+    /// applied.
+    pub fn read_ktx2<R: Read>(r: R) -> Result<Dds, Error> {
+        let mut r = CountingReader::new(r);
+        let mut identifier = [0_u8; 12];
+        r.fill(&mut identifier)?;
+        if identifier != IDENTIFIER {
+            return Err(Error::BadMagicNumber);
+        }
+
+        let vk_format = r.read_u32_le()?;
+        let _type_size = r.read_u32_le()?;
+        let pixel_width = r.read_u32_le()?;
+        let pixel_height = r.read_u32_le()?;
+        let pixel_depth = r.read_u32_le()?;
+        let layer_count = r.read_u32_le()?;
+        let face_count = r.read_u32_le()?;
+        let level_count = r.read_u32_le()?;
+        let supercompression_scheme = r.read_u32_le()?;
+        let dfd_byte_offset = r.read_u32_le()?;
+        let dfd_byte_length = r.read_u32_le()?;
+        let _kvd_byte_offset = r.read_u32_le()?;
+        let _kvd_byte_length = r.read_u32_le()?;
+        let _sgd_byte_offset = r.read_u64_le()?;
+        let _sgd_byte_length = r.read_u64_le()?;
+
+        if pixel_width == 0 {
+            return Err(Error::InvalidField {
+                field: "pixelWidth",
+                value: 0,
+                offset: Some(20),
+                expected: Expected::Range(1, u32::MAX as u64),
+            });
+        }
+        // Check the level count before trusting it with an allocation
+        let max_levels = 32
+            - pixel_width
+                .max(pixel_height)
+                .max(pixel_depth)
+                .leading_zeros();
+        if level_count > max_levels {
+            return Err(Error::InvalidField {
+                field: "levelCount",
+                value: level_count as u64,
+                offset: Some(40),
+                expected: Expected::Range(0, max_levels as u64),
+            });
+        }
+        // A level count of zero asks the loader to generate mipmaps, but only
+        // the base level is present in the file.
+        let level_count = level_count.max(1);
+        let mut level_index: Vec<(u64, u64)> = Vec::with_capacity(level_count as usize);
+        for _ in 0..level_count {
+            let byte_offset = r.read_u64_le()?;
+            let byte_length = r.read_u64_le()?;
+            let _uncompressed_byte_length = r.read_u64_le()?;
+            level_index.push((byte_offset, byte_length));
+        }
+
+        if supercompression_scheme != 0 {
+            return Err(Error::UnsupportedFormat);
+        }
+        let format = match DxgiFormat::try_from_vk_format(vk_format) {
+            Some(f) => f,
+            None => return Err(Error::UnsupportedFormat),
+        };
+        if face_count != 1 && face_count != 6 {
+            return Err(Error::InvalidField {
+                field: "faceCount",
+                value: face_count as u64,
+                offset: Some(36),
+                expected: Expected::OneOf(&[1, 6]),
+            });
+        }
+        let is_cubemap = face_count == 6;
+        let layers = layer_count.max(1);
+        // DDS cannot hold arrays of volume textures
+        if pixel_depth > 0 && (layers > 1 || is_cubemap) {
+            return Err(Error::UnsupportedFormat);
+        }
+
+        // Everything after the level index, which is where all the offsets point
+        let base = HEADER_SIZE + LEVEL_INDEX_ENTRY_SIZE * level_count as u64;
+        let mut rest: Vec<u8> = Vec::new();
+        r.read_to_end(&mut rest)?;
+        let get_bytes = |offset: u64, length: u64| -> Result<&[u8], Error> {
+            if offset < base {
+                return Err(Error::InvalidField {
+                    field: "byteOffset",
+                    value: offset,
+                    offset: None,
+                    expected: Expected::Description("an offset past the level index"),
+                });
+            }
+            // Both numbers come from the file, so their sum may not fit
+            let bytes = match offset.checked_add(length) {
+                Some(end) => rest.get((offset - base) as usize..(end - base) as usize),
+                None => None,
+            };
+            bytes.ok_or(Error::ShortFile {
+                expected: offset.saturating_add(length),
+                actual: base + rest.len() as u64,
+            })
+        };
+
+        // Every layer and face needs data in every level, so the remaining input
+        // bounds how many there can be before the texture is allocated
+        let images = layers as u64 * face_count as u64 * level_count as u64;
+        if images > rest.len() as u64 {
+            return Err(Error::ShortFile {
+                expected: base + images,
+                actual: base + rest.len() as u64,
+            });
+        }
+
+        // The base level has to hold every image at the size the header gives,
+        // and has to be in the input, before the texture is allocated
+        let (_, base_length) = level_index[0];
+        let base_size = format
+            .get_slice_size(pixel_width, pixel_height.max(1))
+            .and_then(|size| (size as u64).checked_mul(pixel_depth.max(1) as u64))
+            .and_then(|size| size.checked_mul(layers as u64 * face_count as u64));
+        let base_size = match base_size {
+            Some(size) => size,
+            None => return Err(Error::UnsupportedFormat),
+        };
+        if base_length != base_size {
+            return Err(Error::InvalidField {
+                field: "levelIndex.byteLength",
+                value: base_length,
+                offset: Some(HEADER_SIZE + 8),
+                expected: Expected::Range(base_size, base_size),
+            });
+        }
+        if base_length > rest.len() as u64 {
+            return Err(Error::ShortFile {
+                expected: base + base_length,
+                actual: base + rest.len() as u64,
+            });
+        }
+
+        let mut alpha_mode = AlphaMode::Unknown;
+        if dfd_byte_length >= 16 {
+            let dfd = get_bytes(dfd_byte_offset as u64, dfd_byte_length as u64)?;
+            if dfd[15] & KHR_DF_FLAG_ALPHA_PREMULTIPLIED != 0 {
+                alpha_mode = AlphaMode::PreMultiplied;
+            }
+        }
+
+        let (depth, resource_dimension, caps2) = if pixel_depth > 0 {
+            (
+                Some(pixel_depth),
+                D3D10ResourceDimension::Texture3D,
+                Some(Caps2::VOLUME),
+            )
+        } else if pixel_height == 0 {
+            (None, D3D10ResourceDimension::Texture1D, None)
+        } else if is_cubemap {
+            (
+                None,
+                D3D10ResourceDimension::Texture2D,
+                Some(Caps2::CUBEMAP | Caps2::CUBEMAP_ALLFACES),
+            )
+        } else {
+            (None, D3D10ResourceDimension::Texture2D, None)
+        };
+
+        let mut dds = Dds::new_dxgi(NewDxgiParams {
+            height: pixel_height.max(1),
+            width: pixel_width,
+            depth,
+            format,
+            mipmap_levels: Some(level_count),
+            array_layers: Some(layers * face_count),
+            caps2,
+            is_cubemap,
+            resource_dimension,
+            alpha_mode,
+        })?;
+
+        for (level, &(byte_offset, byte_length)) in level_index.iter().enumerate() {
+            let level = level as u32;
+            let image_size = dds.get_mipmap_size(level)? as usize;
+            let expected = (image_size * (layers * face_count) as usize) as u64;
+            if byte_length != expected {
+                return Err(Error::InvalidField {
+                    field: "levelIndex.byteLength",
+                    value: byte_length,
+                    offset: Some(HEADER_SIZE + LEVEL_INDEX_ENTRY_SIZE * level as u64 + 8),
+                    expected: Expected::Range(expected, expected),
+                });
+            }
+            let level_data = get_bytes(byte_offset, byte_length)?;
+            for (layer, image) in level_data.chunks_exact(image_size).enumerate() {
+                dds.get_mut_mipmap_data(layer as u32, level)?
+                    .copy_from_slice(image);
+            }
+        }
+
+        Ok(dds)
+    }
+
+    /// Write as a KTX2 file.
+    ///
+    /// The format must have a `VkFormat` equivalent (see
+    /// `DxgiFormat::to_vk_format()`). No supercompression is applied.
+    pub fn write_ktx2<W: Write>(&self, w: &mut W) -> Result<(), Error> {
+        let format = match self.get_dxgi_format() {
+            Some(f) => f,
+            None => return Err(Error::UnsupportedFormat),
+        };
+        let vk_format = match format.to_vk_format() {
+            Some(vk) => vk,
+            None => return Err(Error::UnsupportedFormat),
+        };
+        let alpha_mode = self.get_alpha_mode();
+        let dfd = match build_dfd(format, alpha_mode) {
+            Some(dfd) => dfd,
+            None => return Err(Error::UnsupportedFormat),
+        };
+
+        let is_1d = match self.header10 {
+            Some(ref h10) => h10.resource_dimension == D3D10ResourceDimension::Texture1D,
+            None => false,
+        };
+        let is_volume = self.is_volume();
+        let faces = if self.is_cubemap() { 6 } else { 1 };
+        let layers = self.get_num_array_layers() / faces;
+        let levels = self.get_num_mipmap_levels();
+
+        let orientation = if is_volume {
+            "rdi"
+        } else if is_1d {
+            "r"
+        } else {
+            "rd"
+        };
+        let writer = format!("ddsfile v{}", env!("CARGO_PKG_VERSION"));
+        let mut kvd: Vec<u8> = Vec::new();
+        // keys must be sorted by their byte values
+        write_key_value(&mut kvd, "KTXorientation", orientation)?;
+        write_key_value(&mut kvd, "KTXwriter", &writer)?;
+
+        let texel_block_size = match format.get_block_size() {
+            Some(bs) => bs,
+            None => match format.get_bits_per_pixel() {
+                Some(bpp) => bpp as u32 / 8,
+                None => return Err(Error::UnsupportedFormat),
+            },
+        };
+        let alignment = lcm(texel_block_size as u64, 4);
+
+        let dfd_byte_offset = HEADER_SIZE + LEVEL_INDEX_ENTRY_SIZE * levels as u64;
+        let kvd_byte_offset = dfd_byte_offset + dfd.len() as u64;
+        let mut offset = kvd_byte_offset + kvd.len() as u64;
+
+        // Levels are stored smallest first
+        let mut level_index: Vec<(u64, u64)> = vec![(0, 0); levels as usize];
+        for level in (0..levels).rev() {
+            let byte_length = self.get_mipmap_size(level)? as u64 * (layers * faces) as u64;
+            offset = offset.div_ceil(alignment) * alignment;
+            level_index[level as usize] = (offset, byte_length);
+            offset += byte_length;
+        }
+
+        w.write_all(&IDENTIFIER)?;
+        w.write_u32::<LittleEndian>(vk_format)?;
+        w.write_u32::<LittleEndian>(get_type_size(format))?;
+        w.write_u32::<LittleEndian>(self.get_width())?;
+        w.write_u32::<LittleEndian>(if is_1d { 0 } else { self.get_height() })?;
+        w.write_u32::<LittleEndian>(if is_volume { self.get_depth() } else { 0 })?;
+        w.write_u32::<LittleEndian>(if layers > 1 { layers } else { 0 })?;
+        w.write_u32::<LittleEndian>(faces)?;
+        w.write_u32::<LittleEndian>(levels)?;
+        w.write_u32::<LittleEndian>(0)?; // supercompressionScheme
+        w.write_u32::<LittleEndian>(dfd_byte_offset as u32)?;
+        w.write_u32::<LittleEndian>(dfd.len() as u32)?;
+        w.write_u32::<LittleEndian>(kvd_byte_offset as u32)?;
+        w.write_u32::<LittleEndian>(kvd.len() as u32)?;
+        w.write_u64::<LittleEndian>(0)?; // sgdByteOffset
+        w.write_u64::<LittleEndian>(0)?; // sgdByteLength
+        for &(byte_offset, byte_length) in &level_index {
+            w.write_u64::<LittleEndian>(byte_offset)?;
+            w.write_u64::<LittleEndian>(byte_length)?;
+            w.write_u64::<LittleEndian>(byte_length)?; // uncompressedByteLength
+        }
+        w.write_all(&dfd)?;
+        w.write_all(&kvd)?;
+
+        let mut position = kvd_byte_offset + kvd.len() as u64;
+        for level in (0..levels).rev() {
+            let (byte_offset, byte_length) = level_index[level as usize];
+            let padding = vec![0_u8; (byte_offset - position) as usize];
+            w.write_all(&padding)?;
+            for layer in 0..layers * faces {
+                w.write_all(self.get_mipmap_data(layer, level)?)?;
+            }
+            position = byte_offset + byte_length;
+        }
+
+        Ok(())
+    }
+}
+
+// Append a key/value pair, padded to four bytes, as in KTX1 and KTX2 files
+pub(crate) fn write_key_value(kvd: &mut Vec<u8>, key: &str, value: &str) -> Result<(), Error> {
+    // both the key and the value are NUL terminated
+    let length = key.len() + value.len() + 2;
+    kvd.write_u32::<LittleEndian>(length as u32)?;
+    kvd.extend_from_slice(key.as_bytes());
+    kvd.push(0);
+    kvd.extend_from_slice(value.as_bytes());
+    kvd.push(0);
+    while !kvd.len().is_multiple_of(4) {
+        kvd.push(0);
+    }
+    Ok(())
+}
+
+fn lcm(a: u64, b: u64) -> u64 {
+    let (mut x, mut y) = (a, b);
+    while y != 0 {
+        (x, y) = (y, x % y);
+    }
+    a / x * b
+}
+
+// The size of the data type that should be byte swapped on big endian machines
+fn get_type_size(format: DxgiFormat) -> u32 {
+    match format {
+        DxgiFormat::R16G16B16A16_Float
+        | DxgiFormat::R16G16B16A16_UNorm
+        | DxgiFormat::R16G16B16A16_UInt
+        | DxgiFormat::R16G16B16A16_SNorm
+        | DxgiFormat::R16G16B16A16_SInt
+        | DxgiFormat::R16G16_Float
+        | DxgiFormat::R16G16_UNorm
+        | DxgiFormat::R16G16_UInt
+        | DxgiFormat::R16G16_SNorm
+        | DxgiFormat::R16G16_SInt
+        | DxgiFormat::R16_Float
+        | DxgiFormat::D16_UNorm
+        | DxgiFormat::R16_UNorm
+        | DxgiFormat::R16_UInt
+        | DxgiFormat::R16_SNorm
+        | DxgiFormat::R16_SInt
+        | DxgiFormat::B5G6R5_UNorm
+        | DxgiFormat::B5G5R5A1_UNorm
+        | DxgiFormat::B4G4R4A4_UNorm
+        | DxgiFormat::A4B4G4R4_UNorm => 2,
+
+        DxgiFormat::R32G32B32A32_Float
+        | DxgiFormat::R32G32B32A32_UInt
+        | DxgiFormat::R32G32B32A32_SInt
+        | DxgiFormat::R32G32B32_Float
+        | DxgiFormat::R32G32B32_UInt
+        | DxgiFormat::R32G32B32_SInt
+        | DxgiFormat::R32G32_Float
+        | DxgiFormat::R32G32_UInt
+        | DxgiFormat::R32G32_SInt
+        | DxgiFormat::R10G10B10A2_UNorm
+        | DxgiFormat::R10G10B10A2_UInt
+        | DxgiFormat::R11G11B10_Float
+        | DxgiFormat::D32_Float
+        | DxgiFormat::R32_Float
+        | DxgiFormat::R32_UInt
+        | DxgiFormat::R32_SInt
+        | DxgiFormat::D24_UNorm_S8_UInt
+        | DxgiFormat::R9G9B9E5_SharedExp => 4,
+
+        // block compressed and 8-bit component formats
+        _ => 1,
+    }
+}
+
+// Data Format Descriptor constants from the Khronos Data Format Specification
+const KHR_DF_MODEL_RGBSDA: u8 = 1;
+const KHR_DF_MODEL_BC1A: u8 = 128;
+const KHR_DF_MODEL_BC2: u8 = 129;
+const KHR_DF_MODEL_BC3: u8 = 130;
+const KHR_DF_MODEL_BC4: u8 = 131;
+const KHR_DF_MODEL_BC5: u8 = 132;
+const KHR_DF_MODEL_BC6H: u8 = 133;
+const KHR_DF_MODEL_BC7: u8 = 134;
+const KHR_DF_PRIMARIES_BT709: u8 = 1;
+const KHR_DF_TRANSFER_LINEAR: u8 = 1;
+const KHR_DF_TRANSFER_SRGB: u8 = 2;
+const KHR_DF_FLAG_ALPHA_PREMULTIPLIED: u8 = 1;
+const KHR_DF_CHANNEL_RED: u8 = 0;
+const KHR_DF_CHANNEL_GREEN: u8 = 1;
+const KHR_DF_CHANNEL_BLUE: u8 = 2;
+const KHR_DF_CHANNEL_STENCIL: u8 = 13;
+const KHR_DF_CHANNEL_DEPTH: u8 = 14;
+const KHR_DF_CHANNEL_ALPHA: u8 = 15;
+const KHR_DF_SAMPLE_DATATYPE_LINEAR: u8 = 0x10;
+const KHR_DF_SAMPLE_DATATYPE_EXPONENT: u8 = 0x20;
+const KHR_DF_SAMPLE_DATATYPE_SIGNED: u8 = 0x40;
+const KHR_DF_SAMPLE_DATATYPE_FLOAT: u8 = 0x80;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum SampleType {
+    UNorm,
+    SNorm,
+    UInt,
+    SInt,
+    SFloat,
+    UFloat,
+}
+
+impl SampleType {
+    // Gets the qualifier bits and the sampleLower and sampleUpper values
+    fn get_range(self, bits: u32) -> (u8, u32, u32) {
+        // compressed samples span whole blocks but use the full 32-bit range
+        let bits = bits.min(32);
+        match self {
+            SampleType::UNorm => (0, 0, u32::MAX >> (32 - bits)),
+            SampleType::SNorm => {
+                let max = u32::MAX >> (33 - bits);
+                (KHR_DF_SAMPLE_DATATYPE_SIGNED, max.wrapping_neg(), max)
+            }
+            SampleType::UInt => (0, 0, 1),
+            SampleType::SInt => (KHR_DF_SAMPLE_DATATYPE_SIGNED, u32::MAX, 1),
+            SampleType::SFloat => (
+                KHR_DF_SAMPLE_DATATYPE_SIGNED | KHR_DF_SAMPLE_DATATYPE_FLOAT,
+                (-1.0_f32).to_bits(),
+                1.0_f32.to_bits(),
+            ),
+            SampleType::UFloat => (KHR_DF_SAMPLE_DATATYPE_FLOAT, 0, 1.0_f32.to_bits()),
+        }
+    }
+}
+
+struct Sample {
+    channel: u8,
+    bit_offset: u32,
+    bit_length: u32,
+    sample_type: SampleType,
+}
+
+// Builds the Data Format Descriptor (including the dfdTotalSize field) that
+// describes the given format, if it can be described.
+fn build_dfd(format: DxgiFormat, alpha_mode: AlphaMode) -> Option<Vec<u8>> {
+    use self::SampleType::*;
+    const R: u8 = KHR_DF_CHANNEL_RED;
+    const G: u8 = KHR_DF_CHANNEL_GREEN;
+    const B: u8 = KHR_DF_CHANNEL_BLUE;
+    const A: u8 = KHR_DF_CHANNEL_ALPHA;
+    const D: u8 = KHR_DF_CHANNEL_DEPTH;
+
+    let srgb = matches!(
+        format,
+        DxgiFormat::R8G8B8A8_UNorm_sRGB
+            | DxgiFormat::B8G8R8A8_UNorm_sRGB
+            | DxgiFormat::BC1_UNorm_sRGB
+            | DxgiFormat::BC2_UNorm_sRGB
+            | DxgiFormat::BC3_UNorm_sRGB
+            | DxgiFormat::BC7_UNorm_sRGB
+    );
+
+    let (color_model, samples): (u8, Vec<Sample>) = if format.get_block_size().is_some() {
+        // Channel ids of compressed color models are specific to the model
+        let (color_model, channels, sample_type): (u8, &[(u8, u32, u32)], SampleType) = match format
+        {
+            DxgiFormat::BC1_UNorm | DxgiFormat::BC1_UNorm_sRGB => {
+                // BC1A alpha present channel
+                (KHR_DF_MODEL_BC1A, &[(1, 0, 64)], UNorm)
+            }
+            DxgiFormat::BC2_UNorm | DxgiFormat::BC2_UNorm_sRGB => {
+                (KHR_DF_MODEL_BC2, &[(A, 0, 64), (0, 64, 64)], UNorm)
+            }
+            DxgiFormat::BC3_UNorm | DxgiFormat::BC3_UNorm_sRGB => {
+                (KHR_DF_MODEL_BC3, &[(A, 0, 64), (0, 64, 64)], UNorm)
+            }
+            DxgiFormat::BC4_UNorm => (KHR_DF_MODEL_BC4, &[(0, 0, 64)], UNorm),
+            DxgiFormat::BC4_SNorm => (KHR_DF_MODEL_BC4, &[(0, 0, 64)], SNorm),
+            DxgiFormat::BC5_UNorm => (KHR_DF_MODEL_BC5, &[(0, 0, 64), (1, 64, 64)], UNorm),
+            DxgiFormat::BC5_SNorm => (KHR_DF_MODEL_BC5, &[(0, 0, 64), (1, 64, 64)], SNorm),
+            DxgiFormat::BC6H_UF16 => (KHR_DF_MODEL_BC6H, &[(0, 0, 128)], UFloat),
+            DxgiFormat::BC6H_SF16 => (KHR_DF_MODEL_BC6H, &[(0, 0, 128)], SFloat),
+            DxgiFormat::BC7_UNorm | DxgiFormat::BC7_UNorm_sRGB => {
+                (KHR_DF_MODEL_BC7, &[(0, 0, 128)], UNorm)
+            }
+            _ => return None,
+        };
+        let samples = channels
+            .iter()
+            .map(|&(channel, bit_offset, bit_length)| Sample {
+                channel,
+                bit_offset,
+                bit_length,
+                sample_type,
+            })
+            .collect();
+        (color_model, samples)
+    } else {
+        // Channels and their widths, from the least significant bit up
+        let channels: &[(u8, u32)] = match format {
+            DxgiFormat::R32G32B32A32_Float
+            | DxgiFormat::R32G32B32A32_UInt
+            | DxgiFormat::R32G32B32A32_SInt => &[(R, 32), (G, 32), (B, 32), (A, 32)],
+            DxgiFormat::R32G32B32_Float
+            | DxgiFormat::R32G32B32_UInt
+            | DxgiFormat::R32G32B32_SInt => &[(R, 32), (G, 32), (B, 32)],
+            DxgiFormat::R16G16B16A16_Float
+            | DxgiFormat::R16G16B16A16_UNorm
+            | DxgiFormat::R16G16B16A16_UInt
+            | DxgiFormat::R16G16B16A16_SNorm
+            | DxgiFormat::R16G16B16A16_SInt => &[(R, 16), (G, 16), (B, 16), (A, 16)],
+            DxgiFormat::R32G32_Float | DxgiFormat::R32G32_UInt | DxgiFormat::R32G32_SInt => {
+                &[(R, 32), (G, 32)]
+            }
+            DxgiFormat::R10G10B10A2_UNorm | DxgiFormat::R10G10B10A2_UInt => {
+                &[(R, 10), (G, 10), (B, 10), (A, 2)]
+            }
+            DxgiFormat::R11G11B10_Float => &[(R, 11), (G, 11), (B, 10)],
+            DxgiFormat::R8G8B8A8_UNorm
+            | DxgiFormat::R8G8B8A8_UNorm_sRGB
+            | DxgiFormat::R8G8B8A8_UInt
+            | DxgiFormat::R8G8B8A8_SNorm
+            | DxgiFormat::R8G8B8A8_SInt => &[(R, 8), (G, 8), (B, 8), (A, 8)],
+            DxgiFormat::R16G16_Float
+            | DxgiFormat::R16G16_UNorm
+            | DxgiFormat::R16G16_UInt
+            | DxgiFormat::R16G16_SNorm
+            | DxgiFormat::R16G16_SInt => &[(R, 16), (G, 16)],
+            DxgiFormat::D32_Float => &[(D, 32)],
+            DxgiFormat::R32_Float | DxgiFormat::R32_UInt | DxgiFormat::R32_SInt => &[(R, 32)],
+            DxgiFormat::R8G8_UNorm
+            | DxgiFormat::R8G8_UInt
+            | DxgiFormat::R8G8_SNorm
+            | DxgiFormat::R8G8_SInt => &[(R, 8), (G, 8)],
+            DxgiFormat::D16_UNorm => &[(D, 16)],
+            DxgiFormat::R16_Float
+            | DxgiFormat::R16_UNorm
+            | DxgiFormat::R16_UInt
+            | DxgiFormat::R16_SNorm
+            | DxgiFormat::R16_SInt => &[(R, 16)],
+            DxgiFormat::R8_UNorm
+            | DxgiFormat::R8_UInt
+            | DxgiFormat::R8_SNorm
+            | DxgiFormat::R8_SInt => &[(R, 8)],
+            DxgiFormat::A8_UNorm => &[(A, 8)],
+            DxgiFormat::B5G6R5_UNorm => &[(B, 5), (G, 6), (R, 5)],
+            DxgiFormat::B5G5R5A1_UNorm => &[(B, 5), (G, 5), (R, 5), (A, 1)],
+            DxgiFormat::B8G8R8A8_UNorm | DxgiFormat::B8G8R8A8_UNorm_sRGB => {
+                &[(B, 8), (G, 8), (R, 8), (A, 8)]
+            }
+            DxgiFormat::B4G4R4A4_UNorm => &[(B, 4), (G, 4), (R, 4), (A, 4)],
+            DxgiFormat::A4B4G4R4_UNorm => &[(A, 4), (B, 4), (G, 4), (R, 4)],
+            DxgiFormat::D24_UNorm_S8_UInt => {
+                return Some(encode_dfd(
+                    KHR_DF_MODEL_RGBSDA,
+                    KHR_DF_TRANSFER_LINEAR,
+                    0,
+                    1,
+                    4,
+                    &[
+                        Sample {
+                            channel: D,
+                            bit_offset: 0,
+                            bit_length: 24,
+                            sample_type: UNorm,
+                        },
+                        Sample {
+                            channel: KHR_DF_CHANNEL_STENCIL,
+                            bit_offset: 24,
+                            bit_length: 8,
+                            sample_type: UInt,
+                        },
+                    ],
+                ));
+            }
+            DxgiFormat::R9G9B9E5_SharedExp => return Some(build_shared_exponent_dfd()),
+            _ => return None,
+        };
+        let sample_type = match format {
+            DxgiFormat::R32G32B32A32_Float
+            | DxgiFormat::R32G32B32_Float
+            | DxgiFormat::R16G16B16A16_Float
+            | DxgiFormat::R32G32_Float
+            | DxgiFormat::R16G16_Float
+            | DxgiFormat::D32_Float
+            | DxgiFormat::R32_Float
+            | DxgiFormat::R16_Float => SFloat,
+            DxgiFormat::R11G11B10_Float => UFloat,
+            DxgiFormat::R32G32B32A32_UInt
+            | DxgiFormat::R32G32B32_UInt
+            | DxgiFormat::R16G16B16A16_UInt
+            | DxgiFormat::R32G32_UInt
+            | DxgiFormat::R10G10B10A2_UInt
+            | DxgiFormat::R8G8B8A8_UInt
+            | DxgiFormat::R16G16_UInt
+            | DxgiFormat::R32_UInt
+            | DxgiFormat::R8G8_UInt
+            | DxgiFormat::R16_UInt
+            | DxgiFormat::R8_UInt => UInt,
+            DxgiFormat::R32G32B32A32_SInt
+            | DxgiFormat::R32G32B32_SInt
+            | DxgiFormat::R16G16B16A16_SInt
+            | DxgiFormat::R32G32_SInt
+            | DxgiFormat::R8G8B8A8_SInt
+            | DxgiFormat::R16G16_SInt
+            | DxgiFormat::R32_SInt
+            | DxgiFormat::R8G8_SInt
+            | DxgiFormat::R16_SInt
+            | DxgiFormat::R8_SInt => SInt,
+            DxgiFormat::R16G16B16A16_SNorm
+            | DxgiFormat::R8G8B8A8_SNorm
+            | DxgiFormat::R16G16_SNorm
+            | DxgiFormat::R8G8_SNorm
+            | DxgiFormat::R16_SNorm
+            | DxgiFormat::R8_SNorm => SNorm,
+            _ => UNorm,
+        };
+        let mut bit_offset = 0;
+        let samples = channels
+            .iter()
+            .map(|&(channel, bit_length)| {
+                let sample = Sample {
+                    channel,
+                    bit_offset,
+                    bit_length,
+                    sample_type,
+                };
+                bit_offset += bit_length;
+                sample
+            })
+            .collect();
+        (KHR_DF_MODEL_RGBSDA, samples)
+    };
+
+    let (block_dimension, bytes_plane) = match format.get_block_size() {
+        Some(bs) => (4, bs),
+        None => (1, format.get_bits_per_pixel()? as u32 / 8),
+    };
+    let transfer = if srgb {
+        KHR_DF_TRANSFER_SRGB
+    } else {
+        KHR_DF_TRANSFER_LINEAR
+    };
+    let flags = if alpha_mode == AlphaMode::PreMultiplied {
+        KHR_DF_FLAG_ALPHA_PREMULTIPLIED
+    } else {
+        0
+    };
+    Some(encode_dfd(
+        color_model,
+        transfer,
+        flags,
+        block_dimension,
+        bytes_plane,
+        &samples,
+    ))
+}
+
+// The shared exponent is described by an extra sample per channel
+fn build_shared_exponent_dfd() -> Vec<u8> {
+    let mut samples: Vec<u8> = Vec::new();
+    for (i, channel) in [
+        KHR_DF_CHANNEL_RED,
+        KHR_DF_CHANNEL_GREEN,
+        KHR_DF_CHANNEL_BLUE,
+    ]
+    .iter()
+    .enumerate()
+    {
+        // mantissa
+        write_sample(&mut samples, *channel, 0, 9 * i as u32, 9, 0, 8448);
+        // exponent
+        write_sample(
+            &mut samples,
+            *channel,
+            KHR_DF_SAMPLE_DATATYPE_EXPONENT,
+            27,
+            5,
+            15,
+            31,
+        );
+    }
+    encode_dfd_raw(
+        KHR_DF_MODEL_RGBSDA,
+        KHR_DF_TRANSFER_LINEAR,
+        0,
+        1,
+        4,
+        &samples,
+    )
+}
+
+fn encode_dfd(
+    color_model: u8,
+    transfer: u8,
+    flags: u8,
+    block_dimension: u8,
+    bytes_plane: u32,
+    samples: &[Sample],
+) -> Vec<u8> {
+    let mut encoded: Vec<u8> = Vec::new();
+    for sample in samples {
+        let (mut qualifiers, lower, upper) = sample.sample_type.get_range(sample.bit_length);
+        // alpha is never sRGB encoded
+        if transfer == KHR_DF_TRANSFER_SRGB && sample.channel == KHR_DF_CHANNEL_ALPHA {
+            qualifiers |= KHR_DF_SAMPLE_DATATYPE_LINEAR;
+        }
+        write_sample(
+            &mut encoded,
+            sample.channel,
+            qualifiers,
+            sample.bit_offset,
+            sample.bit_length,
+            lower,
+            upper,
+        );
+    }
+    encode_dfd_raw(
+        color_model,
+        transfer,
+        flags,
+        block_dimension,
+        bytes_plane,
+        &encoded,
+    )
+}
+
+fn write_sample(
+    out: &mut Vec<u8>,
+    channel: u8,
+    qualifiers: u8,
+    bit_offset: u32,
+    bit_length: u32,
+    lower: u32,
+    upper: u32,
+) {
+    out.extend_from_slice(&(bit_offset as u16).to_le_bytes());
+    out.push((bit_length - 1) as u8);
+    out.push(channel | qualifiers);
+    out.extend_from_slice(&[0, 0, 0, 0]); // samplePosition
+    out.extend_from_slice(&lower.to_le_bytes());
+    out.extend_from_slice(&upper.to_le_bytes());
+}
+
+fn encode_dfd_raw(
+    color_model: u8,
+    transfer: u8,
+    flags: u8,
+    block_dimension: u8,
+    bytes_plane: u32,
+    samples: &[u8],
+) -> Vec<u8> {
+    let block_size = 24 + samples.len();
+    let mut dfd: Vec<u8> = Vec::with_capacity(4 + block_size);
+    dfd.extend_from_slice(&((4 + block_size) as u32).to_le_bytes()); // dfdTotalSize
+    dfd.extend_from_slice(&0_u32.to_le_bytes()); // vendorId and descriptorType
+    dfd.extend_from_slice(&2_u16.to_le_bytes()); // versionNumber
+    dfd.extend_from_slice(&(block_size as u16).to_le_bytes());
+    dfd.push(color_model);
+    dfd.push(KHR_DF_PRIMARIES_BT709);
+    dfd.push(transfer);
+    dfd.push(flags);
+    // texelBlockDimension0..3, each stored as one less than the value
+    dfd.extend_from_slice(&[block_dimension - 1, block_dimension - 1, 0, 0]);
+    // bytesPlane0..7
+    dfd.extend_from_slice(&[bytes_plane as u8, 0, 0, 0, 0, 0, 0, 0]);
+    dfd.extend_from_slice(samples);
+    dfd
+}
@@ -25,14 +25,17 @@
 #[macro_use]
 extern crate bitflags;
 
-//#[cfg(test)]
-//mod tests;
+#[cfg(test)]
+mod tests;
 
 mod error;
 pub use error::*;
 
 mod format;
-pub use format::{D3DFormat, DataFormat, DxgiFormat, FourCC, PixelFormat, PixelFormatFlags};
+pub use format::{
+    Channel, ChannelInfo, ComponentType, D3DFormat, DataFormat, DxgiFormat, FormatInfo, FourCC,
+    PixelFormat, PixelFormatFlags, PlaneLayout,
+};
 
 mod header;
 pub use header::{Caps, Caps2, Header, HeaderFlags};
@@ -40,9 +43,54 @@ pub use header::{Caps, Caps2, Header, HeaderFlags};
 mod header10;
 pub use header10::{AlphaMode, D3D10ResourceDimension, Header10, MiscFlag};
 
-use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+mod alpha;
+
+#[cfg(feature = "image")]
+mod atlas;
+#[cfg(feature = "image")]
+pub use atlas::{Atlas, AtlasBuilder, AtlasEntry};
+
+mod assemble;
+pub use assemble::Assembly;
+
+mod blocks;
+pub use blocks::{Block, BlockMut, BlockRegion};
+
+#[cfg(feature = "image")]
+mod builder;
+#[cfg(feature = "image")]
+pub use builder::{Mipmaps, TextureBuilder};
+
+mod channels;
+pub use channels::ChannelSource;
+
+mod codec;
+
+mod compare;
+pub use compare::{Comparison, HeaderDifference, SubresourceMetrics};
+
+#[cfg(feature = "export")]
+mod export;
+#[cfg(feature = "export")]
+pub use export::ExportFormat;
+
+#[cfg(feature = "image")]
+mod image_interop;
+
+mod ktx1;
+
+mod ktx2;
+
+mod transform;
+pub use transform::Rotation;
+
+mod yuv;
+pub use yuv::{YuvMatrix, YuvRange};
+
+use byteorder::{LittleEndian, WriteBytesExt};
 use std::fmt;
 use std::io::{Read, Write};
+use std::ops::Range;
 
 /// This is the main DirectDraw Surface file structure
 #[derive(Clone)]
@@ -82,27 +130,28 @@ pub struct NewDxgiParams {
 impl Dds {
     const MAGIC: u32 = 0x20534444; // b"DDS " in little endian
 
-    /// Create a new DirectDraw Surface with a D3DFormat
+    /// Create a new DirectDraw Surface with a D3DFormat. When `caps2` contains
+    /// `CUBEMAP`, data is allocated for all six faces.
     pub fn new_d3d(params: NewD3dParams) -> Result<Dds, Error> {
-        let size = match get_texture_size(
-            params.format.get_pitch(params.width),
-            None,
-            params.format.get_pitch_height(),
+        let array_stride = match get_mipmap_chain_size(
+            &params.format,
+            params.width,
             params.height,
             params.depth,
+            params.mipmap_levels.unwrap_or(1),
         ) {
             Some(s) => s,
             None => return Err(Error::UnsupportedFormat),
         };
 
-        let mml = params.mipmap_levels.unwrap_or(1);
-        let min_mipmap_size = match params.format.get_minimum_mipmap_size_in_bytes() {
-            Some(mms) => mms,
+        let faces = match params.caps2 {
+            Some(c2) if c2.contains(Caps2::CUBEMAP) => 6,
+            _ => 1,
+        };
+        let data_size = match array_stride.checked_mul(faces) {
+            Some(s) => s,
             None => return Err(Error::UnsupportedFormat),
         };
-        let array_stride = get_array_stride(size, min_mipmap_size, mml);
-
-        let data_size = array_stride;
 
         Ok(Dds {
             header: Header::new_d3d(
@@ -122,25 +171,21 @@ impl Dds {
     pub fn new_dxgi(params: NewDxgiParams) -> Result<Dds, Error> {
         let arraysize = params.array_layers.unwrap_or(1);
 
-        let size = match get_texture_size(
-            params.format.get_pitch(params.width),
-            None,
-            params.format.get_pitch_height(),
+        let array_stride = match get_mipmap_chain_size(
+            &params.format,
+            params.width,
             params.height,
             params.depth,
+            params.mipmap_levels.unwrap_or(1),
         ) {
             Some(s) => s,
             None => return Err(Error::UnsupportedFormat),
         };
 
-        let mml = params.mipmap_levels.unwrap_or(1);
-        let min_mipmap_size = match params.format.get_minimum_mipmap_size_in_bytes() {
-            Some(mms) => mms,
+        let data_size = match array_stride.checked_mul(arraysize) {
+            Some(s) => s,
             None => return Err(Error::UnsupportedFormat),
         };
-        let array_stride = get_array_stride(size, min_mipmap_size, mml);
-
-        let data_size = arraysize * array_stride;
 
         let arraysize = if params.is_cubemap {
             arraysize / 6
@@ -170,17 +215,56 @@ impl Dds {
         })
     }
 
+    /// Replace a legacy header with a DX10 header holding the equivalent
+    /// DxgiFormat, leaving the data untouched. A file that already has a DX10
+    /// header is left as it is.
+    pub fn upgrade_to_dx10(&mut self) -> Result<(), Error> {
+        if self.header10.is_some() {
+            return Ok(());
+        }
+        let format = match self
+            .get_d3d_format()
+            .and_then(|f| f.to_dxgi_format())
+            .or_else(|| self.get_dxgi_format())
+        {
+            Some(f) => f,
+            None => return Err(Error::UnsupportedFormat),
+        };
+        let resource_dimension = if self.is_volume() {
+            D3D10ResourceDimension::Texture3D
+        } else {
+            D3D10ResourceDimension::Texture2D
+        };
+        // DXT2 and DXT4 become BC2 and BC3, declared premultiplied
+        let alpha_mode = self.get_alpha_mode();
+        self.header10 = Some(Header10::new(
+            format,
+            self.is_cubemap(),
+            resource_dimension,
+            1,
+            alpha_mode,
+        ));
+        self.header.spf = From::from(format);
+        Ok(())
+    }
+
     /// Read a DDS file
-    pub fn read<R: Read>(mut r: R) -> Result<Dds, Error> {
-        let magic = r.read_u32::<LittleEndian>()?;
+    pub fn read<R: Read>(r: R) -> Result<Dds, Error> {
+        let mut r = CountingReader::new(r);
+        let magic = r.read_u32_le()?;
         if magic != Self::MAGIC {
             return Err(Error::BadMagicNumber);
         }
 
-        let header = Header::read(&mut r)?;
+        // Read the headers in full first, so a truncated file is reported as such
+        let mut buf = [0_u8; 124];
+        r.fill(&mut buf)?;
+        let header = Header::read(&buf[..])?;
 
         let header10 = if header.spf.fourcc == Some(FourCC(<FourCC>::DX10)) {
-            Some(Header10::read(&mut r)?)
+            let mut buf = [0_u8; 20];
+            r.fill(&mut buf)?;
+            Some(Header10::read(&buf[..])?)
         } else {
             None
         };
@@ -239,6 +323,19 @@ impl Dds {
         }
     }
 
+    /// Get the channels, component types and capabilities of the format of the
+    /// DDS, if known. A legacy header is described by its D3DFormat, which
+    /// covers formats such as luminance that have no DxgiFormat equivalent.
+    pub fn get_format_info(&self) -> Option<FormatInfo> {
+        if let Some(ref h10) = self.header10 {
+            h10.dxgi_format.get_format_info()
+        } else if let Some(d3d) = self.get_d3d_format() {
+            d3d.get_format_info()
+        } else {
+            self.get_format()?.get_format_info()
+        }
+    }
+
     pub fn get_width(&self) -> u32 {
         self.header.width
     }
@@ -251,6 +348,24 @@ impl Dds {
         self.header.depth.unwrap_or(1)
     }
 
+    /// Returns true if this is a cubemap (or an array of cubemaps)
+    pub fn is_cubemap(&self) -> bool {
+        if let Some(ref h10) = self.header10 {
+            h10.misc_flag.contains(MiscFlag::TEXTURECUBE)
+        } else {
+            self.header.caps2.contains(Caps2::CUBEMAP)
+        }
+    }
+
+    /// Returns true if this is a volume (3D) texture
+    pub fn is_volume(&self) -> bool {
+        if let Some(ref h10) = self.header10 {
+            h10.resource_dimension == D3D10ResourceDimension::Texture3D
+        } else {
+            self.header.caps2.contains(Caps2::VOLUME)
+        }
+    }
+
     pub fn get_bits_per_pixel(&self) -> Option<u32> {
         // Try format first
         if let Some(format) = self.get_format() {
@@ -279,7 +394,7 @@ impl Dds {
 
         // Then try to calculate it ourselves
         if let Some(bpp) = self.get_bits_per_pixel() {
-            return Some((bpp * self.get_width() + 7) / 8);
+            return Some((bpp * self.get_width()).div_ceil(8));
         }
         None
     }
@@ -293,6 +408,14 @@ impl Dds {
     }
 
     pub fn get_main_texture_size(&self) -> Option<u32> {
+        // Planar formats hold more than one pitch worth of bytes per row
+        if self.header.linear_size.is_none() {
+            if let Some(format) = self.get_format() {
+                if let Some(size) = format.get_slice_size(self.header.width, self.header.height) {
+                    return size.checked_mul(self.header.depth.unwrap_or(1));
+                }
+            }
+        }
         get_texture_size(
             self.get_pitch(),
             self.header.linear_size,
@@ -302,19 +425,60 @@ impl Dds {
         )
     }
 
-    pub fn get_array_stride(&self) -> Result<u32, Error> {
-        let size = match self.get_main_texture_size() {
+    /// This gets the size in bytes of the given mipmap level of a single array
+    /// layer. For volume textures this includes every depth slice of that level.
+    pub fn get_mipmap_size(&self, mipmap_level: u32) -> Result<u32, Error> {
+        if mipmap_level >= self.get_num_mipmap_levels() {
+            return Err(Error::OutOfBounds);
+        }
+        if let Some(format) = self.get_format() {
+            if let Some(size) = get_mipmap_size(
+                &*format,
+                self.header.width,
+                self.header.height,
+                self.header.depth,
+                mipmap_level,
+            ) {
+                return Ok(size);
+            }
+        }
+
+        // Without a usable format, work down from the main texture size
+        let mut size = match self.get_main_texture_size() {
             Some(s) => s,
             None => return Err(Error::UnsupportedFormat),
         };
-        let mml = self.get_num_mipmap_levels();
         let min_mipmap_size = self.get_min_mipmap_size_in_bytes();
-        Ok(get_array_stride(size, min_mipmap_size, mml))
+        for _ in 0..mipmap_level {
+            size = (size / 4).max(min_mipmap_size);
+        }
+        Ok(size)
     }
 
+    /// This gets the size in bytes of a single array layer, the sum of the
+    /// sizes of its mipmap levels (see `get_mipmap_size`)
+    pub fn get_array_stride(&self) -> Result<u32, Error> {
+        let mut stride: u32 = 0;
+        for mipmap_level in 0..self.get_num_mipmap_levels() {
+            stride = match stride.checked_add(self.get_mipmap_size(mipmap_level)?) {
+                Some(s) => s,
+                None => return Err(Error::UnsupportedFormat),
+            };
+        }
+        Ok(stride)
+    }
+
+    /// The number of array layers, counting each face of a cubemap as a
+    /// layer: six per cube in `array_size` with a DX10 header, and six for a
+    /// legacy cubemap
     pub fn get_num_array_layers(&self) -> u32 {
         if let Some(ref h10) = self.header10 {
-            h10.array_size
+            // array_size counts whole cubes, but each face is its own layer
+            if self.is_cubemap() {
+                h10.array_size * 6
+            } else {
+                h10.array_size
+            }
         } else if self.header.caps2.contains(Caps2::CUBEMAP) {
             6
         } else {
@@ -322,14 +486,20 @@ impl Dds {
         }
     }
 
-    pub fn get_num_mipmap_levels(&self) -> u32 {
-        if let Some(mmc) = self.header.mip_map_count {
-            mmc
-        } else {
-            1 // just the main image
+    /// The byte offset of the texel data within the DDS file, past the magic
+    /// number and headers
+    pub fn get_data_offset(&self) -> usize {
+        match self.header10 {
+            Some(_) => 4 + 124 + 20,
+            None => 4 + 124,
         }
     }
 
+    pub fn get_num_mipmap_levels(&self) -> u32 {
+        // 1 means just the main image
+        self.header.mip_map_count.unwrap_or(1)
+    }
+
     pub fn get_min_mipmap_size_in_bytes(&self) -> u32 {
         if let Some(format) = self.get_format() {
             if let Some(min) = format.get_minimum_mipmap_size_in_bytes() {
@@ -337,7 +507,7 @@ impl Dds {
             }
         }
         if let Some(bpp) = self.get_bits_per_pixel() {
-            (bpp + 7) / 8
+            bpp.div_ceil(8)
         } else {
             1
         }
@@ -347,22 +517,111 @@ impl Dds {
     /// 0 for textures with just one image).
     pub fn get_data(&self, array_layer: u32) -> Result<&[u8], Error> {
         let (offset, size) = self.get_offset_and_size(array_layer)?;
-        let offset = offset as usize;
-        let size = size as usize;
-        self.data
-            .get(offset..offset + size)
-            .ok_or(Error::OutOfBounds)
+        let range = self.get_data_range(offset, size)?;
+        Ok(&self.data[range])
     }
 
     /// This gets a reference to the data at the given `array_layer` (which should be
     /// 0 for textures with just one image).
     pub fn get_mut_data(&mut self, array_layer: u32) -> Result<&mut [u8], Error> {
         let (offset, size) = self.get_offset_and_size(array_layer)?;
-        let offset = offset as usize;
-        let size = size as usize;
-        self.data
-            .get_mut(offset..offset + size)
-            .ok_or(Error::OutOfBounds)
+        let range = self.get_data_range(offset, size)?;
+        Ok(&mut self.data[range])
+    }
+
+    /// This gets a reference to the data of a single mipmap level at the given
+    /// `array_layer`. For cubemaps, each face is its own array layer.
+    pub fn get_mipmap_data(&self, array_layer: u32, mipmap_level: u32) -> Result<&[u8], Error> {
+        let (offset, size) = self.get_mipmap_offset_and_size(array_layer, mipmap_level)?;
+        let range = self.get_data_range(offset, size)?;
+        Ok(&self.data[range])
+    }
+
+    /// This gets a mutable reference to the data of a single mipmap level at the
+    /// given `array_layer`. For cubemaps, each face is its own array layer.
+    pub fn get_mut_mipmap_data(
+        &mut self,
+        array_layer: u32,
+        mipmap_level: u32,
+    ) -> Result<&mut [u8], Error> {
+        let (offset, size) = self.get_mipmap_offset_and_size(array_layer, mipmap_level)?;
+        let range = self.get_data_range(offset, size)?;
+        Ok(&mut self.data[range])
+    }
+
+    /// This gets where the given plane of a mipmap level lies within the data
+    /// of that level, for a single depth slice. Formats that are not planar
+    /// have only plane 0.
+    pub fn get_plane_layout(&self, mipmap_level: u32, plane: u32) -> Result<PlaneLayout, Error> {
+        if mipmap_level >= self.get_num_mipmap_levels() {
+            return Err(Error::OutOfBounds);
+        }
+        let format = match self.get_format() {
+            Some(f) => f,
+            None => return Err(Error::UnsupportedFormat),
+        };
+        if plane >= format.get_num_planes() {
+            return Err(Error::OutOfBounds);
+        }
+        let width = (self.header.width >> mipmap_level).max(1);
+        let height = (self.header.height >> mipmap_level).max(1);
+        match format.get_plane_layout(plane, width, height) {
+            Some(layout) => Ok(layout),
+            None => Err(Error::UnsupportedFormat),
+        }
+    }
+
+    /// This gets a reference to the data of one plane of a mipmap level at the
+    /// given `array_layer`, such as the luma or chroma of a planar video format
+    pub fn get_plane_data(
+        &self,
+        array_layer: u32,
+        mipmap_level: u32,
+        plane: u32,
+    ) -> Result<&[u8], Error> {
+        let range = self.get_plane_range(array_layer, mipmap_level, plane)?;
+        Ok(&self.data[range])
+    }
+
+    /// This gets a mutable reference to the data of one plane of a mipmap level
+    /// at the given `array_layer`
+    pub fn get_mut_plane_data(
+        &mut self,
+        array_layer: u32,
+        mipmap_level: u32,
+        plane: u32,
+    ) -> Result<&mut [u8], Error> {
+        let range = self.get_plane_range(array_layer, mipmap_level, plane)?;
+        Ok(&mut self.data[range])
+    }
+
+    // The range of `data` holding one plane of a mipmap level
+    fn get_plane_range(
+        &self,
+        array_layer: u32,
+        mipmap_level: u32,
+        plane: u32,
+    ) -> Result<Range<usize>, Error> {
+        let layout = self.get_plane_layout(mipmap_level, plane)?;
+        let (offset, _) = self.get_mipmap_offset_and_size(array_layer, mipmap_level)?;
+        match (offset.checked_add(layout.offset), layout.size()) {
+            (Some(offset), Some(size)) => self.get_data_range(offset, size),
+            _ => Err(Error::UnsupportedFormat),
+        }
+    }
+
+    // The range of `data` holding `size` bytes at `offset`, which must all be
+    // present
+    fn get_data_range(&self, offset: u32, size: u32) -> Result<Range<usize>, Error> {
+        let end = offset as usize + size as usize;
+        if end > self.data.len() {
+            let data_offset = self.get_data_offset() as u64;
+            return Err(Error::ShortFile {
+                expected: data_offset + end as u64,
+                actual: data_offset + self.data.len() as u64,
+            });
+        }
+        Ok(offset as usize..end)
     }
 
     fn get_offset_and_size(&self, array_layer: u32) -> Result<(u32, u32), Error> {
@@ -371,10 +630,32 @@ impl Dds {
             return Err(Error::OutOfBounds);
         }
         let array_stride = self.get_array_stride()?;
-        let offset = array_layer * array_stride;
+        let offset = match array_layer.checked_mul(array_stride) {
+            Some(o) => o,
+            None => return Err(Error::UnsupportedFormat),
+        };
 
         Ok((offset, array_stride))
     }
+
+    /// This gets the byte offset (within `data`) and size of a single mipmap
+    /// level at the given `array_layer`.
+    pub fn get_mipmap_offset_and_size(
+        &self,
+        array_layer: u32,
+        mipmap_level: u32,
+    ) -> Result<(u32, u32), Error> {
+        let (mut offset, _) = self.get_offset_and_size(array_layer)?;
+        for level in 0..mipmap_level {
+            offset = match offset.checked_add(self.get_mipmap_size(level)?) {
+                Some(o) => o,
+                None => return Err(Error::UnsupportedFormat),
+            };
+        }
+        let size = self.get_mipmap_size(mipmap_level)?;
+
+        Ok((offset, size))
+    }
 }
 
 fn get_texture_size(
@@ -389,24 +670,123 @@ fn get_texture_size(
     if let Some(ls) = linear_size {
         Some(ls)
     } else if let Some(pitch) = pitch {
-        let row_height = (height + (pitch_height - 1)) / pitch_height;
-        Some(pitch * row_height * depth)
+        let row_height = height.div_ceil(pitch_height);
+        pitch.checked_mul(row_height)?.checked_mul(depth)
     } else {
         None
     }
 }
 
-fn get_array_stride(texture_size: u32, min_mipmap_size: u32, mipmap_levels: u32) -> u32 {
-    let mut stride: u32 = 0;
-    let mut current_mipsize: u32 = texture_size;
-    for _ in 0..mipmap_levels {
-        stride += current_mipsize;
-        current_mipsize /= 4;
-        if current_mipsize < min_mipmap_size {
-            current_mipsize = min_mipmap_size;
+fn get_mipmap_size(
+    format: &dyn DataFormat,
+    width: u32,
+    height: u32,
+    depth: Option<u32>,
+    mipmap_level: u32,
+) -> Option<u32> {
+    let width = (width >> mipmap_level).max(1);
+    let height = (height >> mipmap_level).max(1);
+    let depth = (depth.unwrap_or(1) >> mipmap_level).max(1);
+    format.get_slice_size(width, height)?.checked_mul(depth)
+}
+
+fn get_mipmap_chain_size(
+    format: &dyn DataFormat,
+    width: u32,
+    height: u32,
+    depth: Option<u32>,
+    mipmap_levels: u32,
+) -> Option<u32> {
+    let mut size: u32 = 0;
+    for mipmap_level in 0..mipmap_levels {
+        size = size.checked_add(get_mipmap_size(format, width, height, depth, mipmap_level)?)?;
+    }
+    Some(size)
+}
+
+// A reader that counts the bytes it has read, so that running out of input
+// can be reported as `Error::ShortFile` with the number of bytes needed
+pub(crate) struct CountingReader<R> {
+    inner: R,
+    position: u64,
+}
+
+impl<R: Read> CountingReader<R> {
+    pub(crate) fn new(inner: R) -> CountingReader<R> {
+        CountingReader { inner, position: 0 }
+    }
+
+    // The number of bytes read so far
+    pub(crate) fn position(&self) -> u64 {
+        self.position
+    }
+
+    pub(crate) fn fill(&mut self, buf: &mut [u8]) -> Result<(), Error> {
+        let start = self.position;
+        let mut filled = 0;
+        while filled < buf.len() {
+            match self.read(&mut buf[filled..]) {
+                Ok(0) => {
+                    return Err(Error::ShortFile {
+                        expected: start + buf.len() as u64,
+                        actual: self.position,
+                    })
+                }
+                Ok(n) => filled += n,
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e.into()),
+            }
         }
+        Ok(())
+    }
+
+    // Read past bytes without keeping them, however many the input claims
+    pub(crate) fn skip(&mut self, length: u64) -> Result<(), Error> {
+        let start = self.position;
+        let skipped = std::io::copy(&mut self.by_ref().take(length), &mut std::io::sink())?;
+        if skipped < length {
+            return Err(Error::ShortFile {
+                expected: start + length,
+                actual: self.position,
+            });
+        }
+        Ok(())
+    }
+
+    // Read the given number of bytes, growing the buffer only as they arrive
+    // so that a length claimed by the input is not trusted with an allocation
+    pub(crate) fn read_vec(&mut self, length: u64) -> Result<Vec<u8>, Error> {
+        let start = self.position;
+        let mut buf = Vec::new();
+        self.by_ref().take(length).read_to_end(&mut buf)?;
+        if (buf.len() as u64) < length {
+            return Err(Error::ShortFile {
+                expected: start + length,
+                actual: self.position,
+            });
+        }
+        Ok(buf)
+    }
+
+    pub(crate) fn read_u32_le(&mut self) -> Result<u32, Error> {
+        let mut buf = [0_u8; 4];
+        self.fill(&mut buf)?;
+        Ok(u32::from_le_bytes(buf))
+    }
+
+    pub(crate) fn read_u64_le(&mut self) -> Result<u64, Error> {
+        let mut buf = [0_u8; 8];
+        self.fill(&mut buf)?;
+        Ok(u64::from_le_bytes(buf))
+    }
+}
+
+impl<R: Read> Read for CountingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.position += n as u64;
+        Ok(n)
     }
-    stride
 }
 
 impl fmt::Debug for Dds {
@@ -417,7 +797,7 @@ impl fmt::Debug for Dds {
         } else if let Some(dxgiformat) = self.get_dxgi_format() {
             writeln!(f, "  Format: {:?}", dxgiformat)?;
         } else if let Some(ref fourcc) = self.header.spf.fourcc {
-            writeln!(f, "  Format: FOURCC={:?} (Unknown)", fourcc)?;
+            writeln!(f, "  Format: FOURCC={} (Unknown)", fourcc)?;
         } else {
             writeln!(f, "  Format UNSPECIFIED")?;
         }
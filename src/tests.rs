@@ -20,7 +20,77 @@
 // OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
 // THE SOFTWARE.
 
-//#[test]
-//fn it_works() {
-//    assert_eq!(2 + 2, 4);
-//}
+use crate::{
+    AlphaMode, D3D10ResourceDimension, D3DFormat, Dds, DxgiFormat, NewD3dParams, NewDxgiParams,
+};
+
+mod alpha;
+mod assemble;
+#[cfg(feature = "image")]
+mod atlas;
+mod blocks;
+#[cfg(feature = "image")]
+mod builder;
+mod channels;
+mod codec;
+mod compare;
+#[cfg(feature = "export")]
+mod export;
+mod format;
+mod header;
+mod header10;
+#[cfg(feature = "image")]
+mod image_interop;
+mod ktx1;
+mod ktx2;
+mod layers;
+mod transform;
+mod yuv;
+
+// The parameters of a 2D texture with a DX10 header, one layer and only the
+// main image, for tests to adjust with struct update syntax
+fn dxgi_params(format: DxgiFormat, width: u32, height: u32) -> NewDxgiParams {
+    NewDxgiParams {
+        height,
+        width,
+        depth: None,
+        format,
+        mipmap_levels: None,
+        array_layers: None,
+        caps2: None,
+        is_cubemap: false,
+        resource_dimension: D3D10ResourceDimension::Texture2D,
+        alpha_mode: AlphaMode::Unknown,
+    }
+}
+
+// A zeroed 2D texture with a DX10 header
+fn new_texture(format: DxgiFormat, width: u32, height: u32) -> Dds {
+    Dds::new_dxgi(dxgi_params(format, width, height)).unwrap()
+}
+
+// A zeroed 2D texture with a DX10 header and the given number of mipmap levels
+fn new_mipmapped_texture(format: DxgiFormat, width: u32, height: u32, mipmap_levels: u32) -> Dds {
+    Dds::new_dxgi(NewDxgiParams {
+        mipmap_levels: Some(mipmap_levels),
+        ..dxgi_params(format, width, height)
+    })
+    .unwrap()
+}
+
+// The parameters of a 2D texture with a legacy header and only the main image
+fn d3d_params(format: D3DFormat, width: u32, height: u32) -> NewD3dParams {
+    NewD3dParams {
+        height,
+        width,
+        depth: None,
+        format,
+        mipmap_levels: None,
+        caps2: None,
+    }
+}
+
+// A zeroed 2D texture with a legacy header
+fn new_legacy_texture(format: D3DFormat, width: u32, height: u32) -> Dds {
+    Dds::new_d3d(d3d_params(format, width, height)).unwrap()
+}
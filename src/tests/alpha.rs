@@ -0,0 +1,76 @@
+// The MIT License (MIT)
+//
+// Copyright (c) 2018 Michael Dilger
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+use super::{dxgi_params, new_legacy_texture};
+use crate::{AlphaMode, D3DFormat, Dds, DxgiFormat, NewDxgiParams};
+
+fn rgba8(format: DxgiFormat, texels: &[[u8; 4]]) -> Dds {
+    let mut dds = Dds::new_dxgi(NewDxgiParams {
+        alpha_mode: AlphaMode::Straight,
+        ..dxgi_params(format, texels.len() as u32, 1)
+    })
+    .unwrap();
+    dds.data = texels.concat();
+    dds
+}
+
+#[test]
+fn premultiply_and_back() {
+    let mut dds = rgba8(
+        DxgiFormat::R8G8B8A8_UNorm,
+        &[[200, 100, 50, 128], [255, 255, 255, 0], [10, 20, 30, 255]],
+    );
+    dds.premultiply_alpha().unwrap();
+    assert_eq!(dds.get_alpha_mode(), AlphaMode::PreMultiplied);
+    assert_eq!(dds.data, [100, 50, 25, 128, 0, 0, 0, 0, 10, 20, 30, 255]);
+
+    // Already premultiplied
+    dds.premultiply_alpha().unwrap();
+    assert_eq!(dds.data[..4], [100, 50, 25, 128]);
+
+    dds.unpremultiply_alpha().unwrap();
+    assert_eq!(dds.get_alpha_mode(), AlphaMode::Straight);
+    assert_eq!(dds.data, [199, 100, 50, 128, 0, 0, 0, 0, 10, 20, 30, 255]);
+}
+
+#[test]
+fn srgb_premultiplies_in_linear_space() {
+    let mut dds = rgba8(DxgiFormat::R8G8B8A8_UNorm_sRGB, &[[255, 255, 255, 128]]);
+    dds.premultiply_alpha().unwrap();
+    // Half of linear white is 0.735 in sRGB
+    assert_eq!(dds.data, [188, 188, 188, 128]);
+}
+
+#[test]
+fn legacy_dxt_formats() {
+    let mut dds = new_legacy_texture(D3DFormat::DXT5, 4, 4);
+    assert_eq!(dds.get_alpha_mode(), AlphaMode::Unknown);
+
+    dds.premultiply_alpha().unwrap();
+    assert_eq!(dds.get_d3d_format(), Some(D3DFormat::DXT4));
+    assert_eq!(dds.get_alpha_mode(), AlphaMode::PreMultiplied);
+
+    dds.unpremultiply_alpha().unwrap();
+    // A legacy header cannot say straight, only not premultiplied
+    assert_eq!(dds.get_d3d_format(), Some(D3DFormat::DXT5));
+    assert_eq!(dds.get_alpha_mode(), AlphaMode::Unknown);
+}
@@ -0,0 +1,167 @@
+// The MIT License (MIT)
+//
+// Copyright (c) 2018 Michael Dilger
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+use super::{d3d_params, new_mipmapped_texture};
+use crate::{Assembly, D3DFormat, Dds, DxgiFormat, Error, NewD3dParams};
+
+fn filled(format: DxgiFormat, size: u32, mipmap_levels: u32, fill: u8) -> Dds {
+    let mut dds = new_mipmapped_texture(format, size, size, mipmap_levels);
+    // Each level holds its own value, after the one given for the texture
+    for level in 0..mipmap_levels {
+        dds.get_mut_mipmap_data(0, level)
+            .unwrap()
+            .fill(fill + level as u8);
+    }
+    dds
+}
+
+#[test]
+fn array_round_trip() {
+    let parts: Vec<_> = (0..3)
+        .map(|i| filled(DxgiFormat::R8_UNorm, 4, 3, i * 10))
+        .collect();
+    let array = Dds::assemble(&parts, Assembly::Array).unwrap();
+    assert_eq!(array.get_num_array_layers(), 3);
+    assert_eq!(array.get_num_mipmap_levels(), 3);
+    assert!(!array.is_cubemap());
+    assert_eq!(array.get_mipmap_data(2, 1).unwrap(), [21; 4]);
+
+    let layer = array.extract_layer(1).unwrap();
+    assert_eq!(layer.get_num_array_layers(), 1);
+    assert_eq!(layer.get_dxgi_format(), Some(DxgiFormat::R8_UNorm));
+    assert_eq!(layer.data, parts[1].data);
+
+    let level = array.extract_subresource(2, 1).unwrap();
+    assert_eq!((level.get_width(), level.get_height()), (2, 2));
+    assert_eq!(level.get_num_mipmap_levels(), 1);
+    assert_eq!(level.data, [21; 4]);
+}
+
+#[test]
+fn legacy_cubemap() {
+    let faces: Vec<_> = (0..6)
+        .map(|i| {
+            let mut dds = Dds::new_d3d(NewD3dParams {
+                mipmap_levels: Some(1),
+                ..d3d_params(D3DFormat::DXT1, 8, 8)
+            })
+            .unwrap();
+            dds.data.fill(i);
+            dds
+        })
+        .collect();
+    let cubemap = Dds::assemble(&faces, Assembly::Cubemap).unwrap();
+    // A single cube keeps the legacy header
+    assert!(cubemap.header10.is_none());
+    assert_eq!(cubemap.get_d3d_format(), Some(D3DFormat::DXT1));
+    assert!(cubemap.is_cubemap());
+    assert_eq!(cubemap.get_num_array_layers(), 6);
+    for face in 0..6 {
+        assert_eq!(cubemap.get_mipmap_data(face, 0).unwrap(), [face as u8; 32]);
+    }
+    assert_eq!(cubemap.extract_layer(4).unwrap().data, faces[4].data);
+}
+
+#[test]
+fn volume_from_slices() {
+    let slices: Vec<_> = (0..4)
+        .map(|i| filled(DxgiFormat::R8G8B8A8_UNorm, 2, 1, i))
+        .collect();
+    let volume = Dds::assemble(&slices, Assembly::Volume).unwrap();
+    assert!(volume.is_volume());
+    assert_eq!(volume.get_depth(), 4);
+    assert_eq!(volume.get_num_array_layers(), 1);
+    for (i, slice) in volume.data.chunks_exact(16).enumerate() {
+        assert_eq!(slice, [i as u8; 16]);
+    }
+
+    let extracted = volume.extract_layer(0).unwrap();
+    assert!(extracted.is_volume());
+    assert_eq!(extracted.data, volume.data);
+}
+
+#[test]
+fn mismatched_parts() {
+    match Dds::assemble(&[], Assembly::Array) {
+        Err(Error::InvalidField { field, value, .. }) => {
+            assert_eq!((field, value), ("textures", 0))
+        }
+        other => panic!("unexpected {:?}", other),
+    }
+
+    let r8 = filled(DxgiFormat::R8_UNorm, 4, 1, 0);
+    let parts = [
+        r8.clone(),
+        r8.clone(),
+        filled(DxgiFormat::R8G8_UNorm, 4, 1, 0),
+    ];
+    match Dds::assemble(&parts, Assembly::Array) {
+        Err(Error::Mismatch {
+            index,
+            property,
+            found,
+            expected,
+        }) => {
+            assert_eq!((index, property), (2, "format"));
+            assert_eq!(
+                (found.as_str(), expected.as_str()),
+                ("R8G8_UNORM", "R8_UNORM")
+            );
+        }
+        other => panic!("unexpected {:?}", other),
+    }
+
+    let parts = [r8.clone(), filled(DxgiFormat::R8_UNorm, 8, 1, 0)];
+    assert!(matches!(
+        Dds::assemble(&parts, Assembly::Array),
+        Err(Error::Mismatch {
+            index: 1,
+            property: "dimensions",
+            ..
+        })
+    ));
+
+    let parts = [r8.clone(), filled(DxgiFormat::R8_UNorm, 4, 2, 0)];
+    assert!(matches!(
+        Dds::assemble(&parts, Assembly::Array),
+        Err(Error::Mismatch {
+            index: 1,
+            property: "mipmap levels",
+            ..
+        })
+    ));
+
+    let parts = vec![r8.clone(); 5];
+    assert!(matches!(
+        Dds::assemble(&parts, Assembly::Cubemap),
+        Err(Error::InvalidField { field: "faces", .. })
+    ));
+
+    let parts = vec![filled(DxgiFormat::R8_UNorm, 4, 2, 0); 2];
+    assert!(matches!(
+        Dds::assemble(&parts, Assembly::Volume),
+        Err(Error::InvalidField {
+            field: "mipmap levels",
+            ..
+        })
+    ));
+}
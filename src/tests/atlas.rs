@@ -0,0 +1,136 @@
+// The MIT License (MIT)
+//
+// Copyright (c) 2018 Michael Dilger
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+use crate::{AtlasBuilder, DxgiFormat, Error, Mipmaps, TextureBuilder};
+use image::{DynamicImage, Rgba, RgbaImage};
+
+fn solid(size: u32, color: [u8; 4]) -> DynamicImage {
+    DynamicImage::ImageRgba8(RgbaImage::from_pixel(size, size, Rgba(color)))
+}
+
+fn five_squares(format: DxgiFormat) -> AtlasBuilder {
+    (0..5).fold(AtlasBuilder::new(format, 1024, 1024), |builder, i| {
+        builder.image(format!("square{}", i), solid(64, [i * 50, 0, 0, 255]))
+    })
+}
+
+#[test]
+fn full_mipmaps_with_padding() {
+    for format in [DxgiFormat::R8G8B8A8_UNorm, DxgiFormat::BC1_UNorm] {
+        let atlas = five_squares(format)
+            .padding(1)
+            .mipmaps(Mipmaps::Full)
+            .build()
+            .unwrap();
+        assert_eq!(atlas.textures.len(), 1);
+        assert_eq!(atlas.textures[0].get_num_mipmap_levels(), 11);
+        for entry in &atlas.entries {
+            assert_eq!(entry.page, 0);
+            assert_eq!((entry.width, entry.height), (64, 64));
+            assert!(entry.x.is_multiple_of(64) && entry.y.is_multiple_of(64));
+        }
+    }
+}
+
+#[test]
+fn full_mipmaps_copying_blocks() {
+    let source = TextureBuilder::new(DxgiFormat::BC1_UNorm)
+        .image(DynamicImage::ImageRgba8(RgbaImage::from_fn(
+            64,
+            64,
+            |x, y| Rgba([((x * 37) ^ (y * 91)) as u8, (x * y) as u8, 255, 255]),
+        )))
+        .mipmaps(Mipmaps::Full)
+        .build()
+        .unwrap();
+    let atlas = five_squares(DxgiFormat::BC1_UNorm)
+        .texture("noise", source.clone())
+        .padding(1)
+        .mipmaps(Mipmaps::Full)
+        .build()
+        .unwrap();
+    assert_eq!(atlas.textures.len(), 1);
+    let entry = atlas.get_entry("noise").unwrap();
+
+    // The blocks of levels down to 4x4 are copied as they are
+    let page = &atlas.textures[0];
+    for level in 0..5 {
+        let width = page.get_mipmap_dimensions(level).0;
+        let (bx, by) = ((entry.x >> level) / 4, (entry.y >> level) / 4);
+        let offset = (by * width.div_ceil(4) + bx) as usize * 8;
+        let data = page.get_mipmap_data(0, level).unwrap();
+        let expected = source.get_mipmap_data(0, level).unwrap();
+        assert_eq!(data[offset..offset + 8], expected[..8]);
+    }
+}
+
+#[test]
+fn gutter_repeats_edge_texels() {
+    let atlas = AtlasBuilder::new(DxgiFormat::R8G8B8A8_UNorm, 16, 16)
+        .image("red", solid(4, [255, 0, 0, 255]))
+        .image("blue", solid(4, [0, 0, 255, 255]))
+        .padding(2)
+        .build()
+        .unwrap();
+    assert_eq!(atlas.textures.len(), 1);
+    let rgba = atlas.textures[0].decode_rgba32f(0, 0).unwrap();
+    let texel = |x: u32, y: u32| {
+        let offset = (y as usize * 16 + x as usize) * 4;
+        [
+            rgba[offset],
+            rgba[offset + 1],
+            rgba[offset + 2],
+            rgba[offset + 3],
+        ]
+    };
+    for (name, color) in [
+        ("red", [1.0, 0.0, 0.0, 1.0]),
+        ("blue", [0.0, 0.0, 1.0, 1.0]),
+    ] {
+        let entry = atlas.get_entry(name).unwrap();
+        assert_eq!(texel(entry.x, entry.y), color);
+        assert_eq!(texel(entry.x - 2, entry.y - 2), color);
+        assert_eq!(texel(entry.x + 5, entry.y + 5), color);
+        assert_eq!(
+            entry.uv,
+            [
+                entry.x as f32 / 16.0,
+                entry.y as f32 / 16.0,
+                (entry.x + 4) as f32 / 16.0,
+                (entry.y + 4) as f32 / 16.0,
+            ]
+        );
+    }
+}
+
+#[test]
+fn entry_larger_than_page() {
+    let result = AtlasBuilder::new(DxgiFormat::R8G8B8A8_UNorm, 32, 32)
+        .image("big", solid(64, [0, 0, 0, 255]))
+        .build();
+    match result {
+        Err(Error::InvalidField { field, value, .. }) => {
+            assert_eq!((field, value), ("width", 64));
+        }
+        other => panic!("expected InvalidField, got {:?}", other.map(|a| a.entries)),
+    }
+}
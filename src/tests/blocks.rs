@@ -0,0 +1,140 @@
+// The MIT License (MIT)
+//
+// Copyright (c) 2018 Michael Dilger
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+use super::dxgi_params;
+use crate::{BlockRegion, D3D10ResourceDimension, Dds, DxgiFormat, Error, NewDxgiParams};
+
+// A texture with two mipmap levels, whose bytes count up
+fn counting(format: DxgiFormat, width: u32, height: u32, depth: Option<u32>) -> Dds {
+    let mut dds = Dds::new_dxgi(NewDxgiParams {
+        depth,
+        mipmap_levels: Some(2),
+        resource_dimension: match depth {
+            Some(_) => D3D10ResourceDimension::Texture3D,
+            None => D3D10ResourceDimension::Texture2D,
+        },
+        ..dxgi_params(format, width, height)
+    })
+    .unwrap();
+    for (i, byte) in dds.data.iter_mut().enumerate() {
+        *byte = i as u8;
+    }
+    dds
+}
+
+#[test]
+fn block_positions() {
+    // 10x6 texels take 3x2 blocks, with the last column and row partly used
+    let dds = counting(DxgiFormat::BC1_UNorm, 10, 6, None);
+    assert_eq!(dds.get_block_count(0), Some((3, 2, 1)));
+    assert_eq!(dds.get_block_count(1), Some((2, 1, 1)));
+    let positions: Vec<(u32, u32, u32)> = dds
+        .blocks::<8>(0, 0)
+        .unwrap()
+        .map(|b| (b.x, b.y, b.z))
+        .collect();
+    assert_eq!(
+        positions,
+        [
+            (0, 0, 0),
+            (1, 0, 0),
+            (2, 0, 0),
+            (0, 1, 0),
+            (1, 1, 0),
+            (2, 1, 0)
+        ]
+    );
+    let fifth = dds.blocks::<8>(0, 0).unwrap().nth(4).unwrap();
+    assert_eq!(fifth.data, &[32, 33, 34, 35, 36, 37, 38, 39]);
+
+    let volume = counting(DxgiFormat::BC4_UNorm, 4, 8, Some(2));
+    let last = volume.blocks::<8>(0, 0).unwrap().last().unwrap();
+    assert_eq!((last.x, last.y, last.z), (0, 1, 1));
+
+    let uncompressed = counting(DxgiFormat::R8G8B8A8_UNorm, 4, 4, None);
+    assert_eq!(uncompressed.get_block_count(0), None);
+}
+
+#[test]
+fn wrong_block_size() {
+    let dds = counting(DxgiFormat::BC7_UNorm, 8, 8, None);
+    assert!(matches!(
+        dds.blocks::<8>(0, 0),
+        Err(Error::InvalidField {
+            field: "block size",
+            value: 8,
+            ..
+        })
+    ));
+    assert!(matches!(dds.blocks::<16>(0, 2), Err(Error::OutOfBounds)));
+}
+
+#[test]
+fn patch_blocks() {
+    let mut dds = counting(DxgiFormat::BC3_UNorm, 8, 4, None);
+    for block in dds.blocks_mut::<16>(0, 0).unwrap() {
+        block.data[0] = 100 + block.x as u8;
+    }
+    let first_bytes: Vec<u8> = dds.blocks::<16>(0, 0).unwrap().map(|b| b.data[0]).collect();
+    assert_eq!(first_bytes, [100, 101]);
+    assert_eq!(dds.data[1], 1);
+}
+
+#[test]
+fn copy_blocks() {
+    let source = counting(DxgiFormat::BC1_UNorm_sRGB, 12, 8, None);
+    let mut target = counting(DxgiFormat::BC1_UNorm, 16, 16, None);
+    target.data.fill(0);
+    let region = BlockRegion {
+        array_layer: 0,
+        mipmap_level: 0,
+        x: 1,
+        y: 0,
+        width: 2,
+        height: 2,
+    };
+    target.copy_blocks(&source, &region, 0, 0, 2, 1).unwrap();
+
+    // Source blocks 1 and 2 of each row land in columns 2 and 3 of rows 1 and 2
+    let source_blocks: Vec<[u8; 8]> = source.blocks::<8>(0, 0).unwrap().map(|b| *b.data).collect();
+    for block in target.blocks::<8>(0, 0).unwrap() {
+        let expected = match (block.x, block.y) {
+            (2..=3, 1..=2) => source_blocks[((block.y - 1) * 3 + block.x - 1) as usize],
+            _ => [0; 8],
+        };
+        assert_eq!(*block.data, expected, "block {}, {}", block.x, block.y);
+    }
+
+    // Out of bounds, and a different kind of block
+    assert!(matches!(
+        target.copy_blocks(&source, &region, 0, 0, 3, 0),
+        Err(Error::OutOfBounds)
+    ));
+    let bc3 = counting(DxgiFormat::BC3_UNorm, 8, 8, None);
+    assert!(matches!(
+        target.copy_blocks(&bc3, &region, 0, 0, 0, 0),
+        Err(Error::Mismatch {
+            property: "format",
+            ..
+        })
+    ));
+}
@@ -0,0 +1,94 @@
+// The MIT License (MIT)
+//
+// Copyright (c) 2018 Michael Dilger
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+use crate::{AlphaMode, DxgiFormat, Mipmaps, TextureBuilder};
+use image::{DynamicImage, Rgba32FImage};
+
+fn slice(value: f32) -> DynamicImage {
+    DynamicImage::ImageRgba32F(Rgba32FImage::from_pixel(
+        4,
+        4,
+        image::Rgba([value, value, value, 1.0]),
+    ))
+}
+
+#[test]
+fn odd_depth_volume_mipmaps() {
+    let dds = TextureBuilder::new(DxgiFormat::R32G32B32A32_Float)
+        .images((0..5).map(|i| slice(i as f32 / 8.0)))
+        .volume(true)
+        .mipmaps(Mipmaps::Full)
+        .build()
+        .unwrap();
+    assert_eq!(dds.get_num_mipmap_levels(), 3);
+    assert_eq!(dds.get_mipmap_dimensions(1), (2, 2, 2));
+    assert_eq!(dds.get_mipmap_dimensions(2), (1, 1, 1));
+
+    // The last slice joins the last pair
+    let level1 = dds.decode_rgba32f(0, 1).unwrap();
+    assert_eq!(level1[0], 0.5 / 8.0);
+    assert_eq!(level1[4 * 4], 3.0 / 8.0);
+    let level2 = dds.decode_rgba32f(0, 2).unwrap();
+    assert_eq!(level2[0], 1.75 / 8.0);
+}
+
+#[test]
+fn even_depth_volume_mipmaps() {
+    let dds = TextureBuilder::new(DxgiFormat::R32G32B32A32_Float)
+        .images((0..4).map(|i| slice(i as f32 / 8.0)))
+        .volume(true)
+        .mipmaps(Mipmaps::Full)
+        .build()
+        .unwrap();
+    let level1 = dds.decode_rgba32f(0, 1).unwrap();
+    assert_eq!(level1[0], 0.5 / 8.0);
+    assert_eq!(level1[4 * 4], 2.5 / 8.0);
+}
+
+#[test]
+fn premultiplied_sources() {
+    let image = DynamicImage::ImageRgba32F(Rgba32FImage::from_pixel(
+        4,
+        4,
+        image::Rgba([0.25, 0.25, 0.25, 0.5]),
+    ));
+    for (alpha_mode, premultiplied_sources, expected) in [
+        (AlphaMode::PreMultiplied, false, 0.125),
+        (AlphaMode::PreMultiplied, true, 0.25),
+        (AlphaMode::Straight, true, 0.5),
+        (AlphaMode::Unknown, true, 0.5),
+    ] {
+        let dds = TextureBuilder::new(DxgiFormat::R32G32B32A32_Float)
+            .image(image.clone())
+            .alpha_mode(alpha_mode)
+            .premultiplied_sources(premultiplied_sources)
+            .build()
+            .unwrap();
+        let texel = &dds.decode_rgba32f(0, 0).unwrap()[..4];
+        assert_eq!(
+            texel,
+            [expected, expected, expected, 0.5],
+            "{:?}",
+            alpha_mode
+        );
+    }
+}
@@ -0,0 +1,126 @@
+// The MIT License (MIT)
+//
+// Copyright (c) 2018 Michael Dilger
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+use super::{dxgi_params, new_texture};
+use crate::ChannelSource::{One, ReconstructZ, Zero, A, B, G, R};
+use crate::{Dds, DxgiFormat, Error, NewDxgiParams};
+
+fn texels(format: DxgiFormat, data: &[u8]) -> Dds {
+    let mut dds = new_texture(format, 1, 1);
+    dds.data = data.to_vec();
+    dds
+}
+
+#[test]
+fn swizzle() {
+    let dds = texels(DxgiFormat::R8G8B8A8_UNorm, &[10, 20, 30, 40]);
+    assert_eq!(dds.swizzle([B, G, R, A]).unwrap().data, [30, 20, 10, 40]);
+    assert_eq!(
+        dds.swizzle([One, Zero, A, R]).unwrap().data,
+        [255, 0, 40, 10]
+    );
+}
+
+#[test]
+fn pack_and_split() {
+    let roughness = texels(DxgiFormat::R8_UNorm, &[50]);
+    let metallic = texels(DxgiFormat::R8G8B8A8_UNorm, &[1, 2, 3, 4]);
+    let packed = Dds::pack_channels(
+        DxgiFormat::R8G8B8A8_UNorm,
+        [Some((&roughness, R)), Some((&metallic, B)), None, None],
+    )
+    .unwrap();
+    assert_eq!(packed.data, [50, 3, 0, 255]);
+
+    let green = metallic.extract_channel(G, DxgiFormat::R8_UNorm).unwrap();
+    assert_eq!(green.data, [2]);
+    let split: Vec<Vec<u8>> = metallic
+        .split_channels(DxgiFormat::R8_UNorm)
+        .unwrap()
+        .into_iter()
+        .map(|d| d.data)
+        .collect();
+    assert_eq!(split, [[1], [2], [3], [4]]);
+}
+
+#[test]
+fn reconstruct_z() {
+    // A normal pointing straight out has a Z of one
+    let unsigned = texels(DxgiFormat::R8G8_UNorm, &[128, 128]);
+    let z = unsigned
+        .extract_channel(ReconstructZ, DxgiFormat::R8_UNorm)
+        .unwrap();
+    assert_eq!(z.data, [255]);
+
+    let signed = texels(DxgiFormat::R8G8_SNorm, &[0, 0]);
+    let z = signed
+        .extract_channel(ReconstructZ, DxgiFormat::R8_SNorm)
+        .unwrap();
+    assert_eq!(z.data, [127]);
+}
+
+#[test]
+fn packed_textures_keep_the_shape() {
+    let mut cubemap = Dds::new_dxgi(NewDxgiParams {
+        array_layers: Some(6),
+        is_cubemap: true,
+        ..dxgi_params(DxgiFormat::R8_UNorm, 2, 2)
+    })
+    .unwrap();
+    for (i, byte) in cubemap.data.iter_mut().enumerate() {
+        *byte = i as u8;
+    }
+    let packed = Dds::pack_channels(
+        DxgiFormat::R8G8_UNorm,
+        [Some((&cubemap, R)), Some((&cubemap, One)), None, None],
+    )
+    .unwrap();
+    assert!(packed.is_cubemap());
+    assert_eq!(packed.get_num_array_layers(), 6);
+    for face in 0..6 {
+        let data = packed.get_data(face).unwrap();
+        let expected: Vec<u8> = (0..4).flat_map(|i| [(face * 4 + i) as u8, 255]).collect();
+        assert_eq!(data, expected);
+    }
+}
+
+#[test]
+fn mismatched_sources() {
+    let small = new_texture(DxgiFormat::R8_UNorm, 2, 2);
+    let large = new_texture(DxgiFormat::R8_UNorm, 4, 4);
+    match Dds::pack_channels(
+        DxgiFormat::R8G8_UNorm,
+        [Some((&small, R)), Some((&large, R)), None, None],
+    ) {
+        Err(Error::Mismatch {
+            index, property, ..
+        }) => assert_eq!((index, property), (1, "dimensions")),
+        other => panic!("expected Mismatch, got {:?}", other.map(|d| d.data)),
+    }
+    assert!(matches!(
+        Dds::pack_channels(DxgiFormat::R8_UNorm, [None, None, None, None]),
+        Err(Error::InvalidField {
+            field: "channels",
+            ..
+        })
+    ));
+}
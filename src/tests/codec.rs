@@ -0,0 +1,380 @@
+// The MIT License (MIT)
+//
+// Copyright (c) 2018 Michael Dilger
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+use super::{new_legacy_texture, new_texture};
+use crate::{D3DFormat, DxgiFormat, Error};
+
+// Decode a single 4x4 block
+fn decode_block(format: DxgiFormat, block: &[u8]) -> Vec<[f32; 4]> {
+    let mut dds = new_texture(format, 4, 4);
+    dds.data.copy_from_slice(block);
+    let rgba = dds.decode_rgba32f(0, 0).unwrap();
+    rgba.chunks_exact(4)
+        .map(|t| [t[0], t[1], t[2], t[3]])
+        .collect()
+}
+
+// Pack fields, least significant bit first, as BC6H and BC7 blocks are laid out
+fn pack_bits(fields: &[(u32, u32)]) -> [u8; 16] {
+    let mut block = [0_u8; 16];
+    let mut position = 0;
+    for &(value, count) in fields {
+        for bit in 0..count {
+            if value >> bit & 1 == 1 {
+                block[position / 8] |= 1 << (position % 8);
+            }
+            position += 1;
+        }
+    }
+    block
+}
+
+// An alpha block of BC3, BC4 and BC5 with two endpoints and 3-bit indices
+fn single_block(e0: u8, e1: u8, indices: [u64; 16]) -> [u8; 8] {
+    let packed = indices
+        .iter()
+        .enumerate()
+        .fold(0_u64, |bits, (i, &index)| bits | index << (3 * i));
+    let mut block = [0_u8; 8];
+    block[0] = e0;
+    block[1] = e1;
+    block[2..].copy_from_slice(&packed.to_le_bytes()[..6]);
+    block
+}
+
+fn assert_close(found: &[f32], expected: &[f32], tolerance: f32) {
+    assert_eq!(found.len(), expected.len());
+    for (i, (f, e)) in found.iter().zip(expected).enumerate() {
+        assert!(
+            (f - e).abs() <= tolerance,
+            "value {}: found {}, expected {}",
+            i,
+            f,
+            e
+        );
+    }
+}
+
+const RED_BLUE: [u8; 8] = [0x00, 0xF8, 0x1F, 0x00, 0xE4, 0xE4, 0xE4, 0xE4];
+
+#[test]
+fn bc1_four_colors() {
+    // Red and blue endpoints, with each row going through indices 0 to 3
+    let texels = decode_block(DxgiFormat::BC1_UNorm, &RED_BLUE);
+    let third = 1.0 / 3.0;
+    let expected = [
+        [1.0, 0.0, 0.0, 1.0],
+        [0.0, 0.0, 1.0, 1.0],
+        [1.0 - third, 0.0, third, 1.0],
+        [third, 0.0, 1.0 - third, 1.0],
+    ];
+    for row in texels.chunks_exact(4) {
+        assert_close(row.concat().as_slice(), expected.concat().as_slice(), 0.01);
+    }
+}
+
+#[test]
+fn bc1_three_colors() {
+    // Blue before red selects three colors and transparent black
+    let block = [0x1F, 0x00, 0x00, 0xF8, 0xE4, 0xE4, 0xE4, 0xE4];
+    let texels = decode_block(DxgiFormat::BC1_UNorm, &block);
+    assert_close(&texels[0], &[0.0, 0.0, 1.0, 1.0], 0.0);
+    assert_close(&texels[1], &[1.0, 0.0, 0.0, 1.0], 0.0);
+    assert_close(&texels[2], &[0.5, 0.0, 0.5, 1.0], 0.01);
+    assert_close(&texels[3], &[0.0; 4], 0.0);
+}
+
+#[test]
+fn bc2_explicit_alpha() {
+    let mut block = [0x10, 0x32, 0x54, 0x76, 0x98, 0xBA, 0xDC, 0xFE].to_vec();
+    block.extend_from_slice(&RED_BLUE);
+    let texels = decode_block(DxgiFormat::BC2_UNorm, &block);
+    for (i, texel) in texels.iter().enumerate() {
+        assert_close(&texel[3..], &[i as f32 / 15.0], 1e-6);
+    }
+}
+
+#[test]
+fn bc3_interpolated_alpha() {
+    // Eight alpha values when the first endpoint is greater, six and the two
+    // extremes otherwise
+    let mut block = single_block(255, 0, [0, 1, 2, 7, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]).to_vec();
+    block.extend_from_slice(&RED_BLUE);
+    let texels = decode_block(DxgiFormat::BC3_UNorm, &block);
+    let alpha: Vec<f32> = texels[..4].iter().map(|t| t[3]).collect();
+    assert_close(&alpha, &[1.0, 0.0, 6.0 / 7.0, 1.0 / 7.0], 0.005);
+
+    let mut block = single_block(0, 255, [0, 1, 2, 5, 6, 7, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]).to_vec();
+    block.extend_from_slice(&RED_BLUE);
+    let texels = decode_block(DxgiFormat::BC3_UNorm, &block);
+    let alpha: Vec<f32> = texels[..6].iter().map(|t| t[3]).collect();
+    assert_close(&alpha, &[0.0, 1.0, 0.2, 0.8, 0.0, 1.0], 0.005);
+}
+
+#[test]
+fn bc4_and_bc5() {
+    let indices = [0, 1, 2, 3, 4, 5, 6, 7, 0, 0, 0, 0, 1, 1, 1, 1];
+    let texels = decode_block(DxgiFormat::BC4_UNorm, &single_block(255, 0, indices));
+    assert_close(&texels[0], &[1.0, 0.0, 0.0, 1.0], 0.0);
+    assert_close(&texels[1], &[0.0, 0.0, 0.0, 1.0], 0.0);
+    assert_close(&texels[7][..1], &[1.0 / 7.0], 0.005);
+
+    // Signed endpoints of 127 and -127 are 1.0 and -1.0
+    let texels = decode_block(DxgiFormat::BC4_SNorm, &single_block(0x7F, 0x81, indices));
+    assert_close(&texels[0][..1], &[1.0], 0.0);
+    assert_close(&texels[1][..1], &[-1.0], 0.0);
+
+    let mut block = single_block(255, 0, indices).to_vec();
+    block.extend_from_slice(&single_block(0, 255, [1; 16]));
+    let texels = decode_block(DxgiFormat::BC5_UNorm, &block);
+    assert_close(&texels[0], &[1.0, 1.0, 0.0, 1.0], 0.0);
+    assert_close(&texels[1], &[0.0, 1.0, 0.0, 1.0], 0.0);
+}
+
+#[test]
+fn bc6h_direct_endpoints() {
+    // Mode 11: one region and 10-bit endpoints. An unsigned endpoint of 512
+    // unquantizes to 32800, which scales to the half float 0x3E0F.
+    let block = pack_bits(&[
+        (0b00011, 5),
+        (512, 10),
+        (512, 10),
+        (512, 10),
+        (0, 10),
+        (0, 10),
+        (0, 10),
+        (0, 3),
+        (15, 4),
+    ]);
+    let texels = decode_block(DxgiFormat::BC6H_UF16, &block);
+    let value = 1.0 + 0x20F as f32 / 1024.0;
+    assert_close(&texels[0], &[value, value, value, 1.0], 0.0);
+    assert_close(&texels[1], &[0.0, 0.0, 0.0, 1.0], 0.0);
+    assert_close(&texels[2], &[value, value, value, 1.0], 0.0);
+}
+
+#[test]
+fn bc7_mode_6() {
+    // Mode 6: one subset of 7-bit RGBA endpoints with a p-bit each, and
+    // 4-bit indices
+    let block = pack_bits(&[
+        (0b1000000, 7),
+        (127, 7),
+        (0, 7),
+        (0, 7),
+        (0, 7),
+        (0, 7),
+        (0, 7),
+        (127, 7),
+        (127, 7),
+        (1, 1),
+        (0, 1),
+        (0, 3),
+        (15, 4),
+        (8, 4),
+    ]);
+    let texels = decode_block(DxgiFormat::BC7_UNorm, &block);
+    // The p-bits are the low bits of every channel
+    let first = [1.0, 1.0 / 255.0, 1.0 / 255.0, 1.0];
+    assert_close(&texels[0], &first, 0.0);
+    assert_close(&texels[1], &[0.0, 0.0, 0.0, 254.0 / 255.0], 0.0);
+    // Weight 34 of 64: (30 * 255 + 34 * 0 + 32) >> 6 = 120
+    assert_close(&texels[2], &[120.0 / 255.0, 0.0, 0.0, 254.0 / 255.0], 0.0);
+    assert_close(&texels[3], &first, 0.0);
+}
+
+// A smooth 8x8 diagonal gradient, so that the colors of each block lie on a
+// line as the block formats expect
+fn gradient(color: impl Fn(f32) -> [f32; 3]) -> Vec<f32> {
+    let mut rgba = Vec::new();
+    for y in 0..8 {
+        for x in 0..8 {
+            let t = (x + y) as f32 / 14.0;
+            rgba.extend_from_slice(&color(t));
+            rgba.push(1.0 - 0.5 * t);
+        }
+    }
+    rgba
+}
+
+fn round_trip(format: DxgiFormat, rgba: &[f32]) -> Vec<f32> {
+    let mut dds = new_texture(format, 8, 8);
+    dds.encode_rgba32f(0, 0, rgba).unwrap();
+    dds.decode_rgba32f(0, 0).unwrap()
+}
+
+#[test]
+fn bc_round_trips() {
+    let rgba = gradient(|t| [t, 0.5 * t + 0.25, 1.0 - t]);
+    let channels = |rgba: &[f32], count: usize| -> Vec<f32> {
+        rgba.chunks_exact(4)
+            .flat_map(|t| t[..count].to_vec())
+            .collect()
+    };
+    // Four colors per block, or eight single channel values, along a range of
+    // 6/14 leave errors of up to half a step
+    for (format, count, tolerance) in [
+        (DxgiFormat::BC1_UNorm, 3, 0.08),
+        (DxgiFormat::BC2_UNorm, 4, 0.08),
+        (DxgiFormat::BC3_UNorm, 4, 0.08),
+        (DxgiFormat::BC4_UNorm, 1, 0.035),
+        (DxgiFormat::BC5_UNorm, 2, 0.035),
+        (DxgiFormat::BC7_UNorm, 4, 0.02),
+    ] {
+        let decoded = round_trip(format, &rgba);
+        assert_close(
+            &channels(&decoded, count),
+            &channels(&rgba, count),
+            tolerance,
+        );
+    }
+}
+
+#[test]
+fn bc6h_round_trips() {
+    // Endpoints are interpolated as half float bit patterns, which suits
+    // ranges that keep away from zero
+    let unsigned = gradient(|t| [0.5 + 3.5 * t, 1.0 + t, 4.0 - 3.0 * t]);
+    let signed = gradient(|t| [-0.5 - 3.5 * t, 1.0 + t, -4.0 + 3.0 * t]);
+    for (format, rgba) in [
+        (DxgiFormat::BC6H_UF16, &unsigned),
+        (DxgiFormat::BC6H_SF16, &signed),
+    ] {
+        let decoded = round_trip(format, rgba);
+        for (found, expected) in decoded.chunks_exact(4).zip(rgba.chunks_exact(4)) {
+            for c in 0..3 {
+                let error = (found[c] - expected[c]).abs() / expected[c].abs();
+                assert!(error < 0.1, "found {:?}, expected {:?}", found, expected);
+            }
+            assert_eq!(found[3], 1.0);
+        }
+    }
+}
+
+#[test]
+fn bc6h_opposing_channels() {
+    // Large unquantized values must not overflow the line fit, which would
+    // lose the direction of blue falling as red rises
+    let mut rgba = Vec::new();
+    for i in 0..16 {
+        let red = -0.9 + 1.8 * i as f32 / 15.0;
+        rgba.extend_from_slice(&[red, 0.5 * red, -red, 1.0]);
+    }
+    let mut dds = new_texture(DxgiFormat::BC6H_SF16, 4, 4);
+    dds.encode_rgba32f(0, 0, &rgba).unwrap();
+    let decoded = dds.decode_rgba32f(0, 0).unwrap();
+    for (found, expected) in decoded.chunks_exact(4).zip(rgba.chunks_exact(4)) {
+        if expected[0].abs() >= 0.5 {
+            for c in 0..3 {
+                assert_eq!(found[c].signum(), expected[c].signum());
+            }
+        }
+    }
+}
+
+fn decode_legacy(format: D3DFormat, width: u32, data: &[u8]) -> Vec<[f32; 4]> {
+    let mut dds = new_legacy_texture(format, width, 1);
+    dds.data.copy_from_slice(data);
+    let rgba = dds.decode_rgba32f(0, 0).unwrap();
+    rgba.chunks_exact(4)
+        .map(|t| [t[0], t[1], t[2], t[3]])
+        .collect()
+}
+
+#[test]
+fn legacy_yuv_known_codes() {
+    // White and black share neutral chroma; red is 81, 90, 240 in BT.601
+    let white_black = [235, 128, 16, 128];
+    let texels = decode_legacy(D3DFormat::YUY2, 2, &white_black);
+    assert_close(&texels[0], &[1.0, 1.0, 1.0, 1.0], 1e-5);
+    assert_close(&texels[1], &[0.0, 0.0, 0.0, 1.0], 1e-5);
+
+    // UYVY stores the chroma first
+    let texels = decode_legacy(D3DFormat::UYVY, 2, &[128, 235, 128, 16]);
+    assert_close(&texels[0], &[1.0, 1.0, 1.0, 1.0], 1e-5);
+    assert_close(&texels[1], &[0.0, 0.0, 0.0, 1.0], 1e-5);
+
+    let texels = decode_legacy(D3DFormat::YUY2, 2, &[81, 90, 81, 240]);
+    assert_close(&texels[0], &[1.0, 0.0, 0.0, 1.0], 0.01);
+    assert_eq!(texels[0], texels[1]);
+}
+
+#[test]
+fn legacy_yuv_odd_width() {
+    // Three texels are stored as two pairs
+    let mut dds = new_legacy_texture(D3DFormat::YUY2, 3, 2);
+    assert_eq!(dds.data.len(), 16);
+    let grey = [0.25, 0.25, 0.25, 1.0].repeat(6);
+    dds.encode_rgba32f(0, 0, &grey).unwrap();
+    assert_eq!(dds.data[..4], [71, 128, 71, 128]);
+    assert_close(&dds.decode_rgba32f(0, 0).unwrap(), &grey, 0.005);
+}
+
+#[test]
+fn bump_map_known_values() {
+    let texels = decode_legacy(D3DFormat::V8U8, 1, &[0x7F, 0x81]);
+    assert_close(&texels[0], &[1.0, -1.0, 0.0, 1.0], 1e-6);
+
+    let texels = decode_legacy(D3DFormat::Q8W8V8U8, 1, &[0x7F, 0x00, 0x81, 0x7F]);
+    assert_close(&texels[0], &[1.0, 0.0, -1.0, 1.0], 1e-6);
+
+    // U = 15 and V = -15 in five bits, and a full six bit luminance
+    let l6v5u5: u16 = 15 | (0x11 << 5) | (63 << 10);
+    let texels = decode_legacy(D3DFormat::L6V5U5, 1, &l6v5u5.to_le_bytes());
+    assert_close(&texels[0], &[1.0, -1.0, 1.0, 1.0], 1e-6);
+
+    let a2w10v10u10: u32 = 511 | (0x201 << 20) | (1 << 30);
+    let texels = decode_legacy(D3DFormat::A2W10V10U10, 1, &a2w10v10u10.to_le_bytes());
+    assert_close(&texels[0], &[1.0, 0.0, -1.0, 1.0 / 3.0], 1e-6);
+}
+
+#[test]
+fn cxv8u8_computes_the_third_component() {
+    let texels = decode_legacy(D3DFormat::CXV8U8, 3, &[0, 0, 0x7F, 0, 0, 0x81]);
+    assert_close(&texels[0], &[0.0, 0.0, 1.0, 1.0], 1e-6);
+    assert_close(&texels[1], &[1.0, 0.0, 0.0, 1.0], 1e-6);
+    assert_close(&texels[2], &[0.0, -1.0, 0.0, 1.0], 1e-6);
+
+    // Only the stored components survive encoding
+    let mut dds = new_legacy_texture(D3DFormat::CXV8U8, 1, 1);
+    dds.encode_rgba32f(0, 0, &[0.6, -0.8, 0.0, 1.0]).unwrap();
+    assert_eq!(dds.data, [76, (-102_i8) as u8]);
+    let texel = dds.decode_rgba32f(0, 0).unwrap();
+    assert_close(&texel, &[0.6, -0.8, 0.0, 1.0], 0.01);
+}
+
+#[test]
+fn wrong_rgba_length() {
+    let mut dds = new_texture(DxgiFormat::R8G8B8A8_UNorm, 2, 2);
+    match dds.encode_rgba32f(0, 0, &[0.0; 12]) {
+        Err(Error::Mismatch {
+            property,
+            found,
+            expected,
+            ..
+        }) => assert_eq!(
+            (property, &*found, &*expected),
+            ("RGBA float count", "12", "16")
+        ),
+        other => panic!("expected Mismatch, got {:?}", other.err()),
+    }
+}
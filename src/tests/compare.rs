@@ -0,0 +1,124 @@
+// The MIT License (MIT)
+//
+// Copyright (c) 2018 Michael Dilger
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+use super::new_mipmapped_texture;
+use crate::{DxgiFormat, Error};
+
+#[test]
+fn identical_textures() {
+    let mut dds = new_mipmapped_texture(DxgiFormat::R8G8B8A8_UNorm, 8, 8, 2);
+    for (i, byte) in dds.data.iter_mut().enumerate() {
+        *byte = (i * 7) as u8;
+    }
+    let comparison = dds.compare(&dds).unwrap();
+    assert!(comparison.is_identical());
+    assert_eq!(comparison.subresources.len(), 2);
+    assert_eq!(comparison.min_psnr(), f64::INFINITY);
+    assert!((comparison.min_ssim() - 1.0).abs() < 1e-9);
+    assert_eq!(comparison.max_abs_error(), 0.0);
+}
+
+#[test]
+fn known_error_metrics() {
+    let left = new_mipmapped_texture(DxgiFormat::R8G8B8A8_UNorm, 4, 4, 1);
+    let mut right = left.clone();
+    // Raise red by 0.2 everywhere
+    for texel in right.data.chunks_exact_mut(4) {
+        texel[0] = 51;
+    }
+    let comparison = left.compare(&right).unwrap();
+    assert!(!comparison.is_identical());
+    assert!(comparison.header_differences.is_empty());
+
+    let metrics = &comparison.subresources[0];
+    assert_eq!((metrics.array_layer, metrics.mipmap_level), (0, 0));
+    assert_eq!(metrics.channel_max_abs_error, [0.2, 0.0, 0.0, 0.0]);
+    assert_eq!(metrics.max_abs_error, 0.2);
+    assert!((metrics.channel_rmse[0] - 0.2).abs() < 1e-6);
+    assert_eq!(metrics.channel_rmse[1..], [0.0; 3]);
+    // The mean squared error over RGB is 0.04 / 3
+    let psnr = -10.0 * (0.04_f64 / 3.0).log10();
+    assert!((metrics.psnr - psnr).abs() < 1e-4, "{}", metrics.psnr);
+    assert!(metrics.ssim < 1.0);
+}
+
+#[test]
+fn header_differences() {
+    let left = new_mipmapped_texture(DxgiFormat::R8G8B8A8_UNorm, 4, 4, 1);
+    let right = new_mipmapped_texture(DxgiFormat::B8G8R8A8_UNorm, 4, 4, 1);
+    let differences = left.compare_headers(&right);
+    let fields: Vec<_> = differences.iter().map(|d| d.field).collect();
+    assert_eq!(fields, ["format", "header10.dxgi_format"]);
+    assert_eq!(
+        differences[0].to_string(),
+        "format: R8G8B8A8_UNORM != B8G8R8A8_UNORM"
+    );
+    // Swapping the order of the channels in memory leaves the texels alone
+    let comparison = left.compare(&right).unwrap();
+    assert_eq!(comparison.max_abs_error(), 0.0);
+    assert!(!comparison.is_identical());
+}
+
+#[test]
+fn common_subresources() {
+    let left = new_mipmapped_texture(DxgiFormat::R8_UNorm, 8, 8, 4);
+    let right = new_mipmapped_texture(DxgiFormat::R8_UNorm, 8, 8, 2);
+    assert_eq!(left.compare(&right).unwrap().subresources.len(), 2);
+
+    // Levels of different sizes are skipped, or rejected when asked for
+    let right = new_mipmapped_texture(DxgiFormat::R8_UNorm, 4, 4, 1);
+    assert!(left.compare(&right).unwrap().subresources.is_empty());
+    match left.compare_subresource(&right, 0, 0) {
+        Err(Error::Mismatch {
+            property,
+            found,
+            expected,
+            ..
+        }) => {
+            assert_eq!(property, "mipmap dimensions");
+            assert_eq!(found, "4x4x1");
+            assert_eq!(expected, "8x8x1");
+        }
+        other => panic!("unexpected {:?}", other),
+    }
+}
+
+#[test]
+fn scaled_difference() {
+    let left = new_mipmapped_texture(DxgiFormat::R8G8B8A8_UNorm, 2, 2, 2);
+    let mut right = left.clone();
+    for texel in right.data.chunks_exact_mut(4) {
+        texel[1] = 51;
+    }
+    let diff = left.difference(&right, 2.0).unwrap();
+    assert_eq!(diff.get_dxgi_format(), Some(DxgiFormat::R32G32B32A32_Float));
+    assert_eq!(diff.get_num_mipmap_levels(), 2);
+    for mipmap_level in 0..2 {
+        for texel in diff
+            .decode_rgba32f(0, mipmap_level)
+            .unwrap()
+            .chunks_exact(4)
+        {
+            assert_eq!(texel, [0.0, 0.4, 0.0, 0.0]);
+        }
+    }
+}
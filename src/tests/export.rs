@@ -0,0 +1,155 @@
+// The MIT License (MIT)
+//
+// Copyright (c) 2018 Michael Dilger
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+use super::{dxgi_params, new_mipmapped_texture, new_texture};
+use crate::{D3D10ResourceDimension, Dds, DxgiFormat, Error, ExportFormat, NewDxgiParams};
+use std::fs;
+use std::io::Cursor;
+
+#[test]
+fn export_formats() {
+    for format in [
+        ExportFormat::Png,
+        ExportFormat::Tga,
+        ExportFormat::Hdr,
+        ExportFormat::Exr,
+    ] {
+        assert_eq!(
+            ExportFormat::from_extension(format.extension()),
+            Some(format)
+        );
+    }
+    assert_eq!(ExportFormat::from_extension("PNG"), Some(ExportFormat::Png));
+    assert_eq!(ExportFormat::from_extension("jpg"), None);
+
+    let dds = new_texture(DxgiFormat::BC1_UNorm, 4, 4);
+    assert_eq!(dds.get_export_format(), ExportFormat::Png);
+    let dds = new_texture(DxgiFormat::R16G16B16A16_Float, 4, 4);
+    assert_eq!(dds.get_export_format(), ExportFormat::Exr);
+    let dds = new_texture(DxgiFormat::R8G8B8A8_SNorm, 4, 4);
+    assert_eq!(dds.get_export_format(), ExportFormat::Exr);
+}
+
+#[test]
+fn png_round_trip() {
+    let mut dds = new_mipmapped_texture(DxgiFormat::R8G8B8A8_UNorm, 4, 4, 2);
+    for (i, byte) in dds.data.iter_mut().enumerate() {
+        *byte = (i * 3) as u8;
+    }
+    for (mipmap_level, offset) in [(0, 0), (1, 64)] {
+        let mut png = Cursor::new(Vec::new());
+        dds.export_subresource(0, mipmap_level, ExportFormat::Png, &mut png)
+            .unwrap();
+        let image = image::load_from_memory(png.get_ref()).unwrap().to_rgba8();
+        let size = 4 >> mipmap_level;
+        assert_eq!(image.dimensions(), (size, size));
+        let expected = &dds.data[offset..offset + (size * size * 4) as usize];
+        assert_eq!(image.as_raw(), expected);
+    }
+}
+
+#[test]
+fn contact_sheet_layout() {
+    let mut dds = Dds::new_dxgi(NewDxgiParams {
+        mipmap_levels: Some(4),
+        array_layers: Some(2),
+        ..dxgi_params(DxgiFormat::R8G8B8A8_UNorm, 8, 8)
+    })
+    .unwrap();
+    for array_layer in 0..2 {
+        for mipmap_level in 0..4 {
+            dds.get_mut_mipmap_data(array_layer, mipmap_level)
+                .unwrap()
+                .fill((array_layer * 100 + mipmap_level * 10) as u8);
+        }
+    }
+    let sheet = dds.get_contact_sheet().unwrap().to_rgba8();
+    // Levels of 8, 4, 2 and 1 texels side by side, one row per layer
+    assert_eq!(sheet.dimensions(), (15, 16));
+    for (x, y, value) in [
+        (0, 0, 0),
+        (8, 0, 10),
+        (12, 0, 20),
+        (14, 0, 30),
+        (14, 8, 130),
+    ] {
+        assert_eq!(sheet.get_pixel(x, y).0, [value; 4], "at {}, {}", x, y);
+    }
+    // Space the smaller levels leave below them is empty
+    assert_eq!(sheet.get_pixel(8, 4).0, [0; 4]);
+}
+
+#[test]
+fn export_all_names() {
+    let directory = std::env::temp_dir().join(format!("ddsfile-export-{}", std::process::id()));
+    fs::create_dir_all(&directory).unwrap();
+
+    let array = Dds::new_dxgi(NewDxgiParams {
+        mipmap_levels: Some(2),
+        array_layers: Some(2),
+        ..dxgi_params(DxgiFormat::R8G8B8A8_UNorm, 4, 4)
+    })
+    .unwrap();
+    let paths = array
+        .export_all(&directory, "array", ExportFormat::Tga)
+        .unwrap();
+    let names: Vec<_> = paths
+        .iter()
+        .map(|p| p.file_name().unwrap().to_str().unwrap().to_owned())
+        .collect();
+    assert_eq!(
+        names,
+        [
+            "array_layer0_mip0.tga",
+            "array_layer0_mip1.tga",
+            "array_layer1_mip0.tga",
+            "array_layer1_mip1.tga",
+        ]
+    );
+
+    let mut volume = Dds::new_dxgi(NewDxgiParams {
+        depth: Some(2),
+        resource_dimension: D3D10ResourceDimension::Texture3D,
+        ..dxgi_params(DxgiFormat::R8G8B8A8_UNorm, 2, 2)
+    })
+    .unwrap();
+    volume.data[16..].fill(0xff);
+    let paths = volume
+        .export_all(&directory, "volume", ExportFormat::Png)
+        .unwrap();
+    assert_eq!(paths.len(), 2);
+    assert!(paths[1].ends_with("volume_layer0_mip0_slice1.png"));
+    let slice = image::open(&paths[1]).unwrap().to_rgba8();
+    assert_eq!(slice.dimensions(), (2, 2));
+    assert_eq!(slice.as_raw(), &[0xff; 16]);
+
+    let result = volume.export_subresource_to_path(0, 0, directory.join("volume.jpg"));
+    assert!(matches!(
+        result,
+        Err(Error::Parse {
+            kind: "export file extension",
+            ..
+        })
+    ));
+
+    fs::remove_dir_all(&directory).unwrap();
+}
@@ -0,0 +1,394 @@
+// The MIT License (MIT)
+//
+// Copyright (c) 2018 Michael Dilger
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+use super::{dxgi_params, new_legacy_texture, new_mipmapped_texture};
+use crate::{
+    Channel, ChannelInfo, ComponentType, D3DFormat, DataFormat, Dds, DxgiFormat, Error, FourCC,
+    PlaneLayout,
+};
+
+#[test]
+fn metal_pixel_formats() {
+    let known = [
+        (DxgiFormat::A8_UNorm, 1),
+        (DxgiFormat::R8_UNorm, 10),
+        (DxgiFormat::A4B4G4R4_UNorm, 42),
+        (DxgiFormat::R8G8B8A8_UNorm, 70),
+        (DxgiFormat::B8G8R8A8_UNorm_sRGB, 81),
+        (DxgiFormat::BC1_UNorm, 130),
+        (DxgiFormat::BC6H_UF16, 151),
+        (DxgiFormat::BC7_UNorm_sRGB, 153),
+    ];
+    for (format, value) in known {
+        assert_eq!(format.to_mtl_pixel_format(), Some(value), "{:?}", format);
+    }
+}
+
+#[test]
+fn metal_has_no_typeless_formats() {
+    assert_eq!(DxgiFormat::Unknown.to_mtl_pixel_format(), None);
+    assert_eq!(DxgiFormat::R8G8B8A8_Typeless.to_mtl_pixel_format(), None);
+    assert_eq!(DxgiFormat::BC7_Typeless.to_mtl_pixel_format(), None);
+}
+
+#[test]
+fn dxgi_format_names() {
+    assert_eq!(DxgiFormat::BC7_UNorm_sRGB.to_string(), "BC7_UNORM_SRGB");
+    assert_eq!(DxgiFormat::Format_420_Opaque.to_string(), "420_OPAQUE");
+    for name in [
+        "BC7_UNORM_SRGB",
+        "DXGI_FORMAT_BC7_UNORM_SRGB",
+        "bc7_unorm_srgb",
+    ] {
+        assert_eq!(
+            name.parse::<DxgiFormat>().unwrap(),
+            DxgiFormat::BC7_UNorm_sRGB
+        );
+    }
+    assert_eq!(
+        "420_OPAQUE".parse::<DxgiFormat>().unwrap(),
+        DxgiFormat::Format_420_Opaque
+    );
+    for format in [
+        DxgiFormat::R8G8B8A8_UNorm,
+        DxgiFormat::R9G9B9E5_SharedExp,
+        DxgiFormat::B4G4R4A4_UNorm,
+        DxgiFormat::A4B4G4R4_UNorm,
+    ] {
+        assert_eq!(format.to_string().parse::<DxgiFormat>().unwrap(), format);
+    }
+    match "RGBA8".parse::<DxgiFormat>() {
+        Err(Error::Parse { kind, input }) => {
+            assert_eq!(kind, "DxgiFormat");
+            assert_eq!(input, "RGBA8");
+        }
+        other => panic!("unexpected {:?}", other),
+    }
+}
+
+#[test]
+fn d3d_format_names() {
+    assert_eq!(D3DFormat::A8R8G8B8.to_string(), "A8R8G8B8");
+    for name in ["DXT5", "D3DFMT_DXT5", "dxt5", "d3dfmt_dxt5"] {
+        assert_eq!(name.parse::<D3DFormat>().unwrap(), D3DFormat::DXT5);
+    }
+    assert!(matches!(
+        "BC7_UNORM".parse::<D3DFormat>(),
+        Err(Error::Parse {
+            kind: "D3DFormat",
+            ..
+        })
+    ));
+}
+
+#[test]
+fn fourcc_names() {
+    assert_eq!(FourCC(FourCC::DXT1).to_string(), "DXT1");
+    assert_eq!(FourCC(36).to_string(), "36");
+    assert_eq!("DXT1".parse::<FourCC>().unwrap(), FourCC(FourCC::DXT1));
+    assert_eq!("36".parse::<FourCC>().unwrap(), FourCC(36));
+    assert!(matches!(
+        "DXT".parse::<FourCC>(),
+        Err(Error::Parse { kind: "FourCC", .. })
+    ));
+}
+
+fn channel(channel: Channel, bits: u8, component_type: ComponentType) -> ChannelInfo {
+    ChannelInfo {
+        channel,
+        bits,
+        component_type,
+    }
+}
+
+#[test]
+fn color_format_info() {
+    let info = DxgiFormat::R8G8B8A8_UNorm_sRGB.get_format_info().unwrap();
+    assert_eq!(
+        info.channels,
+        [
+            channel(Channel::R, 8, ComponentType::UNorm),
+            channel(Channel::G, 8, ComponentType::UNorm),
+            channel(Channel::B, 8, ComponentType::UNorm),
+            channel(Channel::A, 8, ComponentType::UNorm),
+        ]
+    );
+    assert_eq!(info.component_type, ComponentType::UNorm);
+    assert!(info.srgb && info.renderable && info.sampleable);
+    assert!(!info.depth_stencil && !info.yuv && !info.planar);
+    assert_eq!((info.block_width, info.block_height), (1, 1));
+
+    let info = DxgiFormat::R8G8B8A8_Typeless.get_format_info().unwrap();
+    assert_eq!(info.component_type, ComponentType::Typeless);
+    assert!(!info.renderable && !info.sampleable);
+
+    let info = DxgiFormat::R9G9B9E5_SharedExp.get_format_info().unwrap();
+    assert_eq!(info.component_type, ComponentType::SharedExp);
+    assert_eq!(
+        info.channels[3],
+        channel(Channel::Exponent, 5, ComponentType::UInt)
+    );
+
+    assert!(DxgiFormat::Unknown.get_format_info().is_none());
+}
+
+#[test]
+fn depth_stencil_format_info() {
+    let info = DxgiFormat::D24_UNorm_S8_UInt.get_format_info().unwrap();
+    assert_eq!(
+        info.channels,
+        [
+            channel(Channel::Depth, 24, ComponentType::UNorm),
+            channel(Channel::Stencil, 8, ComponentType::UInt),
+        ]
+    );
+    assert!(info.depth_stencil && info.renderable && !info.sampleable);
+
+    // The first channel that is not X gives the component type
+    let info = DxgiFormat::X24_Typeless_G8_UInt.get_format_info().unwrap();
+    assert_eq!(info.component_type, ComponentType::UInt);
+    assert!(info.depth_stencil && !info.renderable);
+}
+
+#[test]
+fn block_format_info() {
+    let info = DxgiFormat::BC7_UNorm_sRGB.get_format_info().unwrap();
+    assert_eq!((info.block_width, info.block_height), (4, 4));
+    assert!(info.srgb && info.sampleable && !info.renderable);
+    assert!(info.channels.iter().all(|c| c.bits == 0));
+
+    let info = DxgiFormat::YUY2.get_format_info().unwrap();
+    assert_eq!(info.channels.len(), 4);
+    assert_eq!((info.block_width, info.block_height), (2, 1));
+    assert!(info.yuv && !info.planar && info.sampleable && !info.renderable);
+
+    let info = DxgiFormat::NV12.get_format_info().unwrap();
+    assert_eq!(
+        info.channels.iter().map(|c| c.channel).collect::<Vec<_>>(),
+        [Channel::Y, Channel::U, Channel::V]
+    );
+    assert_eq!((info.block_width, info.block_height), (2, 2));
+    assert!(info.yuv && info.planar && !info.sampleable);
+
+    let info = DxgiFormat::P010.get_format_info().unwrap();
+    assert_eq!(
+        info.channels[0],
+        channel(Channel::Y, 10, ComponentType::UNorm)
+    );
+}
+
+#[test]
+fn legacy_format_info() {
+    let info = D3DFormat::L8.get_format_info().unwrap();
+    assert_eq!(
+        info.channels,
+        [channel(Channel::Luminance, 8, ComponentType::UNorm)]
+    );
+    let info = D3DFormat::V8U8.get_format_info().unwrap();
+    assert_eq!(info.component_type, ComponentType::SNorm);
+    // Formats with a DXGI equivalent are described by it
+    assert_eq!(
+        D3DFormat::DXT1.get_format_info(),
+        DxgiFormat::BC1_UNorm.get_format_info()
+    );
+
+    let dds = new_legacy_texture(D3DFormat::A8L8, 4, 4);
+    assert_eq!(
+        dds.get_format_info().unwrap().channels,
+        [
+            channel(Channel::Luminance, 8, ComponentType::UNorm),
+            channel(Channel::A, 8, ComponentType::UNorm),
+        ]
+    );
+}
+
+#[test]
+fn packed_and_sub_byte_pitch() {
+    let pitches = [
+        (DxgiFormat::R1_UNorm, 10, 2),
+        (DxgiFormat::R8G8_B8G8_UNorm, 3, 8),
+        (DxgiFormat::YUY2, 5, 12),
+        (DxgiFormat::Y210, 3, 16),
+        (DxgiFormat::NV12, 5, 6),
+        (DxgiFormat::P010, 5, 12),
+        (DxgiFormat::NV11, 5, 8),
+        (DxgiFormat::V208, 5, 5),
+        (DxgiFormat::BC1_UNorm, 5, 16),
+    ];
+    for (format, width, pitch) in pitches {
+        assert_eq!(format.get_pitch(width), Some(pitch), "{:?}", format);
+    }
+}
+
+#[test]
+fn planar_layouts() {
+    let format = DxgiFormat::NV12;
+    assert_eq!(format.get_num_planes(), 2);
+    assert_eq!(
+        format.get_plane_layout(1, 5, 3),
+        Some(PlaneLayout {
+            offset: 18,
+            pitch: 6,
+            rows: 2,
+        })
+    );
+    assert_eq!(format.get_plane_layout(2, 5, 3), None);
+    assert_eq!(format.get_slice_size(5, 3), Some(30));
+
+    let format = DxgiFormat::V208;
+    assert_eq!(format.get_num_planes(), 3);
+    let offsets: Vec<_> = (0..3)
+        .map(|plane| format.get_plane_layout(plane, 4, 4).unwrap().offset)
+        .collect();
+    assert_eq!(offsets, [0, 16, 24]);
+    assert_eq!(format.get_slice_size(4, 4), Some(32));
+
+    let format = DxgiFormat::R8G8B8A8_UNorm;
+    assert_eq!(format.get_num_planes(), 1);
+    assert_eq!(format.get_slice_size(3, 2), Some(24));
+}
+
+#[test]
+fn oversized_slices() {
+    let layout = PlaneLayout {
+        offset: 0,
+        pitch: 0x10000,
+        rows: 0x10000,
+    };
+    assert_eq!(layout.size(), None);
+    assert_eq!(DxgiFormat::R8G8B8A8_UNorm.get_pitch(u32::MAX), None);
+    assert_eq!(
+        DxgiFormat::R8G8B8A8_UNorm.get_slice_size(65536, 65536),
+        None
+    );
+    assert_eq!(DxgiFormat::NV12.get_slice_size(65536, 65536), None);
+    assert_eq!(
+        DxgiFormat::BC1_UNorm.get_slice_size(65536, 65536),
+        Some(1 << 31)
+    );
+    assert_eq!(DxgiFormat::BC7_UNorm.get_slice_size(65536, 65536), None);
+
+    let result = Dds::new_dxgi(dxgi_params(DxgiFormat::R8G8B8A8_UNorm, 65536, 65536));
+    assert!(matches!(result, Err(Error::UnsupportedFormat)));
+}
+
+#[test]
+fn plane_data() {
+    let mut dds = new_mipmapped_texture(DxgiFormat::NV12, 4, 4, 2);
+    // 16 + 8 bytes for the first level, and 4 + 2 for the second
+    assert_eq!(dds.data.len(), 30);
+    for (i, byte) in dds.data.iter_mut().enumerate() {
+        *byte = i as u8;
+    }
+
+    assert_eq!(dds.get_plane_data(0, 0, 0).unwrap(), &dds.data[..16]);
+    assert_eq!(dds.get_plane_data(0, 0, 1).unwrap(), &dds.data[16..24]);
+    assert_eq!(dds.get_plane_data(0, 1, 0).unwrap(), &dds.data[24..28]);
+    assert_eq!(dds.get_plane_data(0, 1, 1).unwrap(), &dds.data[28..30]);
+
+    dds.get_mut_plane_data(0, 1, 1).unwrap().fill(0xff);
+    assert_eq!(dds.data[27..], [27, 0xff, 0xff]);
+
+    assert!(matches!(
+        dds.get_plane_layout(0, 2),
+        Err(Error::OutOfBounds)
+    ));
+    assert!(matches!(
+        dds.get_plane_layout(2, 0),
+        Err(Error::OutOfBounds)
+    ));
+    assert!(matches!(
+        dds.get_plane_data(1, 0, 0),
+        Err(Error::OutOfBounds)
+    ));
+}
+
+#[test]
+fn typeless_families() {
+    assert!(DxgiFormat::R8G8B8A8_Typeless.is_typeless());
+    assert!(!DxgiFormat::R8G8B8A8_UNorm.is_typeless());
+    assert_eq!(
+        DxgiFormat::R8G8B8A8_UNorm_sRGB.get_typeless_format(),
+        Some(DxgiFormat::R8G8B8A8_Typeless)
+    );
+    assert_eq!(
+        DxgiFormat::BC7_Typeless.get_typeless_format(),
+        Some(DxgiFormat::BC7_Typeless)
+    );
+    assert_eq!(DxgiFormat::R9G9B9E5_SharedExp.get_typeless_format(), None);
+
+    assert_eq!(
+        DxgiFormat::X24_Typeless_G8_UInt.get_typed_formats(),
+        [
+            DxgiFormat::D24_UNorm_S8_UInt,
+            DxgiFormat::R24_UNorm_X8_Typeless,
+            DxgiFormat::X24_Typeless_G8_UInt,
+        ]
+    );
+    assert!(DxgiFormat::YUY2.get_typed_formats().is_empty());
+}
+
+#[test]
+fn srgb_variants() {
+    assert!(DxgiFormat::BC7_UNorm_sRGB.is_srgb());
+    assert!(!DxgiFormat::BC7_UNorm.is_srgb());
+    assert_eq!(
+        DxgiFormat::BC7_UNorm.with_srgb(true),
+        Some(DxgiFormat::BC7_UNorm_sRGB)
+    );
+    assert_eq!(
+        DxgiFormat::B8G8R8A8_UNorm_sRGB.with_srgb(false),
+        Some(DxgiFormat::B8G8R8A8_UNorm)
+    );
+    assert_eq!(
+        DxgiFormat::BC1_UNorm.with_srgb(false),
+        Some(DxgiFormat::BC1_UNorm)
+    );
+    assert_eq!(DxgiFormat::R16G16B16A16_Float.with_srgb(true), None);
+}
+
+#[test]
+fn view_compatibility() {
+    assert!(DxgiFormat::R8G8B8A8_UNorm.is_view_compatible(DxgiFormat::R8G8B8A8_SInt));
+    assert!(DxgiFormat::R8G8B8A8_Typeless.is_view_compatible(DxgiFormat::R8G8B8A8_UNorm_sRGB));
+    assert!(DxgiFormat::D32_Float.is_view_compatible(DxgiFormat::R32_Float));
+    assert!(DxgiFormat::YUY2.is_view_compatible(DxgiFormat::YUY2));
+    assert!(!DxgiFormat::R8G8B8A8_UNorm.is_view_compatible(DxgiFormat::B8G8R8A8_UNorm));
+    assert!(!DxgiFormat::BC1_UNorm.is_view_compatible(DxgiFormat::BC3_UNorm));
+}
+
+#[test]
+fn legacy_dxgi_equivalents() {
+    let equivalents = [
+        (D3DFormat::A8R8G8B8, Some(DxgiFormat::B8G8R8A8_UNorm)),
+        (D3DFormat::A8B8G8R8, Some(DxgiFormat::R8G8B8A8_UNorm)),
+        (D3DFormat::L8, Some(DxgiFormat::R8_UNorm)),
+        // The premultiplied formats lose that in DXGI
+        (D3DFormat::DXT2, Some(DxgiFormat::BC2_UNorm)),
+        (D3DFormat::DXT4, Some(DxgiFormat::BC3_UNorm)),
+        (D3DFormat::UYVY, None),
+        (D3DFormat::R8G8B8, None),
+    ];
+    for (format, dxgi) in equivalents {
+        assert_eq!(format.to_dxgi_format(), dxgi, "{:?}", format);
+    }
+}
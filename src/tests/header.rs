@@ -0,0 +1,80 @@
+// The MIT License (MIT)
+//
+// Copyright (c) 2018 Michael Dilger
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+use super::{dxgi_params, new_legacy_texture};
+use crate::{D3DFormat, Dds, DxgiFormat, HeaderFlags, NewDxgiParams};
+
+fn new_array(format: DxgiFormat, size: u32, mipmap_levels: u32, array_layers: u32) -> Dds {
+    Dds::new_dxgi(NewDxgiParams {
+        mipmap_levels: Some(mipmap_levels),
+        array_layers: Some(array_layers),
+        ..dxgi_params(format, size, size)
+    })
+    .unwrap()
+}
+
+#[test]
+fn data_offset() {
+    let dds = new_array(DxgiFormat::R8_UNorm, 4, 1, 1);
+    assert_eq!(dds.get_data_offset(), 148);
+    let mut file = Vec::new();
+    dds.write(&mut file).unwrap();
+    assert_eq!(file.len(), dds.get_data_offset() + dds.data.len());
+
+    let dds = new_legacy_texture(D3DFormat::L8, 4, 4);
+    assert_eq!(dds.get_data_offset(), 128);
+    assert!(dds.header.get_flags().contains(HeaderFlags::PITCH));
+    let mut file = Vec::new();
+    dds.write(&mut file).unwrap();
+    assert_eq!(file.len(), dds.get_data_offset() + dds.data.len());
+}
+
+#[test]
+fn mipmap_offsets() {
+    // Levels of 64, 16 and 4 bytes in each of two layers
+    let dds = new_array(DxgiFormat::R8_UNorm, 8, 3, 2);
+    assert_eq!(dds.get_mipmap_offset_and_size(0, 0).unwrap(), (0, 64));
+    assert_eq!(dds.get_mipmap_offset_and_size(0, 2).unwrap(), (80, 4));
+    assert_eq!(dds.get_mipmap_offset_and_size(1, 1).unwrap(), (148, 16));
+    assert!(dds.get_mipmap_offset_and_size(2, 0).is_err());
+    assert!(dds.get_mipmap_offset_and_size(0, 3).is_err());
+}
+
+#[test]
+fn pitch_or_linear_size_flags() {
+    let dds = new_array(DxgiFormat::R8G8B8A8_UNorm, 8, 1, 1);
+    assert!(dds.header.get_flags().contains(HeaderFlags::PITCH));
+    assert!(!dds.header.get_flags().contains(HeaderFlags::LINEARSIZE));
+    assert_eq!(dds.header.pitch, Some(32));
+
+    let mut header = new_array(DxgiFormat::BC1_UNorm, 8, 1, 1).header;
+    assert!(header.get_flags().contains(HeaderFlags::LINEARSIZE));
+    assert_eq!(header.linear_size, Some(32));
+
+    // Switching to an uncompressed format swaps the field that is valid
+    header
+        .set_pitch_or_linear_size(&DxgiFormat::R16_Float)
+        .unwrap();
+    assert!(header.get_flags().contains(HeaderFlags::PITCH));
+    assert!(!header.get_flags().contains(HeaderFlags::LINEARSIZE));
+    assert_eq!((header.pitch, header.linear_size), (Some(16), None));
+}
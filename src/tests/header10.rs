@@ -0,0 +1,124 @@
+// The MIT License (MIT)
+//
+// Copyright (c) 2018 Michael Dilger
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+use super::{d3d_params, new_texture};
+use crate::{
+    AlphaMode, Caps2, D3D10ResourceDimension, D3DFormat, Dds, DxgiFormat, Error, NewD3dParams,
+};
+
+#[test]
+fn unknown_dxgi_format_round_trip() {
+    let mut dds = new_texture(DxgiFormat::R8G8B8A8_UNorm, 4, 4);
+    dds.header10.as_mut().unwrap().set_dxgi_format_value(0x1234);
+    let mut bytes = Vec::new();
+    dds.write(&mut bytes).unwrap();
+
+    let read = Dds::read(&bytes[..]).unwrap();
+    let h10 = read.header10.as_ref().unwrap();
+    assert_eq!(h10.dxgi_format, DxgiFormat::Unknown);
+    assert_eq!(h10.get_unknown_dxgi_format(), Some(0x1234));
+    assert_eq!(h10.get_dxgi_format_value(), 0x1234);
+    assert_eq!(read.data, dds.data);
+
+    let mut rewritten = Vec::new();
+    read.write(&mut rewritten).unwrap();
+    assert_eq!(rewritten, bytes);
+}
+
+#[test]
+fn known_dxgi_format_value() {
+    let mut dds = new_texture(DxgiFormat::R8G8B8A8_UNorm, 4, 4);
+    let h10 = dds.header10.as_mut().unwrap();
+    h10.set_dxgi_format_value(0x1234);
+    h10.set_dxgi_format_value(DxgiFormat::BC7_UNorm as u32);
+    assert_eq!(h10.dxgi_format, DxgiFormat::BC7_UNorm);
+    assert_eq!(h10.get_unknown_dxgi_format(), None);
+
+    // Choosing a format directly hides the unknown value
+    h10.set_dxgi_format_value(0x1234);
+    h10.dxgi_format = DxgiFormat::BC1_UNorm;
+    assert_eq!(h10.get_dxgi_format_value(), DxgiFormat::BC1_UNorm as u32);
+}
+
+fn legacy(format: D3DFormat, depth: Option<u32>, caps2: Option<Caps2>) -> Dds {
+    Dds::new_d3d(NewD3dParams {
+        depth,
+        mipmap_levels: Some(2),
+        caps2,
+        ..d3d_params(format, 4, 4)
+    })
+    .unwrap()
+}
+
+#[test]
+fn upgrade_legacy_cubemap() {
+    let mut dds = legacy(
+        D3DFormat::A8R8G8B8,
+        None,
+        Some(Caps2::CUBEMAP | Caps2::CUBEMAP_ALLFACES),
+    );
+    for (i, byte) in dds.data.iter_mut().enumerate() {
+        *byte = i as u8;
+    }
+    let data = dds.data.clone();
+    dds.upgrade_to_dx10().unwrap();
+
+    let h10 = dds.header10.as_ref().unwrap();
+    assert_eq!(h10.dxgi_format, DxgiFormat::B8G8R8A8_UNorm);
+    assert_eq!(h10.resource_dimension, D3D10ResourceDimension::Texture2D);
+    assert!(dds.is_cubemap());
+    assert_eq!(dds.get_num_array_layers(), 6);
+    assert_eq!(dds.data, data);
+
+    let mut file = Vec::new();
+    dds.write(&mut file).unwrap();
+    let read = Dds::read(&file[..]).unwrap();
+    assert_eq!(read.get_dxgi_format(), Some(DxgiFormat::B8G8R8A8_UNorm));
+    assert_eq!(read.get_num_array_layers(), 6);
+    assert_eq!(read.data, data);
+}
+
+#[test]
+fn upgrade_legacy_formats() {
+    let mut dds = legacy(D3DFormat::DXT5, None, None);
+    dds.upgrade_to_dx10().unwrap();
+    assert_eq!(dds.get_dxgi_format(), Some(DxgiFormat::BC3_UNorm));
+
+    let mut dds = legacy(D3DFormat::L8, Some(4), Some(Caps2::VOLUME));
+    dds.upgrade_to_dx10().unwrap();
+    let h10 = dds.header10.as_ref().unwrap();
+    assert_eq!(h10.dxgi_format, DxgiFormat::R8_UNorm);
+    assert_eq!(h10.resource_dimension, D3D10ResourceDimension::Texture3D);
+
+    // A DX10 header is kept as it is
+    let mut dds = new_texture(DxgiFormat::R8G8B8A8_UNorm, 4, 4);
+    dds.header10.as_mut().unwrap().alpha_mode = AlphaMode::PreMultiplied;
+    dds.upgrade_to_dx10().unwrap();
+    assert_eq!(dds.get_alpha_mode(), AlphaMode::PreMultiplied);
+
+    let mut dds = legacy(D3DFormat::UYVY, None, None);
+    assert!(matches!(
+        dds.upgrade_to_dx10(),
+        Err(Error::UnsupportedFormat)
+    ));
+    assert!(dds.header10.is_none());
+}
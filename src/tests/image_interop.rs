@@ -0,0 +1,66 @@
+// The MIT License (MIT)
+//
+// Copyright (c) 2018 Michael Dilger
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+use crate::{Dds, DxgiFormat, Error};
+use image::{DynamicImage, Rgba, Rgba32FImage, RgbaImage};
+
+fn pattern() -> RgbaImage {
+    RgbaImage::from_fn(6, 5, |x, y| {
+        Rgba([(x * 40) as u8, (y * 60) as u8, (x * y * 9) as u8, 200])
+    })
+}
+
+#[test]
+fn rgba8_round_trip() {
+    let image = pattern();
+    let dds = Dds::from_rgba_image(&image, DxgiFormat::R8G8B8A8_UNorm).unwrap();
+    assert_eq!(dds.data, image.as_raw()[..]);
+    match DynamicImage::try_from(&dds).unwrap() {
+        DynamicImage::ImageRgba8(decoded) => assert_eq!(decoded, image),
+        other => panic!("expected an RGBA8 image, got {:?}", other.color()),
+    }
+}
+
+#[test]
+fn image_types_follow_the_format() {
+    let image = pattern();
+    let rgba16 = Dds::from_rgba_image(&image, DxgiFormat::R16G16B16A16_UNorm).unwrap();
+    let decoded = rgba16.to_image(0, 0).unwrap();
+    assert!(matches!(decoded, DynamicImage::ImageRgba16(_)));
+    assert_eq!(decoded.to_rgba8(), image);
+
+    // Float formats keep values outside 0.0 to 1.0
+    let hdr = Rgba32FImage::from_pixel(4, 4, Rgba([2.5, -1.0, 0.5, 1.0]));
+    let float = Dds::from_rgba32f_image(&hdr, DxgiFormat::R16G16B16A16_Float).unwrap();
+    match float.to_image(0, 0).unwrap() {
+        DynamicImage::ImageRgba32F(decoded) => assert_eq!(decoded, hdr),
+        other => panic!("expected an RGBA32F image, got {:?}", other.color()),
+    }
+}
+
+#[test]
+fn unsupported_format() {
+    assert!(matches!(
+        Dds::from_rgba_image(&pattern(), DxgiFormat::Unknown),
+        Err(Error::UnsupportedFormat)
+    ));
+}
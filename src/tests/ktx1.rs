@@ -0,0 +1,203 @@
+// The MIT License (MIT)
+//
+// Copyright (c) 2018 Michael Dilger
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+use super::{d3d_params, dxgi_params};
+use crate::{D3DFormat, Dds, DxgiFormat, Error, NewD3dParams, NewDxgiParams};
+
+fn texture(format: DxgiFormat, array_layers: u32, is_cubemap: bool) -> Dds {
+    let mut dds = Dds::new_dxgi(NewDxgiParams {
+        mipmap_levels: Some(4),
+        array_layers: Some(array_layers),
+        is_cubemap,
+        ..dxgi_params(format, 8, 8)
+    })
+    .unwrap();
+    fill(&mut dds);
+    dds
+}
+
+fn fill(dds: &mut Dds) {
+    for (i, byte) in dds.data.iter_mut().enumerate() {
+        *byte = (i * 7) as u8;
+    }
+}
+
+fn ktx1_bytes(dds: &Dds) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    dds.write_ktx1(&mut bytes).unwrap();
+    bytes
+}
+
+fn set_u32(bytes: &mut [u8], offset: usize, value: u32) {
+    bytes[offset..offset + 4].copy_from_slice(&value.to_le_bytes());
+}
+
+#[test]
+fn round_trip() {
+    for dds in [
+        texture(DxgiFormat::R8G8B8A8_UNorm, 1, false),
+        texture(DxgiFormat::BC3_UNorm, 3, false),
+        texture(DxgiFormat::BC1_UNorm, 6, true),
+    ] {
+        let read = Dds::read_ktx1(&ktx1_bytes(&dds)[..]).unwrap();
+        assert_eq!(read.get_dxgi_format(), dds.get_dxgi_format());
+        assert_eq!(read.is_cubemap(), dds.is_cubemap());
+        assert_eq!(read.get_num_array_layers(), dds.get_num_array_layers());
+        assert_eq!(read.get_num_mipmap_levels(), 4);
+        assert_eq!(read.data, dds.data);
+    }
+}
+
+#[test]
+fn legacy_format_round_trip() {
+    let mut dds = Dds::new_d3d(NewD3dParams {
+        mipmap_levels: Some(3),
+        ..d3d_params(D3DFormat::L8, 6, 4)
+    })
+    .unwrap();
+    fill(&mut dds);
+    let read = Dds::read_ktx1(&ktx1_bytes(&dds)[..]).unwrap();
+    assert_eq!(read.get_d3d_format(), Some(D3DFormat::L8));
+    assert_eq!((read.get_width(), read.get_height()), (6, 4));
+    assert_eq!(read.data, dds.data);
+}
+
+#[test]
+fn huge_key_value_data() {
+    // Skipped without allocating, so the short file is what gets reported
+    let mut bytes = ktx1_bytes(&texture(DxgiFormat::R8G8B8A8_UNorm, 1, false));
+    set_u32(&mut bytes, 60, u32::MAX);
+    assert!(matches!(
+        Dds::read_ktx1(&bytes[..]),
+        Err(Error::ShortFile { .. })
+    ));
+}
+
+#[test]
+fn huge_mipmap_level_count() {
+    let mut bytes = ktx1_bytes(&texture(DxgiFormat::R8G8B8A8_UNorm, 1, false));
+    set_u32(&mut bytes, 56, u32::MAX);
+    match Dds::read_ktx1(&bytes[..]) {
+        Err(Error::InvalidField {
+            field,
+            offset,
+            expected,
+            ..
+        }) => {
+            assert_eq!((field, offset), ("numberOfMipmapLevels", Some(56)));
+            assert_eq!(expected.to_string(), "0 to 4");
+        }
+        other => panic!("expected InvalidField, got {:?}", other.err()),
+    }
+}
+
+#[test]
+fn huge_array_element_count() {
+    // Six faces of this many elements overflow the layer count
+    let mut bytes = ktx1_bytes(&texture(DxgiFormat::R8G8B8A8_UNorm, 1, false));
+    set_u32(&mut bytes, 48, 0x30000000);
+    set_u32(&mut bytes, 52, 6);
+    match Dds::read_ktx1(&bytes[..]) {
+        Err(Error::InvalidField {
+            field,
+            offset,
+            value,
+            ..
+        }) => assert_eq!(
+            (field, offset, value),
+            ("numberOfArrayElements", Some(48), 0x30000000)
+        ),
+        other => panic!("expected InvalidField, got {:?}", other.err()),
+    }
+
+    // The first imageSize has to cover every element before the texture is
+    // allocated
+    let mut bytes = ktx1_bytes(&texture(DxgiFormat::R8G8B8A8_UNorm, 1, false));
+    set_u32(&mut bytes, 48, 0x10000000);
+    match Dds::read_ktx1(&bytes[..]) {
+        Err(Error::InvalidField {
+            field, expected, ..
+        }) => {
+            assert_eq!(field, "imageSize");
+            assert_eq!(expected.to_string(), format!("{}", 256_u64 << 28));
+        }
+        other => panic!("expected InvalidField, got {:?}", other.err()),
+    }
+}
+
+#[test]
+fn huge_image_size() {
+    // An imageSize that matches the header is only trusted as far as the
+    // input goes
+    let mut bytes = ktx1_bytes(&texture(DxgiFormat::R8G8B8A8_UNorm, 1, false));
+    bytes.truncate(68);
+    set_u32(&mut bytes, 36, 16384);
+    set_u32(&mut bytes, 40, 16384);
+    set_u32(&mut bytes, 56, 1);
+    set_u32(&mut bytes, 60, 0);
+    set_u32(&mut bytes, 64, 1 << 30);
+    assert!(matches!(
+        Dds::read_ktx1(&bytes[..]),
+        Err(Error::ShortFile { .. })
+    ));
+}
+
+#[test]
+fn oversized_texture() {
+    // A level that does not fit in a u32 is refused rather than overflowing
+    let mut bytes = ktx1_bytes(&texture(DxgiFormat::R8G8B8A8_UNorm, 1, false));
+    bytes.truncate(64);
+    set_u32(&mut bytes, 36, 65536);
+    set_u32(&mut bytes, 40, 65536);
+    set_u32(&mut bytes, 56, 1);
+    set_u32(&mut bytes, 60, 0);
+    assert!(Dds::read_ktx1(&bytes[..]).is_err());
+    bytes.extend_from_slice(&0_u32.to_le_bytes());
+    assert!(Dds::read_ktx1(&bytes[..]).is_err());
+}
+
+#[test]
+fn bad_header() {
+    let bytes = ktx1_bytes(&texture(DxgiFormat::R8G8B8A8_UNorm, 1, false));
+    for (offset, value, field) in [(12, 0x12345678, "endianness"), (52, 2, "numberOfFaces")] {
+        let mut bytes = bytes.clone();
+        set_u32(&mut bytes, offset, value);
+        match Dds::read_ktx1(&bytes[..]) {
+            Err(Error::InvalidField { field: found, .. }) => assert_eq!(found, field),
+            other => panic!("expected InvalidField, got {:?}", other.err()),
+        }
+    }
+
+    let mut bytes = bytes;
+    bytes[0] = 0;
+    assert!(matches!(
+        Dds::read_ktx1(&bytes[..]),
+        Err(Error::BadMagicNumber)
+    ));
+}
+
+#[test]
+fn truncated_data() {
+    let mut bytes = ktx1_bytes(&texture(DxgiFormat::R8G8B8A8_UNorm, 1, false));
+    bytes.truncate(bytes.len() - 8);
+    assert!(Dds::read_ktx1(&bytes[..]).is_err());
+}
@@ -0,0 +1,169 @@
+// The MIT License (MIT)
+//
+// Copyright (c) 2018 Michael Dilger
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+use super::dxgi_params;
+use crate::{Dds, DxgiFormat, Error, NewDxgiParams};
+
+fn texture(format: DxgiFormat, array_layers: u32, is_cubemap: bool) -> Dds {
+    let mut dds = Dds::new_dxgi(NewDxgiParams {
+        mipmap_levels: Some(4),
+        array_layers: Some(array_layers),
+        is_cubemap,
+        ..dxgi_params(format, 8, 8)
+    })
+    .unwrap();
+    for (i, byte) in dds.data.iter_mut().enumerate() {
+        *byte = (i * 7) as u8;
+    }
+    dds
+}
+
+fn ktx2_bytes(dds: &Dds) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    dds.write_ktx2(&mut bytes).unwrap();
+    bytes
+}
+
+fn set_u32(bytes: &mut [u8], offset: usize, value: u32) {
+    bytes[offset..offset + 4].copy_from_slice(&value.to_le_bytes());
+}
+
+#[test]
+fn round_trip() {
+    for dds in [
+        texture(DxgiFormat::R8G8B8A8_UNorm, 1, false),
+        texture(DxgiFormat::BC1_UNorm_sRGB, 3, false),
+        texture(DxgiFormat::BC7_UNorm, 6, true),
+    ] {
+        let read = Dds::read_ktx2(&ktx2_bytes(&dds)[..]).unwrap();
+        let (h10, expected) = (read.header10.as_ref().unwrap(), dds.header10.as_ref());
+        assert_eq!(h10.dxgi_format, expected.unwrap().dxgi_format);
+        assert_eq!(read.is_cubemap(), dds.is_cubemap());
+        assert_eq!(read.get_num_array_layers(), dds.get_num_array_layers());
+        assert_eq!(read.get_num_mipmap_levels(), 4);
+        assert_eq!(read.data, dds.data);
+    }
+}
+
+#[test]
+fn bad_identifier() {
+    let mut bytes = ktx2_bytes(&texture(DxgiFormat::R8G8B8A8_UNorm, 1, false));
+    bytes[1] = b'X';
+    assert!(matches!(
+        Dds::read_ktx2(&bytes[..]),
+        Err(Error::BadMagicNumber)
+    ));
+}
+
+#[test]
+fn huge_level_count() {
+    // Only a header: the level count must be rejected before the level index
+    // is read or allocated
+    let mut bytes = ktx2_bytes(&texture(DxgiFormat::R8G8B8A8_UNorm, 1, false));
+    bytes.truncate(104);
+    set_u32(&mut bytes, 40, u32::MAX);
+    match Dds::read_ktx2(&bytes[..]) {
+        Err(Error::InvalidField {
+            field,
+            offset,
+            value,
+            ..
+        }) => assert_eq!(
+            (field, offset, value),
+            ("levelCount", Some(40), u32::MAX as u64)
+        ),
+        other => panic!("expected InvalidField, got {:?}", other.err()),
+    }
+}
+
+#[test]
+fn huge_layer_count() {
+    let mut bytes = ktx2_bytes(&texture(DxgiFormat::R8G8B8A8_UNorm, 1, false));
+    set_u32(&mut bytes, 32, u32::MAX);
+    assert!(matches!(
+        Dds::read_ktx2(&bytes[..]),
+        Err(Error::ShortFile { .. })
+    ));
+}
+
+#[test]
+fn bad_face_count() {
+    let mut bytes = ktx2_bytes(&texture(DxgiFormat::R8G8B8A8_UNorm, 1, false));
+    set_u32(&mut bytes, 36, 2);
+    assert!(matches!(
+        Dds::read_ktx2(&bytes[..]),
+        Err(Error::InvalidField {
+            field: "faceCount",
+            ..
+        })
+    ));
+}
+
+#[test]
+fn huge_byte_offset() {
+    let mut bytes = ktx2_bytes(&texture(DxgiFormat::R8G8B8A8_UNorm, 1, false));
+    bytes[80..88].copy_from_slice(&u64::MAX.to_le_bytes());
+    assert!(matches!(
+        Dds::read_ktx2(&bytes[..]),
+        Err(Error::ShortFile { .. })
+    ));
+}
+
+#[test]
+fn oversized_base_level() {
+    // The base level is checked against the header before anything is
+    // allocated for it
+    let mut bytes = ktx2_bytes(&texture(DxgiFormat::R8G8B8A8_UNorm, 1, false));
+    set_u32(&mut bytes, 20, 16384);
+    set_u32(&mut bytes, 24, 16384);
+    set_u32(&mut bytes, 40, 1);
+    match Dds::read_ktx2(&bytes[..]) {
+        Err(Error::InvalidField {
+            field, expected, ..
+        }) => {
+            assert_eq!(field, "levelIndex.byteLength");
+            assert_eq!(expected.to_string(), format!("{}", 1_u64 << 30));
+        }
+        other => panic!("expected InvalidField, got {:?}", other.err()),
+    }
+
+    // A byteLength that matches is only trusted as far as the input goes
+    bytes[88..96].copy_from_slice(&(1_u64 << 30).to_le_bytes());
+    assert!(matches!(
+        Dds::read_ktx2(&bytes[..]),
+        Err(Error::ShortFile { .. })
+    ));
+
+    set_u32(&mut bytes, 20, 65536);
+    set_u32(&mut bytes, 24, 65536);
+    assert!(matches!(
+        Dds::read_ktx2(&bytes[..]),
+        Err(Error::UnsupportedFormat)
+    ));
+}
+
+#[test]
+fn truncated_data() {
+    let mut bytes = ktx2_bytes(&texture(DxgiFormat::R8G8B8A8_UNorm, 1, false));
+    bytes.truncate(bytes.len() - 1);
+    assert!(Dds::read_ktx2(&bytes[..]).is_err());
+}
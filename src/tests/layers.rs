@@ -0,0 +1,102 @@
+// The MIT License (MIT)
+//
+// Copyright (c) 2018 Michael Dilger
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+use super::{d3d_params, dxgi_params};
+use crate::{
+    Caps2, D3D10ResourceDimension, D3DFormat, Dds, DxgiFormat, NewD3dParams, NewDxgiParams,
+};
+
+fn new_dxgi(
+    format: DxgiFormat,
+    (width, height, depth): (u32, u32, Option<u32>),
+    mipmap_levels: u32,
+    array_layers: u32,
+    is_cubemap: bool,
+) -> Dds {
+    Dds::new_dxgi(NewDxgiParams {
+        depth,
+        mipmap_levels: Some(mipmap_levels),
+        array_layers: Some(array_layers),
+        is_cubemap,
+        resource_dimension: match depth {
+            Some(_) => D3D10ResourceDimension::Texture3D,
+            None => D3D10ResourceDimension::Texture2D,
+        },
+        ..dxgi_params(format, width, height)
+    })
+    .unwrap()
+}
+
+#[test]
+fn legacy_cubemap() {
+    let dds = Dds::new_d3d(NewD3dParams {
+        mipmap_levels: Some(4),
+        caps2: Some(Caps2::CUBEMAP | Caps2::CUBEMAP_ALLFACES),
+        ..d3d_params(D3DFormat::A8R8G8B8, 8, 8)
+    })
+    .unwrap();
+    assert_eq!(dds.get_num_array_layers(), 6);
+    assert_eq!(dds.get_array_stride().unwrap(), 256 + 64 + 16 + 4);
+    assert_eq!(dds.data.len(), 340 * 6);
+}
+
+#[test]
+fn dx10_cubemap_array() {
+    let dds = new_dxgi(DxgiFormat::R8G8B8A8_UNorm, (8, 8, None), 4, 12, true);
+    assert_eq!(dds.header10.as_ref().unwrap().array_size, 2);
+    assert_eq!(dds.get_num_array_layers(), 12);
+    assert_eq!(dds.get_array_stride().unwrap(), 340);
+    assert_eq!(dds.data.len(), 340 * 12);
+}
+
+#[test]
+fn compressed_array() {
+    // Levels below 4x4 still take a whole block
+    let dds = new_dxgi(DxgiFormat::BC1_UNorm, (8, 8, None), 4, 3, false);
+    assert_eq!(dds.get_num_array_layers(), 3);
+    let sizes: Vec<u32> = (0..4).map(|l| dds.get_mipmap_size(l).unwrap()).collect();
+    assert_eq!(sizes, [32, 8, 8, 8]);
+    assert_eq!(dds.get_array_stride().unwrap(), 56);
+    assert_eq!(dds.data.len(), 56 * 3);
+}
+
+#[test]
+fn volume() {
+    // Depth halves along with width and height
+    let dds = new_dxgi(DxgiFormat::R8G8B8A8_UNorm, (4, 4, Some(4)), 3, 1, false);
+    assert_eq!(dds.get_num_array_layers(), 1);
+    let sizes: Vec<u32> = (0..3).map(|l| dds.get_mipmap_size(l).unwrap()).collect();
+    assert_eq!(sizes, [256, 32, 4]);
+    assert_eq!(dds.get_array_stride().unwrap(), 292);
+    assert_eq!(dds.data.len(), 292);
+}
+
+#[test]
+fn cubemap_survives_a_file() {
+    let dds = new_dxgi(DxgiFormat::BC3_UNorm, (16, 16, None), 5, 6, true);
+    let mut bytes = Vec::new();
+    dds.write(&mut bytes).unwrap();
+    let read = Dds::read(&bytes[..]).unwrap();
+    assert_eq!(read.get_num_array_layers(), 6);
+    assert_eq!(read.get_array_stride().unwrap(), 256 + 64 + 16 * 3);
+    assert_eq!(read.data.len(), dds.data.len());
+}
@@ -0,0 +1,128 @@
+// The MIT License (MIT)
+//
+// Copyright (c) 2018 Michael Dilger
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+use super::new_mipmapped_texture;
+use crate::{Dds, DxgiFormat, Error, Rotation};
+
+// A 3x2 texture whose texels count up row by row
+fn counting() -> Dds {
+    let mut dds = new_mipmapped_texture(DxgiFormat::R8_UNorm, 3, 2, 1);
+    dds.data = (0..6).collect();
+    dds
+}
+
+#[test]
+fn flips_and_rotations() {
+    let dds = counting();
+    let flipped = dds.flip_vertical().unwrap();
+    assert_eq!(flipped.data, [3, 4, 5, 0, 1, 2]);
+    let mirrored = dds.flip_horizontal().unwrap();
+    assert_eq!(mirrored.data, [2, 1, 0, 5, 4, 3]);
+
+    for (rotation, size, data) in [
+        (Rotation::Rotate90, (2, 3), [3, 0, 4, 1, 5, 2]),
+        (Rotation::Rotate180, (3, 2), [5, 4, 3, 2, 1, 0]),
+        (Rotation::Rotate270, (2, 3), [2, 5, 1, 4, 0, 3]),
+    ] {
+        let rotated = dds.rotate(rotation).unwrap();
+        assert_eq!((rotated.get_width(), rotated.get_height()), size);
+        assert_eq!(rotated.data, data);
+    }
+}
+
+#[test]
+fn crop() {
+    let cropped = counting().crop(1, 0, 2, 2).unwrap();
+    assert_eq!((cropped.get_width(), cropped.get_height()), (2, 2));
+    assert_eq!(cropped.data, [1, 2, 4, 5]);
+
+    for (x, y, width, height, field) in [
+        (3, 0, 1, 1, "x"),
+        (0, 2, 1, 1, "y"),
+        (1, 0, 3, 1, "width"),
+        (0, 0, 1, 0, "height"),
+    ] {
+        match counting().crop(x, y, width, height) {
+            Err(Error::InvalidField { field: found, .. }) => assert_eq!(found, field),
+            other => panic!("expected InvalidField, got {:?}", other.map(|d| d.data)),
+        }
+    }
+}
+
+#[test]
+fn every_mipmap_level_is_flipped() {
+    let mut dds = new_mipmapped_texture(DxgiFormat::R8_UNorm, 4, 2, 2);
+    dds.data = (0..10).collect();
+    let flipped = dds.flip_vertical().unwrap();
+    assert_eq!(flipped.data, [4, 5, 6, 7, 0, 1, 2, 3, 8, 9]);
+}
+
+#[test]
+fn block_flips_are_lossless() {
+    let mut dds = new_mipmapped_texture(DxgiFormat::BC1_UNorm, 8, 8, 1);
+    let rgba: Vec<f32> = (0..64)
+        .flat_map(|i| {
+            let (x, y) = ((i % 8) as f32 / 7.0, (i / 8) as f32 / 7.0);
+            [x, y, 1.0 - x, 1.0]
+        })
+        .collect();
+    dds.encode_rgba32f(0, 0, &rgba).unwrap();
+    let decoded = dds.decode_rgba32f(0, 0).unwrap();
+
+    // Flipping the blocks matches flipping the decoded texels
+    let flipped = dds.flip_vertical().unwrap();
+    let flipped_decoded = flipped.decode_rgba32f(0, 0).unwrap();
+    for y in 0..8 {
+        let row = |data: &[f32], y: usize| data[y * 32..y * 32 + 32].to_vec();
+        assert_eq!(row(&flipped_decoded, y), row(&decoded, 7 - y));
+    }
+
+    let turned = dds
+        .rotate(Rotation::Rotate180)
+        .unwrap()
+        .flip_horizontal()
+        .unwrap()
+        .flip_vertical()
+        .unwrap();
+    assert_eq!(turned.data, dds.data);
+}
+
+#[cfg(feature = "image")]
+#[test]
+fn resize() {
+    use image::imageops::FilterType;
+
+    let mut dds = new_mipmapped_texture(DxgiFormat::R32G32B32A32_Float, 4, 4, 3);
+    for level in 0..3 {
+        let (w, h, _) = dds.get_mipmap_dimensions(level);
+        let rgba = [0.25, 0.5, 0.75, 1.0].repeat((w * h) as usize);
+        dds.encode_rgba32f(0, level, &rgba).unwrap();
+    }
+    let resized = dds.resize(8, 2, FilterType::Triangle).unwrap();
+    assert_eq!((resized.get_width(), resized.get_height()), (8, 2));
+    assert_eq!(resized.get_num_mipmap_levels(), 3);
+    for level in 0..3 {
+        let (w, h, _) = resized.get_mipmap_dimensions(level);
+        let rgba = resized.decode_rgba32f(0, level).unwrap();
+        assert_eq!(rgba, [0.25, 0.5, 0.75, 1.0].repeat((w * h) as usize));
+    }
+}
@@ -0,0 +1,174 @@
+// The MIT License (MIT)
+//
+// Copyright (c) 2018 Michael Dilger
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+use crate::{Dds, DxgiFormat, Error, YuvMatrix, YuvRange};
+
+fn solid(width: u32, height: u32, rgb: [f32; 3]) -> Vec<f32> {
+    (0..width * height)
+        .flat_map(|_| [rgb[0], rgb[1], rgb[2], 1.0])
+        .collect()
+}
+
+#[test]
+fn nv12_limited_range_codes() {
+    // The usual BT.601 codes for white, black and red
+    for (rgb, codes) in [
+        ([1.0, 1.0, 1.0], [235, 128, 128]),
+        ([0.0, 0.0, 0.0], [16, 128, 128]),
+        ([1.0, 0.0, 0.0], [81, 90, 240]),
+    ] {
+        let dds = Dds::from_rgba32f_yuv(
+            2,
+            2,
+            &solid(2, 2, rgb),
+            DxgiFormat::NV12,
+            YuvMatrix::Bt601,
+            YuvRange::Limited,
+        )
+        .unwrap();
+        let [y, u, v] = codes;
+        assert_eq!(dds.data, [y, y, y, y, u, v]);
+
+        let rgba = dds
+            .decode_yuv_rgba32f(0, 0, YuvMatrix::Bt601, YuvRange::Limited)
+            .unwrap();
+        for texel in rgba.chunks_exact(4) {
+            for c in 0..3 {
+                assert!((texel[c] - rgb[c]).abs() < 0.01, "{:?}", texel);
+            }
+            assert_eq!(texel[3], 1.0);
+        }
+    }
+}
+
+#[test]
+fn p010_full_range_codes() {
+    // Ten bit codes sit in the high bits of each 16 bit sample
+    let dds = Dds::from_rgba32f_yuv(
+        2,
+        2,
+        &solid(2, 2, [1.0, 1.0, 1.0]),
+        DxgiFormat::P010,
+        YuvMatrix::Bt709,
+        YuvRange::Full,
+    )
+    .unwrap();
+    assert_eq!(
+        dds.data[..8],
+        [0xC0, 0xFF, 0xC0, 0xFF, 0xC0, 0xFF, 0xC0, 0xFF]
+    );
+    let half = (512_u16 << 6).to_le_bytes();
+    assert_eq!(dds.data[8..], [half[0], half[1], half[0], half[1]]);
+    let rgba8 = dds
+        .decode_yuv_rgba8(0, 0, YuvMatrix::Bt709, YuvRange::Full)
+        .unwrap();
+    assert!(rgba8.iter().all(|&v| v >= 254));
+}
+
+#[test]
+fn chroma_is_averaged() {
+    // NV11 shares chroma across four texels of a row, and a mix of red and
+    // blue has the chroma of their average
+    let mut rgba = solid(2, 1, [1.0, 0.0, 0.0]);
+    rgba.extend(solid(2, 1, [0.0, 0.0, 1.0]));
+    let dds = Dds::from_rgba32f_yuv(
+        4,
+        1,
+        &rgba,
+        DxgiFormat::NV11,
+        YuvMatrix::Bt709,
+        YuvRange::Full,
+    )
+    .unwrap();
+    let average = Dds::from_rgba32f_yuv(
+        4,
+        1,
+        &solid(4, 1, [0.5, 0.0, 0.5]),
+        DxgiFormat::NV11,
+        YuvMatrix::Bt709,
+        YuvRange::Full,
+    )
+    .unwrap();
+    let chroma = dds.get_plane_data(0, 0, 1).unwrap();
+    let expected = average.get_plane_data(0, 0, 1).unwrap();
+    for (found, expected) in chroma.iter().zip(expected) {
+        assert!(found.abs_diff(*expected) <= 1);
+    }
+}
+
+#[test]
+fn grey_round_trip() {
+    // Greys have no chroma, so subsampling loses nothing
+    let rgba: Vec<f32> = (0..16)
+        .flat_map(|i| {
+            let v = i as f32 / 15.0;
+            [v, v, v, 1.0]
+        })
+        .collect();
+    for format in [DxgiFormat::NV12, DxgiFormat::P016, DxgiFormat::P208] {
+        let dds = Dds::from_rgba32f_yuv(4, 4, &rgba, format, YuvMatrix::Bt2020, YuvRange::Limited)
+            .unwrap();
+        let decoded = dds
+            .decode_yuv_rgba32f(0, 0, YuvMatrix::Bt2020, YuvRange::Limited)
+            .unwrap();
+        for (found, expected) in decoded.iter().zip(&rgba) {
+            assert!((found - expected).abs() <= 0.5 / 219.0 + 1e-6);
+        }
+    }
+}
+
+#[test]
+fn not_planar() {
+    let result = Dds::from_rgba32f_yuv(
+        2,
+        2,
+        &solid(2, 2, [0.0; 3]),
+        DxgiFormat::R8G8B8A8_UNorm,
+        YuvMatrix::Bt601,
+        YuvRange::Full,
+    );
+    assert!(matches!(result, Err(Error::UnsupportedFormat)));
+}
+
+#[test]
+fn wrong_rgba_length() {
+    let result = Dds::from_rgba32f_yuv(
+        2,
+        2,
+        &solid(2, 1, [0.0; 3]),
+        DxgiFormat::NV12,
+        YuvMatrix::Bt601,
+        YuvRange::Full,
+    );
+    match result {
+        Err(Error::Mismatch {
+            property,
+            found,
+            expected,
+            ..
+        }) => assert_eq!(
+            (property, &*found, &*expected),
+            ("RGBA float count", "8", "16")
+        ),
+        other => panic!("expected Mismatch, got {:?}", other.err()),
+    }
+}
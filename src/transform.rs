@@ -0,0 +1,356 @@
+// The MIT License (MIT)
+//
+// Copyright (c) 2018 Michael Dilger
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+// Flipping, rotating, cropping and resizing textures
+
+#[cfg(feature = "image")]
+use crate::alpha::{premultiply, unpremultiply};
+use crate::codec::Layout;
+#[cfg(feature = "image")]
+use crate::codec::{linear_to_srgb, srgb_to_linear};
+#[cfg(feature = "image")]
+use crate::AlphaMode;
+use crate::{Dds, Error, Expected};
+#[cfg(feature = "image")]
+use image::imageops::{self, FilterType};
+#[cfg(feature = "image")]
+use image::Rgba32FImage;
+
+/// A clockwise rotation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rotation {
+    /// A quarter turn clockwise
+    Rotate90,
+    /// A half turn
+    Rotate180,
+    /// A quarter turn counterclockwise
+    Rotate270,
+}
+
+// A rearrangement of the texels of a surface
+#[derive(Debug, Clone, Copy)]
+enum Transform {
+    FlipVertical,
+    FlipHorizontal,
+    Rotate(Rotation),
+    // The left and top of the region kept
+    Crop(u32, u32),
+}
+
+impl Transform {
+    // The position in a source of the given width and height of a texel (or
+    // unit of storage) of the result
+    fn source(self, x: u32, y: u32, width: u32, height: u32) -> (u32, u32) {
+        match self {
+            Transform::FlipVertical => (x, height - 1 - y),
+            Transform::FlipHorizontal => (width - 1 - x, y),
+            Transform::Rotate(Rotation::Rotate90) => (y, height - 1 - x),
+            Transform::Rotate(Rotation::Rotate180) => (width - 1 - x, height - 1 - y),
+            Transform::Rotate(Rotation::Rotate270) => (width - 1 - y, x),
+            Transform::Crop(left, top) => (x + left, y + top),
+        }
+    }
+
+    // Whether the texels within each unit of storage end up mirrored
+    // horizontally and vertically
+    fn mirrors(self) -> (bool, bool) {
+        match self {
+            Transform::FlipVertical => (false, true),
+            Transform::FlipHorizontal => (true, false),
+            Transform::Rotate(Rotation::Rotate180) => (true, true),
+            _ => (false, false),
+        }
+    }
+
+    // Whether whole units of storage of the given width and height can be
+    // moved, for a source of the given width and height. Units only partly
+    // covered by the surface would otherwise bring their padding along.
+    fn moves_units(self, unit_width: u32, unit_height: u32, width: u32, height: u32) -> bool {
+        match self {
+            Transform::FlipVertical => height.is_multiple_of(unit_height),
+            Transform::FlipHorizontal => width.is_multiple_of(unit_width),
+            Transform::Rotate(Rotation::Rotate180) => {
+                width.is_multiple_of(unit_width) && height.is_multiple_of(unit_height)
+            }
+            Transform::Rotate(_) => unit_width == 1 && unit_height == 1,
+            Transform::Crop(left, top) => {
+                left.is_multiple_of(unit_width) && top.is_multiple_of(unit_height)
+            }
+        }
+    }
+
+    // The same transform counted in units of storage rather than texels
+    fn in_units(self, unit_width: u32, unit_height: u32) -> Transform {
+        match self {
+            Transform::Crop(left, top) => Transform::Crop(left / unit_width, top / unit_height),
+            transform => transform,
+        }
+    }
+}
+
+// Rearrange the units (texels, blocks or floats) of a surface, slice by slice.
+// Each surface is its units with its width and height in units.
+fn rearrange<T: Copy>(
+    transform: Transform,
+    size: usize,
+    (src, src_width, src_height): (&[T], u32, u32),
+    (dst, dst_width, dst_height): (&mut [T], u32, u32),
+    depth: u32,
+) {
+    let (src_slice, dst_slice) = (
+        src_width as usize * src_height as usize * size,
+        dst_width as usize * dst_height as usize * size,
+    );
+    for (src, dst) in src
+        .chunks_exact(src_slice)
+        .zip(dst.chunks_exact_mut(dst_slice))
+        .take(depth as usize)
+    {
+        for y in 0..dst_height {
+            for x in 0..dst_width {
+                let (sx, sy) = transform.source(x, y, src_width, src_height);
+                let from = (sy as usize * src_width as usize + sx as usize) * size;
+                let to = (y as usize * dst_width as usize + x as usize) * size;
+                dst[to..to + size].copy_from_slice(&src[from..from + size]);
+            }
+        }
+    }
+}
+
+impl Dds {
+    /// Flip every subresource upside down, into a new texture.
+    ///
+    /// Uncompressed data, and BC1 through BC5 blocks, are rearranged without
+    /// decoding them. Only BC6H and BC7, and mipmap levels whose height is not
+    /// a multiple of four, are decoded and compressed again.
+    pub fn flip_vertical(&self) -> Result<Dds, Error> {
+        self.transform(Transform::FlipVertical, self.get_width(), self.get_height())
+    }
+
+    /// Mirror every subresource left to right, into a new texture. See
+    /// `flip_vertical`; here it is the width that must be a multiple of four.
+    pub fn flip_horizontal(&self) -> Result<Dds, Error> {
+        self.transform(
+            Transform::FlipHorizontal,
+            self.get_width(),
+            self.get_height(),
+        )
+    }
+
+    /// Rotate every subresource clockwise, into a new texture. Half turns are
+    /// lossless like `flip_vertical`, but quarter turns decode and encode block
+    /// compressed formats again.
+    pub fn rotate(&self, rotation: Rotation) -> Result<Dds, Error> {
+        let (width, height) = match rotation {
+            Rotation::Rotate180 => (self.get_width(), self.get_height()),
+            _ => (self.get_height(), self.get_width()),
+        };
+        self.transform(Transform::Rotate(rotation), width, height)
+    }
+
+    /// Crop every subresource to a region, into a new texture. Mipmap levels
+    /// keep the region scaled down to their size, and block compressed data is
+    /// copied without decoding it wherever the region starts on a block.
+    pub fn crop(&self, x: u32, y: u32, width: u32, height: u32) -> Result<Dds, Error> {
+        for (field, start, length, size) in [
+            ("x", x, width, self.get_width()),
+            ("y", y, height, self.get_height()),
+        ] {
+            if start >= size {
+                return Err(Error::InvalidField {
+                    field,
+                    value: start as u64,
+                    offset: None,
+                    expected: Expected::Range(0, size as u64 - 1),
+                });
+            }
+            if length == 0 || length > size - start {
+                return Err(Error::InvalidField {
+                    field: if field == "x" { "width" } else { "height" },
+                    value: length as u64,
+                    offset: None,
+                    expected: Expected::Range(1, (size - start) as u64),
+                });
+            }
+        }
+        self.transform(Transform::Crop(x, y), width, height)
+    }
+
+    /// Resize every subresource with the given filter, into a new texture.
+    /// Mipmap levels are each resized to the matching level of the result, and
+    /// volume textures keep their depth.
+    ///
+    /// sRGB data is filtered in linear space, and straight alpha is filtered
+    /// premultiplied, as with the `TextureBuilder`.
+    #[cfg(feature = "image")]
+    pub fn resize(&self, width: u32, height: u32, filter: FilterType) -> Result<Dds, Error> {
+        for (field, value) in [("width", width), ("height", height)] {
+            if value == 0 {
+                return Err(Error::InvalidField {
+                    field,
+                    value: 0,
+                    offset: None,
+                    expected: Expected::Description("at least one"),
+                });
+            }
+        }
+        let mut dds = self.new_transformed(width, height)?;
+        let srgb = match self.header10 {
+            Some(ref h10) => h10.dxgi_format.is_srgb(),
+            None => false,
+        };
+        let straight = self.get_alpha_mode() == AlphaMode::Straight;
+
+        for array_layer in 0..self.get_num_array_layers() {
+            for mipmap_level in 0..dds.get_num_mipmap_levels() {
+                let (src_width, src_height, _) = self.get_mipmap_dimensions(mipmap_level);
+                let (dst_width, dst_height, _) = dds.get_mipmap_dimensions(mipmap_level);
+                let mut src = self.decode_rgba32f(array_layer, mipmap_level)?;
+                if srgb {
+                    for texel in src.chunks_exact_mut(4) {
+                        for c in &mut texel[..3] {
+                            *c = srgb_to_linear(*c);
+                        }
+                    }
+                }
+                if straight {
+                    premultiply(&mut src);
+                }
+
+                let mut dst = Vec::new();
+                for slice in src.chunks_exact(src_width as usize * src_height as usize * 4) {
+                    let image = match Rgba32FImage::from_raw(src_width, src_height, slice.to_vec())
+                    {
+                        Some(image) => image,
+                        None => return Err(Error::OutOfBounds),
+                    };
+                    let resized = imageops::resize(&image, dst_width, dst_height, filter);
+                    dst.extend_from_slice(resized.as_raw());
+                }
+
+                if straight {
+                    unpremultiply(&mut dst);
+                }
+                if srgb {
+                    for texel in dst.chunks_exact_mut(4) {
+                        for c in &mut texel[..3] {
+                            *c = linear_to_srgb(*c);
+                        }
+                    }
+                }
+                dds.encode_rgba32f(array_layer, mipmap_level, &dst)?;
+            }
+        }
+        Ok(dds)
+    }
+
+    // Create an empty texture like this one with new dimensions, keeping as
+    // many mipmap levels as fit
+    fn new_transformed(&self, width: u32, height: u32) -> Result<Dds, Error> {
+        if self.is_cubemap() && width != height {
+            return Err(Error::InvalidField {
+                field: "height",
+                value: height as u64,
+                offset: None,
+                expected: Expected::Description("the same as the width, for a cubemap"),
+            });
+        }
+        let depth = match self.is_volume() {
+            true => Some(self.get_depth()),
+            false => None,
+        };
+        let full = 32 - width.max(height).max(depth.unwrap_or(1)).leading_zeros();
+        self.new_like(
+            width,
+            height,
+            depth,
+            self.get_num_mipmap_levels().min(full),
+            self.get_num_array_layers(),
+            self.is_cubemap(),
+        )
+    }
+
+    fn transform(&self, transform: Transform, width: u32, height: u32) -> Result<Dds, Error> {
+        let layout = match Layout::of(self) {
+            Some(layout) => layout,
+            None => return Err(Error::UnsupportedFormat),
+        };
+        let mut dds = self.new_transformed(width, height)?;
+        let (unit_width, unit_height, unit_size) = layout.unit();
+        let (horizontal, vertical) = transform.mirrors();
+
+        for array_layer in 0..self.get_num_array_layers() {
+            for mipmap_level in 0..dds.get_num_mipmap_levels() {
+                let (src_width, src_height, depth) = self.get_mipmap_dimensions(mipmap_level);
+                let (dst_width, dst_height, _) = dds.get_mipmap_dimensions(mipmap_level);
+                // Keep the scaled down region inside the smaller levels
+                let transform = match transform {
+                    Transform::Crop(left, top) => Transform::Crop(
+                        (left >> mipmap_level).min(src_width - dst_width),
+                        (top >> mipmap_level).min(src_height - dst_height),
+                    ),
+                    transform => transform,
+                };
+
+                if transform.moves_units(unit_width, unit_height, src_width, src_height)
+                    && (layout.can_mirror_unit() || !(horizontal || vertical))
+                {
+                    let src = self.get_mipmap_data(array_layer, mipmap_level)?;
+                    let dst = dds.get_mut_mipmap_data(array_layer, mipmap_level)?;
+                    rearrange(
+                        transform.in_units(unit_width, unit_height),
+                        unit_size,
+                        (
+                            src,
+                            src_width.div_ceil(unit_width),
+                            src_height.div_ceil(unit_height),
+                        ),
+                        (
+                            dst,
+                            dst_width.div_ceil(unit_width),
+                            dst_height.div_ceil(unit_height),
+                        ),
+                        depth,
+                    );
+                    if horizontal || vertical {
+                        for unit in dst.chunks_exact_mut(unit_size) {
+                            layout.mirror_unit(unit, horizontal, vertical);
+                        }
+                    }
+                } else {
+                    let src = self.decode_rgba32f(array_layer, mipmap_level)?;
+                    let mut dst =
+                        vec![0.0; dst_width as usize * dst_height as usize * depth as usize * 4];
+                    rearrange(
+                        transform,
+                        4,
+                        (&src, src_width, src_height),
+                        (&mut dst, dst_width, dst_height),
+                        depth,
+                    );
+                    dds.encode_rgba32f(array_layer, mipmap_level, &dst)?;
+                }
+            }
+        }
+        Ok(dds)
+    }
+}
@@ -0,0 +1,315 @@
+// The MIT License (MIT)
+//
+// Copyright (c) 2018 Michael Dilger
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+// Conversion between RGB and the planar YUV video formats
+
+use crate::{AlphaMode, D3D10ResourceDimension, Dds, DxgiFormat, Error, NewDxgiParams};
+
+/// The matrix relating YUV to RGB, which differs between video standards
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum YuvMatrix {
+    /// ITU-R BT.601, for standard definition video
+    Bt601,
+    /// ITU-R BT.709, for high definition video
+    Bt709,
+    /// ITU-R BT.2020, for ultra high definition video
+    Bt2020,
+}
+
+impl YuvMatrix {
+    // The weights of red and blue in luma
+    fn coefficients(&self) -> (f32, f32) {
+        match *self {
+            YuvMatrix::Bt601 => (0.299, 0.114),
+            YuvMatrix::Bt709 => (0.2126, 0.0722),
+            YuvMatrix::Bt2020 => (0.2627, 0.0593),
+        }
+    }
+}
+
+/// The range of code values that YUV samples use
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum YuvRange {
+    /// Luma from 16 to 235 and chroma from 16 to 240 (scaled up for deeper
+    /// formats), as is usual for video
+    Limited,
+    /// Every code value is used
+    Full,
+}
+
+// How a planar format stores its samples: a luma plane followed by a plane of
+// interleaved U and V samples
+struct Planar {
+    // Significant bits per sample, held in the most significant bits of
+    // `bytes` bytes
+    bits: u32,
+    bytes: usize,
+    // The number of texels across and down that share one chroma sample
+    chroma_width: u32,
+    chroma_height: u32,
+}
+
+impl Planar {
+    fn of(format: DxgiFormat) -> Option<Planar> {
+        let (bits, bytes, chroma_width, chroma_height) = match format {
+            DxgiFormat::NV12 => (8, 1, 2, 2),
+            DxgiFormat::P010 => (10, 2, 2, 2),
+            DxgiFormat::P016 => (16, 2, 2, 2),
+            DxgiFormat::NV11 => (8, 1, 4, 1),
+            DxgiFormat::P208 => (8, 1, 2, 1),
+            _ => return None,
+        };
+        Some(Planar {
+            bits,
+            bytes,
+            chroma_width,
+            chroma_height,
+        })
+    }
+
+    fn read(&self, data: &[u8], offset: usize) -> u32 {
+        match self.bytes {
+            1 => data[offset] as u32,
+            _ => u16::from_le_bytes([data[offset], data[offset + 1]]) as u32 >> (16 - self.bits),
+        }
+    }
+
+    fn write(&self, data: &mut [u8], offset: usize, value: u32) {
+        match self.bytes {
+            1 => data[offset] = value as u8,
+            _ => data[offset..offset + 2]
+                .copy_from_slice(&((value << (16 - self.bits)) as u16).to_le_bytes()),
+        }
+    }
+
+    // Luma from 0.0 to 1.0 and chroma from -0.5 to 0.5
+    fn normalize(&self, range: YuvRange, y: u32, u: u32, v: u32) -> (f32, f32, f32) {
+        let (y, u, v) = (y as f32, u as f32, v as f32);
+        match range {
+            YuvRange::Limited => {
+                let scale = (1 << (self.bits - 8)) as f32;
+                (
+                    (y / scale - 16.0) / 219.0,
+                    (u / scale - 128.0) / 224.0,
+                    (v / scale - 128.0) / 224.0,
+                )
+            }
+            YuvRange::Full => {
+                let max = ((1_u32 << self.bits) - 1) as f32;
+                let half = (1 << (self.bits - 1)) as f32;
+                (y / max, (u - half) / max, (v - half) / max)
+            }
+        }
+    }
+
+    // The inverse of `normalize`
+    fn quantize(&self, range: YuvRange, y: f32, u: f32, v: f32) -> (u32, u32, u32) {
+        let max = ((1_u32 << self.bits) - 1) as f32;
+        let code = |value: f32| value.round().clamp(0.0, max) as u32;
+        match range {
+            YuvRange::Limited => {
+                let scale = (1 << (self.bits - 8)) as f32;
+                (
+                    code((16.0 + 219.0 * y) * scale),
+                    code((128.0 + 224.0 * u) * scale),
+                    code((128.0 + 224.0 * v) * scale),
+                )
+            }
+            YuvRange::Full => {
+                let half = (1 << (self.bits - 1)) as f32;
+                (code(y * max), code(u * max + half), code(v * max + half))
+            }
+        }
+    }
+}
+
+pub(crate) fn yuv_to_rgb(matrix: YuvMatrix, y: f32, u: f32, v: f32) -> [f32; 3] {
+    let (kr, kb) = matrix.coefficients();
+    let r = y + 2.0 * (1.0 - kr) * v;
+    let b = y + 2.0 * (1.0 - kb) * u;
+    let g = (y - kr * r - kb * b) / (1.0 - kr - kb);
+    [r, g, b]
+}
+
+pub(crate) fn rgb_to_yuv(matrix: YuvMatrix, rgb: &[f32]) -> (f32, f32, f32) {
+    let (kr, kb) = matrix.coefficients();
+    let y = kr * rgb[0] + (1.0 - kr - kb) * rgb[1] + kb * rgb[2];
+    let u = (rgb[2] - y) / (2.0 * (1.0 - kb));
+    let v = (rgb[0] - y) / (2.0 * (1.0 - kr));
+    (y, u, v)
+}
+
+impl Dds {
+    /// Decode a single mipmap level of an array layer of a planar YUV texture
+    /// (`NV12`, `P010`, `P016`, `NV11` or `P208`) into RGBA floats, four per
+    /// texel, row by row. Alpha is 1.0.
+    ///
+    /// Each texel takes the chroma sample that covers it. No transfer function
+    /// is applied, so the RGB values are as gamma encoded as the video was. The
+    /// luma and chroma planes themselves are available from `get_plane_data`
+    /// as planes 0 and 1.
+    pub fn decode_yuv_rgba32f(
+        &self,
+        array_layer: u32,
+        mipmap_level: u32,
+        matrix: YuvMatrix,
+        range: YuvRange,
+    ) -> Result<Vec<f32>, Error> {
+        let planar = self.get_planar()?;
+        let (width, height, _) = self.get_mipmap_dimensions(mipmap_level);
+        let luma_pitch = self.get_plane_layout(mipmap_level, 0)?.pitch as usize;
+        let chroma_pitch = self.get_plane_layout(mipmap_level, 1)?.pitch as usize;
+        let luma = self.get_plane_data(array_layer, mipmap_level, 0)?;
+        let chroma = self.get_plane_data(array_layer, mipmap_level, 1)?;
+
+        let mut rgba = Vec::with_capacity(width as usize * height as usize * 4);
+        for y in 0..height {
+            let luma_row = y as usize * luma_pitch;
+            let chroma_row = (y / planar.chroma_height) as usize * chroma_pitch;
+            for x in 0..width {
+                let chroma_offset =
+                    chroma_row + (x / planar.chroma_width) as usize * 2 * planar.bytes;
+                let (yn, un, vn) = planar.normalize(
+                    range,
+                    planar.read(luma, luma_row + x as usize * planar.bytes),
+                    planar.read(chroma, chroma_offset),
+                    planar.read(chroma, chroma_offset + planar.bytes),
+                );
+                rgba.extend_from_slice(&yuv_to_rgb(matrix, yn, un, vn));
+                rgba.push(1.0);
+            }
+        }
+        Ok(rgba)
+    }
+
+    /// Decode a single mipmap level of an array layer of a planar YUV texture
+    /// into RGBA bytes, four per texel, row by row. See `decode_yuv_rgba32f`.
+    pub fn decode_yuv_rgba8(
+        &self,
+        array_layer: u32,
+        mipmap_level: u32,
+        matrix: YuvMatrix,
+        range: YuvRange,
+    ) -> Result<Vec<u8>, Error> {
+        let rgba = self.decode_yuv_rgba32f(array_layer, mipmap_level, matrix, range)?;
+        Ok(rgba
+            .iter()
+            .map(|v| (v.clamp(0.0, 1.0) * 255.0).round() as u8)
+            .collect())
+    }
+
+    /// Encode RGBA floats, four per texel, into a single mipmap level of an
+    /// array layer of a planar YUV texture. Alpha is dropped, and each chroma
+    /// sample is the average of the texels it covers. This is the inverse of
+    /// `decode_yuv_rgba32f`.
+    pub fn encode_yuv_rgba32f(
+        &mut self,
+        array_layer: u32,
+        mipmap_level: u32,
+        rgba: &[f32],
+        matrix: YuvMatrix,
+        range: YuvRange,
+    ) -> Result<(), Error> {
+        let planar = self.get_planar()?;
+        let (width, height, _) = self.get_mipmap_dimensions(mipmap_level);
+        let (w, h) = (width as usize, height as usize);
+        let count = (w * h * 4) as u64;
+        if rgba.len() as u64 != count {
+            return Err(Error::Mismatch {
+                index: 0,
+                property: "RGBA float count",
+                found: rgba.len().to_string(),
+                expected: count.to_string(),
+            });
+        }
+        let luma_layout = self.get_plane_layout(mipmap_level, 0)?;
+        let chroma_layout = self.get_plane_layout(mipmap_level, 1)?;
+        let (luma_pitch, chroma_pitch) = (luma_layout.pitch as usize, chroma_layout.pitch as usize);
+        let data = self.get_mut_mipmap_data(array_layer, mipmap_level)?;
+        let (luma, chroma) = data.split_at_mut(chroma_layout.offset as usize);
+
+        let chroma_width = width.div_ceil(planar.chroma_width);
+        let chroma_height = height.div_ceil(planar.chroma_height);
+        for cy in 0..chroma_height {
+            for cx in 0..chroma_width {
+                let (mut u_sum, mut v_sum, mut n) = (0.0, 0.0, 0.0);
+                for y in cy * planar.chroma_height..((cy + 1) * planar.chroma_height).min(height) {
+                    for x in cx * planar.chroma_width..((cx + 1) * planar.chroma_width).min(width) {
+                        let texel = (y as usize * w + x as usize) * 4;
+                        let (yn, un, vn) = rgb_to_yuv(matrix, &rgba[texel..texel + 3]);
+                        let (y_code, _, _) = planar.quantize(range, yn, 0.0, 0.0);
+                        planar.write(
+                            luma,
+                            y as usize * luma_pitch + x as usize * planar.bytes,
+                            y_code,
+                        );
+                        u_sum += un;
+                        v_sum += vn;
+                        n += 1.0;
+                    }
+                }
+                let (_, u_code, v_code) = planar.quantize(range, 0.0, u_sum / n, v_sum / n);
+                let offset = cy as usize * chroma_pitch + cx as usize * 2 * planar.bytes;
+                planar.write(chroma, offset, u_code);
+                planar.write(chroma, offset + planar.bytes, v_code);
+            }
+        }
+        Ok(())
+    }
+
+    /// Create a planar YUV texture (`NV12`, `P010`, `P016`, `NV11` or `P208`)
+    /// of a single image from RGBA floats, four per texel, row by row. See
+    /// `encode_yuv_rgba32f`.
+    pub fn from_rgba32f_yuv(
+        width: u32,
+        height: u32,
+        rgba: &[f32],
+        format: DxgiFormat,
+        matrix: YuvMatrix,
+        range: YuvRange,
+    ) -> Result<Dds, Error> {
+        if Planar::of(format).is_none() {
+            return Err(Error::UnsupportedFormat);
+        }
+        let mut dds = Dds::new_dxgi(NewDxgiParams {
+            height,
+            width,
+            depth: None,
+            format,
+            mipmap_levels: Some(1),
+            array_layers: None,
+            caps2: None,
+            is_cubemap: false,
+            resource_dimension: D3D10ResourceDimension::Texture2D,
+            alpha_mode: AlphaMode::Unknown,
+        })?;
+        dds.encode_yuv_rgba32f(0, 0, rgba, matrix, range)?;
+        Ok(dds)
+    }
+
+    fn get_planar(&self) -> Result<Planar, Error> {
+        match self.get_dxgi_format().and_then(Planar::of) {
+            Some(planar) => Ok(planar),
+            None => Err(Error::UnsupportedFormat),
+        }
+    }
+}